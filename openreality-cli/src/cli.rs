@@ -65,6 +65,29 @@ pub enum Command {
         #[arg(long, default_value_t = true)]
         compress_textures: bool,
     },
+    /// Deduplicate assets in exported .orsb scenes and pack them into one file
+    Pack {
+        /// Exported .orsb scenes or bundles to pack
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+        /// Output file path (.orsb)
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Weld, reorder and quantize meshes (u16 indices, packed normals/UVs)
+        #[arg(long)]
+        optimize_meshes: bool,
+    },
+    /// Print the contents of an .orsb scene or bundle
+    Inspect {
+        /// Scene file (.orsb) or multi-scene bundle
+        file: PathBuf,
+        /// Only show this scene of a bundle
+        #[arg(long)]
+        scene: Option<String>,
+        /// Emit JSON instead of a text report
+        #[arg(long)]
+        json: bool,
+    },
     /// Package a built application for distribution
     Package {
         #[command(subcommand)]
@@ -312,6 +335,52 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cli_pack() {
+        let cli = Cli::try_parse_from([
+            "orcli",
+            "pack",
+            "menu.orsb",
+            "level1.orsb",
+            "-o",
+            "game.orsb",
+        ])
+        .unwrap();
+        match cli.command.unwrap() {
+            Command::Pack {
+                inputs,
+                output,
+                optimize_meshes,
+            } => {
+                assert!(!optimize_meshes);
+                assert_eq!(
+                    inputs,
+                    vec![PathBuf::from("menu.orsb"), PathBuf::from("level1.orsb")]
+                );
+                assert_eq!(output, PathBuf::from("game.orsb"));
+            }
+            _ => panic!("Expected Pack command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_pack_requires_input() {
+        assert!(Cli::try_parse_from(["orcli", "pack", "-o", "game.orsb"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_inspect() {
+        let cli = Cli::try_parse_from(["orcli", "inspect", "game.orsb", "--json"]).unwrap();
+        match cli.command.unwrap() {
+            Command::Inspect { file, scene, json } => {
+                assert_eq!(file, PathBuf::from("game.orsb"));
+                assert_eq!(scene, None);
+                assert!(json);
+            }
+            _ => panic!("Expected Inspect command"),
+        }
+    }
+
     #[test]
     fn test_cli_test() {
         let cli = Cli::try_parse_from(["orcli", "test"]).unwrap();
@@ -80,6 +80,10 @@ pub async fn run(
         .await?;
 
     if status.success() {
+        if let ExportFormat::Orsb = format {
+            let report = crate::commands::pack::dedup_file(&output_abs)?;
+            crate::commands::pack::print_report(&report);
+        }
         println!(
             "Export complete: {} ({})",
             output_abs.display(),
@@ -0,0 +1,58 @@
+use std::path::PathBuf;
+
+use openreality_gpu_shared::inspect::{dump_bundle_json, dump_bundle_text, dump_json, dump_text};
+use openreality_gpu_shared::scene_bundle::parse_bundle;
+use openreality_gpu_shared::scene_format::{parse_orsb_with_layout, ORSB_MAGIC};
+
+pub async fn run(file: PathBuf, scene: Option<String>, json: bool) -> anyhow::Result<()> {
+    let data = std::fs::read(&file)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", file.display(), e))?;
+
+    // Plain scenes keep their section layout; bundle scenes are resolved
+    // against the shared pool and have no per-section sizes.
+    if data.starts_with(&ORSB_MAGIC) && scene.is_none() {
+        let (parsed, sections) = parse_orsb_with_layout(&data)
+            .map_err(|e| anyhow::anyhow!("{}: {}", file.display(), e))?;
+        print!(
+            "{}",
+            if json {
+                dump_json(&parsed, &sections)
+            } else {
+                dump_text(&parsed, &sections)
+            }
+        );
+        return Ok(());
+    }
+
+    let bundle = parse_bundle(&data).map_err(|e| anyhow::anyhow!("{}: {}", file.display(), e))?;
+    let Some(name) = scene else {
+        print!(
+            "{}",
+            if json {
+                dump_bundle_json(&bundle)
+            } else {
+                dump_bundle_text(&bundle)
+            }
+        );
+        return Ok(());
+    };
+
+    let index = bundle.scene_index(&name).ok_or_else(|| {
+        anyhow::anyhow!(
+            "No scene '{}' in {} (available: {})",
+            name,
+            file.display(),
+            bundle.scene_names().join(", ")
+        )
+    })?;
+    let resolved = bundle.resolve_scene(index).expect("scene index in range");
+    print!(
+        "{}",
+        if json {
+            dump_json(&resolved, &[])
+        } else {
+            dump_text(&resolved, &[])
+        }
+    );
+    Ok(())
+}
@@ -3,7 +3,9 @@ pub mod cache_cmd;
 pub mod export;
 pub mod info;
 pub mod init;
+pub mod inspect;
 pub mod new;
+pub mod pack;
 pub mod package;
 pub mod run_cmd;
 pub mod setup;
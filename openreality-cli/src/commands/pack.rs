@@ -0,0 +1,155 @@
+use std::path::{Path, PathBuf};
+
+use openreality_gpu_shared::mesh_opt::{optimize_meshes, MeshOptOptions, MeshOptReport};
+use openreality_gpu_shared::scene_bundle::{parse_bundle, write_bundle, ParsedBundle};
+use openreality_gpu_shared::scene_format::{parse_orsb, write_orsb, ORSB_MAGIC};
+use openreality_gpu_shared::scene_pack::{dedup_scene, AssetSavings, DedupReport};
+
+pub async fn run(
+    inputs: Vec<PathBuf>,
+    output: PathBuf,
+    optimize_meshes_flag: bool,
+) -> anyhow::Result<()> {
+    println!("Packing {} scene file(s)...", inputs.len());
+
+    let mut bundles: Vec<ParsedBundle> = Vec::new();
+    let mut all_plain = true;
+    for input in &inputs {
+        let data = std::fs::read(input)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", input.display(), e))?;
+        let single = data.starts_with(&ORSB_MAGIC);
+        all_plain &= single;
+        // A plain .orsb contributes one scene, named after its file. Bundles
+        // keep their pools so assets their scenes share aren't counted again.
+        let bundle = if single {
+            let scene =
+                parse_orsb(&data).map_err(|e| anyhow::anyhow!("{}: {}", input.display(), e))?;
+            ParsedBundle::wrap_scene(scene_name(input), scene)
+        } else {
+            parse_bundle(&data).map_err(|e| anyhow::anyhow!("{}: {}", input.display(), e))?
+        };
+        for s in &bundle.scenes {
+            if bundles.iter().flat_map(|b| &b.scenes).any(|other| other.name == s.name) {
+                anyhow::bail!("Duplicate scene name '{}' (from {})", s.name, input.display());
+            }
+        }
+        bundles.push(bundle);
+    }
+
+    // A single plain scene stays a plain .orsb; anything else becomes a bundle.
+    let opts = MeshOptOptions::default();
+    let (bytes, report, mesh_report) = if all_plain && bundles.len() == 1 {
+        let mut scene = bundles[0].resolve_scene(0).expect("plain scene");
+        let report = dedup_scene(&mut scene);
+        let mesh_report = optimize_meshes_flag.then(|| optimize_meshes(&mut scene.meshes, &opts));
+        (write_orsb(&scene), report, mesh_report)
+    } else {
+        let (mut bundle, report) = ParsedBundle::merge(bundles);
+        let mesh_report = optimize_meshes_flag.then(|| optimize_meshes(&mut bundle.meshes, &opts));
+        (write_bundle(&bundle), report, mesh_report)
+    };
+
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&output, &bytes)?;
+
+    print_report(&report);
+    if let Some(mesh_report) = &mesh_report {
+        print_mesh_report(mesh_report);
+    }
+    println!(
+        "Wrote {} ({})",
+        output.display(),
+        format_bytes(bytes.len() as u64)
+    );
+    Ok(())
+}
+
+/// Deduplicate the assets of an exported .orsb file in place.
+pub fn dedup_file(path: &Path) -> anyhow::Result<DedupReport> {
+    let data = std::fs::read(path)?;
+    let mut scene = parse_orsb(&data).map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+    let report = dedup_scene(&mut scene);
+    if report.bytes_saved() > 0 {
+        std::fs::write(path, write_orsb(&scene))?;
+    }
+    Ok(report)
+}
+
+pub fn print_report(report: &DedupReport) {
+    println!("Asset deduplication:");
+    print_savings("Meshes", &report.meshes);
+    print_savings("Materials", &report.materials);
+    print_savings("Textures", &report.textures);
+    println!(
+        "  Total: {} -> {} (saved {})",
+        format_bytes(report.bytes_before()),
+        format_bytes(report.bytes_after()),
+        format_bytes(report.bytes_saved())
+    );
+}
+
+fn print_mesh_report(report: &MeshOptReport) {
+    println!("Mesh optimization ({} mesh(es)):", report.meshes);
+    println!(
+        "  Vertices:  {} -> {}",
+        report.vertices_before, report.vertices_after
+    );
+    println!(
+        "  ACMR:      {:.3} -> {:.3}",
+        report.acmr_before, report.acmr_after
+    );
+    println!(
+        "  Size:      {} -> {} (saved {})",
+        format_bytes(report.bytes_before),
+        format_bytes(report.bytes_after),
+        format_bytes(report.bytes_saved())
+    );
+}
+
+fn print_savings(label: &str, s: &AssetSavings) {
+    println!(
+        "  {:<10} {} -> {} ({} duplicate(s), saved {})",
+        format!("{}:", label),
+        s.count_before,
+        s.count_after,
+        s.duplicates_removed(),
+        format_bytes(s.bytes_saved())
+    );
+}
+
+fn scene_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "main".to_string())
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    let b = bytes as f64;
+    if b >= KB * KB {
+        format!("{:.2} MB", b / (KB * KB))
+    } else if b >= KB {
+        format!("{:.1} KB", b / KB)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scene_name_from_file_stem() {
+        assert_eq!(scene_name(Path::new("build/level1.orsb")), "level1");
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(2048), "2.0 KB");
+        assert_eq!(format_bytes(3 * 1024 * 1024), "3.00 MB");
+    }
+}
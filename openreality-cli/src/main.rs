@@ -64,6 +64,14 @@ async fn main() -> Result<()> {
             let ctx = project::detect_project_context()?;
             commands::export::run(scene, output, format, physics, compress_textures, ctx).await
         }
+        Some(cli::Command::Pack {
+            inputs,
+            output,
+            optimize_meshes,
+        }) => commands::pack::run(inputs, output, optimize_meshes).await,
+        Some(cli::Command::Inspect { file, scene, json }) => {
+            commands::inspect::run(file, scene, json).await
+        }
         Some(cli::Command::Package { target }) => {
             let ctx = project::detect_project_context()?;
             commands::package::run(target, ctx).await
@@ -0,0 +1,102 @@
+// Histogram-based auto exposure (compute).
+//   cs_histogram: bins the log2 luminance of every HDR pixel
+//   cs_average:   meters the histogram, adapts toward it over time, writes the
+//                 exposure multiplier to a 1x1 target and clears the bins
+// Bin 0 counts near-black pixels, which are left out of the average.
+
+struct PostProcessParams {
+    bloom_threshold: f32,
+    bloom_intensity: f32,
+    gamma: f32,
+    tone_mapping_mode: i32,
+    horizontal: i32,
+    vignette_intensity: f32,
+    vignette_radius: f32,
+    vignette_softness: f32,
+    color_brightness: f32,
+    color_contrast: f32,
+    color_saturation: f32,
+    exposure_mode: i32,
+    exposure_ev: f32,
+    adaptation_speed: f32,
+    delta_time: f32,
+    _pad1: f32,
+};
+
+struct Histogram {
+    bins: array<atomic<u32>, 256>,
+    // Luminance the eye has adapted to; 0 until the first frame is metered.
+    adapted_luminance: f32,
+};
+
+@group(0) @binding(0) var<uniform> params: PostProcessParams;
+@group(0) @binding(1) var scene_texture: texture_2d<f32>;
+@group(0) @binding(2) var<storage, read_write> histogram: Histogram;
+@group(0) @binding(3) var exposure_texture: texture_storage_2d<r32float, write>;
+
+const BINS: u32 = 256u;
+const MIN_LOG_LUMINANCE: f32 = -10.0;
+const LOG_LUMINANCE_RANGE: f32 = 22.0;
+// Average luminance is exposed to middle grey.
+const MIDDLE_GREY: f32 = 0.18;
+
+var<workgroup> local_bins: array<atomic<u32>, 256>;
+var<workgroup> weighted: array<f32, 256>;
+
+fn luminance_bin(color: vec3<f32>) -> u32 {
+    let luminance = dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+    if luminance < exp2(MIN_LOG_LUMINANCE) {
+        return 0u;
+    }
+    let t = clamp((log2(luminance) - MIN_LOG_LUMINANCE) / LOG_LUMINANCE_RANGE, 0.0, 1.0);
+    return 1u + u32(t * f32(BINS - 2u));
+}
+
+@compute @workgroup_size(16, 16)
+fn cs_histogram(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(local_invocation_index) index: u32,
+) {
+    let size = textureDimensions(scene_texture);
+    if id.x < size.x && id.y < size.y {
+        let color = textureLoad(scene_texture, id.xy, 0).rgb;
+        atomicAdd(&local_bins[luminance_bin(color)], 1u);
+    }
+    workgroupBarrier();
+    atomicAdd(&histogram.bins[index], atomicLoad(&local_bins[index]));
+}
+
+@compute @workgroup_size(256)
+fn cs_average(@builtin(local_invocation_index) index: u32) {
+    let count = atomicLoad(&histogram.bins[index]);
+    atomicStore(&histogram.bins[index], 0u);
+    weighted[index] = f32(count) * f32(index);
+    workgroupBarrier();
+
+    for (var stride = BINS / 2u; stride > 0u; stride >>= 1u) {
+        if index < stride {
+            weighted[index] += weighted[index + stride];
+        }
+        workgroupBarrier();
+    }
+
+    if index == 0u {
+        // Thread 0 holds bin 0: the black pixels.
+        let size = textureDimensions(scene_texture);
+        let lit = max(f32(size.x * size.y) - f32(count), 1.0);
+        let mean_bin = max(weighted[0] / lit, 1.0);
+        let log_luminance = (mean_bin - 1.0) / f32(BINS - 2u) * LOG_LUMINANCE_RANGE + MIN_LOG_LUMINANCE;
+        let metered = exp2(log_luminance);
+
+        var adapted = histogram.adapted_luminance;
+        if adapted <= 0.0 {
+            adapted = metered;
+        } else {
+            adapted += (metered - adapted) * (1.0 - exp(-params.delta_time * params.adaptation_speed));
+        }
+        histogram.adapted_luminance = adapted;
+
+        let exposure = MIDDLE_GREY / adapted * exp2(params.exposure_ev);
+        textureStore(exposure_texture, vec2<i32>(0, 0), vec4<f32>(exposure, 0.0, 0.0, 1.0));
+    }
+}
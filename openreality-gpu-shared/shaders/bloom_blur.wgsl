@@ -13,6 +13,10 @@ struct PostProcessParams {
     color_brightness: f32,
     color_contrast: f32,
     color_saturation: f32,
+    exposure_mode: i32,
+    exposure_ev: f32,
+    adaptation_speed: f32,
+    delta_time: f32,
     _pad1: f32,
 };
 
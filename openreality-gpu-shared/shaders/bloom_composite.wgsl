@@ -1,4 +1,4 @@
-// Bloom composite + tone mapping + gamma correction.
+// Bloom composite + exposure + tone mapping + gamma correction.
 
 struct PostProcessParams {
     bloom_threshold: f32,
@@ -12,6 +12,10 @@ struct PostProcessParams {
     color_brightness: f32,
     color_contrast: f32,
     color_saturation: f32,
+    exposure_mode: i32,
+    exposure_ev: f32,
+    adaptation_speed: f32,
+    delta_time: f32,
     _pad1: f32,
 };
 
@@ -19,6 +23,10 @@ struct PostProcessParams {
 @group(0) @binding(1) var scene_texture: texture_2d<f32>;
 @group(0) @binding(2) var bloom_texture: texture_2d<f32>;
 @group(0) @binding(3) var tex_sampler: sampler;
+// 1x1 exposure multiplier written by auto_exposure.wgsl (auto mode only).
+@group(0) @binding(4) var exposure_texture: texture_2d<f32>;
+
+const EXPOSURE_MODE_AUTO: i32 = 1;
 
 struct FragmentInput {
     @location(0) uv: vec2<f32>,
@@ -47,6 +55,36 @@ fn uncharted2_tonemap(x: vec3<f32>) -> vec3<f32> {
     return ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F;
 }
 
+// AgX base look (Troy Sobotka), fitted by Benjamin Wrensch. Inset into the
+// AgX working space, log2 encode, sigmoid, outset; the result is display
+// encoded, so it is decoded back to linear for the gamma step below.
+const AGX_INSET: mat3x3<f32> = mat3x3<f32>(
+    0.842479062253094, 0.0423282422610123, 0.0423756549057051,
+    0.0784335999999992, 0.878468636469772, 0.0784336,
+    0.0792237451477643, 0.0791661274605434, 0.879142973793104,
+);
+const AGX_OUTSET: mat3x3<f32> = mat3x3<f32>(
+    1.19687900512017, -0.0528968517574562, -0.0529716355144438,
+    -0.0980208811401368, 1.15190312990417, -0.0980434501171241,
+    -0.0990297440797205, -0.0989611768448433, 1.15107367264116,
+);
+
+fn agx_contrast(x: vec3<f32>) -> vec3<f32> {
+    let x2 = x * x;
+    let x4 = x2 * x2;
+    return 15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x - 0.00232;
+}
+
+fn agx(color: vec3<f32>) -> vec3<f32> {
+    let min_ev = -12.47393;
+    let max_ev = 4.026069;
+    var v = AGX_INSET * color;
+    v = clamp(log2(max(v, vec3<f32>(1e-10))), vec3<f32>(min_ev), vec3<f32>(max_ev));
+    v = (v - min_ev) / (max_ev - min_ev);
+    v = AGX_OUTSET * agx_contrast(v);
+    return pow(clamp(v, vec3<f32>(0.0), vec3<f32>(1.0)), vec3<f32>(2.2));
+}
+
 @fragment
 fn fs_main(in: FragmentInput) -> @location(0) vec4<f32> {
     var hdr_color = textureSample(scene_texture, tex_sampler, in.uv).rgb;
@@ -55,12 +93,21 @@ fn fs_main(in: FragmentInput) -> @location(0) vec4<f32> {
     // Add bloom
     hdr_color += bloom * params.bloom_intensity;
 
+    // Exposure: metered by the auto-exposure pass, or manual stops
+    var exposure = exp2(params.exposure_ev);
+    if params.exposure_mode == EXPOSURE_MODE_AUTO {
+        exposure = textureLoad(exposure_texture, vec2<i32>(0, 0), 0).r;
+    }
+    hdr_color *= exposure;
+
     // Tone mapping
     var mapped: vec3<f32>;
     if params.tone_mapping_mode == 0 {
         mapped = reinhard(hdr_color);
     } else if params.tone_mapping_mode == 1 {
         mapped = aces(hdr_color);
+    } else if params.tone_mapping_mode == 3 {
+        mapped = agx(hdr_color);
     } else {
         let W = 11.2;
         mapped = uncharted2_tonemap(hdr_color * 2.0) / uncharted2_tonemap(vec3<f32>(W));
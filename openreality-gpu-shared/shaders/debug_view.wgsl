@@ -0,0 +1,49 @@
+// Debug view — shows an intermediate target in place of the final image,
+// remapped to a displayable range. Texels are loaded, not sampled, so depth
+// and half-resolution targets bind the same way.
+
+struct DebugViewParams {
+    remap: u32,
+    channel: u32,
+    near: f32,
+    far: f32,
+};
+
+@group(0) @binding(0) var<uniform> params: DebugViewParams;
+@group(0) @binding(1) var source: texture_2d<f32>;
+
+const REMAP_COLOR: u32 = 0u;
+const REMAP_CHANNEL: u32 = 1u;
+const REMAP_DEPTH: u32 = 2u;
+const REMAP_HDR: u32 = 3u;
+
+struct FragmentInput {
+    @location(0) uv: vec2<f32>,
+};
+
+@fragment
+fn fs_main(in: FragmentInput) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(source, 0));
+    let texel = vec2<i32>(clamp(in.uv * size, vec2<f32>(0.0), size - 1.0));
+    let value = textureLoad(source, texel, 0);
+
+    var color: vec3<f32>;
+    switch params.remap {
+        case REMAP_CHANNEL: {
+            color = vec3<f32>(value[min(params.channel, 3u)]);
+        }
+        case REMAP_DEPTH: {
+            // [0, 1] depth to view distance, shown as a fraction of the far plane.
+            let d = value.r;
+            let linear = params.near * params.far / (params.far - d * (params.far - params.near));
+            color = vec3<f32>(clamp(linear / params.far, 0.0, 1.0));
+        }
+        case REMAP_HDR: {
+            color = value.rgb / (vec3<f32>(1.0) + value.rgb);
+        }
+        default: {
+            color = value.rgb;
+        }
+    }
+    return vec4<f32>(color, 1.0);
+}
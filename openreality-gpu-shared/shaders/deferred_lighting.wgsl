@@ -22,6 +22,29 @@ struct PointLight {
     _pad2: f32,
 };
 
+struct LocalLight {
+    position: vec4<f32>,
+    color: vec4<f32>,
+    direction: vec4<f32>,
+    intensity: f32,
+    range: f32,
+    inner_cos: f32,
+    outer_cos: f32,
+    is_spot: u32,
+    shadow_index: i32,
+    _pad1: f32,
+    _pad2: f32,
+};
+
+struct ShadowTile {
+    light_view_proj: mat4x4<f32>,
+    atlas_rect: vec4<f32>,
+    texel_angle: f32,
+    _pad1: f32,
+    _pad2: f32,
+    _pad3: f32,
+};
+
 struct DirLight {
     direction: vec4<f32>,
     color: vec4<f32>,
@@ -40,27 +63,85 @@ struct LightData {
     ibl_intensity: f32,
 };
 
+struct ClusterParams {
+    grid_size: vec3<u32>,
+    num_lights: u32,
+    screen_size: vec2<f32>,
+    z_near: f32,
+    z_far: f32,
+};
+
+struct CascadeData {
+    matrix: mat4x4<f32>,
+    split_depth: f32,
+    _pad1: f32,
+    _pad2: f32,
+    _pad3: f32,
+};
+
+struct ShadowUniforms {
+    cascades: array<CascadeData, 4>,
+    num_cascades: i32,
+    shadow_bias: f32,
+    _pad1: f32,
+    _pad2: f32,
+};
+
 // Bind group 0: per-frame + G-Buffer textures
 @group(0) @binding(0) var<uniform> frame: PerFrame;
 @group(0) @binding(1) var g_albedo_metallic: texture_2d<f32>;
 @group(0) @binding(2) var g_normal_roughness: texture_2d<f32>;
 @group(0) @binding(3) var g_emissive_ao: texture_2d<f32>;
 @group(0) @binding(4) var g_advanced_material: texture_2d<f32>;
-@group(0) @binding(5) var g_depth: texture_depth_2d;
+@group(0) @binding(5) var g_depth: texture_2d<f32>;
 @group(0) @binding(6) var ssao_texture: texture_2d<f32>;
 @group(0) @binding(7) var ssr_texture: texture_2d<f32>;
 @group(0) @binding(8) var gbuffer_sampler: sampler;
 @group(0) @binding(9) var depth_sampler: sampler;
 
-// Bind group 1: light data
+// Bind group 1: light data. Point and spot lights are read from the
+// clustered storage buffers; `lights.point_lights` is unused.
 @group(1) @binding(0) var<uniform> lights: LightData;
+@group(1) @binding(1) var<uniform> cluster: ClusterParams;
+@group(1) @binding(2) var<storage, read> cluster_lights: array<LocalLight>;
+@group(1) @binding(3) var<storage, read> cluster_ranges: array<vec2<u32>>;
+@group(1) @binding(4) var<storage, read> cluster_indices: array<u32>;
+// Image-based lighting, sampled when `lights.has_ibl` is set
+@group(1) @binding(5) var irradiance_map: texture_cube<f32>;
+@group(1) @binding(6) var prefilter_map: texture_cube<f32>;
+@group(1) @binding(7) var brdf_lut: texture_2d<f32>;
+@group(1) @binding(8) var ibl_sampler: sampler;
+
+// Bind group 2: cascaded shadow maps (cast by the first directional light)
+@group(2) @binding(0) var<uniform> shadow: ShadowUniforms;
+@group(2) @binding(1) var shadow_map_0: texture_depth_2d;
+@group(2) @binding(2) var shadow_map_1: texture_depth_2d;
+@group(2) @binding(3) var shadow_map_2: texture_depth_2d;
+@group(2) @binding(4) var shadow_map_3: texture_depth_2d;
+@group(2) @binding(5) var shadow_sampler: sampler_comparison;
+// Point and spot light shadow tiles, indexed by `LocalLight.shadow_index`
+@group(2) @binding(6) var shadow_atlas: texture_depth_2d;
+@group(2) @binding(7) var<storage, read> shadow_tiles: array<ShadowTile>;
+
+// Fraction of each cascade's range over which it fades into the next one.
+const CASCADE_BLEND: f32 = 0.1;
 
 struct FragmentInput {
     @location(0) uv: vec2<f32>,
 };
 
+// Cluster holding a fragment at screen `uv` (top-down) and view depth `depth`.
+// Matches `openreality_gpu_shared::clusters`: screen tiles by log depth slices.
+fn cluster_index(uv: vec2<f32>, depth: f32) -> u32 {
+    let grid = cluster.grid_size;
+    let tile = min(vec2<u32>(max(uv, vec2<f32>(0.0)) * vec2<f32>(grid.xy)), grid.xy - vec2<u32>(1u));
+    let slice = log(max(depth, cluster.z_near) / cluster.z_near) / log(cluster.z_far / cluster.z_near) * f32(grid.z);
+    return tile.x + tile.y * grid.x + min(u32(slice), grid.z - 1u) * grid.x * grid.y;
+}
+
 fn reconstruct_world_pos(uv: vec2<f32>, depth: f32) -> vec3<f32> {
-    let clip_pos = vec4<f32>(uv * 2.0 - 1.0, depth, 1.0);
+    // uv runs top-down, NDC y bottom-up.
+    let clip_pos = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, depth, 1.0);
     let world_pos = frame.inv_view_proj * clip_pos;
     return world_pos.xyz / world_pos.w;
 }
@@ -84,16 +165,161 @@ fn geometry_smith(N: vec3<f32>, V: vec3<f32>, L: vec3<f32>, roughness: f32) -> f
            geometry_schlick_ggx(max(dot(N, L), 0.0), roughness);
 }
 
+// Fresnel for ambient light, which arrives from every direction.
+fn fresnel_schlick_roughness(cos_theta: f32, F0: vec3<f32>, roughness: f32) -> vec3<f32> {
+    return F0 + (max(vec3<f32>(1.0 - roughness), F0) - F0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+// Split-sum image-based lighting: diffuse irradiance plus prefiltered specular.
+fn ibl_ambient(N: vec3<f32>, V: vec3<f32>, albedo: vec3<f32>, metallic: f32, roughness: f32, F0: vec3<f32>) -> vec3<f32> {
+    let NdotV = max(dot(N, V), 0.0);
+    let F = fresnel_schlick_roughness(NdotV, F0, roughness);
+    let kD = (vec3<f32>(1.0) - F) * (1.0 - metallic);
+    let irradiance = textureSampleLevel(irradiance_map, ibl_sampler, N, 0.0).rgb;
+
+    let R = reflect(-V, N);
+    let max_lod = f32(textureNumLevels(prefilter_map) - 1u);
+    let prefiltered = textureSampleLevel(prefilter_map, ibl_sampler, R, roughness * max_lod).rgb;
+    let brdf = textureSampleLevel(brdf_lut, ibl_sampler, vec2<f32>(NdotV, roughness), 0.0).rg;
+    return kD * irradiance * albedo + prefiltered * (F * brdf.x + brdf.y);
+}
+
 fn fresnel_schlick(cos_theta: f32, F0: vec3<f32>) -> vec3<f32> {
     return F0 + (1.0 - F0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
 }
 
+// ---- Point/spot light shadows ----
+
+// Cube face looking along the major axis of `d`: +X, -X, +Y, -Y, +Z, -Z.
+fn cube_face(d: vec3<f32>) -> i32 {
+    let a = abs(d);
+    if a.x >= a.y && a.x >= a.z {
+        return select(1, 0, d.x > 0.0);
+    }
+    if a.y >= a.z {
+        return select(3, 2, d.y > 0.0);
+    }
+    return select(5, 4, d.z > 0.0);
+}
+
+// 3x3 PCF lookup in the light's shadow-atlas tile; returns the lit fraction.
+fn local_light_shadow(light: LocalLight, world_pos: vec3<f32>, N: vec3<f32>) -> f32 {
+    if light.shadow_index < 0 {
+        return 1.0;
+    }
+    let to_frag = world_pos - light.position.xyz;
+    var index = light.shadow_index;
+    if light.is_spot == 0u {
+        index += cube_face(to_frag);
+    }
+    let tile = shadow_tiles[index];
+
+    // Offset by about a texel's footprint along the normal and towards the
+    // light instead of a depth bias, which the perspective depth would skew.
+    let texel_world = tile.texel_angle * length(to_frag);
+    let L = normalize(-to_frag);
+    let offset_pos = world_pos + (N * 1.5 + L) * texel_world;
+    let clip = tile.light_view_proj * vec4<f32>(offset_pos, 1.0);
+    let ndc = clip.xyz / clip.w;
+    if clip.w <= 0.0 || ndc.z > 1.0 {
+        return 1.0;
+    }
+
+    // Keep the filter footprint inside the tile.
+    let texel = 1.0 / f32(textureDimensions(shadow_atlas).x);
+    let tile_uv = vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+    let lo = tile.atlas_rect.xy + vec2<f32>(texel * 1.5);
+    let hi = tile.atlas_rect.xy + tile.atlas_rect.zw - vec2<f32>(texel * 1.5);
+    let uv = clamp(tile.atlas_rect.xy + tile_uv * tile.atlas_rect.zw, lo, hi);
+
+    var lit = 0.0;
+    for (var x = -1; x <= 1; x++) {
+        for (var y = -1; y <= 1; y++) {
+            let offset = vec2<f32>(f32(x), f32(y)) * texel;
+            lit += textureSampleCompareLevel(shadow_atlas, shadow_sampler, uv + offset, ndc.z);
+        }
+    }
+    return lit / 9.0;
+}
+
+// Spot cone falloff between the outer and inner angles (1.0 for point lights).
+fn spot_factor(light: LocalLight, L: vec3<f32>) -> f32 {
+    if light.is_spot == 0u {
+        return 1.0;
+    }
+    return smoothstep(light.outer_cos, light.inner_cos, dot(-L, normalize(light.direction.xyz)));
+}
+
+// ---- CSM Shadow ----
+
+// 5x5 PCF lookup; returns the lit fraction (1.0 = fully lit).
+fn sample_cascade(shadow_tex: texture_depth_2d, matrix: mat4x4<f32>, world_pos: vec3<f32>, bias: f32) -> f32 {
+    let clip = matrix * vec4<f32>(world_pos, 1.0);
+    let ndc = clip.xyz / clip.w;
+    let uv = vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+    if ndc.z > 1.0 || any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) {
+        return 1.0;
+    }
+
+    let texel = 1.0 / vec2<f32>(textureDimensions(shadow_tex));
+    var lit = 0.0;
+    for (var x = -2; x <= 2; x++) {
+        for (var y = -2; y <= 2; y++) {
+            let offset = vec2<f32>(f32(x), f32(y)) * texel;
+            lit += textureSampleCompareLevel(shadow_tex, shadow_sampler, uv + offset, ndc.z - bias);
+        }
+    }
+    return lit / 25.0;
+}
+
+fn sample_cascade_index(idx: i32, world_pos: vec3<f32>, bias: f32) -> f32 {
+    // Textures can't be indexed dynamically, so dispatch per cascade.
+    let scaled_bias = bias * (1.0 + f32(idx));
+    switch idx {
+        case 0: { return sample_cascade(shadow_map_0, shadow.cascades[0].matrix, world_pos, scaled_bias); }
+        case 1: { return sample_cascade(shadow_map_1, shadow.cascades[1].matrix, world_pos, scaled_bias); }
+        case 2: { return sample_cascade(shadow_map_2, shadow.cascades[2].matrix, world_pos, scaled_bias); }
+        case 3: { return sample_cascade(shadow_map_3, shadow.cascades[3].matrix, world_pos, scaled_bias); }
+        default: { return 1.0; }
+    }
+}
+
+// Lit fraction for the shadow-casting light, blending across cascade borders.
+fn compute_csm_shadow(world_pos: vec3<f32>, N: vec3<f32>, L: vec3<f32>) -> f32 {
+    let num_cascades = min(shadow.num_cascades, 4);
+    if num_cascades <= 0 {
+        return 1.0;
+    }
+
+    let view_depth = -(frame.view * vec4<f32>(world_pos, 1.0)).z;
+    var idx = 0;
+    while idx < num_cascades && view_depth > shadow.cascades[idx].split_depth {
+        idx++;
+    }
+    if idx >= num_cascades {
+        return 1.0;
+    }
+
+    let bias = shadow.shadow_bias * max(1.0 - dot(N, L), 0.1);
+    let lit = sample_cascade_index(idx, world_pos, bias);
+
+    // Fade into the next cascade near the far end of this one.
+    let range_start = select(0.0, shadow.cascades[max(idx - 1, 0)].split_depth, idx > 0);
+    let range_end = shadow.cascades[idx].split_depth;
+    let blend_start = range_end - (range_end - range_start) * CASCADE_BLEND;
+    if idx + 1 < num_cascades && view_depth > blend_start {
+        let t = (view_depth - blend_start) / (range_end - blend_start);
+        return mix(lit, sample_cascade_index(idx + 1, world_pos, bias), t);
+    }
+    return lit;
+}
+
 @fragment
 fn fs_main(in: FragmentInput) -> @location(0) vec4<f32> {
     let albedo_metallic = textureSample(g_albedo_metallic, gbuffer_sampler, in.uv);
     let normal_roughness = textureSample(g_normal_roughness, gbuffer_sampler, in.uv);
     let emissive_ao = textureSample(g_emissive_ao, gbuffer_sampler, in.uv);
-    let depth = textureSample(g_depth, depth_sampler, in.uv);
+    let depth = textureSample(g_depth, depth_sampler, in.uv).r;
 
     // Skip background pixels
     if depth >= 1.0 {
@@ -111,10 +337,13 @@ fn fs_main(in: FragmentInput) -> @location(0) vec4<f32> {
     let V = normalize(frame.camera_pos.xyz - world_pos);
     let F0 = mix(vec3<f32>(0.04), albedo, metallic);
 
-    // Ambient (modulated by SSAO)
+    // Ambient: the IBL environment if loaded, else a constant (modulated by SSAO)
     let ssao = textureSample(ssao_texture, gbuffer_sampler, in.uv).r;
-    let ambient = vec3<f32>(0.03) * albedo * ao * ssao;
-    var Lo = ambient;
+    var ambient = vec3<f32>(0.03) * albedo;
+    if lights.has_ibl != 0 {
+        ambient = ibl_ambient(N, V, albedo, metallic, roughness, F0) * lights.ibl_intensity;
+    }
+    var Lo = ambient * ao * ssao;
 
     // Directional lights
     for (var i = 0; i < lights.num_dir_lights; i++) {
@@ -128,21 +357,27 @@ fn fs_main(in: FragmentInput) -> @location(0) vec4<f32> {
 
         let specular = (D * G * F) / (4.0 * max(dot(N, V), 0.0) * NdotL + 0.0001);
         let kD = (vec3<f32>(1.0) - F) * (1.0 - metallic);
-        let radiance = lights.dir_lights[i].color.rgb * lights.dir_lights[i].intensity;
+        var radiance = lights.dir_lights[i].color.rgb * lights.dir_lights[i].intensity;
+        if i == 0 {
+            radiance *= compute_csm_shadow(world_pos, N, L);
+        }
         Lo += (kD * albedo / PI + specular) * radiance * NdotL;
     }
 
-    // Point lights
-    for (var i = 0; i < lights.num_point_lights; i++) {
-        let light_pos = lights.point_lights[i].position.xyz;
+    // Point and spot lights binned into this pixel's cluster
+    let view_depth = -(frame.view * vec4<f32>(world_pos, 1.0)).z;
+    let range = cluster_ranges[cluster_index(in.uv, view_depth)];
+    for (var j = 0u; j < range.y; j++) {
+        let light = cluster_lights[cluster_indices[range.x + j]];
+        let light_pos = light.position.xyz;
         let L = normalize(light_pos - world_pos);
         let H = normalize(V + L);
         let NdotL = max(dot(N, L), 0.0);
 
         let dist = length(light_pos - world_pos);
         var attenuation = 1.0 / (dist * dist + 0.0001);
-        let range_factor = clamp(1.0 - pow(dist / max(lights.point_lights[i].range, 0.001), 4.0), 0.0, 1.0);
-        attenuation *= range_factor * range_factor;
+        let range_factor = clamp(1.0 - pow(dist / max(light.range, 0.001), 4.0), 0.0, 1.0);
+        attenuation *= range_factor * range_factor * spot_factor(light, L);
 
         let D = distribution_ggx(N, H, roughness);
         let G = geometry_smith(N, V, L, roughness);
@@ -150,7 +385,7 @@ fn fs_main(in: FragmentInput) -> @location(0) vec4<f32> {
 
         let specular = (D * G * F) / (4.0 * max(dot(N, V), 0.0) * NdotL + 0.0001);
         let kD = (vec3<f32>(1.0) - F) * (1.0 - metallic);
-        let radiance = lights.point_lights[i].color.rgb * lights.point_lights[i].intensity * attenuation;
+        let radiance = light.color.rgb * light.intensity * attenuation * local_light_shadow(light, world_pos, N);
         Lo += (kD * albedo / PI + specular) * radiance * NdotL;
     }
 
@@ -12,7 +12,7 @@ struct DOFCoCParams {
 };
 
 @group(0) @binding(0) var<uniform> coc_params: DOFCoCParams;
-@group(0) @binding(1) var depth_texture: texture_depth_2d;
+@group(0) @binding(1) var depth_texture: texture_2d<f32>;
 @group(0) @binding(2) var depth_sampler: sampler;
 
 struct FragmentInput {
@@ -26,7 +26,7 @@ fn linearize_depth(d: f32, near: f32, far: f32) -> f32 {
 
 @fragment
 fn fs_coc(in: FragmentInput) -> @location(0) f32 {
-    let depth = textureSample(depth_texture, depth_sampler, in.uv);
+    let depth = textureSample(depth_texture, depth_sampler, in.uv).r;
     let linear_depth = linearize_depth(depth, coc_params.near_plane, coc_params.far_plane);
 
     // CoC: distance from focus plane, normalized by focus range
@@ -20,7 +20,10 @@ struct PerObject {
     normal_matrix_col0: vec4<f32>,
     normal_matrix_col1: vec4<f32>,
     normal_matrix_col2: vec4<f32>,
-    _pad: vec4<f32>,
+    entity_id: u32,
+    _pad0: u32,
+    _pad1: u32,
+    _pad2: u32,
 };
 
 struct MaterialUBO {
@@ -53,6 +56,29 @@ struct PointLight {
     _pad2: f32,
 };
 
+struct LocalLight {
+    position: vec4<f32>,
+    color: vec4<f32>,
+    direction: vec4<f32>,
+    intensity: f32,
+    range: f32,
+    inner_cos: f32,
+    outer_cos: f32,
+    is_spot: u32,
+    shadow_index: i32,
+    _pad1: f32,
+    _pad2: f32,
+};
+
+struct ShadowTile {
+    light_view_proj: mat4x4<f32>,
+    atlas_rect: vec4<f32>,
+    texel_angle: f32,
+    _pad1: f32,
+    _pad2: f32,
+    _pad3: f32,
+};
+
 struct DirLight {
     direction: vec4<f32>,
     color: vec4<f32>,
@@ -71,13 +97,24 @@ struct LightData {
     ibl_intensity: f32,
 };
 
+struct ClusterParams {
+    grid_size: vec3<u32>,
+    num_lights: u32,
+    screen_size: vec2<f32>,
+    z_near: f32,
+    z_far: f32,
+};
+
 struct CascadeData {
     matrix: mat4x4<f32>,
+    split_depth: f32,
+    _pad1: f32,
+    _pad2: f32,
+    _pad3: f32,
 };
 
 struct ShadowUniforms {
     cascades: array<CascadeData, 4>,
-    splits: vec4<f32>,
     num_cascades: i32,
     shadow_bias: f32,
     _pad1: f32,
@@ -108,6 +145,23 @@ struct ShadowUniforms {
 @group(3) @binding(4) var shadow_map_2: texture_depth_2d;
 @group(3) @binding(5) var shadow_map_3: texture_depth_2d;
 @group(3) @binding(6) var shadow_sampler: sampler_comparison;
+// Clustered point and spot lights (`lights.point_lights` is unused)
+@group(3) @binding(7) var<uniform> cluster: ClusterParams;
+@group(3) @binding(8) var<storage, read> cluster_lights: array<LocalLight>;
+// Point and spot light shadow tiles, indexed by `LocalLight.shadow_index`
+@group(3) @binding(11) var shadow_atlas: texture_depth_2d;
+@group(3) @binding(12) var<storage, read> shadow_tiles: array<ShadowTile>;
+@group(3) @binding(9) var<storage, read> cluster_ranges: array<vec2<u32>>;
+@group(3) @binding(10) var<storage, read> cluster_indices: array<u32>;
+
+// Cluster holding a fragment at screen `uv` (top-down) and view depth `depth`.
+// Matches `openreality_gpu_shared::clusters`: screen tiles by log depth slices.
+fn cluster_index(uv: vec2<f32>, depth: f32) -> u32 {
+    let grid = cluster.grid_size;
+    let tile = min(vec2<u32>(max(uv, vec2<f32>(0.0)) * vec2<f32>(grid.xy)), grid.xy - vec2<u32>(1u));
+    let slice = log(max(depth, cluster.z_near) / cluster.z_near) / log(cluster.z_far / cluster.z_near) * f32(grid.z);
+    return tile.x + tile.y * grid.x + min(u32(slice), grid.z - 1u) * grid.x * grid.y;
+}
 
 struct VertexInput {
     @location(0) position: vec3<f32>,
@@ -120,6 +174,13 @@ struct VertexOutput {
     @location(0) world_pos: vec3<f32>,
     @location(1) normal: vec3<f32>,
     @location(2) uv: vec2<f32>,
+    @location(3) @interpolate(flat) entity_id: u32,
+};
+
+// The entity ID is only stored when the pipeline has an entity-ID target.
+struct ForwardOutput {
+    @location(0) color: vec4<f32>,
+    @location(1) entity_id: u32,
 };
 
 @vertex
@@ -136,6 +197,7 @@ fn vs_main(in: VertexInput) -> VertexOutput {
     );
     out.normal = normalize(normal_matrix * in.normal);
     out.uv = in.uv;
+    out.entity_id = object.entity_id;
     out.clip_position = frame.projection * frame.view * world_pos;
 
     return out;
@@ -180,6 +242,68 @@ fn compute_radiance(N: vec3<f32>, V: vec3<f32>, L: vec3<f32>, radiance: vec3<f32
     return (kD * albedo / PI + specular) * radiance * NdotL;
 }
 
+// ---- Point/spot light shadows ----
+
+// Cube face looking along the major axis of `d`: +X, -X, +Y, -Y, +Z, -Z.
+fn cube_face(d: vec3<f32>) -> i32 {
+    let a = abs(d);
+    if a.x >= a.y && a.x >= a.z {
+        return select(1, 0, d.x > 0.0);
+    }
+    if a.y >= a.z {
+        return select(3, 2, d.y > 0.0);
+    }
+    return select(5, 4, d.z > 0.0);
+}
+
+// 3x3 PCF lookup in the light's shadow-atlas tile; returns the lit fraction.
+fn local_light_shadow(light: LocalLight, world_pos: vec3<f32>, N: vec3<f32>) -> f32 {
+    if light.shadow_index < 0 {
+        return 1.0;
+    }
+    let to_frag = world_pos - light.position.xyz;
+    var index = light.shadow_index;
+    if light.is_spot == 0u {
+        index += cube_face(to_frag);
+    }
+    let tile = shadow_tiles[index];
+
+    // Offset by about a texel's footprint along the normal and towards the
+    // light instead of a depth bias, which the perspective depth would skew.
+    let texel_world = tile.texel_angle * length(to_frag);
+    let L = normalize(-to_frag);
+    let offset_pos = world_pos + (N * 1.5 + L) * texel_world;
+    let clip = tile.light_view_proj * vec4<f32>(offset_pos, 1.0);
+    let ndc = clip.xyz / clip.w;
+    if clip.w <= 0.0 || ndc.z > 1.0 {
+        return 1.0;
+    }
+
+    // Keep the filter footprint inside the tile.
+    let texel = 1.0 / f32(textureDimensions(shadow_atlas).x);
+    let tile_uv = vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+    let lo = tile.atlas_rect.xy + vec2<f32>(texel * 1.5);
+    let hi = tile.atlas_rect.xy + tile.atlas_rect.zw - vec2<f32>(texel * 1.5);
+    let uv = clamp(tile.atlas_rect.xy + tile_uv * tile.atlas_rect.zw, lo, hi);
+
+    var lit = 0.0;
+    for (var x = -1; x <= 1; x++) {
+        for (var y = -1; y <= 1; y++) {
+            let offset = vec2<f32>(f32(x), f32(y)) * texel;
+            lit += textureSampleCompareLevel(shadow_atlas, shadow_sampler, uv + offset, ndc.z);
+        }
+    }
+    return lit / 9.0;
+}
+
+// Spot cone falloff between the outer and inner angles (1.0 for point lights).
+fn spot_factor(light: LocalLight, L: vec3<f32>) -> f32 {
+    if light.is_spot == 0u {
+        return 1.0;
+    }
+    return smoothstep(light.outer_cos, light.inner_cos, dot(-L, normalize(light.direction.xyz)));
+}
+
 // ---- CSM Shadow ----
 
 fn compute_shadow_for_cascade(world_pos: vec3<f32>, N: vec3<f32>, L: vec3<f32>,
@@ -187,7 +311,7 @@ fn compute_shadow_for_cascade(world_pos: vec3<f32>, N: vec3<f32>, L: vec3<f32>,
                                shadow_tex: texture_depth_2d) -> f32 {
     let frag_pos_light = cascade_matrix * vec4<f32>(world_pos, 1.0);
     let proj_coords = frag_pos_light.xyz / frag_pos_light.w;
-    let uv = proj_coords.xy * 0.5 + 0.5;
+    let uv = vec2<f32>(proj_coords.x * 0.5 + 0.5, 0.5 - proj_coords.y * 0.5);
     let depth = proj_coords.z;
 
     // Out of bounds check
@@ -218,17 +342,17 @@ fn compute_csm_shadow(world_pos: vec3<f32>, N: vec3<f32>, L: vec3<f32>) -> f32 {
         return 0.0;
     }
 
-    let view_depth = length(frame.camera_pos.xyz - world_pos);
+    let view_depth = -(frame.view * vec4<f32>(world_pos, 1.0)).z;
 
     // Select cascade based on view depth
     var cascade_idx = shadow.num_cascades - 1;
-    if view_depth < shadow.splits.x {
+    if view_depth < shadow.cascades[0].split_depth {
         cascade_idx = 0;
-    } else if view_depth < shadow.splits.y {
+    } else if view_depth < shadow.cascades[1].split_depth {
         cascade_idx = 1;
-    } else if view_depth < shadow.splits.z {
+    } else if view_depth < shadow.cascades[2].split_depth {
         cascade_idx = 2;
-    } else if view_depth < shadow.splits.w {
+    } else if view_depth < shadow.cascades[3].split_depth {
         cascade_idx = 3;
     }
 
@@ -265,7 +389,7 @@ fn get_normal_from_map(world_pos: vec3<f32>, normal: vec3<f32>, uv: vec2<f32>) -
 }
 
 @fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+fn fs_main(in: VertexOutput) -> ForwardOutput {
     // Albedo
     var albedo = material.albedo.rgb;
     var opacity = material.albedo.a;
@@ -302,18 +426,22 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
 
     var Lo = vec3<f32>(0.0);
 
-    // Point lights
-    for (var i = 0; i < lights.num_point_lights; i++) {
-        let light_pos = lights.point_lights[i].position.xyz;
+    // Point and spot lights binned into this fragment's cluster
+    let view_depth = -(frame.view * vec4<f32>(in.world_pos, 1.0)).z;
+    let screen_uv = in.clip_position.xy / cluster.screen_size;
+    let range = cluster_ranges[cluster_index(screen_uv, view_depth)];
+    for (var j = 0u; j < range.y; j++) {
+        let light = cluster_lights[cluster_indices[range.x + j]];
+        let light_pos = light.position.xyz;
         let L_vec = light_pos - in.world_pos;
         let dist = length(L_vec);
         let L = normalize(L_vec);
 
         var attenuation = 1.0 / (dist * dist + 0.0001);
-        let range_factor = clamp(1.0 - pow(dist / max(lights.point_lights[i].range, 0.001), 4.0), 0.0, 1.0);
-        attenuation *= range_factor * range_factor;
+        let range_factor = clamp(1.0 - pow(dist / max(light.range, 0.001), 4.0), 0.0, 1.0);
+        attenuation *= range_factor * range_factor * spot_factor(light, L);
 
-        let radiance = lights.point_lights[i].color.rgb * lights.point_lights[i].intensity * attenuation;
+        let radiance = light.color.rgb * light.intensity * attenuation * local_light_shadow(light, in.world_pos, N);
         Lo += compute_radiance(N, V, L, radiance, albedo, metallic, roughness, F0);
     }
 
@@ -342,5 +470,5 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     }
 
     // Output linear HDR — post-processing handles tone mapping and gamma
-    return vec4<f32>(color, opacity);
+    return ForwardOutput(vec4<f32>(color, opacity), in.entity_id);
 }
@@ -47,6 +47,7 @@ struct FragmentInput {
     @location(1) normal: vec3<f32>,
     @location(2) uv: vec2<f32>,
     @location(3) camera_pos: vec3<f32>,
+    @location(4) @interpolate(flat) entity_id: u32,
 };
 
 struct GBufferOutput {
@@ -54,6 +55,8 @@ struct GBufferOutput {
     @location(1) normal_roughness: vec4<f32>,
     @location(2) emissive_ao: vec4<f32>,
     @location(3) advanced_material: vec4<f32>,
+    // Only stored when the pipeline has an entity-ID target (picking).
+    @location(4) entity_id: u32,
 };
 
 // Bayer 4x4 dithering matrix for LOD crossfade
@@ -150,6 +153,7 @@ fn fs_main(in: FragmentInput) -> GBufferOutput {
         sss_val = material.subsurface;
     }
     out.advanced_material = vec4<f32>(clearcoat_val, sss_val, 0.0, 1.0);
+    out.entity_id = in.entity_id;
 
     return out;
 }
@@ -1,5 +1,5 @@
 // G-Buffer geometry pass — instanced vertex shader.
-// Reads per-instance model + normal matrices from vertex attributes (step_mode=Instance).
+// Reads per-instance model + normal matrices and entity IDs from vertex attributes (step_mode=Instance).
 
 struct PerFrame {
     view: mat4x4<f32>,
@@ -28,6 +28,7 @@ struct VertexInput {
     @location(7) normal_col0: vec4<f32>,
     @location(8) normal_col1: vec4<f32>,
     @location(9) normal_col2: vec4<f32>,
+    @location(10) entity_id: u32,
 };
 
 struct VertexOutput {
@@ -36,6 +37,7 @@ struct VertexOutput {
     @location(1) normal: vec3<f32>,
     @location(2) uv: vec2<f32>,
     @location(3) camera_pos: vec3<f32>,
+    @location(4) @interpolate(flat) entity_id: u32,
 };
 
 @vertex
@@ -60,6 +62,7 @@ fn vs_main(in: VertexInput) -> VertexOutput {
     out.normal = normalize(normal_matrix * in.normal);
     out.uv = in.uv;
     out.camera_pos = frame.camera_pos.xyz;
+    out.entity_id = in.entity_id;
     out.clip_position = frame.projection * frame.view * world_pos;
 
     return out;
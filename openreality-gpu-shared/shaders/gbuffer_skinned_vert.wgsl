@@ -16,7 +16,10 @@ struct PerObject {
     normal_matrix_col0: vec4<f32>,
     normal_matrix_col1: vec4<f32>,
     normal_matrix_col2: vec4<f32>,
-    _pad: vec4<f32>,
+    entity_id: u32,
+    _pad0: u32,
+    _pad1: u32,
+    _pad2: u32,
 };
 
 struct BoneData {
@@ -45,6 +48,7 @@ struct VertexOutput {
     @location(1) normal: vec3<f32>,
     @location(2) uv: vec2<f32>,
     @location(3) camera_pos: vec3<f32>,
+    @location(4) @interpolate(flat) entity_id: u32,
 };
 
 @vertex
@@ -87,6 +91,7 @@ fn vs_main(in: VertexInput) -> VertexOutput {
     out.normal = normalize(normal_matrix * skinned_normal.xyz);
     out.uv = in.uv;
     out.camera_pos = frame.camera_pos.xyz;
+    out.entity_id = object.entity_id;
     out.clip_position = frame.projection * frame.view * world_pos;
 
     return out;
@@ -0,0 +1,189 @@
+// IBL precomputation passes. Each draw renders one face (at one mip level) of
+// a cubemap as a fullscreen triangle; see `ibl.rs` for the pass order.
+//   fs_equirect_to_cube: RGBE equirectangular image -> environment cubemap
+//   fs_downsample:       environment mip N-1 -> mip N
+//   fs_irradiance:       cosine-weighted diffuse convolution
+//   fs_prefilter:        GGX specular convolution for one roughness level
+//   fs_brdf_lut:         split-sum BRDF scale/bias (a 2D target, no face)
+
+const PI: f32 = 3.14159265359;
+
+struct BakeParams {
+    face: u32,
+    roughness: f32,
+    // Face width of the source cubemap's mip 0, for sample LOD selection
+    source_size: f32,
+    sample_count: u32,
+};
+
+@group(0) @binding(0) var<uniform> params: BakeParams;
+@group(0) @binding(1) var equirect: texture_2d<f32>;
+@group(0) @binding(2) var source_cube: texture_cube<f32>;
+@group(0) @binding(3) var source_sampler: sampler;
+
+struct FragmentInput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+// World direction through `uv` (top-left origin) of cube face `face`.
+fn cube_direction(face: u32, uv: vec2<f32>) -> vec3<f32> {
+    let u = uv.x * 2.0 - 1.0;
+    let v = uv.y * 2.0 - 1.0;
+    var dir: vec3<f32>;
+    switch face {
+        case 0u: { dir = vec3<f32>(1.0, -v, -u); }
+        case 1u: { dir = vec3<f32>(-1.0, -v, u); }
+        case 2u: { dir = vec3<f32>(u, 1.0, v); }
+        case 3u: { dir = vec3<f32>(u, -1.0, -v); }
+        case 4u: { dir = vec3<f32>(u, -v, 1.0); }
+        default: { dir = vec3<f32>(-u, -v, -1.0); }
+    }
+    return normalize(dir);
+}
+
+// Must match `hdr::rgbe_decode`.
+fn decode_rgbe(texel: vec4<f32>) -> vec3<f32> {
+    let e = round(texel.a * 255.0);
+    if e == 0.0 {
+        return vec3<f32>(0.0);
+    }
+    return (round(texel.rgb * 255.0) + 0.5) * exp2(e - 136.0);
+}
+
+fn load_equirect(texel: vec2<i32>, size: vec2<i32>) -> vec3<f32> {
+    // Wrap around the horizon, clamp at the poles.
+    let x = (texel.x % size.x + size.x) % size.x;
+    let y = clamp(texel.y, 0, size.y - 1);
+    return decode_rgbe(textureLoad(equirect, vec2<i32>(x, y), 0));
+}
+
+@fragment
+fn fs_equirect_to_cube(in: FragmentInput) -> @location(0) vec4<f32> {
+    let dir = cube_direction(params.face, in.uv);
+    // u = 0.5 looks down +X, v = 0 is straight up.
+    let uv = vec2<f32>(atan2(dir.z, dir.x) / (2.0 * PI) + 0.5, acos(clamp(dir.y, -1.0, 1.0)) / PI);
+
+    // RGBE can't be filtered by the sampler, so interpolate after decoding.
+    let size = vec2<i32>(textureDimensions(equirect));
+    let p = uv * vec2<f32>(size) - 0.5;
+    let base = vec2<i32>(floor(p));
+    let f = fract(p);
+    let top = mix(load_equirect(base, size), load_equirect(base + vec2<i32>(1, 0), size), f.x);
+    let bottom = mix(load_equirect(base + vec2<i32>(0, 1), size), load_equirect(base + vec2<i32>(1, 1), size), f.x);
+    return vec4<f32>(mix(top, bottom, f.y), 1.0);
+}
+
+@fragment
+fn fs_downsample(in: FragmentInput) -> @location(0) vec4<f32> {
+    // `source_cube` is a view of the previous mip only; bilinear filtering at
+    // this texel's centre averages the 2x2 texels beneath it.
+    let dir = cube_direction(params.face, in.uv);
+    return vec4<f32>(textureSampleLevel(source_cube, source_sampler, dir, 0.0).rgb, 1.0);
+}
+
+fn hammersley(i: u32, n: u32) -> vec2<f32> {
+    return vec2<f32>(f32(i) / f32(n), f32(reverseBits(i)) * 2.3283064365386963e-10);
+}
+
+// Orthonormal basis around `n`, returning `v` (tangent space, z along n) in world space.
+fn to_world(v: vec3<f32>, n: vec3<f32>) -> vec3<f32> {
+    let up = select(vec3<f32>(1.0, 0.0, 0.0), vec3<f32>(0.0, 0.0, 1.0), abs(n.z) < 0.999);
+    let tangent = normalize(cross(up, n));
+    let bitangent = cross(n, tangent);
+    return tangent * v.x + bitangent * v.y + n * v.z;
+}
+
+// Source mip whose texels cover the solid angle of one sample with density `pdf`.
+fn sample_lod(pdf: f32) -> f32 {
+    let sample_solid_angle = 1.0 / (f32(params.sample_count) * pdf + 0.0001);
+    let texel_solid_angle = 4.0 * PI / (6.0 * params.source_size * params.source_size);
+    return max(0.5 * log2(sample_solid_angle / texel_solid_angle) + 1.0, 0.0);
+}
+
+@fragment
+fn fs_irradiance(in: FragmentInput) -> @location(0) vec4<f32> {
+    let n = cube_direction(params.face, in.uv);
+    var sum = vec3<f32>(0.0);
+    for (var i = 0u; i < params.sample_count; i++) {
+        // Cosine-weighted hemisphere sample: the average is irradiance / PI.
+        let xi = hammersley(i, params.sample_count);
+        let phi = 2.0 * PI * xi.x;
+        let cos_theta = sqrt(1.0 - xi.y);
+        let sin_theta = sqrt(xi.y);
+        let l = to_world(vec3<f32>(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta), n);
+        let lod = sample_lod(cos_theta / PI);
+        sum += textureSampleLevel(source_cube, source_sampler, l, lod).rgb;
+    }
+    return vec4<f32>(sum / f32(params.sample_count), 1.0);
+}
+
+fn importance_sample_ggx(xi: vec2<f32>, roughness: f32) -> vec3<f32> {
+    let a = roughness * roughness;
+    let phi = 2.0 * PI * xi.x;
+    let cos_theta = sqrt((1.0 - xi.y) / (1.0 + (a * a - 1.0) * xi.y));
+    let sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+    return vec3<f32>(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta);
+}
+
+fn distribution_ggx(n_dot_h: f32, roughness: f32) -> f32 {
+    let a2 = roughness * roughness * roughness * roughness;
+    let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    return a2 / (PI * d * d);
+}
+
+@fragment
+fn fs_prefilter(in: FragmentInput) -> @location(0) vec4<f32> {
+    // Split-sum assumption: view and reflection along the normal.
+    let n = cube_direction(params.face, in.uv);
+    if params.roughness <= 0.0 {
+        return vec4<f32>(textureSampleLevel(source_cube, source_sampler, n, 0.0).rgb, 1.0);
+    }
+
+    var sum = vec3<f32>(0.0);
+    var weight = 0.0;
+    for (var i = 0u; i < params.sample_count; i++) {
+        let h_tangent = importance_sample_ggx(hammersley(i, params.sample_count), params.roughness);
+        let h = to_world(h_tangent, n);
+        let l = normalize(2.0 * dot(n, h) * h - n);
+        let n_dot_l = dot(n, l);
+        if n_dot_l > 0.0 {
+            // pdf of l is D * NdotH / (4 * VdotH), and V = N.
+            let lod = sample_lod(distribution_ggx(h_tangent.z, params.roughness) / 4.0);
+            sum += textureSampleLevel(source_cube, source_sampler, l, lod).rgb * n_dot_l;
+            weight += n_dot_l;
+        }
+    }
+    return vec4<f32>(sum / max(weight, 0.0001), 1.0);
+}
+
+fn geometry_schlick_ggx_ibl(n_dot_x: f32, roughness: f32) -> f32 {
+    let k = roughness * roughness / 2.0;
+    return n_dot_x / (n_dot_x * (1.0 - k) + k);
+}
+
+@fragment
+fn fs_brdf_lut(in: FragmentInput) -> @location(0) vec2<f32> {
+    // x: NdotV, y: roughness (top row is smooth).
+    let n_dot_v = max(in.uv.x, 0.001);
+    let roughness = in.uv.y;
+    let v = vec3<f32>(sqrt(1.0 - n_dot_v * n_dot_v), 0.0, n_dot_v);
+
+    var scale = 0.0;
+    var bias = 0.0;
+    for (var i = 0u; i < params.sample_count; i++) {
+        let h = importance_sample_ggx(hammersley(i, params.sample_count), roughness);
+        let l = normalize(2.0 * dot(v, h) * h - v);
+        let n_dot_l = max(l.z, 0.0);
+        if n_dot_l > 0.0 {
+            let n_dot_h = max(h.z, 0.0);
+            let v_dot_h = max(dot(v, h), 0.0);
+            let g = geometry_schlick_ggx_ibl(n_dot_v, roughness) * geometry_schlick_ggx_ibl(n_dot_l, roughness);
+            let g_vis = g * v_dot_h / max(n_dot_h * n_dot_v, 0.001);
+            let fc = pow(1.0 - v_dot_h, 5.0);
+            scale += (1.0 - fc) * g_vis;
+            bias += fc * g_vis;
+        }
+    }
+    return vec2<f32>(scale, bias) / f32(params.sample_count);
+}
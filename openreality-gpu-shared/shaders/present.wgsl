@@ -1,6 +1,7 @@
 // Final present pass — blit post-processed result to swapchain.
-// Bloom composite already handles tone mapping + gamma correction,
-// so this pass just copies the final result.
+// Bloom composite already handles tone mapping + gamma correction, so this
+// pass only applies optional 3D LUT colour grading to the display-referred
+// result, cross-fading between two LUTs.
 
 struct PresentParams {
     bloom_threshold: f32,
@@ -14,12 +15,38 @@ struct PresentParams {
     color_brightness: f32,
     color_contrast: f32,
     color_saturation: f32,
+    exposure_mode: i32,
+    exposure_ev: f32,
+    adaptation_speed: f32,
+    delta_time: f32,
+    _pad1: f32,
+};
+
+struct ColorGrading {
+    domain_min_a: vec4<f32>, // w = LUT size
+    domain_max_a: vec4<f32>,
+    domain_min_b: vec4<f32>,
+    domain_max_b: vec4<f32>,
+    blend: f32,
+    intensity: f32,
+    _pad0: f32,
     _pad1: f32,
 };
 
 @group(0) @binding(0) var<uniform> params: PresentParams;
 @group(0) @binding(1) var scene_texture: texture_2d<f32>;
 @group(0) @binding(2) var tex_sampler: sampler;
+@group(0) @binding(3) var<uniform> grading: ColorGrading;
+@group(0) @binding(4) var lut_a: texture_3d<f32>;
+@group(0) @binding(5) var lut_b: texture_3d<f32>;
+
+// Texture coordinate of `color` in a LUT, landing on texel centres at the
+// domain edges so the lattice is interpolated rather than the border.
+fn lut_coord(color: vec3<f32>, domain_min: vec4<f32>, domain_max: vec4<f32>) -> vec3<f32> {
+    let size = domain_min.w;
+    let t = clamp((color - domain_min.xyz) / (domain_max.xyz - domain_min.xyz), vec3<f32>(0.0), vec3<f32>(1.0));
+    return (t * (size - 1.0) + 0.5) / size;
+}
 
 struct FragmentInput {
     @location(0) uv: vec2<f32>,
@@ -28,5 +55,8 @@ struct FragmentInput {
 @fragment
 fn fs_main(in: FragmentInput) -> @location(0) vec4<f32> {
     let color = textureSample(scene_texture, tex_sampler, in.uv).rgb;
-    return vec4<f32>(color, 1.0);
+    let a = textureSampleLevel(lut_a, tex_sampler, lut_coord(color, grading.domain_min_a, grading.domain_max_a), 0.0).rgb;
+    let b = textureSampleLevel(lut_b, tex_sampler, lut_coord(color, grading.domain_min_b, grading.domain_max_b), 0.0).rgb;
+    let graded = mix(a, b, grading.blend);
+    return vec4<f32>(mix(color, graded, grading.intensity), 1.0);
 }
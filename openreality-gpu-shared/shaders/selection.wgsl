@@ -0,0 +1,68 @@
+// Selection highlight — outlines and tints entities by ID over the presented
+// frame (alpha blended). Reads the entity-ID target written during the
+// G-Buffer and forward passes.
+
+struct SelectionParams {
+    outline_color: vec4<f32>,
+    tint_color: vec4<f32>,
+    outline_width: f32,
+    count: u32,
+    _pad1: f32,
+    _pad2: f32,
+};
+
+@group(0) @binding(0) var<uniform> params: SelectionParams;
+@group(0) @binding(1) var<storage, read> selected: array<u32>;
+@group(0) @binding(2) var entity_ids: texture_2d<u32>;
+
+// Matches MAX_SELECTION_OUTLINE_WIDTH.
+const MAX_RADIUS: i32 = 8;
+
+fn is_selected(id: u32) -> bool {
+    if id == 0u {
+        return false;
+    }
+    for (var i = 0u; i < params.count; i++) {
+        if selected[i] == id {
+            return true;
+        }
+    }
+    return false;
+}
+
+fn selected_at(p: vec2<i32>, size: vec2<i32>) -> bool {
+    let q = clamp(p, vec2<i32>(0), size - 1);
+    return is_selected(textureLoad(entity_ids, q, 0).r);
+}
+
+@fragment
+fn fs_main(@builtin(position) frag_coord: vec4<f32>) -> @location(0) vec4<f32> {
+    let size = vec2<i32>(textureDimensions(entity_ids, 0));
+    let p = vec2<i32>(frag_coord.xy);
+
+    if selected_at(p, size) {
+        if params.tint_color.a <= 0.0 {
+            discard;
+        }
+        return params.tint_color;
+    }
+
+    // Distance to the nearest selected pixel within the outline width; the
+    // outer edge fades over one pixel.
+    let width = params.outline_width;
+    let radius = min(i32(ceil(width)), MAX_RADIUS);
+    var nearest = width + 1.0;
+    for (var dy = -radius; dy <= radius; dy++) {
+        for (var dx = -radius; dx <= radius; dx++) {
+            let d = length(vec2<f32>(f32(dx), f32(dy)));
+            if d < nearest && selected_at(p + vec2<i32>(dx, dy), size) {
+                nearest = d;
+            }
+        }
+    }
+    let coverage = clamp(width + 1.0 - nearest, 0.0, 1.0);
+    if coverage <= 0.0 {
+        discard;
+    }
+    return vec4<f32>(params.outline_color.rgb, params.outline_color.a * coverage);
+}
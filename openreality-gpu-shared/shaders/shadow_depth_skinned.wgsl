@@ -0,0 +1,49 @@
+// Shadow depth pass for skinned meshes — same as shadow_depth.wgsl with
+// bone skinning applied before the model matrix.
+
+struct PerFrame {
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    inv_view_proj: mat4x4<f32>,
+    camera_pos: vec4<f32>,
+    time: f32,
+    _pad1: f32,
+    _pad2: f32,
+    _pad3: f32,
+};
+
+struct PerObject {
+    model: mat4x4<f32>,
+    normal_matrix_col0: vec4<f32>,
+    normal_matrix_col1: vec4<f32>,
+    normal_matrix_col2: vec4<f32>,
+    _pad: vec4<f32>,
+};
+
+struct BoneData {
+    has_skinning: i32,
+    _pad1: i32,
+    _pad2: i32,
+    _pad3: i32,
+    bone_matrices: array<mat4x4<f32>, 128>,
+};
+
+@group(0) @binding(0) var<uniform> frame: PerFrame;
+@group(1) @binding(0) var<uniform> object: PerObject;
+@group(2) @binding(0) var<uniform> bones: BoneData;
+
+@vertex
+fn vs_main(
+    @location(0) position: vec3<f32>,
+    @location(1) bone_weights: vec4<f32>,
+    @location(2) bone_indices: vec4<u32>,
+) -> @builtin(position) vec4<f32> {
+    var skinned_pos = vec4<f32>(position, 1.0);
+    if bones.has_skinning != 0 {
+        skinned_pos = bones.bone_matrices[bone_indices.x] * skinned_pos * bone_weights.x
+                    + bones.bone_matrices[bone_indices.y] * skinned_pos * bone_weights.y
+                    + bones.bone_matrices[bone_indices.z] * skinned_pos * bone_weights.z
+                    + bones.bone_matrices[bone_indices.w] * skinned_pos * bone_weights.w;
+    }
+    return frame.projection * frame.view * object.model * skinned_pos;
+}
@@ -0,0 +1,199 @@
+// Sky background: the IBL environment cubemap or single-scattering
+// atmosphere (Rayleigh + Mie), drawn at the far plane behind the lit scene.
+//   vs_background / fs_background: the background pass (depth-tested against
+//                                  the G-Buffer, so only empty pixels pass)
+//   fs_sky_cube:                   one cube face of the atmosphere, for IBL bakes
+
+const PI: f32 = 3.14159265359;
+
+struct PerFrame {
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    inv_view_proj: mat4x4<f32>,
+    camera_pos: vec4<f32>,
+    time: f32,
+    _pad1: f32,
+    _pad2: f32,
+    _pad3: f32,
+};
+
+struct SkyUniforms {
+    sun_direction: vec4<f32>,
+    sun_intensity: f32,
+    turbidity: f32,
+    mode: i32,
+    face: u32,
+    environment_intensity: f32,
+    sun_disk: i32,
+    _pad1: f32,
+    _pad2: f32,
+};
+
+@group(0) @binding(0) var<uniform> frame: PerFrame;
+@group(0) @binding(1) var<uniform> sky: SkyUniforms;
+@group(0) @binding(2) var environment_map: texture_cube<f32>;
+@group(0) @binding(3) var environment_sampler: sampler;
+
+const MODE_ENVIRONMENT: i32 = 0;
+
+// Atmosphere model, in kilometres. The viewer stands just above the ground;
+// only the view direction matters.
+const EARTH_RADIUS: f32 = 6360.0;
+const ATMOSPHERE_RADIUS: f32 = 6420.0;
+const VIEW_ALTITUDE: f32 = 0.001;
+const RAYLEIGH_SCATTERING: vec3<f32> = vec3<f32>(5.8e-3, 13.5e-3, 33.1e-3);
+const RAYLEIGH_HEIGHT: f32 = 8.0;
+const MIE_SCATTERING: f32 = 21e-3;
+const MIE_EXTINCTION_RATIO: f32 = 1.11;
+const MIE_HEIGHT: f32 = 1.2;
+const MIE_G: f32 = 0.76;
+const GROUND_ALBEDO: vec3<f32> = vec3<f32>(0.1);
+const VIEW_SAMPLES: u32 = 16u;
+const LIGHT_SAMPLES: u32 = 8u;
+// cos of the sun's drawn angular radius (about 0.85 degrees, exaggerated so
+// the disk survives low resolutions) and its radiance relative to `sun_intensity`.
+const SUN_DISK_COS: f32 = 0.99989;
+const SUN_DISK_RADIANCE: f32 = 100.0;
+
+struct FragmentInput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_background(@builtin(vertex_index) vertex_index: u32) -> FragmentInput {
+    var out: FragmentInput;
+    let x = f32(i32(vertex_index & 1u) * 4 - 1);
+    let y = f32(i32(vertex_index >> 1u) * 4 - 1);
+    // On the far plane: passes the LessEqual test only where nothing was drawn.
+    out.position = vec4<f32>(x, y, 1.0, 1.0);
+    out.uv = vec2<f32>((x + 1.0) * 0.5, (1.0 - y) * 0.5);
+    return out;
+}
+
+// Distances along `dir` from `origin` to the two intersections with a sphere
+// of `radius` at the planet centre (x > y means the sphere was missed). The
+// constant term is factored so it stays exact near the surface.
+fn ray_sphere(origin: vec3<f32>, dir: vec3<f32>, radius: f32) -> vec2<f32> {
+    let r = length(origin);
+    let b = dot(origin, dir);
+    let c = (r - radius) * (r + radius);
+    let d = b * b - c;
+    if d < 0.0 {
+        return vec2<f32>(1.0, -1.0);
+    }
+    let s = sqrt(d);
+    return vec2<f32>(-b - s, -b + s);
+}
+
+// Rayleigh and Mie optical depth from `p` to the top of the atmosphere toward
+// the sun; z is 1 when the planet blocks the sun.
+fn sun_optical_depth(p: vec3<f32>, sun: vec3<f32>) -> vec3<f32> {
+    let ground = ray_sphere(p, sun, EARTH_RADIUS);
+    if ground.x <= ground.y && ground.x > 0.0 {
+        return vec3<f32>(0.0, 0.0, 1.0);
+    }
+    let segment = ray_sphere(p, sun, ATMOSPHERE_RADIUS).y / f32(LIGHT_SAMPLES);
+    var depth = vec2<f32>(0.0);
+    for (var i = 0u; i < LIGHT_SAMPLES; i++) {
+        let h = length(p + sun * (f32(i) + 0.5) * segment) - EARTH_RADIUS;
+        depth += vec2<f32>(exp(-h / RAYLEIGH_HEIGHT), exp(-h / MIE_HEIGHT)) * segment;
+    }
+    return vec3<f32>(depth, 0.0);
+}
+
+fn extinction(depth: vec2<f32>) -> vec3<f32> {
+    let mie = MIE_SCATTERING * sky.turbidity * MIE_EXTINCTION_RATIO;
+    return exp(-(RAYLEIGH_SCATTERING * depth.x + vec3<f32>(mie * depth.y)));
+}
+
+// Radiance arriving at the viewer from `dir`, excluding the sun disk.
+fn atmosphere(dir: vec3<f32>, sun: vec3<f32>) -> vec3<f32> {
+    let origin = vec3<f32>(0.0, EARTH_RADIUS + VIEW_ALTITUDE, 0.0);
+    let ground = ray_sphere(origin, dir, EARTH_RADIUS);
+    let hits_ground = ground.x <= ground.y && ground.x > 0.0;
+    let ray_length = select(ray_sphere(origin, dir, ATMOSPHERE_RADIUS).y, ground.x, hits_ground);
+    let segment = ray_length / f32(VIEW_SAMPLES);
+
+    var view_depth = vec2<f32>(0.0);
+    var rayleigh = vec3<f32>(0.0);
+    var mie = vec3<f32>(0.0);
+    for (var i = 0u; i < VIEW_SAMPLES; i++) {
+        let p = origin + dir * (f32(i) + 0.5) * segment;
+        let h = length(p) - EARTH_RADIUS;
+        let density = vec2<f32>(exp(-h / RAYLEIGH_HEIGHT), exp(-h / MIE_HEIGHT)) * segment;
+        view_depth += density;
+        let to_sun = sun_optical_depth(p, sun);
+        if to_sun.z == 0.0 {
+            let attenuation = extinction(view_depth + to_sun.xy);
+            rayleigh += attenuation * density.x;
+            mie += attenuation * density.y;
+        }
+    }
+
+    let mu = dot(dir, sun);
+    let g2 = MIE_G * MIE_G;
+    let phase_rayleigh = 3.0 / (16.0 * PI) * (1.0 + mu * mu);
+    let phase_mie = 3.0 / (8.0 * PI) * ((1.0 - g2) * (1.0 + mu * mu))
+        / ((2.0 + g2) * pow(1.0 + g2 - 2.0 * MIE_G * mu, 1.5));
+    var radiance = rayleigh * RAYLEIGH_SCATTERING * phase_rayleigh
+        + mie * MIE_SCATTERING * sky.turbidity * phase_mie;
+
+    // Lambertian ground, lit by the attenuated sun.
+    if hits_ground {
+        let p = origin + dir * ground.x;
+        let to_sun = sun_optical_depth(p, sun);
+        if to_sun.z == 0.0 {
+            let irradiance = extinction(to_sun.xy) * max(dot(normalize(p), sun), 0.0);
+            radiance += GROUND_ALBEDO / PI * irradiance * extinction(view_depth);
+        }
+    }
+    return radiance * sky.sun_intensity;
+}
+
+fn sky_radiance(dir: vec3<f32>) -> vec3<f32> {
+    let sun = normalize(sky.sun_direction.xyz);
+    var radiance = atmosphere(dir, sun);
+    if sky.sun_disk != 0 && dot(dir, sun) > SUN_DISK_COS {
+        let origin = vec3<f32>(0.0, EARTH_RADIUS + VIEW_ALTITUDE, 0.0);
+        let to_sun = sun_optical_depth(origin, sun);
+        if to_sun.z == 0.0 {
+            radiance += extinction(to_sun.xy) * sky.sun_intensity * SUN_DISK_RADIANCE;
+        }
+    }
+    return radiance;
+}
+
+@fragment
+fn fs_background(in: FragmentInput) -> @location(0) vec4<f32> {
+    let ndc = vec2<f32>(in.uv.x * 2.0 - 1.0, 1.0 - in.uv.y * 2.0);
+    let far = frame.inv_view_proj * vec4<f32>(ndc, 1.0, 1.0);
+    let dir = normalize(far.xyz / far.w - frame.camera_pos.xyz);
+    if sky.mode == MODE_ENVIRONMENT {
+        let radiance = textureSampleLevel(environment_map, environment_sampler, dir, 0.0).rgb;
+        return vec4<f32>(radiance * sky.environment_intensity, 1.0);
+    }
+    return vec4<f32>(sky_radiance(dir), 1.0);
+}
+
+// World direction through `uv` of cube face `face`; must match `cube_direction`
+// in ibl_bake.wgsl.
+fn cube_direction(face: u32, uv: vec2<f32>) -> vec3<f32> {
+    let u = uv.x * 2.0 - 1.0;
+    let v = uv.y * 2.0 - 1.0;
+    var dir: vec3<f32>;
+    switch face {
+        case 0u: { dir = vec3<f32>(1.0, -v, -u); }
+        case 1u: { dir = vec3<f32>(-1.0, -v, u); }
+        case 2u: { dir = vec3<f32>(u, 1.0, v); }
+        case 3u: { dir = vec3<f32>(u, -1.0, -v); }
+        case 4u: { dir = vec3<f32>(u, -v, 1.0); }
+        default: { dir = vec3<f32>(-u, -v, -1.0); }
+    }
+    return normalize(dir);
+}
+
+@fragment
+fn fs_sky_cube(in: FragmentInput) -> @location(0) vec4<f32> {
+    return vec4<f32>(sky_radiance(cube_direction(sky.face, in.uv)), 1.0);
+}
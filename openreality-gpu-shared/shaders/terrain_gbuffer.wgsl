@@ -52,6 +52,7 @@ struct GBufferOutput {
     @location(1) normal_roughness: vec4<f32>,
     @location(2) emissive_ao: vec4<f32>,
     @location(3) advanced_material: vec4<f32>,
+    @location(4) entity_id: u32,
 };
 
 @vertex
@@ -107,5 +108,6 @@ fn fs_main(in: VertexOutput) -> GBufferOutput {
     out.normal_roughness = vec4<f32>(normalize(in.normal) * 0.5 + 0.5, 0.85);  // Roughness = 0.85
     out.emissive_ao = vec4<f32>(0.0, 0.0, 0.0, 1.0);  // No emissive, full AO
     out.advanced_material = vec4<f32>(0.0, 0.0, 0.0, 1.0);  // No clearcoat/SSS
+    out.entity_id = 0u;  // Terrain is not pickable
     return out;
 }
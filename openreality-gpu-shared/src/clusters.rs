@@ -0,0 +1,273 @@
+//! Clustered (froxel) point and spot light culling.
+//!
+//! The view frustum is split into screen tiles and log-spaced depth slices,
+//! and each light is listed in every cluster its range sphere touches.
+//! Shaders find a fragment's cluster from its screen position and view depth
+//! and only loop over that cluster's lights, so the number of lights is no
+//! longer bounded by a uniform array.
+
+use glam::{Mat4, Vec3, Vec4};
+
+use crate::uniforms::{ClusterUniforms, LocalLightData};
+
+/// Screen tiles across, tiles down and depth slices.
+pub const CLUSTER_GRID: [u32; 3] = [16, 9, 24];
+
+/// Total number of clusters in `CLUSTER_GRID`.
+pub const CLUSTER_COUNT: usize = (CLUSTER_GRID[0] * CLUSTER_GRID[1] * CLUSTER_GRID[2]) as usize;
+
+/// Local lights assigned to clusters for one frame, ready for upload.
+pub struct LightClusters {
+    pub params: ClusterUniforms,
+    /// `[offset into indices, light count]` per cluster; x varies fastest,
+    /// then y (top row first), then depth slice.
+    pub ranges: Vec<[u32; 2]>,
+    /// Indices into the frame's local light array.
+    pub indices: Vec<u32>,
+}
+
+/// View depth where depth slice `slice` begins; `slice == slices` gives `z_far`.
+pub fn slice_depth(slice: u32, slices: u32, z_near: f32, z_far: f32) -> f32 {
+    z_near * (z_far / z_near).powf(slice as f32 / slices as f32)
+}
+
+/// Depth slice holding view depth `depth`, clamped to the grid. Must match
+/// `cluster_index` in the shaders.
+pub fn depth_slice(depth: f32, slices: u32, z_near: f32, z_far: f32) -> u32 {
+    if depth <= z_near {
+        return 0;
+    }
+    let slice = ((depth / z_near).ln() / (z_far / z_near).ln() * slices as f32).floor();
+    (slice as u32).min(slices - 1)
+}
+
+/// Flat cluster index of tile (`x`, `y`) in depth slice `z`.
+pub fn cluster_index(x: u32, y: u32, z: u32) -> usize {
+    let [gx, gy, _] = CLUSTER_GRID;
+    (x + y * gx + z * gx * gy) as usize
+}
+
+/// View-space bounding boxes of every cluster for a perspective projection.
+fn cluster_aabbs(projection: &Mat4, z_near: f32, z_far: f32) -> Vec<(Vec3, Vec3)> {
+    let [gx, gy, gz] = CLUSTER_GRID;
+    let inv_proj = projection.inverse();
+
+    // View-space rays through the tile corners, scaled to unit depth. Tile
+    // rows run top-down, like fragment coordinates.
+    let rays: Vec<Vec3> = (0..=gy)
+        .flat_map(|y| (0..=gx).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let ndc_x = -1.0 + 2.0 * x as f32 / gx as f32;
+            let ndc_y = 1.0 - 2.0 * y as f32 / gy as f32;
+            let p = inv_proj * Vec4::new(ndc_x, ndc_y, 0.5, 1.0);
+            let p = p.truncate() / p.w;
+            p / -p.z
+        })
+        .collect();
+    let ray = |x: u32, y: u32| rays[(x + y * (gx + 1)) as usize];
+
+    let mut aabbs = Vec::with_capacity(CLUSTER_COUNT);
+    for z in 0..gz {
+        // Fragments nearer than `z_near` fall into slice 0, so it starts at the eye.
+        let start = if z == 0 { 0.0 } else { slice_depth(z, gz, z_near, z_far) };
+        let depths = [start, slice_depth(z + 1, gz, z_near, z_far)];
+        for y in 0..gy {
+            for x in 0..gx {
+                let corners = [ray(x, y), ray(x + 1, y), ray(x, y + 1), ray(x + 1, y + 1)];
+                let mut min = Vec3::splat(f32::MAX);
+                let mut max = Vec3::splat(f32::MIN);
+                for depth in depths {
+                    for corner in corners {
+                        min = min.min(corner * depth);
+                        max = max.max(corner * depth);
+                    }
+                }
+                aabbs.push((min, max));
+            }
+        }
+    }
+    aabbs
+}
+
+/// Assign `lights` to the clusters of a camera with the given view and
+/// perspective projection. Light ranges are the culling radius, matching the
+/// shaders' range falloff (which reaches zero at `range`); spot lights are
+/// culled by the same sphere as point lights.
+pub fn build_light_clusters(
+    view: &Mat4,
+    projection: &Mat4,
+    z_near: f32,
+    z_far: f32,
+    screen_size: [f32; 2],
+    lights: &[LocalLightData],
+) -> LightClusters {
+    let [gx, gy, gz] = CLUSTER_GRID;
+    let aabbs = cluster_aabbs(projection, z_near, z_far);
+
+    let mut lists: Vec<Vec<u32>> = vec![Vec::new(); CLUSTER_COUNT];
+    for (i, light) in lights.iter().enumerate() {
+        let center = view.transform_point3(Vec3::new(light.position[0], light.position[1], light.position[2]));
+        let radius = light.range.max(0.001);
+        let depth = -center.z;
+        if depth + radius < 0.0 || depth - radius > z_far {
+            continue;
+        }
+
+        let first = depth_slice(depth - radius, gz, z_near, z_far);
+        let last = depth_slice(depth + radius, gz, z_near, z_far);
+        for z in first..=last {
+            for y in 0..gy {
+                for x in 0..gx {
+                    let index = cluster_index(x, y, z);
+                    let (min, max) = aabbs[index];
+                    if center.clamp(min, max).distance_squared(center) <= radius * radius {
+                        lists[index].push(i as u32);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut ranges = Vec::with_capacity(CLUSTER_COUNT);
+    let mut indices = Vec::new();
+    for list in &lists {
+        ranges.push([indices.len() as u32, list.len() as u32]);
+        indices.extend_from_slice(list);
+    }
+
+    LightClusters {
+        params: ClusterUniforms {
+            grid_size: CLUSTER_GRID,
+            num_lights: lights.len() as u32,
+            screen_size,
+            z_near,
+            z_far,
+        },
+        ranges,
+        indices,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uniforms::PointLightData;
+
+    const NEAR: f32 = 0.1;
+    const FAR: f32 = 200.0;
+
+    fn light(position: Vec3, range: f32) -> LocalLightData {
+        LocalLightData::from(&PointLightData {
+            position: [position.x, position.y, position.z, 1.0],
+            color: [1.0; 4],
+            intensity: 1.0,
+            range,
+            _pad1: 0.0,
+            _pad2: 0.0,
+        })
+    }
+
+    fn camera() -> (Mat4, Mat4) {
+        let view = Mat4::look_at_rh(Vec3::new(0.0, 2.0, 10.0), Vec3::ZERO, Vec3::Y);
+        let proj = Mat4::perspective_rh(60f32.to_radians(), 16.0 / 9.0, NEAR, FAR);
+        (view, proj)
+    }
+
+    fn lights_in(clusters: &LightClusters, index: usize) -> &[u32] {
+        let [offset, count] = clusters.ranges[index];
+        &clusters.indices[offset as usize..(offset + count) as usize]
+    }
+
+    #[test]
+    fn test_depth_slices_round_trip() {
+        let slices = CLUSTER_GRID[2];
+        assert_eq!(slice_depth(0, slices, NEAR, FAR), NEAR);
+        assert!((slice_depth(slices, slices, NEAR, FAR) - FAR).abs() < 1e-3);
+        for z in 0..slices {
+            let mid = 0.5 * (slice_depth(z, slices, NEAR, FAR) + slice_depth(z + 1, slices, NEAR, FAR));
+            assert_eq!(depth_slice(mid, slices, NEAR, FAR), z);
+        }
+        assert_eq!(depth_slice(0.0, slices, NEAR, FAR), 0);
+        assert_eq!(depth_slice(FAR * 10.0, slices, NEAR, FAR), slices - 1);
+    }
+
+    #[test]
+    fn test_ranges_cover_indices() {
+        let (view, proj) = camera();
+        let lights: Vec<_> = (0..50).map(|i| light(Vec3::new(i as f32 - 25.0, 0.5, -(i as f32)), 3.0)).collect();
+        let clusters = build_light_clusters(&view, &proj, NEAR, FAR, [1600.0, 900.0], &lights);
+
+        assert_eq!(clusters.ranges.len(), CLUSTER_COUNT);
+        let mut expected_offset = 0;
+        for &[offset, count] in &clusters.ranges {
+            assert_eq!(offset, expected_offset);
+            expected_offset += count;
+        }
+        assert_eq!(expected_offset as usize, clusters.indices.len());
+        assert_eq!(clusters.params.num_lights, 50);
+    }
+
+    #[test]
+    fn test_light_behind_camera_is_culled() {
+        let (view, proj) = camera();
+        let lights = [light(Vec3::new(0.0, 2.0, 20.0), 5.0)];
+        let clusters = build_light_clusters(&view, &proj, NEAR, FAR, [1600.0, 900.0], &lights);
+        assert!(clusters.indices.is_empty());
+    }
+
+    #[test]
+    fn test_small_light_stays_local() {
+        let (view, proj) = camera();
+        let lights = [light(Vec3::ZERO, 0.5)];
+        let clusters = build_light_clusters(&view, &proj, NEAR, FAR, [1600.0, 900.0], &lights);
+
+        // The origin is at the centre of the screen, so only central tiles see it.
+        let depth = Vec3::new(0.0, 2.0, 10.0).length();
+        let z = depth_slice(depth, CLUSTER_GRID[2], NEAR, FAR);
+        assert_eq!(lights_in(&clusters, cluster_index(7, 4, z)), &[0]);
+        assert!(lights_in(&clusters, cluster_index(0, 0, z)).is_empty());
+        assert!(lights_in(&clusters, cluster_index(7, 4, 0)).is_empty());
+        assert!(clusters.indices.len() < 40);
+    }
+
+    #[test]
+    fn test_every_lit_point_finds_its_lights() {
+        let (view, proj) = camera();
+        let inv_view = view.inverse();
+        let inv_proj = proj.inverse();
+
+        // Deterministic pseudo-random lights and sample points.
+        let mut seed = 0x2545_f491_u32;
+        let mut next = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed as f32 / u32::MAX as f32
+        };
+        let lights: Vec<_> = (0..300)
+            .map(|_| light(Vec3::new(next() * 40.0 - 20.0, next() * 6.0 - 1.0, next() * 40.0 - 30.0), 0.5 + next() * 4.0))
+            .collect();
+        let clusters = build_light_clusters(&view, &proj, NEAR, FAR, [1600.0, 900.0], &lights);
+
+        for _ in 0..2000 {
+            // A point on screen (uv top-down, as in the shaders) at some view depth.
+            let (u, v) = (next(), next());
+            let depth = NEAR + next() * 50.0;
+            let p = inv_proj * Vec4::new(u * 2.0 - 1.0, 1.0 - v * 2.0, 0.5, 1.0);
+            let ray = p.truncate() / p.w;
+            let world = inv_view.transform_point3(ray / -ray.z * depth);
+
+            let x = ((u * CLUSTER_GRID[0] as f32) as u32).min(CLUSTER_GRID[0] - 1);
+            let y = ((v * CLUSTER_GRID[1] as f32) as u32).min(CLUSTER_GRID[1] - 1);
+            let z = depth_slice(depth, CLUSTER_GRID[2], NEAR, FAR);
+            let listed = lights_in(&clusters, cluster_index(x, y, z));
+
+            for (i, l) in lights.iter().enumerate() {
+                let center = Vec3::new(l.position[0], l.position[1], l.position[2]);
+                if center.distance(world) < l.range {
+                    assert!(listed.contains(&(i as u32)), "light {i} missing from cluster ({x}, {y}, {z})");
+                }
+            }
+        }
+    }
+}
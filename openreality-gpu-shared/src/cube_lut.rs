@@ -0,0 +1,138 @@
+//! Adobe/Resolve `.cube` 3D lookup tables for colour grading.
+//!
+//! Only 3D tables are supported. Entries are stored in file order, red
+//! varying fastest, so entry `(r, g, b)` lives at `r + g * size + b * size²`.
+
+/// A 3D colour lookup table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CubeLut {
+    pub size: u32,
+    pub domain_min: [f32; 3],
+    pub domain_max: [f32; 3],
+    pub table: Vec<[f32; 3]>,
+}
+
+/// Largest `LUT_3D_SIZE` the format allows.
+pub const MAX_CUBE_LUT_SIZE: u32 = 256;
+
+impl CubeLut {
+    /// A table that maps every colour to itself.
+    pub fn identity(size: u32) -> Self {
+        let step = 1.0 / (size.max(2) - 1) as f32;
+        let mut table = Vec::with_capacity((size * size * size) as usize);
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    table.push([r as f32 * step, g as f32 * step, b as f32 * step]);
+                }
+            }
+        }
+        Self { size, domain_min: [0.0; 3], domain_max: [1.0; 3], table }
+    }
+
+    /// Entry for lattice point `(r, g, b)`.
+    pub fn entry(&self, r: u32, g: u32, b: u32) -> [f32; 3] {
+        self.table[(r + (g + b * self.size) * self.size) as usize]
+    }
+
+    /// Parse the text of a `.cube` file.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut size = None;
+        let mut domain_min = [0.0; 3];
+        let mut domain_max = [1.0; 3];
+        let mut table = Vec::new();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let number = number + 1;
+            match words[0] {
+                "TITLE" => {}
+                "LUT_1D_SIZE" => return Err("1D .cube LUTs are not supported".into()),
+                "LUT_3D_SIZE" => {
+                    let [n] = floats(&words[1..], number)?;
+                    if n.fract() != 0.0 || !(2.0..=MAX_CUBE_LUT_SIZE as f32).contains(&n) {
+                        return Err(format!("line {number}: LUT_3D_SIZE {n} out of range"));
+                    }
+                    size = Some(n as u32);
+                }
+                "DOMAIN_MIN" => domain_min = floats(&words[1..], number)?,
+                "DOMAIN_MAX" => domain_max = floats(&words[1..], number)?,
+                "LUT_3D_INPUT_RANGE" => {
+                    let [lo, hi] = floats(&words[1..], number)?;
+                    domain_min = [lo; 3];
+                    domain_max = [hi; 3];
+                }
+                // Unknown keywords (vendor extensions) are ignored.
+                k if k.starts_with(|c: char| c.is_ascii_alphabetic()) => {}
+                _ => table.push(floats(&words, number)?),
+            }
+        }
+
+        let size = size.ok_or("missing LUT_3D_SIZE")?;
+        let expected = (size * size * size) as usize;
+        if table.len() != expected {
+            return Err(format!("expected {expected} LUT entries, found {}", table.len()));
+        }
+        if (0..3).any(|c| domain_max[c] <= domain_min[c]) {
+            return Err("LUT domain is empty".into());
+        }
+        Ok(Self { size, domain_min, domain_max, table })
+    }
+}
+
+/// Parse exactly `N` numbers from one line's words.
+fn floats<const N: usize>(words: &[&str], number: usize) -> Result<[f32; N], String> {
+    let values = words
+        .iter()
+        .map(|w| w.parse::<f32>().map_err(|e| format!("line {number}: {e}")))
+        .collect::<Result<Vec<_>, _>>()?;
+    <[f32; N]>::try_from(values).map_err(|_| format!("line {number}: expected {N} values"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cube_file() {
+        let text = "# warm look\nTITLE \"Warm\"\nLUT_3D_SIZE 2\nDOMAIN_MIN 0 0 0\nDOMAIN_MAX 1 1 1\n\n\
+                    0 0 0\n1 0 0\n0 1 0\n1 1 0\n0 0 1\n1 0 1\n0 1 1\n1 1 0.5\n";
+        let lut = CubeLut::parse(text).unwrap();
+        assert_eq!(lut.size, 2);
+        assert_eq!(lut.table.len(), 8);
+        assert_eq!(lut.entry(1, 0, 0), [1.0, 0.0, 0.0]);
+        assert_eq!(lut.entry(0, 1, 1), [0.0, 1.0, 1.0]);
+        assert_eq!(lut.entry(1, 1, 1), [1.0, 1.0, 0.5]);
+    }
+
+    #[test]
+    fn test_parse_input_range() {
+        let mut text = String::from("LUT_3D_SIZE 2\nLUT_3D_INPUT_RANGE 0.0 4.0\n");
+        for _ in 0..8 {
+            text.push_str("0.5 0.5 0.5\n");
+        }
+        let lut = CubeLut::parse(&text).unwrap();
+        assert_eq!(lut.domain_min, [0.0; 3]);
+        assert_eq!(lut.domain_max, [4.0; 3]);
+    }
+
+    #[test]
+    fn test_identity_matches_lattice() {
+        let lut = CubeLut::identity(17);
+        assert_eq!(lut.table.len(), 17 * 17 * 17);
+        assert_eq!(lut.entry(16, 8, 0), [1.0, 0.5, 0.0]);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_input() {
+        assert!(CubeLut::parse("0 0 0\n").unwrap_err().contains("LUT_3D_SIZE"));
+        assert!(CubeLut::parse("LUT_1D_SIZE 4\n").is_err());
+        assert!(CubeLut::parse("LUT_3D_SIZE 2\n0 0 0\n").unwrap_err().contains("expected 8"));
+        assert!(CubeLut::parse("LUT_3D_SIZE 2\n0 0\n").is_err());
+        assert!(CubeLut::parse("LUT_3D_SIZE 1\n").is_err());
+    }
+}
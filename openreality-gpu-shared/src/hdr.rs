@@ -0,0 +1,202 @@
+//! Radiance HDR (`.hdr`, RGBE) images for image-based lighting.
+//!
+//! Texels stay in the file's shared-exponent RGBE encoding: four bytes per
+//! texel, uploaded as-is and decoded in the IBL shaders, which keeps large
+//! equirectangular environments compact without needing float16 conversion.
+
+/// An HDR image in RGBE texels, top row first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HdrImage {
+    pub width: u32,
+    pub height: u32,
+    pub rgbe: Vec<[u8; 4]>,
+}
+
+/// Encode a linear RGB colour as RGBE (negative components clamp to zero).
+pub fn rgbe_encode(rgb: [f32; 3]) -> [u8; 4] {
+    let max = rgb[0].max(rgb[1]).max(rgb[2]);
+    if max < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+    // max = m * 2^exp with m in [0.5, 1)
+    let exp = max.log2().floor() as i32 + 1;
+    let scale = 256.0 / 2f32.powi(exp);
+    let byte = |c: f32| (c.max(0.0) * scale).min(255.0) as u8;
+    [byte(rgb[0]), byte(rgb[1]), byte(rgb[2]), (exp + 128).clamp(0, 255) as u8]
+}
+
+/// Decode an RGBE texel to linear RGB. Must match `decode_rgbe` in `ibl_bake.wgsl`.
+pub fn rgbe_decode(rgbe: [u8; 4]) -> [f32; 3] {
+    if rgbe[3] == 0 {
+        return [0.0; 3];
+    }
+    let f = 2f32.powi(rgbe[3] as i32 - 136);
+    [(rgbe[0] as f32 + 0.5) * f, (rgbe[1] as f32 + 0.5) * f, (rgbe[2] as f32 + 0.5) * f]
+}
+
+impl HdrImage {
+    /// Build an image from linear RGB texels, top row first.
+    pub fn from_linear(width: u32, height: u32, rgb: &[[f32; 3]]) -> Result<Self, String> {
+        if rgb.len() != (width * height) as usize {
+            return Err(format!("expected {} texels for {width}x{height}, got {}", width * height, rgb.len()));
+        }
+        Ok(Self { width, height, rgbe: rgb.iter().map(|&c| rgbe_encode(c)).collect() })
+    }
+
+    /// Linear RGB of the texel at (`x`, `y`).
+    pub fn texel(&self, x: u32, y: u32) -> [f32; 3] {
+        rgbe_decode(self.rgbe[(y * self.width + x) as usize])
+    }
+
+    /// Parse a Radiance HDR file with flat or run-length encoded scanlines.
+    /// Only the standard `-Y height +X width` orientation is supported.
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        let mut pos = 0;
+        let mut next_line = || -> Result<&[u8], String> {
+            let start = pos;
+            let len = bytes[start..].iter().position(|&b| b == b'\n').ok_or("truncated HDR header")?;
+            pos = start + len + 1;
+            Ok(&bytes[start..start + len])
+        };
+
+        let magic = next_line()?;
+        if !magic.starts_with(b"#?") {
+            return Err("not a Radiance HDR file".into());
+        }
+        loop {
+            let line = next_line()?;
+            if line.is_empty() {
+                break;
+            }
+            if let Some(format) = line.strip_prefix(b"FORMAT=") {
+                if format != b"32-bit_rle_rgbe" {
+                    return Err(format!("unsupported HDR format {}", String::from_utf8_lossy(format)));
+                }
+            }
+        }
+        let resolution = String::from_utf8_lossy(next_line()?).into_owned();
+        let (height, width) = match resolution.split_whitespace().collect::<Vec<_>>()[..] {
+            ["-Y", h, "+X", w] => (
+                h.parse::<u32>().map_err(|e| format!("bad HDR height: {e}"))?,
+                w.parse::<u32>().map_err(|e| format!("bad HDR width: {e}"))?,
+            ),
+            _ => return Err(format!("unsupported HDR orientation \"{resolution}\"")),
+        };
+
+        let mut data = &bytes[pos..];
+        let mut rgbe = Vec::with_capacity((width * height) as usize);
+        for _ in 0..height {
+            data = read_scanline(data, width as usize, &mut rgbe)?;
+        }
+        Ok(Self { width, height, rgbe })
+    }
+}
+
+/// Append one scanline of `width` texels to `out`, returning the remaining data.
+fn read_scanline<'a>(data: &'a [u8], width: usize, out: &mut Vec<[u8; 4]>) -> Result<&'a [u8], String> {
+    let truncated = || "truncated HDR pixel data".to_string();
+    let is_rle = (8..0x8000).contains(&width)
+        && data.len() >= 4
+        && data[0] == 2
+        && data[1] == 2
+        && ((data[2] as usize) << 8 | data[3] as usize) == width;
+    if !is_rle {
+        let bytes = data.get(..width * 4).ok_or_else(truncated)?;
+        out.extend(bytes.chunks_exact(4).map(|c| [c[0], c[1], c[2], c[3]]));
+        return Ok(&data[width * 4..]);
+    }
+
+    // Each channel is run-length encoded separately.
+    let start = out.len();
+    out.resize(start + width, [0; 4]);
+    let mut data = &data[4..];
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let (&count, rest) = data.split_first().ok_or_else(truncated)?;
+            if count > 128 {
+                let run = count as usize - 128;
+                let &value = rest.first().ok_or_else(truncated)?;
+                if x + run > width {
+                    return Err("HDR run overflows scanline".into());
+                }
+                out[start + x..start + x + run].iter_mut().for_each(|t| t[channel] = value);
+                x += run;
+                data = &rest[1..];
+            } else {
+                let run = count as usize;
+                if run == 0 || x + run > width {
+                    return Err("bad HDR scanline run".into());
+                }
+                let values = rest.get(..run).ok_or_else(truncated)?;
+                for (t, &value) in out[start + x..start + x + run].iter_mut().zip(values) {
+                    t[channel] = value;
+                }
+                x += run;
+                data = &rest[run..];
+            }
+        }
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(width: u32, height: u32) -> Vec<u8> {
+        format!("#?RADIANCE\nFORMAT=32-bit_rle_rgbe\nEXPOSURE=1.0\n\n-Y {height} +X {width}\n").into_bytes()
+    }
+
+    #[test]
+    fn test_rgbe_round_trip() {
+        for rgb in [[1.0, 0.5, 0.25], [1000.0, 20.0, 0.0], [0.001, 0.002, 0.003], [0.0, 0.0, 0.0]] {
+            let back = rgbe_decode(rgbe_encode(rgb));
+            let max = rgb[0].max(rgb[1]).max(rgb[2]);
+            for c in 0..3 {
+                assert!((back[c] - rgb[c]).abs() <= max / 128.0 + 1e-30, "{rgb:?} -> {back:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_flat_scanlines() {
+        let mut file = header(2, 2);
+        for texel in [[128, 64, 32, 129], [0, 0, 0, 0], [255, 255, 255, 140], [10, 20, 30, 128]] {
+            file.extend(texel);
+        }
+        let image = HdrImage::parse(&file).unwrap();
+        assert_eq!((image.width, image.height), (2, 2));
+        assert_eq!(image.rgbe[2], [255, 255, 255, 140]);
+        assert_eq!(image.texel(1, 0), [0.0; 3]);
+    }
+
+    #[test]
+    fn test_parse_rle_scanline() {
+        let width = 10u32;
+        let mut file = header(width, 1);
+        file.extend([2, 2, 0, width as u8]);
+        // R: a run of 10; G: 10 literals; B: run of 4 then 6 literals; E: run of 10.
+        file.extend([128 + 10, 7]);
+        file.push(10);
+        file.extend(0..10u8);
+        file.extend([128 + 4, 9, 6, 1, 2, 3, 4, 5, 6]);
+        file.extend([128 + 10, 130]);
+
+        let image = HdrImage::parse(&file).unwrap();
+        assert_eq!(image.rgbe.len(), 10);
+        assert_eq!(image.rgbe[0], [7, 0, 9, 130]);
+        assert_eq!(image.rgbe[5], [7, 5, 2, 130]);
+        assert_eq!(image.rgbe[9], [7, 9, 6, 130]);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_input() {
+        assert!(HdrImage::parse(b"P6\n1 1\n255\n").is_err());
+        let mut file = header(4, 4);
+        file.extend([0u8; 12]);
+        assert!(HdrImage::parse(&file).unwrap_err().contains("truncated"));
+        let flipped = b"#?RADIANCE\n\n+Y 1 +X 1\n\0\0\0\0";
+        assert!(HdrImage::parse(flipped).is_err());
+    }
+}
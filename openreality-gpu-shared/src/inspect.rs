@@ -0,0 +1,698 @@
+//! Human-readable and JSON dumps of a parsed ORSB scene, for debugging
+//! exported files without a hex editor.
+//!
+//! Both dumps cover the entity tree with component masks, mesh statistics,
+//! material values, animation clip summaries, script sources and, when the
+//! scene came from `parse_orsb_with_layout`, the byte size of each section.
+//! Bundles are dumped scene by scene, each resolved against the shared pool.
+
+use std::fmt::Write as _;
+
+use crate::scene_bundle::ParsedBundle;
+use crate::scene_format::*;
+
+/// Names of the component flags, in bit order.
+const COMPONENT_NAMES: [(u64, &str); 15] = [
+    (ComponentMask::TRANSFORM, "transform"),
+    (ComponentMask::MESH, "mesh"),
+    (ComponentMask::MATERIAL, "material"),
+    (ComponentMask::CAMERA, "camera"),
+    (ComponentMask::POINT_LIGHT, "point_light"),
+    (ComponentMask::DIR_LIGHT, "dir_light"),
+    (ComponentMask::COLLIDER, "collider"),
+    (ComponentMask::RIGIDBODY, "rigidbody"),
+    (ComponentMask::ANIMATION, "animation"),
+    (ComponentMask::SKELETON, "skeleton"),
+    (ComponentMask::PARTICLE, "particle"),
+    (ComponentMask::AUDIO_SOURCE, "audio_source"),
+    (ComponentMask::AUDIO_LISTENER, "audio_listener"),
+    (ComponentMask::IBL, "ibl"),
+    (ComponentMask::TERRAIN, "terrain"),
+];
+
+const MATERIAL_TEXTURE_SLOTS: [&str; 7] = ["albedo", "normal", "metallic_roughness", "ao", "emissive", "height", "clearcoat"];
+
+/// Component names set in a mask. Unknown bits are reported as `bitN`.
+pub fn component_names(mask: ComponentMask) -> Vec<String> {
+    let mut names: Vec<String> = COMPONENT_NAMES.iter()
+        .filter(|(flag, _)| mask.has(*flag))
+        .map(|(_, name)| name.to_string())
+        .collect();
+    let known = COMPONENT_NAMES.iter().fold(0u64, |acc, (flag, _)| acc | flag);
+    let unknown = mask.0 & !known;
+    names.extend((0..64).filter(|bit| unknown & (1u64 << bit) != 0).map(|bit| format!("bit{}", bit)));
+    names
+}
+
+fn callback_name(callback_type: u8) -> &'static str {
+    match callback_type {
+        0 => "on_start",
+        1 => "on_update",
+        2 => "on_destroy",
+        _ => "unknown",
+    }
+}
+
+fn compression_name(compression: u32) -> &'static str {
+    match compression {
+        0 => "raw",
+        1 => "png",
+        2 => "basis",
+        _ => "unknown",
+    }
+}
+
+fn encoding_names(encoding: MeshEncoding) -> Vec<&'static str> {
+    let mut names = Vec::new();
+    if encoding.has(MeshEncoding::INDICES_U16) { names.push("indices_u16"); }
+    if encoding.has(MeshEncoding::NORMALS_OCT16) { names.push("normals_oct16"); }
+    if encoding.has(MeshEncoding::UVS_UNORM16) { names.push("uvs_unorm16"); }
+    names
+}
+
+fn mesh_bounds(mesh: &MeshParsed) -> Option<([f32; 3], [f32; 3])> {
+    crate::math::positions_aabb(&mesh.positions)
+}
+
+/// Root entities first, each followed by its subtree, as (index, depth).
+/// Entities whose parent index is out of range are treated as roots.
+fn entity_tree_order(scene: &ParsedScene) -> Vec<(usize, usize)> {
+    let n = scene.entity_ids.len();
+    let mut children = vec![Vec::new(); n];
+    let mut roots = Vec::new();
+    for (i, parent) in scene.parent_indices.iter().enumerate() {
+        match parent {
+            Some(p) if *p < n && *p != i => children[*p].push(i),
+            _ => roots.push(i),
+        }
+    }
+
+    let mut order = Vec::with_capacity(n);
+    let mut visited = vec![false; n];
+    let mut stack: Vec<(usize, usize)> = roots.into_iter().rev().map(|i| (i, 0)).collect();
+    while let Some((i, depth)) = stack.pop() {
+        if std::mem::replace(&mut visited[i], true) {
+            continue;
+        }
+        order.push((i, depth));
+        stack.extend(children[i].iter().rev().map(|&c| (c, depth + 1)));
+    }
+    // Entities caught in a parent cycle are unreachable from any root.
+    order.extend((0..n).filter(|&i| !visited[i]).map(|i| (i, 0)));
+    order
+}
+
+fn fmt_vec(v: &[f32]) -> String {
+    let parts: Vec<String> = v.iter().map(|x| format!("{}", x)).collect();
+    format!("({})", parts.join(", "))
+}
+
+fn fmt_vec64(v: &[f64]) -> String {
+    let parts: Vec<String> = v.iter().map(|x| format!("{}", x)).collect();
+    format!("({})", parts.join(", "))
+}
+
+fn game_ref_value(r: &GameRefParsed) -> (&'static str, String) {
+    match r.value_type {
+        1 => ("bool", r.default_bool.unwrap_or_default().to_string()),
+        2 => ("i64", r.default_i64.unwrap_or_default().to_string()),
+        3 => ("string", format!("{:?}", r.default_string.as_deref().unwrap_or_default())),
+        _ => ("f64", r.default_f64.unwrap_or_default().to_string()),
+    }
+}
+
+// ── Text dump ──
+
+/// Pretty-print a scene as an indented, human-readable report. `sections`
+/// may be empty (e.g. for a scene resolved out of a bundle).
+pub fn dump_text(scene: &ParsedScene, sections: &[SectionSpan]) -> String {
+    let mut out = String::new();
+    let h = &scene.header;
+    let _ = writeln!(out, "ORSB v{} (flags 0x{:x})", h.version, h.flags);
+    let _ = writeln!(
+        out,
+        "  {} entities, {} meshes, {} materials, {} textures",
+        scene.entity_ids.len(), scene.meshes.len(), scene.materials.len(), scene.textures.len()
+    );
+
+    if !sections.is_empty() {
+        let _ = writeln!(out, "\nSections:");
+        for s in sections {
+            let _ = writeln!(out, "  {:<16} offset {:>10}  size {:>10} B", s.section.name(), s.offset, s.size);
+        }
+    }
+
+    if !scene.entity_ids.is_empty() {
+        let _ = writeln!(out, "\nEntities:");
+        for (i, depth) in entity_tree_order(scene) {
+            let indent = "  ".repeat(depth + 1);
+            let mut line = format!("{}[{}] id={} {}", indent, i, scene.entity_ids[i], component_names(scene.component_masks[i]).join("|"));
+            if let Some(m) = scene.mesh_indices[i] {
+                let _ = write!(line, " mesh={}", m);
+            }
+            if let Some(m) = scene.material_indices[i] {
+                let _ = write!(line, " material={}", m);
+            }
+            if let Some(t) = scene.transforms.get(i) {
+                let _ = write!(line, " pos={}", fmt_vec64(&t.position));
+            }
+            let _ = writeln!(out, "{}", line);
+        }
+    }
+
+    if !scene.meshes.is_empty() {
+        let _ = writeln!(out, "\nMeshes:");
+        for (i, m) in scene.meshes.iter().enumerate() {
+            let mut line = format!("  [{}] {} vertices, {} triangles", i, m.positions.len() / 3, m.indices.len() / 3);
+            let enc = encoding_names(m.encoding);
+            if !enc.is_empty() {
+                let _ = write!(line, ", {}", enc.join("+"));
+            }
+            if m.bone_weights.is_some() {
+                line.push_str(", skinned");
+            }
+            if let Some((min, max)) = mesh_bounds(m) {
+                let _ = write!(line, ", bounds {} .. {}", fmt_vec(&min), fmt_vec(&max));
+            }
+            let _ = writeln!(out, "{}", line);
+        }
+    }
+
+    if !scene.materials.is_empty() {
+        let _ = writeln!(out, "\nMaterials:");
+        for (i, m) in scene.materials.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "  [{}] color={} metallic={} roughness={} opacity={} alpha_cutoff={}",
+                i, fmt_vec(&m.color), m.metallic, m.roughness, m.opacity, m.alpha_cutoff
+            );
+            let _ = writeln!(
+                out,
+                "      emissive={} clearcoat={} subsurface={}",
+                fmt_vec(&m.emissive_factor), m.clearcoat, m.subsurface
+            );
+            let mut copy = *m;
+            let textures: Vec<String> = MATERIAL_TEXTURE_SLOTS.iter().zip(copy.texture_indices_mut())
+                .filter(|(_, idx)| **idx >= 0)
+                .map(|(slot, idx)| format!("{}={}", slot, idx))
+                .collect();
+            if !textures.is_empty() {
+                let _ = writeln!(out, "      textures: {}", textures.join(" "));
+            }
+        }
+    }
+
+    if !scene.textures.is_empty() {
+        let _ = writeln!(out, "\nTextures:");
+        for (i, t) in scene.textures.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "  [{}] {}x{} {}ch {} ({} B)",
+                i, t.width, t.height, t.channels, compression_name(t.compression), t.data.len()
+            );
+        }
+    }
+
+    if !scene.point_lights.is_empty() || !scene.dir_lights.is_empty() {
+        let _ = writeln!(out, "\nLights:");
+        for l in &scene.point_lights {
+            let _ = writeln!(
+                out,
+                "  point pos={} color={} intensity={} range={}",
+                fmt_vec(&l.position), fmt_vec(&l.color), l.intensity, l.range
+            );
+        }
+        for l in &scene.dir_lights {
+            let _ = writeln!(
+                out,
+                "  directional dir={} color={} intensity={}",
+                fmt_vec(&l.direction), fmt_vec(&l.color), l.intensity
+            );
+        }
+    }
+
+    if !scene.cameras.is_empty() {
+        let _ = writeln!(out, "\nCameras:");
+        for c in &scene.cameras {
+            let _ = writeln!(out, "  fov={} near={} far={} aspect={}", c.fov, c.near, c.far, c.aspect);
+        }
+    }
+
+    if !scene.colliders.is_empty() || !scene.rigidbodies.is_empty() || !scene.particles.is_empty() {
+        let _ = writeln!(
+            out,
+            "\nPhysics objects: {} collider(s), {} rigidbody(ies); {} particle system(s)",
+            scene.colliders.len(), scene.rigidbodies.len(), scene.particles.len()
+        );
+    }
+    if let Some(p) = &scene.physics_config {
+        let _ = writeln!(
+            out,
+            "\nPhysics config: gravity={} fixed_dt={} substeps={} iterations={}",
+            fmt_vec64(&p.gravity), p.fixed_dt, p.max_substeps, p.solver_iterations
+        );
+    }
+
+    if !scene.animations.is_empty() {
+        let _ = writeln!(out, "\nAnimations:");
+        for (i, a) in scene.animations.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "  [{}] {} clip(s), active={} playing={} looping={} speed={}",
+                i, a.clips.len(), a.active_clip, a.playing, a.looping, a.speed
+            );
+            for clip in &a.clips {
+                let keys: usize = clip.channels.iter().map(|ch| ch.times.len()).sum();
+                let _ = writeln!(
+                    out,
+                    "      {:?} {}s, {} channel(s), {} keyframe(s)",
+                    clip.name, clip.duration, clip.channels.len(), keys
+                );
+            }
+        }
+    }
+
+    if !scene.skeletons.is_empty() {
+        let _ = writeln!(out, "\nSkeletons:");
+        for (i, s) in scene.skeletons.iter().enumerate() {
+            let _ = writeln!(out, "  [{}] {} bone(s)", i, s.bones.len());
+        }
+    }
+
+    if !scene.terrains.is_empty() {
+        let _ = writeln!(out, "\nTerrains:");
+        for (i, t) in scene.terrains.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "  [{}] entity={} size={}x{} max_height={} heightmap={}x{} chunk={} lods={} splatmap={}x{} layers={}",
+                i, t.entity_index, t.size[0], t.size[1], t.max_height, t.heightmap_width, t.heightmap_depth,
+                t.chunk_size, t.num_lod_levels, t.splatmap_width, t.splatmap_height, t.layers.len()
+            );
+        }
+    }
+
+    if !scene.game_refs.is_empty() {
+        let _ = writeln!(out, "\nGame state:");
+        for r in &scene.game_refs {
+            let (ty, value) = game_ref_value(r);
+            let _ = writeln!(out, "  {}: {} = {}", r.name, ty, value);
+        }
+    }
+
+    if !scene.scripts.is_empty() {
+        let _ = writeln!(out, "\nScripts:");
+        for (i, s) in scene.scripts.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "  [{}] entity={} {} ({} B)",
+                i, s.entity_index, callback_name(s.callback_type), s.rhai_source.len()
+            );
+            for line in s.rhai_source.lines() {
+                let _ = writeln!(out, "      | {}", line);
+            }
+        }
+    }
+
+    out
+}
+
+// ── JSON dump ──
+
+/// Minimal JSON value tree; numbers are kept pre-formatted so f32 values
+/// print with their shortest round-trip representation.
+enum Json {
+    Null,
+    Bool(bool),
+    Num(String),
+    Str(String),
+    Arr(Vec<Json>),
+    Obj(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    fn f32(v: f32) -> Self {
+        if v.is_finite() { Json::Num(v.to_string()) } else { Json::Null }
+    }
+
+    fn f64(v: f64) -> Self {
+        if v.is_finite() { Json::Num(v.to_string()) } else { Json::Null }
+    }
+
+    fn int(v: impl Into<i64>) -> Self {
+        Json::Num(v.into().to_string())
+    }
+
+    fn usize(v: usize) -> Self {
+        Json::Num(v.to_string())
+    }
+
+    fn str(s: impl Into<String>) -> Self {
+        Json::Str(s.into())
+    }
+
+    fn f32s(v: &[f32]) -> Self {
+        Json::Arr(v.iter().map(|&x| Json::f32(x)).collect())
+    }
+
+    fn f64s(v: &[f64]) -> Self {
+        Json::Arr(v.iter().map(|&x| Json::f64(x)).collect())
+    }
+
+    fn opt_index(v: Option<usize>) -> Self {
+        v.map_or(Json::Null, Json::usize)
+    }
+
+    fn write(&self, out: &mut String, indent: usize) {
+        match self {
+            Json::Null => out.push_str("null"),
+            Json::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            Json::Num(n) => out.push_str(n),
+            Json::Str(s) => write_json_string(out, s),
+            Json::Arr(items) if items.is_empty() => out.push_str("[]"),
+            Json::Obj(fields) if fields.is_empty() => out.push_str("{}"),
+            Json::Arr(items) => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    out.push_str(if i == 0 { "\n" } else { ",\n" });
+                    push_indent(out, indent + 1);
+                    item.write(out, indent + 1);
+                }
+                out.push('\n');
+                push_indent(out, indent);
+                out.push(']');
+            }
+            Json::Obj(fields) => {
+                out.push('{');
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    out.push_str(if i == 0 { "\n" } else { ",\n" });
+                    push_indent(out, indent + 1);
+                    write_json_string(out, key);
+                    out.push_str(": ");
+                    value.write(out, indent + 1);
+                }
+                out.push('\n');
+                push_indent(out, indent);
+                out.push('}');
+            }
+        }
+    }
+}
+
+fn push_indent(out: &mut String, indent: usize) {
+    for _ in 0..indent {
+        out.push_str("  ");
+    }
+}
+
+fn write_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn scene_json(scene: &ParsedScene, sections: &[SectionSpan]) -> Json {
+    let h = &scene.header;
+    let header = Json::Obj(vec![
+        ("version", Json::int(h.version)),
+        ("flags", Json::int(h.flags)),
+        ("num_entities", Json::int(h.num_entities)),
+        ("num_meshes", Json::int(h.num_meshes)),
+        ("num_textures", Json::int(h.num_textures)),
+        ("num_materials", Json::int(h.num_materials)),
+    ]);
+
+    let sections = sections.iter().map(|s| Json::Obj(vec![
+        ("name", Json::str(s.section.name())),
+        ("offset", Json::usize(s.offset)),
+        ("size", Json::usize(s.size)),
+    ])).collect();
+
+    let entities = (0..scene.entity_ids.len()).map(|i| {
+        let mut fields = vec![
+            ("index", Json::usize(i)),
+            ("id", Json::Num(scene.entity_ids[i].to_string())),
+            ("parent", Json::opt_index(scene.parent_indices[i])),
+            ("mask", Json::Num(scene.component_masks[i].0.to_string())),
+            ("components", Json::Arr(component_names(scene.component_masks[i]).into_iter().map(Json::Str).collect())),
+            ("mesh", Json::opt_index(scene.mesh_indices[i])),
+            ("material", Json::opt_index(scene.material_indices[i])),
+        ];
+        if let Some(t) = scene.transforms.get(i) {
+            fields.push(("position", Json::f64s(&t.position)));
+            fields.push(("rotation", Json::f64s(&t.rotation)));
+            fields.push(("scale", Json::f64s(&t.scale)));
+        }
+        Json::Obj(fields)
+    }).collect();
+
+    let meshes = scene.meshes.iter().map(|m| {
+        let (min, max) = mesh_bounds(m).unwrap_or_default();
+        Json::Obj(vec![
+            ("vertices", Json::usize(m.positions.len() / 3)),
+            ("triangles", Json::usize(m.indices.len() / 3)),
+            ("skinned", Json::Bool(m.bone_weights.is_some())),
+            ("encoding", Json::Arr(encoding_names(m.encoding).into_iter().map(Json::str).collect())),
+            ("bounds_min", Json::f32s(&min)),
+            ("bounds_max", Json::f32s(&max)),
+        ])
+    }).collect();
+
+    let materials = scene.materials.iter().map(|m| {
+        let mut copy = *m;
+        let textures = MATERIAL_TEXTURE_SLOTS.iter().zip(copy.texture_indices_mut())
+            .map(|(slot, idx)| (*slot, if *idx >= 0 { Json::int(*idx) } else { Json::Null }))
+            .collect();
+        Json::Obj(vec![
+            ("color", Json::f32s(&m.color)),
+            ("metallic", Json::f32(m.metallic)),
+            ("roughness", Json::f32(m.roughness)),
+            ("opacity", Json::f32(m.opacity)),
+            ("alpha_cutoff", Json::f32(m.alpha_cutoff)),
+            ("emissive_factor", Json::f32s(&m.emissive_factor)),
+            ("clearcoat", Json::f32(m.clearcoat)),
+            ("clearcoat_roughness", Json::f32(m.clearcoat_roughness)),
+            ("subsurface", Json::f32(m.subsurface)),
+            ("subsurface_color", Json::f32s(&m.subsurface_color)),
+            ("parallax_height_scale", Json::f32(m.parallax_height_scale)),
+            ("textures", Json::Obj(textures)),
+        ])
+    }).collect();
+
+    let textures = scene.textures.iter().map(|t| Json::Obj(vec![
+        ("width", Json::int(t.width)),
+        ("height", Json::int(t.height)),
+        ("channels", Json::int(t.channels)),
+        ("compression", Json::str(compression_name(t.compression))),
+        ("bytes", Json::usize(t.data.len())),
+    ])).collect();
+
+    let point_lights = scene.point_lights.iter().map(|l| Json::Obj(vec![
+        ("position", Json::f32s(&l.position)),
+        ("color", Json::f32s(&l.color)),
+        ("intensity", Json::f32(l.intensity)),
+        ("range", Json::f32(l.range)),
+    ])).collect();
+
+    let dir_lights = scene.dir_lights.iter().map(|l| Json::Obj(vec![
+        ("direction", Json::f32s(&l.direction)),
+        ("color", Json::f32s(&l.color)),
+        ("intensity", Json::f32(l.intensity)),
+    ])).collect();
+
+    let cameras = scene.cameras.iter().map(|c| Json::Obj(vec![
+        ("fov", Json::f32(c.fov)),
+        ("near", Json::f32(c.near)),
+        ("far", Json::f32(c.far)),
+        ("aspect", Json::f32(c.aspect)),
+    ])).collect();
+
+    let animations = scene.animations.iter().map(|a| Json::Obj(vec![
+        ("active_clip", Json::int(a.active_clip)),
+        ("playing", Json::Bool(a.playing)),
+        ("looping", Json::Bool(a.looping)),
+        ("speed", Json::f32(a.speed)),
+        ("clips", Json::Arr(a.clips.iter().map(|clip| Json::Obj(vec![
+            ("name", Json::str(clip.name.clone())),
+            ("duration", Json::f32(clip.duration)),
+            ("channels", Json::usize(clip.channels.len())),
+            ("keyframes", Json::usize(clip.channels.iter().map(|ch| ch.times.len()).sum())),
+        ])).collect())),
+    ])).collect();
+
+    let skeletons = scene.skeletons.iter().map(|s| Json::Obj(vec![
+        ("bones", Json::usize(s.bones.len())),
+    ])).collect();
+
+    let physics_config = scene.physics_config.as_ref().map_or(Json::Null, |p| Json::Obj(vec![
+        ("gravity", Json::f64s(&p.gravity)),
+        ("fixed_dt", Json::f64(p.fixed_dt)),
+        ("max_substeps", Json::int(p.max_substeps)),
+        ("solver_iterations", Json::int(p.solver_iterations)),
+    ]));
+
+    let terrains = scene.terrains.iter().map(|t| Json::Obj(vec![
+        ("entity_index", Json::int(t.entity_index)),
+        ("size", Json::f32s(&t.size)),
+        ("max_height", Json::f32(t.max_height)),
+        ("chunk_size", Json::int(t.chunk_size)),
+        ("num_lod_levels", Json::int(t.num_lod_levels)),
+        ("heightmap", Json::Arr(vec![Json::int(t.heightmap_width), Json::int(t.heightmap_depth)])),
+        ("splatmap", Json::Arr(vec![Json::int(t.splatmap_width), Json::int(t.splatmap_height)])),
+        ("layers", Json::usize(t.layers.len())),
+    ])).collect();
+
+    let game_refs = scene.game_refs.iter().map(|r| {
+        let value = match r.value_type {
+            1 => r.default_bool.map_or(Json::Null, Json::Bool),
+            2 => r.default_i64.map_or(Json::Null, Json::int),
+            3 => r.default_string.clone().map_or(Json::Null, Json::Str),
+            _ => r.default_f64.map_or(Json::Null, Json::f64),
+        };
+        Json::Obj(vec![
+            ("name", Json::str(r.name.clone())),
+            ("type", Json::str(game_ref_value(r).0)),
+            ("default", value),
+        ])
+    }).collect();
+
+    let scripts = scene.scripts.iter().map(|s| Json::Obj(vec![
+        ("entity_index", Json::int(s.entity_index)),
+        ("callback", Json::str(callback_name(s.callback_type))),
+        ("source", Json::str(s.rhai_source.clone())),
+    ])).collect();
+
+    Json::Obj(vec![
+        ("header", header),
+        ("sections", Json::Arr(sections)),
+        ("entities", Json::Arr(entities)),
+        ("meshes", Json::Arr(meshes)),
+        ("materials", Json::Arr(materials)),
+        ("textures", Json::Arr(textures)),
+        ("point_lights", Json::Arr(point_lights)),
+        ("dir_lights", Json::Arr(dir_lights)),
+        ("cameras", Json::Arr(cameras)),
+        ("colliders", Json::usize(scene.colliders.len())),
+        ("rigidbodies", Json::usize(scene.rigidbodies.len())),
+        ("particles", Json::usize(scene.particles.len())),
+        ("physics_config", physics_config),
+        ("animations", Json::Arr(animations)),
+        ("skeletons", Json::Arr(skeletons)),
+        ("terrains", Json::Arr(terrains)),
+        ("game_refs", Json::Arr(game_refs)),
+        ("scripts", Json::Arr(scripts)),
+    ])
+}
+
+/// Dump a scene as pretty-printed JSON. `sections` may be empty.
+pub fn dump_json(scene: &ParsedScene, sections: &[SectionSpan]) -> String {
+    let mut out = String::new();
+    scene_json(scene, sections).write(&mut out, 0);
+    out.push('\n');
+    out
+}
+
+/// Pretty-print every scene of a bundle, each resolved against the pool.
+pub fn dump_bundle_text(bundle: &ParsedBundle) -> String {
+    let mut out = format!(
+        "ORSM bundle: {} scene(s), pool of {} meshes, {} materials, {} textures\n",
+        bundle.scenes.len(), bundle.meshes.len(), bundle.materials.len(), bundle.textures.len()
+    );
+    for (i, s) in bundle.scenes.iter().enumerate() {
+        let default = if i == bundle.default_scene { " (default)" } else { "" };
+        let _ = writeln!(out, "\n=== Scene {:?}{} ===", s.name, default);
+        if let Some(scene) = bundle.resolve_scene(i) {
+            out.push_str(&dump_text(&scene, &[]));
+        }
+    }
+    out
+}
+
+/// Dump a bundle as JSON: `{"default_scene": ..., "scenes": [{"name": ..., <scene>}]}`.
+pub fn dump_bundle_json(bundle: &ParsedBundle) -> String {
+    let scenes = (0..bundle.scenes.len()).filter_map(|i| {
+        let scene = bundle.resolve_scene(i)?;
+        let Json::Obj(mut fields) = scene_json(&scene, &[]) else { unreachable!() };
+        fields.insert(0, ("name", Json::str(bundle.scenes[i].name.clone())));
+        Some(Json::Obj(fields))
+    }).collect();
+    let default_scene = bundle.scenes.get(bundle.default_scene).map_or(Json::Null, |s| Json::str(s.name.clone()));
+    let mut out = String::new();
+    Json::Obj(vec![("default_scene", default_scene), ("scenes", Json::Arr(scenes))]).write(&mut out, 0);
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_scene() -> ParsedScene {
+        let mut scene = ParsedScene::default();
+        for (id, parent) in [(10u64, None), (11, Some(0)), (12, None), (13, Some(1))] {
+            scene.entity_ids.push(id);
+            scene.parent_indices.push(parent);
+            scene.component_masks.push(ComponentMask(ComponentMask::TRANSFORM));
+            scene.mesh_indices.push(None);
+            scene.material_indices.push(None);
+            scene.transforms.push(TransformData { position: [0.0; 3], rotation: [1.0, 0.0, 0.0, 0.0], scale: [1.0; 3] });
+        }
+        scene.header.num_entities = 4;
+        scene.scripts.push(ScriptParsed { entity_index: 1, callback_type: 1, rhai_source: "let s = \"hi\";\nprint(s);".into() });
+        scene
+    }
+
+    #[test]
+    fn test_component_names() {
+        let mask = ComponentMask(ComponentMask::TRANSFORM | ComponentMask::TERRAIN | (1 << 40));
+        assert_eq!(component_names(mask), vec!["transform", "terrain", "bit40"]);
+    }
+
+    #[test]
+    fn test_entity_tree_order_nests_children() {
+        let order = entity_tree_order(&sample_scene());
+        assert_eq!(order, vec![(0, 0), (1, 1), (3, 2), (2, 0)]);
+    }
+
+    #[test]
+    fn test_text_dump_includes_tree_and_scripts() {
+        let data = write_orsb(&sample_scene());
+        let (scene, sections) = parse_orsb_with_layout(&data).unwrap();
+        let text = dump_text(&scene, &sections);
+        assert!(text.contains("\n      [3] id=13 transform"));
+        assert!(text.contains("scripts"));
+        assert!(text.contains("      | print(s);"));
+    }
+
+    #[test]
+    fn test_json_dump_escapes_strings() {
+        let json = dump_json(&sample_scene(), &[]);
+        assert!(json.contains(r#""source": "let s = \"hi\";\nprint(s);""#));
+        assert!(json.contains(r#""callback": "on_update""#));
+        assert!(json.contains(r#""sections": []"#));
+        // Balanced braces as a cheap well-formedness check.
+        assert_eq!(json.matches('{').count(), json.matches('}').count());
+    }
+
+    #[test]
+    fn test_bundle_dump_names_scenes() {
+        let bundle = ParsedBundle::from_scenes(vec![("menu".into(), sample_scene()), ("level".into(), ParsedScene::default())]);
+        let text = dump_bundle_text(&bundle);
+        assert!(text.contains("=== Scene \"menu\" (default) ==="));
+        assert!(text.contains("=== Scene \"level\" ==="));
+        let json = dump_bundle_json(&bundle);
+        assert!(json.contains(r#""default_scene": "menu""#));
+        assert!(json.contains(r#""name": "level""#));
+    }
+}
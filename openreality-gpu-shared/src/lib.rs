@@ -2,3 +2,15 @@ pub mod uniforms;
 pub mod shaders;
 pub mod math;
 pub mod scene_format;
+pub mod scene_bundle;
+pub mod scene_pack;
+pub mod mesh_opt;
+pub mod terrain;
+pub mod inspect;
+pub mod clusters;
+pub mod shadow_atlas;
+pub mod hdr;
+pub mod cube_lut;
+
+#[cfg(test)]
+mod test_fixtures;
@@ -42,6 +42,47 @@ pub fn sphere_in_frustum(planes: &[[f32; 4]; 6], center: Vec3, radius: f32) -> b
     true
 }
 
+/// Test if an axis-aligned bounding box is inside or intersects the frustum.
+pub fn aabb_in_frustum(planes: &[[f32; 4]; 6], min: Vec3, max: Vec3) -> bool {
+    for plane in planes {
+        // Corner furthest along the plane normal
+        let p = Vec3::new(
+            if plane[0] >= 0.0 { max.x } else { min.x },
+            if plane[1] >= 0.0 { max.y } else { min.y },
+            if plane[2] >= 0.0 { max.z } else { min.z },
+        );
+        if plane[0] * p.x + plane[1] * p.y + plane[2] * p.z + plane[3] < 0.0 {
+            return false;
+        }
+    }
+    true
+}
+
+/// Axis-aligned bounds of packed xyz positions, or `None` if there are none.
+pub fn positions_aabb(positions: &[f32]) -> Option<([f32; 3], [f32; 3])> {
+    let mut points = positions.chunks_exact(3);
+    let first = points.next()?;
+    let init = ([first[0], first[1], first[2]], [first[0], first[1], first[2]]);
+    Some(points.fold(init, |(mut min, mut max), p| {
+        for k in 0..3 {
+            min[k] = min[k].min(p[k]);
+            max[k] = max[k].max(p[k]);
+        }
+        (min, max)
+    }))
+}
+
+/// World-space bounding sphere of a local AABB under `model`. The radius is
+/// scaled by the largest axis scale, so it stays conservative under
+/// non-uniform scaling.
+pub fn aabb_bounding_sphere(model: &Mat4, min: Vec3, max: Vec3) -> (Vec3, f32) {
+    let center = model.transform_point3((min + max) * 0.5);
+    let scale = model.x_axis.truncate().length()
+        .max(model.y_axis.truncate().length())
+        .max(model.z_axis.truncate().length());
+    (center, (max - min).length() * 0.5 * scale)
+}
+
 /// Compute cascade split distances using PSSM (Practical Split Scheme Method).
 pub fn compute_cascade_splits(near: f32, far: f32, num_cascades: usize, lambda: f32) -> Vec<f32> {
     let mut splits = Vec::with_capacity(num_cascades + 1);
@@ -57,6 +98,61 @@ pub fn compute_cascade_splits(near: f32, far: f32, num_cascades: usize, lambda:
     splits
 }
 
+/// How far (in cascade radii) each cascade's depth range extends towards the
+/// light, so that casters outside the view frustum still land in the map.
+pub const CASCADE_CASTER_EXTENT: f32 = 4.0;
+
+/// Light view-projection matrices for cascaded shadow maps, one per range of
+/// `splits` (view-space distances as returned by `compute_cascade_splits`).
+///
+/// Each frustum slice is wrapped in a bounding sphere so the cascade keeps its
+/// size while the camera rotates, and the projection is snapped to whole
+/// shadow-map texels so shadow edges don't shimmer as the camera moves.
+/// Output depth is in [0, 1] (wgpu clip space).
+pub fn compute_cascade_matrices(view: &Mat4, proj: &Mat4, splits: &[f32], light_dir: Vec3, resolution: u32) -> Vec<Mat4> {
+    let inv_view = view.inverse();
+    let inv_proj = proj.inverse();
+
+    // View-space rays through the four frustum corners, scaled to unit depth.
+    let rays = [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)].map(|(x, y)| {
+        let p = inv_proj * Vec4::new(x, y, 0.5, 1.0);
+        let p = p.truncate() / p.w;
+        p / -p.z
+    });
+
+    let dir = light_dir.try_normalize().unwrap_or(Vec3::NEG_Y);
+    let up = if dir.y.abs() > 0.99 { Vec3::Z } else { Vec3::Y };
+    let half_res = resolution.max(1) as f32 * 0.5;
+
+    splits.windows(2).map(|range| {
+        let corners: Vec<Vec3> = rays.iter()
+            .flat_map(|r| [*r * range[0], *r * range[1]])
+            .map(|p| inv_view.transform_point3(p))
+            .collect();
+        let center = corners.iter().copied().sum::<Vec3>() / corners.len() as f32;
+        let radius = corners.iter().map(|c| c.distance(center)).fold(0.0, f32::max);
+        let radius = (radius * 16.0).ceil() / 16.0;
+
+        let light_view = Mat4::look_at_rh(center, center + dir, up);
+        let mut light_proj = Mat4::orthographic_rh(-radius, radius, -radius, radius, -radius * CASCADE_CASTER_EXTENT, radius);
+
+        // Snap the world origin to a texel so the cascade only moves in whole texels.
+        let origin = (light_proj * light_view).transform_point3(Vec3::ZERO) * half_res;
+        let offset = (origin.truncate().round() - origin.truncate()) / half_res;
+        light_proj.w_axis.x += offset.x;
+        light_proj.w_axis.y += offset.y;
+
+        light_proj * light_view
+    }).collect()
+}
+
+/// Near and far planes of an OpenGL-style perspective projection (clip depth
+/// in [-1, 1], as built by the Julia side's `perspective_matrix`).
+pub fn perspective_near_far(proj: &Mat4) -> (f32, f32) {
+    let (a, b) = (proj.z_axis.z, proj.w_axis.z);
+    (b / (a - 1.0), b / (a + 1.0))
+}
+
 /// Cook-Torrance GGX distribution function.
 pub fn distribution_ggx(n_dot_h: f32, roughness: f32) -> f32 {
     let a = roughness * roughness;
@@ -118,6 +214,41 @@ mod tests {
         assert!(sphere_in_frustum(&planes, Vec3::ZERO, 0.5));
     }
 
+    #[test]
+    fn test_aabb_frustum() {
+        let proj = Mat4::perspective_rh_gl(PI / 4.0, 1.0, 0.1, 100.0);
+        let view = Mat4::look_at_rh(Vec3::new(0.0, 0.0, 5.0), Vec3::ZERO, Vec3::Y);
+        let planes = extract_frustum_planes(&(proj * view));
+        assert!(aabb_in_frustum(&planes, Vec3::splat(-1.0), Vec3::splat(1.0)));
+        // Straddles the camera but extends into view
+        assert!(aabb_in_frustum(&planes, Vec3::new(-1.0, -1.0, 0.0), Vec3::new(1.0, 1.0, 50.0)));
+        assert!(!aabb_in_frustum(&planes, Vec3::new(-1.0, -1.0, 10.0), Vec3::new(1.0, 1.0, 20.0)));
+    }
+
+    #[test]
+    fn test_positions_aabb() {
+        assert_eq!(positions_aabb(&[]), None);
+        let (min, max) = positions_aabb(&[1.0, -2.0, 3.0, -1.0, 4.0, 0.5]).unwrap();
+        assert_eq!(min, [-1.0, -2.0, 0.5]);
+        assert_eq!(max, [1.0, 4.0, 3.0]);
+    }
+
+    #[test]
+    fn test_aabb_bounding_sphere_transformed() {
+        let model = Mat4::from_scale_rotation_translation(
+            Vec3::new(1.0, 3.0, 1.0),
+            glam::Quat::from_rotation_y(0.7),
+            Vec3::new(10.0, 0.0, -2.0),
+        );
+        let (center, radius) = aabb_bounding_sphere(&model, Vec3::splat(-1.0), Vec3::splat(1.0));
+        assert!(center.distance(Vec3::new(10.0, 0.0, -2.0)) < EPSILON);
+        assert!(approx_eq(radius, 3.0f32.sqrt() * 3.0));
+        // Every transformed corner lies inside the sphere.
+        for corner in [Vec3::new(1.0, 1.0, 1.0), Vec3::new(-1.0, 1.0, -1.0), Vec3::new(1.0, -1.0, -1.0)] {
+            assert!(model.transform_point3(corner).distance(center) <= radius + EPSILON);
+        }
+    }
+
     #[test]
     fn test_sphere_outside_frustum() {
         let proj = Mat4::perspective_rh_gl(PI / 4.0, 1.0, 0.1, 100.0);
@@ -182,6 +313,56 @@ mod tests {
         }
     }
 
+    // ── compute_cascade_matrices ──
+
+    #[test]
+    fn test_cascade_matrices_cover_slices() {
+        let proj = Mat4::perspective_rh(PI / 3.0, 16.0 / 9.0, 0.1, 100.0);
+        let view = Mat4::look_at_rh(Vec3::new(3.0, 2.0, 5.0), Vec3::ZERO, Vec3::Y);
+        let splits = compute_cascade_splits(0.1, 100.0, 4, 0.75);
+        let light_dir = Vec3::new(-0.3, -1.0, -0.2);
+        let matrices = compute_cascade_matrices(&view, &proj, &splits, light_dir, 2048);
+        assert_eq!(matrices.len(), 4);
+
+        let inv_vp = (proj * view).inverse();
+        let cam_pos = Vec3::new(3.0, 2.0, 5.0);
+        let forward = (Vec3::ZERO - cam_pos).normalize();
+        for (i, m) in matrices.iter().enumerate() {
+            // Sample points on the frustum at both ends of this cascade's range.
+            for &(x, y) in &[(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0), (0.0, 0.0)] {
+                let far_point = inv_vp.project_point3(Vec3::new(x, y, 1.0));
+                let ray = far_point - cam_pos;
+                for &d in &[splits[i], splits[i + 1]] {
+                    let p = cam_pos + ray * (d / ray.dot(forward));
+                    let clip = m.project_point3(p);
+                    assert!(clip.x.abs() <= 1.0 && clip.y.abs() <= 1.0, "cascade {i}: {clip:?}");
+                    assert!((0.0..=1.0).contains(&clip.z), "cascade {i}: {clip:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_cascade_matrices_snap_to_texels() {
+        let proj = Mat4::perspective_rh(PI / 3.0, 1.0, 0.1, 50.0);
+        let light_dir = Vec3::new(0.4, -1.0, 0.1);
+        let splits = [0.1, 10.0];
+        let a = compute_cascade_matrices(&Mat4::look_at_rh(Vec3::new(0.0, 1.0, 5.0), Vec3::ZERO, Vec3::Y), &proj, &splits, light_dir, 1024)[0];
+        let b = compute_cascade_matrices(&Mat4::look_at_rh(Vec3::new(0.013, 1.0, 5.0), Vec3::new(0.013, 0.0, 0.0), Vec3::Y), &proj, &splits, light_dir, 1024)[0];
+        // A small camera move shifts the projection by a whole number of texels.
+        let texels = (b.transform_point3(Vec3::ZERO) - a.transform_point3(Vec3::ZERO)) * 512.0;
+        assert!((texels.x - texels.x.round()).abs() < 1e-2, "{texels:?}");
+        assert!((texels.y - texels.y.round()).abs() < 1e-2, "{texels:?}");
+    }
+
+    #[test]
+    fn test_perspective_near_far() {
+        let proj = Mat4::perspective_rh_gl(PI / 3.0, 16.0 / 9.0, 0.1, 500.0);
+        let (near, far) = perspective_near_far(&proj);
+        assert!(approx_eq(near, 0.1), "near={near}");
+        assert!((far / 500.0 - 1.0).abs() < 1e-3, "far={far}");
+    }
+
     // ── distribution_ggx ──
 
     #[test]
@@ -0,0 +1,563 @@
+//! Optional mesh optimization stage for ORSB export/packing.
+//!
+//! Runs on parsed meshes before they are written back out:
+//!
+//! 1. **Weld** — merge vertices whose attributes are bit-identical.
+//! 2. **Vertex cache** — reorder triangles with Forsyth's linear-speed
+//!    algorithm so recently transformed vertices are reused.
+//! 3. **Overdraw** — split the cache-optimized order into clusters at cache
+//!    restarts and draw outward-facing clusters first.
+//! 4. **Vertex fetch** — renumber vertices in first-use order.
+//! 5. **Quantize** — mark the mesh for u16 indices (when they fit),
+//!    octahedral snorm16 normals and unorm16 UVs. The parser decodes these
+//!    back to f32/u32, so loaders see ordinary meshes.
+//!
+//! Steps 1-4 are lossless; only quantization changes attribute values.
+
+use std::collections::HashMap;
+
+use crate::scene_format::*;
+
+/// Cache size assumed by the vertex cache optimizer.
+const FORSYTH_CACHE_SIZE: usize = 32;
+/// FIFO size used to measure ACMR and find overdraw cluster boundaries.
+const FIFO_CACHE_SIZE: usize = 16;
+
+/// Which optimization steps to run. All are enabled by default.
+#[derive(Clone, Copy, Debug)]
+pub struct MeshOptOptions {
+    pub weld: bool,
+    pub vertex_cache: bool,
+    pub overdraw: bool,
+    pub quantize: bool,
+}
+
+impl Default for MeshOptOptions {
+    fn default() -> Self {
+        Self { weld: true, vertex_cache: true, overdraw: true, quantize: true }
+    }
+}
+
+/// Totals over every mesh an optimization pass touched.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MeshOptReport {
+    pub meshes: usize,
+    pub vertices_before: usize,
+    pub vertices_after: usize,
+    /// Encoded ORSB size of the mesh section.
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+    /// Average cache miss ratio (transformed vertices per triangle) on a
+    /// 16-entry FIFO, weighted by triangle count.
+    pub acmr_before: f32,
+    pub acmr_after: f32,
+}
+
+impl MeshOptReport {
+    pub fn bytes_saved(&self) -> u64 {
+        self.bytes_before.saturating_sub(self.bytes_after)
+    }
+}
+
+/// Optimize every mesh in place. Works on `ParsedScene::meshes` as well as a
+/// bundle's shared pool; entity references are unaffected since meshes keep
+/// their positions in the list.
+pub fn optimize_meshes(meshes: &mut [MeshParsed], opts: &MeshOptOptions) -> MeshOptReport {
+    let mut report = MeshOptReport { meshes: meshes.len(), ..Default::default() };
+    let mut tris = 0usize;
+    let (mut misses_before, mut misses_after) = (0.0f32, 0.0f32);
+
+    for mesh in meshes.iter_mut() {
+        let n = mesh.indices.len() / 3;
+        report.vertices_before += mesh.positions.len() / 3;
+        report.bytes_before += encoded_size(mesh);
+        misses_before += acmr(&mesh.indices, FIFO_CACHE_SIZE) * n as f32;
+
+        optimize_mesh(mesh, opts);
+
+        report.vertices_after += mesh.positions.len() / 3;
+        report.bytes_after += encoded_size(mesh);
+        misses_after += acmr(&mesh.indices, FIFO_CACHE_SIZE) * n as f32;
+        tris += n;
+    }
+
+    if tris > 0 {
+        report.acmr_before = misses_before / tris as f32;
+        report.acmr_after = misses_after / tris as f32;
+    }
+    report
+}
+
+/// Optimize one mesh in place. Meshes with malformed index or attribute
+/// streams are left untouched.
+pub fn optimize_mesh(mesh: &mut MeshParsed, opts: &MeshOptOptions) {
+    if !is_well_formed(mesh) {
+        return;
+    }
+
+    if opts.weld {
+        weld_vertices(mesh);
+    }
+    let vertex_count = mesh.positions.len() / 3;
+    if opts.vertex_cache {
+        mesh.indices = optimize_vertex_cache(&mesh.indices, vertex_count);
+    }
+    if opts.overdraw {
+        mesh.indices = optimize_overdraw(&mesh.indices, &mesh.positions);
+    }
+    if opts.weld || opts.vertex_cache || opts.overdraw {
+        reorder_vertex_fetch(mesh);
+    }
+    if opts.quantize {
+        let nv = mesh.positions.len() / 3;
+        if nv <= u16::MAX as usize + 1 {
+            mesh.encoding.set(MeshEncoding::INDICES_U16);
+        }
+        if !mesh.normals.is_empty() {
+            mesh.encoding.set(MeshEncoding::NORMALS_OCT16);
+        }
+        if !mesh.uvs.is_empty() {
+            mesh.encoding.set(MeshEncoding::UVS_UNORM16);
+        }
+    }
+}
+
+/// Average cache miss ratio of an index buffer on a FIFO cache.
+pub fn acmr(indices: &[u32], cache_size: usize) -> f32 {
+    let tris = indices.len() / 3;
+    if tris == 0 {
+        return 0.0;
+    }
+    let mut fifo = std::collections::VecDeque::with_capacity(cache_size);
+    let mut misses = 0usize;
+    for &i in &indices[..tris * 3] {
+        if !fifo.contains(&i) {
+            misses += 1;
+            if fifo.len() == cache_size {
+                fifo.pop_front();
+            }
+            fifo.push_back(i);
+        }
+    }
+    misses as f32 / tris as f32
+}
+
+fn encoded_size(mesh: &MeshParsed) -> u64 {
+    let mut w = Writer::new();
+    write_meshes(&mut w, std::slice::from_ref(mesh));
+    w.into_bytes().len() as u64
+}
+
+fn is_well_formed(mesh: &MeshParsed) -> bool {
+    let nv = mesh.positions.len() / 3;
+    let attr_ok = |len: usize, width: usize| len == 0 || len == nv * width;
+    mesh.positions.len().is_multiple_of(3)
+        && mesh.indices.len().is_multiple_of(3)
+        && mesh.indices.iter().all(|&i| (i as usize) < nv)
+        && attr_ok(mesh.normals.len(), 3)
+        && attr_ok(mesh.uvs.len(), 2)
+        && mesh.bone_weights.as_ref().is_none_or(|b| b.len() == nv * 4)
+        && mesh.bone_indices.as_ref().is_none_or(|b| b.len() == nv * 4)
+}
+
+/// Apply a vertex permutation: `order[new] = old`.
+fn gather<T: Copy>(data: &[T], width: usize, order: &[u32]) -> Vec<T> {
+    if data.is_empty() {
+        return Vec::new();
+    }
+    let mut out = Vec::with_capacity(order.len() * width);
+    for &old in order {
+        let o = old as usize * width;
+        out.extend_from_slice(&data[o..o + width]);
+    }
+    out
+}
+
+fn remap_vertices(mesh: &mut MeshParsed, order: &[u32]) {
+    mesh.positions = gather(&mesh.positions, 3, order);
+    mesh.normals = gather(&mesh.normals, 3, order);
+    mesh.uvs = gather(&mesh.uvs, 2, order);
+    if let Some(bw) = &mesh.bone_weights {
+        mesh.bone_weights = Some(gather(bw, 4, order));
+    }
+    if let Some(bi) = &mesh.bone_indices {
+        mesh.bone_indices = Some(gather(bi, 4, order));
+    }
+}
+
+/// Merge vertices whose every attribute is bit-identical.
+fn weld_vertices(mesh: &mut MeshParsed) {
+    let nv = mesh.positions.len() / 3;
+    let mut unique: HashMap<Vec<u32>, u32> = HashMap::with_capacity(nv);
+    let mut order = Vec::with_capacity(nv);
+    let mut remap = Vec::with_capacity(nv);
+
+    for v in 0..nv {
+        let mut key: Vec<u32> = mesh.positions[v * 3..v * 3 + 3].iter().map(|f| f.to_bits()).collect();
+        if !mesh.normals.is_empty() {
+            key.extend(mesh.normals[v * 3..v * 3 + 3].iter().map(|f| f.to_bits()));
+        }
+        if !mesh.uvs.is_empty() {
+            key.extend(mesh.uvs[v * 2..v * 2 + 2].iter().map(|f| f.to_bits()));
+        }
+        if let Some(bw) = &mesh.bone_weights {
+            key.extend(bw[v * 4..v * 4 + 4].iter().map(|f| f.to_bits()));
+        }
+        if let Some(bi) = &mesh.bone_indices {
+            key.extend(bi[v * 4..v * 4 + 4].iter().map(|&i| i as u32));
+        }
+        let next = order.len() as u32;
+        let idx = *unique.entry(key).or_insert_with(|| {
+            order.push(v as u32);
+            next
+        });
+        remap.push(idx);
+    }
+
+    if order.len() == nv {
+        return;
+    }
+    for i in mesh.indices.iter_mut() {
+        *i = remap[*i as usize];
+    }
+    remap_vertices(mesh, &order);
+}
+
+/// Renumber vertices in order of first use and drop unreferenced ones.
+fn reorder_vertex_fetch(mesh: &mut MeshParsed) {
+    let nv = mesh.positions.len() / 3;
+    let mut remap = vec![u32::MAX; nv];
+    let mut order = Vec::with_capacity(nv);
+    for i in mesh.indices.iter_mut() {
+        let old = *i as usize;
+        if remap[old] == u32::MAX {
+            remap[old] = order.len() as u32;
+            order.push(old as u32);
+        }
+        *i = remap[old];
+    }
+    remap_vertices(mesh, &order);
+}
+
+/// Forsyth vertex score: favours vertices still in cache and vertices with
+/// few triangles left, so that isolated vertices are finished off early.
+fn vertex_score(cache_pos: Option<usize>, remaining: u32) -> f32 {
+    if remaining == 0 {
+        return -1.0;
+    }
+    let cache_score = match cache_pos {
+        // The most recent triangle's vertices get a fixed score so the
+        // optimizer doesn't just strip along one edge.
+        Some(p) if p < 3 => 0.75,
+        Some(p) => (1.0 - (p - 3) as f32 / (FORSYTH_CACHE_SIZE - 3) as f32).powf(1.5),
+        None => 0.0,
+    };
+    cache_score + 2.0 * (remaining as f32).powf(-0.5)
+}
+
+/// Reorder triangles for post-transform vertex cache reuse (Forsyth, 2006).
+fn optimize_vertex_cache(indices: &[u32], vertex_count: usize) -> Vec<u32> {
+    let tri_count = indices.len() / 3;
+    if tri_count == 0 {
+        return indices.to_vec();
+    }
+
+    // Vertex → triangle adjacency (CSR layout).
+    let mut offsets = vec![0usize; vertex_count + 1];
+    for &i in indices {
+        offsets[i as usize + 1] += 1;
+    }
+    for v in 0..vertex_count {
+        offsets[v + 1] += offsets[v];
+    }
+    let mut fill = offsets.clone();
+    let mut adjacency = vec![0usize; indices.len()];
+    for (t, tri) in indices.chunks_exact(3).enumerate() {
+        for &v in tri {
+            adjacency[fill[v as usize]] = t;
+            fill[v as usize] += 1;
+        }
+    }
+
+    let mut remaining: Vec<u32> = (0..vertex_count).map(|v| (offsets[v + 1] - offsets[v]) as u32).collect();
+    let mut cache_pos: Vec<Option<usize>> = vec![None; vertex_count];
+    let mut vscore: Vec<f32> = remaining.iter().map(|&r| vertex_score(None, r)).collect();
+    let tri_score = |t: usize, vscore: &[f32]| -> f32 { indices[t * 3..t * 3 + 3].iter().map(|&v| vscore[v as usize]).sum() };
+
+    let mut emitted = vec![false; tri_count];
+    let mut cache: Vec<u32> = Vec::with_capacity(FORSYTH_CACHE_SIZE + 3);
+    let mut out = Vec::with_capacity(indices.len());
+    let mut cursor = 0usize;
+    let mut best = (0..tri_count).max_by(|&a, &b| tri_score(a, &vscore).total_cmp(&tri_score(b, &vscore)));
+
+    while let Some(t) = best {
+        emitted[t] = true;
+        let tri = &indices[t * 3..t * 3 + 3];
+        out.extend_from_slice(tri);
+
+        let mut touched: Vec<u32> = Vec::with_capacity(FORSYTH_CACHE_SIZE + 3);
+        for &v in tri {
+            remaining[v as usize] -= 1;
+            if !touched.contains(&v) {
+                touched.push(v);
+            }
+        }
+        for &v in &cache {
+            if !touched.contains(&v) {
+                touched.push(v);
+            }
+        }
+        for (p, &v) in touched.iter().enumerate() {
+            cache_pos[v as usize] = (p < FORSYTH_CACHE_SIZE).then_some(p);
+        }
+        for &v in &touched {
+            vscore[v as usize] = vertex_score(cache_pos[v as usize], remaining[v as usize]);
+        }
+
+        best = None;
+        let mut best_score = f32::MIN;
+        for &v in &touched {
+            for &at in &adjacency[offsets[v as usize]..offsets[v as usize + 1]] {
+                if emitted[at] {
+                    continue;
+                }
+                let s = tri_score(at, &vscore);
+                if s > best_score {
+                    best_score = s;
+                    best = Some(at);
+                }
+            }
+        }
+
+        touched.truncate(FORSYTH_CACHE_SIZE);
+        cache = touched;
+
+        if best.is_none() {
+            while cursor < tri_count && emitted[cursor] {
+                cursor += 1;
+            }
+            best = (cursor < tri_count).then_some(cursor);
+        }
+    }
+    out
+}
+
+/// Reorder clusters of a cache-optimized index buffer so that triangles
+/// facing away from the mesh centre are drawn first (Sander et al. 2007).
+/// Clusters start where the FIFO cache restarts, so cache efficiency is kept.
+fn optimize_overdraw(indices: &[u32], positions: &[f32]) -> Vec<u32> {
+    let tri_count = indices.len() / 3;
+    if tri_count < 2 {
+        return indices.to_vec();
+    }
+    let pos = |i: u32| glam::Vec3::from_slice(&positions[i as usize * 3..i as usize * 3 + 3]);
+
+    // Cluster boundaries: triangles whose three vertices all miss the cache.
+    let mut fifo = std::collections::VecDeque::with_capacity(FIFO_CACHE_SIZE);
+    let mut starts = Vec::new();
+    for (t, tri) in indices.chunks_exact(3).enumerate() {
+        let mut misses = 0;
+        for &v in tri {
+            if !fifo.contains(&v) {
+                misses += 1;
+                if fifo.len() == FIFO_CACHE_SIZE {
+                    fifo.pop_front();
+                }
+                fifo.push_back(v);
+            }
+        }
+        if t == 0 || misses == 3 {
+            starts.push(t);
+        }
+    }
+    if starts.len() < 2 {
+        return indices.to_vec();
+    }
+
+    let mesh_centroid = indices.iter().map(|&i| pos(i)).sum::<glam::Vec3>() / indices.len() as f32;
+
+    let mut clusters: Vec<(f32, usize, usize)> = Vec::with_capacity(starts.len());
+    for (k, &start) in starts.iter().enumerate() {
+        let end = starts.get(k + 1).copied().unwrap_or(tri_count);
+        let mut centroid = glam::Vec3::ZERO;
+        let mut normal = glam::Vec3::ZERO;
+        let mut area = 0.0;
+        for tri in indices[start * 3..end * 3].chunks_exact(3) {
+            let (a, b, c) = (pos(tri[0]), pos(tri[1]), pos(tri[2]));
+            let n = (b - a).cross(c - a);
+            let tri_area = n.length();
+            centroid += (a + b + c) / 3.0 * tri_area;
+            normal += n;
+            area += tri_area;
+        }
+        let key = if area > 0.0 {
+            (centroid / area - mesh_centroid).dot(normal.normalize_or_zero())
+        } else {
+            0.0
+        };
+        clusters.push((key, start, end));
+    }
+
+    clusters.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let mut out = Vec::with_capacity(indices.len());
+    for (_, start, end) in clusters {
+        out.extend_from_slice(&indices[start * 3..end * 3]);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `n` x `n` grid of quads, with every triangle carrying its own copies of
+    /// its vertices (as an unindexed exporter would produce).
+    fn unwelded_grid(n: usize) -> MeshParsed {
+        let mut mesh = MeshParsed {
+            positions: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            indices: Vec::new(),
+            bone_weights: None,
+            bone_indices: None,
+            encoding: MeshEncoding::default(),
+        };
+        let mut push = |x: usize, y: usize| {
+            let i = mesh.positions.len() as u32 / 3;
+            mesh.positions.extend_from_slice(&[x as f32, y as f32, 0.0]);
+            mesh.normals.extend_from_slice(&[0.0, 0.0, 1.0]);
+            mesh.uvs.extend_from_slice(&[x as f32 / n as f32, y as f32 / n as f32]);
+            mesh.indices.push(i);
+        };
+        // Emit quads column-major to give the cache optimizer something to do.
+        for x in 0..n {
+            for y in 0..n {
+                for (dx, dy) in [(0, 0), (1, 0), (1, 1), (0, 0), (1, 1), (0, 1)] {
+                    push(x + dx, y + dy);
+                }
+            }
+        }
+        mesh
+    }
+
+    fn triangles(mesh: &MeshParsed) -> Vec<[[u32; 3]; 3]> {
+        let p = |i: u32| {
+            let o = i as usize * 3;
+            [mesh.positions[o].to_bits(), mesh.positions[o + 1].to_bits(), mesh.positions[o + 2].to_bits()]
+        };
+        let mut tris: Vec<_> = mesh.indices.chunks_exact(3).map(|t| {
+            // Canonical rotation keeps winding while making triangles comparable.
+            let r = [p(t[0]), p(t[1]), p(t[2])];
+            let k = (0..3).min_by_key(|&k| r[k]).unwrap();
+            [r[k], r[(k + 1) % 3], r[(k + 2) % 3]]
+        }).collect();
+        tris.sort();
+        tris
+    }
+
+    #[test]
+    fn test_weld_merges_identical_vertices() {
+        let mut mesh = unwelded_grid(4);
+        let opts = MeshOptOptions { vertex_cache: false, overdraw: false, quantize: false, ..Default::default() };
+        optimize_mesh(&mut mesh, &opts);
+        assert_eq!(mesh.positions.len() / 3, 25);
+        assert_eq!(mesh.normals.len(), 25 * 3);
+        assert_eq!(mesh.uvs.len(), 25 * 2);
+        assert_eq!(mesh.indices.len(), 16 * 6);
+    }
+
+    #[test]
+    fn test_optimize_preserves_triangles() {
+        let original = unwelded_grid(8);
+        let mut mesh = original.clone();
+        let opts = MeshOptOptions { quantize: false, ..Default::default() };
+        optimize_mesh(&mut mesh, &opts);
+        assert_eq!(triangles(&mesh), triangles(&original));
+    }
+
+    #[test]
+    fn test_vertex_cache_improves_acmr() {
+        let mut mesh = unwelded_grid(32);
+        weld_vertices(&mut mesh);
+        reorder_vertex_fetch(&mut mesh);
+        let before = acmr(&mesh.indices, FIFO_CACHE_SIZE);
+        let optimized = optimize_vertex_cache(&mesh.indices, mesh.positions.len() / 3);
+        let after = acmr(&optimized, FIFO_CACHE_SIZE);
+        assert!(after < before, "ACMR {after} should be below {before}");
+        assert!(after < 0.8);
+    }
+
+    #[test]
+    fn test_vertex_fetch_is_first_use_order() {
+        let mut mesh = unwelded_grid(3);
+        optimize_mesh(&mut mesh, &MeshOptOptions::default());
+        let mut next = 0;
+        for &i in &mesh.indices {
+            assert!(i <= next);
+            if i == next {
+                next += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn test_quantized_roundtrip_through_orsb() {
+        let mut scene = ParsedScene { meshes: vec![unwelded_grid(4)], ..Default::default() };
+        let report = optimize_meshes(&mut scene.meshes, &MeshOptOptions::default());
+        assert!(report.bytes_after < report.bytes_before / 2);
+        assert!(report.acmr_after <= report.acmr_before);
+        assert_eq!(report.vertices_after, 25);
+
+        let encoding = scene.meshes[0].encoding;
+        assert!(encoding.has(MeshEncoding::INDICES_U16));
+        assert!(encoding.has(MeshEncoding::NORMALS_OCT16));
+        assert!(encoding.has(MeshEncoding::UVS_UNORM16));
+
+        let parsed = parse_orsb(&write_orsb(&scene)).unwrap();
+        let (a, b) = (&scene.meshes[0], &parsed.meshes[0]);
+        assert_eq!(b.encoding, encoding);
+        assert_eq!(a.indices, b.indices);
+        assert_eq!(a.positions, b.positions);
+        for (x, y) in a.normals.iter().zip(&b.normals) {
+            assert!((x - y).abs() < 1e-4);
+        }
+        for (x, y) in a.uvs.iter().zip(&b.uvs) {
+            assert!((x - y).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_oct_roundtrip() {
+        for n in [[0.0, 0.0, 1.0], [0.0, 0.0, -1.0], [0.6, -0.8, 0.0], [-0.48, 0.6, -0.64]] {
+            let (x, y) = oct_encode(n);
+            let d = oct_decode(x, y);
+            for k in 0..3 {
+                assert!((n[k] - d[k]).abs() < 1e-3, "{n:?} -> {d:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_large_mesh_keeps_u32_indices() {
+        let mut mesh = unwelded_grid(1);
+        mesh.encoding.set(MeshEncoding::INDICES_U16);
+        mesh.indices[0] = 70_000;
+        mesh.positions.resize(70_001 * 3, 0.0);
+        mesh.normals.resize(70_001 * 3, 0.0);
+        mesh.uvs.resize(70_001 * 2, 0.0);
+        let scene = ParsedScene { meshes: vec![mesh], ..Default::default() };
+        let parsed = parse_orsb(&write_orsb(&scene)).unwrap();
+        assert!(!parsed.meshes[0].encoding.has(MeshEncoding::INDICES_U16));
+        assert_eq!(parsed.meshes[0].indices[0], 70_000);
+    }
+
+    #[test]
+    fn test_malformed_mesh_untouched() {
+        let mut mesh = unwelded_grid(2);
+        mesh.indices.pop();
+        let before = mesh.indices.clone();
+        optimize_mesh(&mut mesh, &MeshOptOptions::default());
+        assert_eq!(mesh.indices, before);
+        assert_eq!(mesh.encoding, MeshEncoding::default());
+    }
+}
@@ -0,0 +1,460 @@
+//! Multi-scene ORSB bundles.
+//!
+//! A bundle packs several named scenes that share one asset pool of meshes,
+//! materials and textures. Each scene is stored as a regular ORSB blob that
+//! declares no assets of its own; its entity mesh/material indices point into
+//! the shared pool. A plain single-scene .orsb file is accepted wherever a
+//! bundle is, and loads as a bundle with one scene named `main`.
+//!
+//! Layout (little-endian):
+//!
+//! ```text
+//! header   magic "ORSM", version, num_scenes, default_scene      (16 bytes)
+//! pool     num_meshes, num_textures, num_materials, reserved     (16 bytes)
+//!          meshes, materials, textures — same encoding as ORSB
+//! scenes   per scene: name (u16 length + UTF-8), blob size (u64), ORSB blob
+//! ```
+
+use crate::scene_format::*;
+use crate::scene_pack::{remap_index, AssetPool, AssetRemap, DedupReport};
+
+/// Magic bytes at the start of every multi-scene bundle.
+pub const BUNDLE_MAGIC: [u8; 4] = *b"ORSM";
+pub const BUNDLE_VERSION: u32 = 1;
+/// Version written when a pooled mesh is stored encoded (see
+/// `ORSB_VERSION_MESH_ENCODING`).
+pub const BUNDLE_VERSION_MESH_ENCODING: u32 = 2;
+
+/// Scene name used when a single-scene .orsb file is loaded as a bundle.
+pub const DEFAULT_SCENE_NAME: &str = "main";
+
+/// A named scene inside a bundle.
+///
+/// `scene.meshes`, `scene.materials` and `scene.textures` are empty; the
+/// scene's mesh and material indices refer to the bundle's asset pool.
+#[derive(Clone, Debug)]
+pub struct BundleScene {
+    pub name: String,
+    pub scene: ParsedScene,
+}
+
+/// Parsed multi-scene bundle — shared asset pool plus named scenes.
+#[derive(Clone, Debug, Default)]
+pub struct ParsedBundle {
+    pub meshes: Vec<MeshParsed>,
+    pub materials: Vec<MaterialData>,
+    pub textures: Vec<TextureParsed>,
+    pub scenes: Vec<BundleScene>,
+    /// Index into `scenes` of the scene to load on startup.
+    pub default_scene: usize,
+}
+
+impl ParsedBundle {
+    /// Build a bundle from self-contained scenes, moving their assets into a
+    /// single content-addressed pool. Identical meshes, materials and textures
+    /// are stored once and every scene's indices are rewritten to point at the
+    /// pool.
+    pub fn from_scenes(scenes: Vec<(String, ParsedScene)>) -> Self {
+        Self::pack(scenes).0
+    }
+
+    /// Like [`ParsedBundle::from_scenes`], also reporting what deduplication
+    /// saved.
+    pub fn pack(scenes: Vec<(String, ParsedScene)>) -> (Self, DedupReport) {
+        Self::merge(scenes.into_iter().map(|(name, scene)| Self::wrap_scene(name, scene)).collect())
+    }
+
+    /// Combine bundles into one whose pool holds every distinct asset once.
+    /// Each input pool is absorbed as a whole, so assets its scenes already
+    /// share don't count as duplicates; pool assets no scene uses are dropped.
+    pub fn merge(bundles: Vec<ParsedBundle>) -> (Self, DedupReport) {
+        let mut pool = AssetPool::new();
+        let mut scenes = Vec::new();
+        for mut bundle in bundles {
+            bundle.drop_unused_assets();
+            let remap = pool.absorb_assets(bundle.meshes, bundle.materials, bundle.textures);
+            for mut s in bundle.scenes {
+                remap.apply(&mut s.scene);
+                scenes.push(s);
+            }
+        }
+
+        let report = pool.report();
+        let (meshes, materials, textures) = pool.into_assets();
+        let bundle = Self { meshes, materials, textures, scenes, default_scene: 0 };
+        (bundle, report)
+    }
+
+    /// Wrap a self-contained scene as a one-scene bundle whose pool is the
+    /// scene's own assets, as they are.
+    pub fn wrap_scene(name: String, mut scene: ParsedScene) -> Self {
+        let meshes = std::mem::take(&mut scene.meshes);
+        let materials = std::mem::take(&mut scene.materials);
+        let textures = std::mem::take(&mut scene.textures);
+        scene.header.num_meshes = 0;
+        scene.header.num_textures = 0;
+        scene.header.num_materials = 0;
+        Self { meshes, materials, textures, scenes: vec![BundleScene { name, scene }], default_scene: 0 }
+    }
+
+    /// Remove pool assets that no scene references, remapping indices.
+    fn drop_unused_assets(&mut self) {
+        let mut used_meshes = vec![false; self.meshes.len()];
+        let mut used_materials = vec![false; self.materials.len()];
+        let mut used_textures = vec![false; self.textures.len()];
+        let mark = |used: &mut [bool], i: usize| {
+            if let Some(u) = used.get_mut(i) {
+                *u = true;
+            }
+        };
+        for s in &mut self.scenes {
+            s.scene.mesh_indices.iter().flatten().for_each(|&i| mark(&mut used_meshes, i));
+            s.scene.material_indices.iter().flatten().for_each(|&i| mark(&mut used_materials, i));
+            for t in &mut s.scene.terrains {
+                t.texture_indices_mut().filter(|i| **i >= 0).for_each(|i| mark(&mut used_textures, *i as usize));
+            }
+        }
+        for (m, &used) in self.materials.iter_mut().zip(&used_materials) {
+            if used {
+                m.texture_indices_mut().into_iter().filter(|i| **i >= 0).for_each(|i| mark(&mut used_textures, *i as usize));
+            }
+        }
+
+        let remap = AssetRemap {
+            meshes: retain_used(&mut self.meshes, &used_meshes),
+            materials: retain_used(&mut self.materials, &used_materials),
+            textures: retain_used(&mut self.textures, &used_textures),
+        };
+        for m in &mut self.materials {
+            m.texture_indices_mut().into_iter().for_each(|idx| remap_index(idx, &remap.textures));
+        }
+        for s in &mut self.scenes {
+            remap.apply(&mut s.scene);
+        }
+    }
+
+    /// Wrap a single self-contained scene as a one-scene bundle named `main`.
+    pub fn from_single(scene: ParsedScene) -> Self {
+        Self::from_scenes(vec![(DEFAULT_SCENE_NAME.to_string(), scene)])
+    }
+
+    /// Look up a scene by name.
+    pub fn scene_index(&self, name: &str) -> Option<usize> {
+        self.scenes.iter().position(|s| s.name == name)
+    }
+
+    /// Names of all scenes, in bundle order.
+    pub fn scene_names(&self) -> Vec<&str> {
+        self.scenes.iter().map(|s| s.name.as_str()).collect()
+    }
+
+    /// Return a self-contained copy of scene `index` with the whole asset pool
+    /// attached, so it can be handled like a scene from a single .orsb file.
+    pub fn resolve_scene(&self, index: usize) -> Option<ParsedScene> {
+        let mut scene = self.scenes.get(index)?.scene.clone();
+        scene.meshes = self.meshes.clone();
+        scene.materials = self.materials.clone();
+        scene.textures = self.textures.clone();
+        scene.header.num_meshes = scene.meshes.len() as u32;
+        scene.header.num_textures = scene.textures.len() as u32;
+        scene.header.num_materials = scene.materials.len() as u32;
+        Some(scene)
+    }
+}
+
+/// Keep the items flagged in `used`, returning each old index's new index.
+fn retain_used<T>(items: &mut Vec<T>, used: &[bool]) -> Vec<usize> {
+    let mut next = 0;
+    let map = used.iter().map(|&u| {
+        let i = next;
+        next += u as usize;
+        i
+    }).collect();
+    let mut flags = used.iter();
+    items.retain(|_| *flags.next().unwrap());
+    map
+}
+
+/// Parse either a multi-scene bundle or a plain single-scene .orsb file.
+pub fn parse_bundle(data: &[u8]) -> Result<ParsedBundle, String> {
+    if data.len() >= 4 && data[0..4] == ORSB_MAGIC {
+        return Ok(ParsedBundle::from_single(parse_orsb(data)?));
+    }
+    if data.len() < 32 || data[0..4] != BUNDLE_MAGIC {
+        return Err("Invalid bundle header".to_string());
+    }
+
+    let mut c = Cursor::new(data);
+    c.skip(4); // magic
+    let version = c.read_u32().ok_or("Truncated bundle header")?;
+    if !(BUNDLE_VERSION..=BUNDLE_VERSION_MESH_ENCODING).contains(&version) {
+        return Err(format!("Unsupported bundle version {version}"));
+    }
+    let num_scenes = c.read_u32().ok_or("Truncated bundle header")? as usize;
+    let default_scene = c.read_u32().ok_or("Truncated bundle header")? as usize;
+
+    // ── Shared asset pool ──
+    let num_meshes = c.read_u32().ok_or("Truncated asset pool header")? as usize;
+    let num_textures = c.read_u32().ok_or("Truncated asset pool header")? as usize;
+    let num_materials = c.read_u32().ok_or("Truncated asset pool header")? as usize;
+    c.skip(4); // reserved
+
+    let meshes = read_meshes(&mut c, num_meshes)?;
+    let materials = read_materials(&mut c, num_materials);
+    let textures = read_textures(&mut c, num_textures)?;
+    let bad_texture = |idx: i32| idx != -1 && usize::try_from(idx).map_or(true, |i| i >= textures.len());
+    for (i, m) in materials.iter().enumerate() {
+        if let Some(bad) = m.texture_indices().into_iter().find(|&idx| bad_texture(idx)) {
+            return Err(format!("Material {i}: texture index {bad} outside asset pool"));
+        }
+    }
+
+    // ── Scene table ──
+    let mut scenes = Vec::with_capacity(num_scenes);
+    for _ in 0..num_scenes {
+        let name_len = c.read_u16().ok_or("Truncated scene name")? as usize;
+        let name_bytes = c.read_bytes(name_len).ok_or("Truncated scene name")?;
+        let name = String::from_utf8_lossy(name_bytes).to_string();
+        let size = c.read_u64().ok_or("Truncated scene size")? as usize;
+        let blob = c.read_bytes(size).ok_or("Truncated scene data")?;
+        let scene = parse_orsb(blob).map_err(|e| format!("Scene '{name}': {e}"))?;
+
+        if let Some(&bad) = scene.mesh_indices.iter().flatten().find(|&&i| i >= meshes.len()) {
+            return Err(format!("Scene '{name}': mesh index {bad} outside asset pool"));
+        }
+        if let Some(&bad) = scene.material_indices.iter().flatten().find(|&&i| i >= materials.len()) {
+            return Err(format!("Scene '{name}': material index {bad} outside asset pool"));
+        }
+        let mut terrain_textures = scene.terrains.iter().flat_map(|t| &t.layers).flat_map(|l| [l.albedo_texture_index, l.normal_texture_index]);
+        if let Some(bad) = terrain_textures.find(|&idx| bad_texture(idx)) {
+            return Err(format!("Scene '{name}': terrain texture index {bad} outside asset pool"));
+        }
+        scenes.push(BundleScene { name, scene });
+    }
+
+    if !scenes.is_empty() && default_scene >= scenes.len() {
+        return Err(format!("Default scene {default_scene} out of range"));
+    }
+
+    Ok(ParsedBundle { meshes, materials, textures, scenes, default_scene })
+}
+
+/// Serialize a bundle. Scenes must not carry their own assets.
+pub fn write_bundle(bundle: &ParsedBundle) -> Vec<u8> {
+    let mut w = Writer::new();
+
+    w.write_bytes(&BUNDLE_MAGIC);
+    w.write_u32(if meshes_encoded(&bundle.meshes) { BUNDLE_VERSION_MESH_ENCODING } else { BUNDLE_VERSION });
+    w.write_u32(bundle.scenes.len() as u32);
+    w.write_u32(bundle.default_scene as u32);
+
+    w.write_u32(bundle.meshes.len() as u32);
+    w.write_u32(bundle.textures.len() as u32);
+    w.write_u32(bundle.materials.len() as u32);
+    w.write_u32(0); // reserved
+    write_meshes(&mut w, &bundle.meshes);
+    write_materials(&mut w, &bundle.materials);
+    write_textures(&mut w, &bundle.textures);
+
+    for s in &bundle.scenes {
+        debug_assert!(
+            s.scene.meshes.is_empty() && s.scene.materials.is_empty() && s.scene.textures.is_empty(),
+            "bundle scene '{}' carries its own assets",
+            s.name,
+        );
+        let blob = write_orsb(&s.scene);
+        w.write_str16(&s.name);
+        w.write_u64(blob.len() as u64);
+        w.write_bytes(&blob);
+    }
+
+    w.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{material, texture};
+
+    fn triangle() -> MeshParsed {
+        MeshParsed {
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.5, 1.0, 0.0],
+            normals: vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+            uvs: vec![0.0; 6],
+            indices: vec![0, 1, 2],
+            bone_weights: None,
+            bone_indices: None,
+            encoding: MeshEncoding::default(),
+        }
+    }
+
+    /// One entity drawing mesh 0 with material 0.
+    fn scene_with(meshes: Vec<MeshParsed>, materials: Vec<MaterialData>, textures: Vec<TextureParsed>) -> ParsedScene {
+        ParsedScene {
+            entity_ids: vec![1],
+            parent_indices: vec![None],
+            component_masks: vec![ComponentMask(ComponentMask::TRANSFORM | ComponentMask::MESH | ComponentMask::MATERIAL)],
+            mesh_indices: vec![Some(meshes.len() - 1)],
+            material_indices: vec![Some(materials.len() - 1)],
+            transforms: vec![TransformData { position: [0.0; 3], rotation: [1.0, 0.0, 0.0, 0.0], scale: [1.0; 3] }],
+            meshes,
+            materials,
+            textures,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_from_scenes_shares_identical_assets() {
+        let a = scene_with(vec![triangle()], vec![material(0)], vec![texture(255)]);
+        let b = scene_with(vec![triangle()], vec![material(0)], vec![texture(255)]);
+        let bundle = ParsedBundle::from_scenes(vec![("a".into(), a), ("b".into(), b)]);
+
+        assert_eq!(bundle.meshes.len(), 1);
+        assert_eq!(bundle.materials.len(), 1);
+        assert_eq!(bundle.textures.len(), 1);
+        assert_eq!(bundle.scenes[1].scene.mesh_indices[0], Some(0));
+        assert!(bundle.scenes[1].scene.meshes.is_empty());
+    }
+
+    #[test]
+    fn test_from_scenes_remaps_indices() {
+        let a = scene_with(vec![triangle()], vec![material(0)], vec![texture(1)]);
+        let mut other = triangle();
+        other.positions[0] = 2.0;
+        // Scene b's material references its own texture 1, which lands at pool index 1.
+        let b = scene_with(vec![triangle(), other], vec![material(-1), material(1)], vec![texture(1), texture(2)]);
+        let bundle = ParsedBundle::from_scenes(vec![("a".into(), a), ("b".into(), b)]);
+
+        assert_eq!(bundle.meshes.len(), 2);
+        assert_eq!(bundle.textures.len(), 2);
+        let b = &bundle.scenes[1].scene;
+        assert_eq!(b.mesh_indices[0], Some(1));
+        let mat = &bundle.materials[b.material_indices[0].unwrap()];
+        assert_eq!(mat.albedo_texture_index, 1);
+        assert_eq!(bundle.textures[1].data, vec![2; 4]);
+    }
+
+    #[test]
+    fn test_bundle_roundtrip() {
+        let a = scene_with(vec![triangle()], vec![material(0)], vec![texture(7)]);
+        let b = scene_with(vec![triangle()], vec![material(-1)], vec![]);
+        let mut bundle = ParsedBundle::from_scenes(vec![("menu".into(), a), ("level1".into(), b)]);
+        bundle.default_scene = 1;
+
+        let bytes = write_bundle(&bundle);
+        let parsed = parse_bundle(&bytes).unwrap();
+        assert_eq!(parsed.scene_names(), vec!["menu", "level1"]);
+        assert_eq!(parsed.default_scene, 1);
+        assert_eq!(parsed.meshes.len(), 1);
+        assert_eq!(parsed.materials.len(), 2);
+        assert_eq!(parsed.textures[0].data, vec![7; 4]);
+        assert_eq!(parsed.scenes[1].scene.material_indices[0], Some(1));
+        assert_eq!(parsed.scene_index("level1"), Some(1));
+        assert_eq!(parsed.scene_index("missing"), None);
+    }
+
+    #[test]
+    fn test_repacking_packed_bundle_saves_nothing() {
+        let scenes = (0..3)
+            .map(|i| (format!("level{i}"), scene_with(vec![triangle()], vec![material(0)], vec![texture(7)])))
+            .collect();
+        let bundle = ParsedBundle::from_scenes(scenes);
+        let parsed = parse_bundle(&write_bundle(&bundle)).unwrap();
+
+        let (repacked, report) = ParsedBundle::merge(vec![parsed]);
+        assert_eq!(report.bytes_saved(), 0);
+        assert_eq!(report.meshes.count_before, 1);
+        assert_eq!(repacked.scene_names(), vec!["level0", "level1", "level2"]);
+        assert_eq!(write_bundle(&repacked), write_bundle(&bundle));
+    }
+
+    #[test]
+    fn test_merge_drops_unused_pool_assets() {
+        let scene = scene_with(vec![triangle()], vec![material(1)], vec![texture(1), texture(2)]);
+        let mut bundle = ParsedBundle::wrap_scene("main".into(), scene);
+        let mut unused = triangle();
+        unused.positions[0] = 5.0;
+        bundle.meshes.insert(0, unused);
+        bundle.scenes[0].scene.mesh_indices[0] = Some(1);
+
+        let (merged, report) = ParsedBundle::merge(vec![bundle]);
+        assert_eq!(report.bytes_saved(), 0);
+        assert_eq!(merged.meshes.len(), 1);
+        assert_eq!(merged.meshes[0].positions[0], 0.0);
+        assert_eq!(merged.scenes[0].scene.mesh_indices[0], Some(0));
+        assert_eq!(merged.textures.len(), 1);
+        assert_eq!(merged.textures[0].data, vec![2; 4]);
+        assert_eq!(merged.materials[0].albedo_texture_index, 0);
+    }
+
+    #[test]
+    fn test_encoded_pool_writes_version_2() {
+        let scene = scene_with(vec![triangle()], vec![material(-1)], vec![]);
+        let mut bundle = ParsedBundle::from_single(scene);
+        let version = |bytes: &[u8]| u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+        assert_eq!(version(&write_bundle(&bundle)), BUNDLE_VERSION);
+
+        bundle.meshes[0].encoding.set(MeshEncoding::NORMALS_OCT16);
+        let bytes = write_bundle(&bundle);
+        assert_eq!(version(&bytes), BUNDLE_VERSION_MESH_ENCODING);
+        assert_eq!(parse_bundle(&bytes).unwrap().meshes[0].encoding, bundle.meshes[0].encoding);
+    }
+
+    #[test]
+    fn test_parse_single_orsb_as_bundle() {
+        let scene = scene_with(vec![triangle()], vec![material(-1)], vec![]);
+        let bundle = parse_bundle(&write_orsb(&scene)).unwrap();
+        assert_eq!(bundle.scene_names(), vec![DEFAULT_SCENE_NAME]);
+        assert_eq!(bundle.meshes.len(), 1);
+        assert_eq!(bundle.meshes[0].indices, vec![0, 1, 2]);
+
+        let resolved = bundle.resolve_scene(0).unwrap();
+        assert_eq!(resolved.meshes.len(), 1);
+        assert_eq!(resolved.header.num_meshes, 1);
+    }
+
+    #[test]
+    fn test_parse_bundle_rejects_out_of_pool_index() {
+        let scene = scene_with(vec![triangle()], vec![material(-1)], vec![]);
+        let mut bundle = ParsedBundle::from_single(scene);
+        bundle.scenes[0].scene.mesh_indices[0] = Some(5);
+        assert!(parse_bundle(&write_bundle(&bundle)).is_err());
+    }
+
+    #[test]
+    fn test_parse_bundle_rejects_out_of_pool_texture() {
+        let scene = scene_with(vec![triangle()], vec![material(0)], vec![texture(1)]);
+        let mut bundle = ParsedBundle::from_single(scene);
+        bundle.materials[0].normal_texture_index = 1;
+        let err = parse_bundle(&write_bundle(&bundle)).unwrap_err();
+        assert!(err.contains("texture index 1"), "{err}");
+
+        bundle.materials[0].normal_texture_index = -2;
+        assert!(parse_bundle(&write_bundle(&bundle)).is_err());
+        bundle.materials[0].normal_texture_index = -1;
+        assert!(parse_bundle(&write_bundle(&bundle)).is_ok());
+
+        bundle.scenes[0].scene.terrains.push(TerrainParsed {
+            entity_index: 0,
+            size: [1.0, 1.0],
+            max_height: 1.0,
+            chunk_size: 2,
+            num_lod_levels: 1,
+            heightmap_width: 2,
+            heightmap_depth: 2,
+            heights: vec![0.0; 4],
+            splatmap_width: 0,
+            splatmap_height: 0,
+            splatmap: Vec::new(),
+            layers: vec![TerrainLayerParsed { albedo_texture_index: 3, normal_texture_index: -1, uv_scale: 1.0 }],
+        });
+        let err = parse_bundle(&write_bundle(&bundle)).unwrap_err();
+        assert!(err.contains("terrain texture index 3"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_bundle_invalid_magic() {
+        assert!(parse_bundle(&[0u8; 64]).is_err());
+    }
+}
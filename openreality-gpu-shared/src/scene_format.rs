@@ -6,6 +6,10 @@
 /// Magic bytes at the start of every .orsb file.
 pub const ORSB_MAGIC: [u8; 4] = *b"ORSB";
 pub const ORSB_VERSION: u32 = 1;
+/// Version written when any mesh has a non-zero `MeshEncoding`. Version 1
+/// readers skip the encoding word as padding, so they must reject the file
+/// rather than misread the quantized streams.
+pub const ORSB_VERSION_MESH_ENCODING: u32 = 2;
 
 /// File header (32 bytes).
 #[repr(C)]
@@ -21,6 +25,21 @@ pub struct OrsbHeader {
     pub num_animations: u32,
 }
 
+impl Default for OrsbHeader {
+    fn default() -> Self {
+        Self {
+            magic: ORSB_MAGIC,
+            version: ORSB_VERSION,
+            flags: 0,
+            num_entities: 0,
+            num_meshes: 0,
+            num_textures: 0,
+            num_materials: 0,
+            num_animations: 0,
+        }
+    }
+}
+
 /// Section identifiers in the table of contents.
 #[repr(u32)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -38,6 +57,32 @@ pub enum SectionType {
     Skeletons = 11,
     Particles = 12,
     PhysicsConfig = 13,
+    Scripts = 14,
+    GameState = 15,
+    Terrains = 16,
+}
+
+impl SectionType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SectionType::EntityGraph => "entity_graph",
+            SectionType::Transforms => "transforms",
+            SectionType::Meshes => "meshes",
+            SectionType::Materials => "materials",
+            SectionType::Textures => "textures",
+            SectionType::Lights => "lights",
+            SectionType::Cameras => "cameras",
+            SectionType::Colliders => "colliders",
+            SectionType::RigidBodies => "rigidbodies",
+            SectionType::Animations => "animations",
+            SectionType::Skeletons => "skeletons",
+            SectionType::Particles => "particles",
+            SectionType::PhysicsConfig => "physics_config",
+            SectionType::Scripts => "scripts",
+            SectionType::GameState => "game_state",
+            SectionType::Terrains => "terrains",
+        }
+    }
 }
 
 /// Table of contents entry.
@@ -49,6 +94,15 @@ pub struct TocEntry {
     pub size: u64,
 }
 
+/// Byte range a section occupies in a parsed .orsb file. ORSB sections are
+/// sequential with no table of contents, so these are recorded while parsing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SectionSpan {
+    pub section: SectionType,
+    pub offset: usize,
+    pub size: usize,
+}
+
 /// Component mask bitfield — indicates which components an entity has.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default)]
@@ -69,6 +123,7 @@ impl ComponentMask {
     pub const AUDIO_SOURCE: u64 = 1 << 11;
     pub const AUDIO_LISTENER: u64 = 1 << 12;
     pub const IBL: u64 = 1 << 13;
+    pub const TERRAIN: u64 = 1 << 14;
 
     pub fn has(&self, flag: u64) -> bool {
         self.0 & flag != 0
@@ -79,6 +134,30 @@ impl ComponentMask {
     }
 }
 
+/// Storage encoding of a mesh's vertex and index streams, kept in the fourth
+/// word of the mesh header. Zero (what the Julia exporter writes) means plain
+/// f32 attributes and u32 indices; anything else needs a version 2 file.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MeshEncoding(pub u32);
+
+impl MeshEncoding {
+    /// Indices stored as u16, padded to a 4-byte boundary.
+    pub const INDICES_U16: u32 = 1 << 0;
+    /// Normals stored as octahedral-mapped 2x snorm16.
+    pub const NORMALS_OCT16: u32 = 1 << 1;
+    /// UVs stored as 2x unorm16 over the mesh's UV bounds (4 f32 prefix).
+    pub const UVS_UNORM16: u32 = 1 << 2;
+
+    pub fn has(&self, flag: u32) -> bool {
+        self.0 & flag != 0
+    }
+
+    pub fn set(&mut self, flag: u32) {
+        self.0 |= flag;
+    }
+}
+
 /// Entity entry in the entity graph section.
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
@@ -144,6 +223,34 @@ pub struct MaterialData {
     pub _pad: i32,
 }
 
+impl MaterialData {
+    /// All seven texture index slots, in section order.
+    pub fn texture_indices(&self) -> [i32; 7] {
+        [
+            self.albedo_texture_index,
+            self.normal_texture_index,
+            self.metallic_roughness_texture_index,
+            self.ao_texture_index,
+            self.emissive_texture_index,
+            self.height_texture_index,
+            self.clearcoat_texture_index,
+        ]
+    }
+
+    /// Mutable references to all seven texture index slots, in section order.
+    pub fn texture_indices_mut(&mut self) -> [&mut i32; 7] {
+        [
+            &mut self.albedo_texture_index,
+            &mut self.normal_texture_index,
+            &mut self.metallic_roughness_texture_index,
+            &mut self.ao_texture_index,
+            &mut self.emissive_texture_index,
+            &mut self.height_texture_index,
+            &mut self.clearcoat_texture_index,
+        ]
+    }
+}
+
 /// Texture header in the texture section.
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
@@ -230,6 +337,20 @@ pub struct PhysicsConfigData {
     pub slop: f32,
 }
 
+impl Default for PhysicsConfigData {
+    /// Matches the Julia `PhysicsWorldConfig()` defaults.
+    fn default() -> Self {
+        Self {
+            gravity: [0.0, -9.81, 0.0],
+            fixed_dt: 1.0 / 120.0,
+            max_substeps: 8,
+            solver_iterations: 10,
+            position_correction: 0.2,
+            slop: 0.005,
+        }
+    }
+}
+
 /// Parsed point light from the lights section.
 #[derive(Clone, Debug)]
 pub struct PointLightParsed {
@@ -302,6 +423,9 @@ pub struct MeshParsed {
     pub indices: Vec<u32>,
     pub bone_weights: Option<Vec<f32>>,
     pub bone_indices: Option<Vec<u16>>,
+    /// How the mesh is stored on disk. The fields above always hold decoded
+    /// f32/u32 data; the writer re-encodes according to these flags.
+    pub encoding: MeshEncoding,
 }
 
 /// Parsed texture data.
@@ -372,8 +496,52 @@ pub struct ParticleConfigParsed {
     pub additive: bool,
 }
 
-/// Complete parsed ORSB scene — all sections.
+/// One splat-map layer of a terrain.
+#[derive(Clone, Copy, Debug)]
+pub struct TerrainLayerParsed {
+    /// Index into the textures section, or -1.
+    pub albedo_texture_index: i32,
+    pub normal_texture_index: i32,
+    /// World-space tiling factor.
+    pub uv_scale: f32,
+}
+
+/// Most levels of detail a terrain may declare.
+pub const MAX_TERRAIN_LOD_LEVELS: u32 = 32;
+
+/// Parsed heightmap terrain. The terrain is centred on the world origin and
+/// split into chunks of `chunk_size` vertices per edge, each with
+/// `num_lod_levels` levels of detail.
 #[derive(Clone, Debug)]
+pub struct TerrainParsed {
+    pub entity_index: u32,
+    /// World-space X and Z extent.
+    pub size: [f32; 2],
+    pub max_height: f32,
+    pub chunk_size: u32,
+    pub num_lod_levels: u32,
+    /// Heightmap samples along X and Z.
+    pub heightmap_width: u32,
+    pub heightmap_depth: u32,
+    /// World-space heights, row-major by Z (`heights[z * width + x]`).
+    pub heights: Vec<f32>,
+    /// RGBA8 splat weights, one channel per layer. Empty if absent.
+    pub splatmap_width: u32,
+    pub splatmap_height: u32,
+    pub splatmap: Vec<u8>,
+    /// Up to four layers.
+    pub layers: Vec<TerrainLayerParsed>,
+}
+
+impl TerrainParsed {
+    /// Texture indices referenced by the layers, for remapping.
+    pub fn texture_indices_mut(&mut self) -> impl Iterator<Item = &mut i32> {
+        self.layers.iter_mut().flat_map(|l| [&mut l.albedo_texture_index, &mut l.normal_texture_index])
+    }
+}
+
+/// Complete parsed ORSB scene — all sections.
+#[derive(Clone, Debug, Default)]
 pub struct ParsedScene {
     pub header: OrsbHeader,
     pub entity_ids: Vec<u64>,
@@ -396,25 +564,26 @@ pub struct ParsedScene {
     pub physics_config: Option<PhysicsConfigData>,
     pub scripts: Vec<ScriptParsed>,
     pub game_refs: Vec<GameRefParsed>,
+    pub terrains: Vec<TerrainParsed>,
 }
 
 // ── Cursor-based binary reader helpers ──
 
-struct Cursor<'a> {
+pub(crate) struct Cursor<'a> {
     data: &'a [u8],
     pos: usize,
 }
 
 impl<'a> Cursor<'a> {
-    fn new(data: &'a [u8]) -> Self {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
         Self { data, pos: 0 }
     }
 
-    fn remaining(&self) -> usize {
+    pub(crate) fn remaining(&self) -> usize {
         self.data.len().saturating_sub(self.pos)
     }
 
-    fn read_u8(&mut self) -> Option<u8> {
+    pub(crate) fn read_u8(&mut self) -> Option<u8> {
         if self.pos < self.data.len() {
             let v = self.data[self.pos];
             self.pos += 1;
@@ -424,7 +593,7 @@ impl<'a> Cursor<'a> {
         }
     }
 
-    fn read_u16(&mut self) -> Option<u16> {
+    pub(crate) fn read_u16(&mut self) -> Option<u16> {
         if self.pos + 2 <= self.data.len() {
             let v = u16::from_le_bytes(self.data[self.pos..self.pos + 2].try_into().ok()?);
             self.pos += 2;
@@ -434,7 +603,7 @@ impl<'a> Cursor<'a> {
         }
     }
 
-    fn read_u32(&mut self) -> Option<u32> {
+    pub(crate) fn read_u32(&mut self) -> Option<u32> {
         if self.pos + 4 <= self.data.len() {
             let v = u32::from_le_bytes(self.data[self.pos..self.pos + 4].try_into().ok()?);
             self.pos += 4;
@@ -444,7 +613,7 @@ impl<'a> Cursor<'a> {
         }
     }
 
-    fn read_u64(&mut self) -> Option<u64> {
+    pub(crate) fn read_u64(&mut self) -> Option<u64> {
         if self.pos + 8 <= self.data.len() {
             let v = u64::from_le_bytes(self.data[self.pos..self.pos + 8].try_into().ok()?);
             self.pos += 8;
@@ -454,7 +623,7 @@ impl<'a> Cursor<'a> {
         }
     }
 
-    fn read_i32(&mut self) -> Option<i32> {
+    pub(crate) fn read_i32(&mut self) -> Option<i32> {
         if self.pos + 4 <= self.data.len() {
             let v = i32::from_le_bytes(self.data[self.pos..self.pos + 4].try_into().ok()?);
             self.pos += 4;
@@ -464,7 +633,7 @@ impl<'a> Cursor<'a> {
         }
     }
 
-    fn read_f32(&mut self) -> Option<f32> {
+    pub(crate) fn read_f32(&mut self) -> Option<f32> {
         if self.pos + 4 <= self.data.len() {
             let v = f32::from_le_bytes(self.data[self.pos..self.pos + 4].try_into().ok()?);
             self.pos += 4;
@@ -474,7 +643,7 @@ impl<'a> Cursor<'a> {
         }
     }
 
-    fn read_f64(&mut self) -> Option<f64> {
+    pub(crate) fn read_f64(&mut self) -> Option<f64> {
         if self.pos + 8 <= self.data.len() {
             let v = f64::from_le_bytes(self.data[self.pos..self.pos + 8].try_into().ok()?);
             self.pos += 8;
@@ -484,7 +653,7 @@ impl<'a> Cursor<'a> {
         }
     }
 
-    fn read_i64(&mut self) -> Option<i64> {
+    pub(crate) fn read_i64(&mut self) -> Option<i64> {
         if self.pos + 8 <= self.data.len() {
             let v = i64::from_le_bytes(self.data[self.pos..self.pos + 8].try_into().ok()?);
             self.pos += 8;
@@ -494,7 +663,7 @@ impl<'a> Cursor<'a> {
         }
     }
 
-    fn read_bytes(&mut self, n: usize) -> Option<&'a [u8]> {
+    pub(crate) fn read_bytes(&mut self, n: usize) -> Option<&'a [u8]> {
         if self.pos + n <= self.data.len() {
             let slice = &self.data[self.pos..self.pos + n];
             self.pos += n;
@@ -504,7 +673,7 @@ impl<'a> Cursor<'a> {
         }
     }
 
-    fn skip(&mut self, n: usize) {
+    pub(crate) fn skip(&mut self, n: usize) {
         self.pos += n;
     }
 }
@@ -519,7 +688,7 @@ pub fn parse_header(data: &[u8]) -> Option<OrsbHeader> {
     }
 
     let version = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
-    if version != ORSB_VERSION {
+    if !(ORSB_VERSION..=ORSB_VERSION_MESH_ENCODING).contains(&version) {
         return None;
     }
 
@@ -537,6 +706,13 @@ pub fn parse_header(data: &[u8]) -> Option<OrsbHeader> {
 
 /// Parse a complete ORSB file into a `ParsedScene`.
 pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
+    parse_orsb_with_layout(data).map(|(scene, _)| scene)
+}
+
+/// Parse an ORSB file and also report where each section present in the
+/// file starts and how many bytes it takes. Optional trailing sections that
+/// the file doesn't contain are omitted.
+pub fn parse_orsb_with_layout(data: &[u8]) -> Result<(ParsedScene, Vec<SectionSpan>), String> {
     let header = parse_header(data).ok_or("Invalid ORSB header")?;
     let num_entities = header.num_entities as usize;
     let num_meshes = header.num_meshes as usize;
@@ -545,8 +721,10 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
 
     let mut c = Cursor::new(data);
     c.skip(32); // past header
+    let mut starts: Vec<(SectionType, usize)> = Vec::new();
 
     // ── Entity graph (28 bytes per entity) ──
+    starts.push((SectionType::EntityGraph, c.pos));
     let mut entity_ids = Vec::with_capacity(num_entities);
     let mut parent_indices = Vec::with_capacity(num_entities);
     let mut component_masks = Vec::with_capacity(num_entities);
@@ -568,6 +746,7 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
     }
 
     // ── Transforms (80 bytes per entity) ──
+    starts.push((SectionType::Transforms, c.pos));
     let mut transforms = Vec::with_capacity(num_entities);
     for _ in 0..num_entities {
         let px = c.read_f64().ok_or("Truncated transforms")?;
@@ -587,125 +766,19 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
         });
     }
 
-    // ── Meshes ──
-    let mut meshes = Vec::with_capacity(num_meshes);
-    for _ in 0..num_meshes {
-        let nv = c.read_u32().ok_or("Truncated mesh header")? as usize;
-        let ni = c.read_u32().ok_or("Truncated mesh header")? as usize;
-        let has_bones = c.read_u32().ok_or("Truncated mesh header")? != 0;
-        c.skip(4); // padding
-
-        let mut positions = Vec::with_capacity(nv * 3);
-        for _ in 0..nv * 3 {
-            positions.push(c.read_f32().ok_or("Truncated mesh positions")?);
-        }
-
-        let mut normals = Vec::with_capacity(nv * 3);
-        for _ in 0..nv * 3 {
-            normals.push(c.read_f32().ok_or("Truncated mesh normals")?);
-        }
-
-        let mut uvs = Vec::with_capacity(nv * 2);
-        for _ in 0..nv * 2 {
-            uvs.push(c.read_f32().ok_or("Truncated mesh uvs")?);
-        }
-
-        let mut indices = Vec::with_capacity(ni);
-        for _ in 0..ni {
-            indices.push(c.read_u32().ok_or("Truncated mesh indices")?);
-        }
-
-        let (bone_weights, bone_indices) = if has_bones {
-            let mut bw = Vec::with_capacity(nv * 4);
-            for _ in 0..nv * 4 {
-                bw.push(c.read_f32().ok_or("Truncated bone weights")?);
-            }
-            let mut bi = Vec::with_capacity(nv * 4);
-            for _ in 0..nv * 4 {
-                bi.push(c.read_u16().ok_or("Truncated bone indices")?);
-            }
-            (Some(bw), Some(bi))
-        } else {
-            (None, None)
-        };
-
-        meshes.push(MeshParsed { positions, normals, uvs, indices, bone_weights, bone_indices });
-    }
-
-    // ── Materials (96 bytes each) ──
-    let mut materials = Vec::with_capacity(num_materials);
-    for _ in 0..num_materials {
-        if c.remaining() < 96 {
-            break;
-        }
-        let color = [c.read_f32().unwrap(), c.read_f32().unwrap(), c.read_f32().unwrap(), c.read_f32().unwrap()];
-        let metallic = c.read_f32().unwrap();
-        let roughness = c.read_f32().unwrap();
-        let opacity = c.read_f32().unwrap();
-        let alpha_cutoff = c.read_f32().unwrap();
-        let emissive_factor = [c.read_f32().unwrap(), c.read_f32().unwrap(), c.read_f32().unwrap(), c.read_f32().unwrap()];
-        let clearcoat = c.read_f32().unwrap();
-        let clearcoat_roughness = c.read_f32().unwrap();
-        let subsurface = c.read_f32().unwrap();
-        // The Julia exporter writes parallax_height_scale here, not subsurface_color
-        // Reread: subsurface_color is 3 floats then parallax. Let me check.
-        // Actually from scene_format.rs struct: subsurface_color: [f32; 3], parallax_height_scale: f32
-        // But the Julia exporter writes: clearcoat, clearcoat_roughness, subsurface, parallax (4 floats)
-        // There's a mismatch. The Julia exporter skips subsurface_color.
-        // To match the Julia exporter, we read the 4th float as parallax_height_scale.
-        let parallax_height_scale = c.read_f32().unwrap();
-        let albedo_texture_index = c.read_i32().unwrap();
-        let normal_texture_index = c.read_i32().unwrap();
-        let metallic_roughness_texture_index = c.read_i32().unwrap();
-        let ao_texture_index = c.read_i32().unwrap();
-        let emissive_texture_index = c.read_i32().unwrap();
-        let height_texture_index = c.read_i32().unwrap();
-        let clearcoat_texture_index = c.read_i32().unwrap();
-        let _pad = c.read_i32().unwrap();
-
-        materials.push(MaterialData {
-            color,
-            metallic,
-            roughness,
-            opacity,
-            alpha_cutoff,
-            emissive_factor,
-            clearcoat,
-            clearcoat_roughness,
-            subsurface,
-            subsurface_color: [0.0; 3],
-            parallax_height_scale,
-            albedo_texture_index,
-            normal_texture_index,
-            metallic_roughness_texture_index,
-            ao_texture_index,
-            emissive_texture_index,
-            height_texture_index,
-            clearcoat_texture_index,
-            _pad,
-        });
-    }
-
-    // ── Textures ──
-    let mut textures = Vec::with_capacity(num_textures);
-    for _ in 0..num_textures {
-        let width = c.read_u32().ok_or("Truncated texture header")?;
-        let height = c.read_u32().ok_or("Truncated texture header")?;
-        let channels = c.read_u32().ok_or("Truncated texture header")?;
-        let compression = c.read_u32().ok_or("Truncated texture header")?;
-        let data_size = c.read_u64().ok_or("Truncated texture header")? as usize;
-        let data = if data_size > 0 {
-            c.read_bytes(data_size).ok_or("Truncated texture data")?.to_vec()
-        } else {
-            Vec::new()
-        };
-        textures.push(TextureParsed { width, height, channels, compression, data });
-    }
+    // ── Meshes, materials, textures ──
+    starts.push((SectionType::Meshes, c.pos));
+    let meshes = read_meshes(&mut c, num_meshes)?;
+    starts.push((SectionType::Materials, c.pos));
+    let materials = read_materials(&mut c, num_materials);
+    starts.push((SectionType::Textures, c.pos));
+    let textures = read_textures(&mut c, num_textures)?;
 
     // ── Lights ──
     let mut point_lights = Vec::new();
     let mut dir_lights = Vec::new();
     if c.remaining() >= 4 {
+        starts.push((SectionType::Lights, c.pos));
         let n_point = c.read_u32().unwrap() as usize;
         for _ in 0..n_point {
             if c.remaining() < 32 { break; }
@@ -731,6 +804,7 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
     // ── Cameras ──
     let mut cameras = Vec::new();
     if c.remaining() >= 4 {
+        starts.push((SectionType::Cameras, c.pos));
         let n_cam = c.read_u32().unwrap() as usize;
         for _ in 0..n_cam {
             if c.remaining() < 16 { break; }
@@ -745,6 +819,7 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
     // ── Colliders ──
     let mut colliders = Vec::new();
     if c.remaining() >= 4 {
+        starts.push((SectionType::Colliders, c.pos));
         let n_col = c.read_u32().unwrap() as usize;
         for _ in 0..n_col {
             if c.remaining() < 29 { break; }
@@ -760,6 +835,7 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
     // ── RigidBodies ──
     let mut rigidbodies = Vec::new();
     if c.remaining() >= 4 {
+        starts.push((SectionType::RigidBodies, c.pos));
         let n_rb = c.read_u32().unwrap() as usize;
         for _ in 0..n_rb {
             if c.remaining() < 40 { break; }
@@ -781,6 +857,7 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
     // ── Animations ──
     let mut animations = Vec::new();
     if c.remaining() >= 4 {
+        starts.push((SectionType::Animations, c.pos));
         let n_anim = c.read_u32().unwrap_or(0) as usize;
         for _ in 0..n_anim {
             let num_clips = c.read_u32().ok_or("Truncated animation")? as usize;
@@ -844,6 +921,7 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
     // ── Skeletons ──
     let mut skeletons = Vec::new();
     if c.remaining() >= 4 {
+        starts.push((SectionType::Skeletons, c.pos));
         let num_skeletons = c.read_u32().unwrap() as usize;
         for _ in 0..num_skeletons {
             let num_bones = c.read_u32().ok_or("Truncated skeleton bone count")? as usize;
@@ -873,6 +951,7 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
     // ── Particles ──
     let mut particles = Vec::new();
     if c.remaining() >= 4 {
+        starts.push((SectionType::Particles, c.pos));
         let num_particles = c.read_u32().unwrap() as usize;
         for _ in 0..num_particles {
             let max_particles = c.read_u32().ok_or("Truncated particle config")?;
@@ -928,6 +1007,7 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
 
     // ── Physics config ──
     let physics_config = if c.remaining() >= 48 {
+        starts.push((SectionType::PhysicsConfig, c.pos));
         let gravity = [c.read_f64().unwrap(), c.read_f64().unwrap(), c.read_f64().unwrap()];
         let fixed_dt = c.read_f64().unwrap();
         let max_substeps = c.read_u32().unwrap();
@@ -944,6 +1024,7 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
     // ── Scripts ──
     let mut scripts = Vec::new();
     if c.remaining() >= 4 {
+        starts.push((SectionType::Scripts, c.pos));
         let num_scripts = c.read_u32().unwrap() as usize;
         for _ in 0..num_scripts {
             let entity_index = c.read_u32().ok_or("Truncated script entity index")?;
@@ -958,6 +1039,7 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
     // ── Game State (Refs) ──
     let mut game_refs = Vec::new();
     if c.remaining() >= 4 {
+        starts.push((SectionType::GameState, c.pos));
         let num_refs = c.read_u32().unwrap() as usize;
         for _ in 0..num_refs {
             let name_len = c.read_u16().ok_or("Truncated game ref name length")? as usize;
@@ -979,7 +1061,23 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
         }
     }
 
-    Ok(ParsedScene {
+    // ── Terrains ──
+    let mut terrains = Vec::new();
+    if c.remaining() >= 4 {
+        starts.push((SectionType::Terrains, c.pos));
+        let num_terrains = c.read_u32().unwrap() as usize;
+        for _ in 0..num_terrains {
+            terrains.push(read_terrain(&mut c)?);
+        }
+    }
+
+    let end = c.pos.min(data.len());
+    let sections = starts.iter().enumerate().map(|(i, &(section, offset))| {
+        let next = starts.get(i + 1).map_or(end, |&(_, o)| o);
+        SectionSpan { section, offset, size: next.saturating_sub(offset) }
+    }).collect();
+
+    let scene = ParsedScene {
         header,
         entity_ids,
         parent_indices,
@@ -1001,9 +1099,627 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
         physics_config,
         scripts,
         game_refs,
+        terrains,
+    };
+    Ok((scene, sections))
+}
+
+fn read_terrain(c: &mut Cursor) -> Result<TerrainParsed, String> {
+    const TRUNC: &str = "Truncated terrain";
+    let entity_index = c.read_u32().ok_or(TRUNC)?;
+    let size = [c.read_f32().ok_or(TRUNC)?, c.read_f32().ok_or(TRUNC)?];
+    let max_height = c.read_f32().ok_or(TRUNC)?;
+    let chunk_size = c.read_u32().ok_or(TRUNC)?;
+    let num_lod_levels = c.read_u32().ok_or(TRUNC)?;
+    let heightmap_width = c.read_u32().ok_or(TRUNC)?;
+    let heightmap_depth = c.read_u32().ok_or(TRUNC)?;
+    let splatmap_width = c.read_u32().ok_or(TRUNC)?;
+    let splatmap_height = c.read_u32().ok_or(TRUNC)?;
+    let num_layers = c.read_u32().ok_or(TRUNC)?;
+    if chunk_size < 2 || heightmap_width < 2 || heightmap_depth < 2 || num_layers > 4
+        || num_lod_levels == 0 || num_lod_levels > MAX_TERRAIN_LOD_LEVELS
+    {
+        return Err("Invalid terrain parameters".to_string());
+    }
+
+    let mut layers = Vec::with_capacity(num_layers as usize);
+    for _ in 0..num_layers {
+        let albedo_texture_index = c.read_i32().ok_or(TRUNC)?;
+        let normal_texture_index = c.read_i32().ok_or(TRUNC)?;
+        let uv_scale = c.read_f32().ok_or(TRUNC)?;
+        c.skip(4); // padding
+        layers.push(TerrainLayerParsed { albedo_texture_index, normal_texture_index, uv_scale });
+    }
+
+    let num_heights = heightmap_width as usize * heightmap_depth as usize;
+    let mut heights = Vec::with_capacity(num_heights);
+    for _ in 0..num_heights {
+        heights.push(c.read_f32().ok_or("Truncated terrain heightmap")?);
+    }
+    let splat_len = splatmap_width as usize * splatmap_height as usize * 4;
+    let splatmap = c.read_bytes(splat_len).ok_or("Truncated terrain splatmap")?.to_vec();
+
+    Ok(TerrainParsed {
+        entity_index,
+        size,
+        max_height,
+        chunk_size,
+        num_lod_levels,
+        heightmap_width,
+        heightmap_depth,
+        heights,
+        splatmap_width,
+        splatmap_height,
+        splatmap,
+        layers,
     })
 }
 
+/// Read `count` meshes in ORSB mesh-section encoding.
+pub(crate) fn read_meshes(c: &mut Cursor, count: usize) -> Result<Vec<MeshParsed>, String> {
+    let mut meshes = Vec::with_capacity(count);
+    for _ in 0..count {
+        let nv = c.read_u32().ok_or("Truncated mesh header")? as usize;
+        let ni = c.read_u32().ok_or("Truncated mesh header")? as usize;
+        let has_bones = c.read_u32().ok_or("Truncated mesh header")? != 0;
+        let encoding = MeshEncoding(c.read_u32().ok_or("Truncated mesh header")?);
+
+        let mut positions = Vec::with_capacity(nv * 3);
+        for _ in 0..nv * 3 {
+            positions.push(c.read_f32().ok_or("Truncated mesh positions")?);
+        }
+
+        let mut normals = Vec::with_capacity(nv * 3);
+        if encoding.has(MeshEncoding::NORMALS_OCT16) {
+            for _ in 0..nv {
+                let x = c.read_u16().ok_or("Truncated mesh normals")? as i16;
+                let y = c.read_u16().ok_or("Truncated mesh normals")? as i16;
+                normals.extend_from_slice(&oct_decode(x, y));
+            }
+        } else {
+            for _ in 0..nv * 3 {
+                normals.push(c.read_f32().ok_or("Truncated mesh normals")?);
+            }
+        }
+
+        let mut uvs = Vec::with_capacity(nv * 2);
+        if encoding.has(MeshEncoding::UVS_UNORM16) {
+            let mut bounds = [0.0f32; 4];
+            for b in &mut bounds {
+                *b = c.read_f32().ok_or("Truncated mesh uv bounds")?;
+            }
+            for i in 0..nv * 2 {
+                let q = c.read_u16().ok_or("Truncated mesh uvs")?;
+                let (lo, hi) = (bounds[i % 2], bounds[2 + i % 2]);
+                uvs.push(lo + (hi - lo) * (q as f32 / u16::MAX as f32));
+            }
+        } else {
+            for _ in 0..nv * 2 {
+                uvs.push(c.read_f32().ok_or("Truncated mesh uvs")?);
+            }
+        }
+
+        let mut indices = Vec::with_capacity(ni);
+        if encoding.has(MeshEncoding::INDICES_U16) {
+            for _ in 0..ni {
+                indices.push(c.read_u16().ok_or("Truncated mesh indices")? as u32);
+            }
+            if ni % 2 == 1 {
+                c.skip(2); // pad to 4 bytes
+            }
+        } else {
+            for _ in 0..ni {
+                indices.push(c.read_u32().ok_or("Truncated mesh indices")?);
+            }
+        }
+
+        let (bone_weights, bone_indices) = if has_bones {
+            let mut bw = Vec::with_capacity(nv * 4);
+            for _ in 0..nv * 4 {
+                bw.push(c.read_f32().ok_or("Truncated bone weights")?);
+            }
+            let mut bi = Vec::with_capacity(nv * 4);
+            for _ in 0..nv * 4 {
+                bi.push(c.read_u16().ok_or("Truncated bone indices")?);
+            }
+            (Some(bw), Some(bi))
+        } else {
+            (None, None)
+        };
+
+        meshes.push(MeshParsed { positions, normals, uvs, indices, bone_weights, bone_indices, encoding });
+    }
+    Ok(meshes)
+}
+
+/// Octahedral-map a unit normal to two snorm16 values.
+pub fn oct_encode(n: [f32; 3]) -> (i16, i16) {
+    let len = n[0].abs() + n[1].abs() + n[2].abs();
+    if len == 0.0 {
+        return (0, i16::MAX);
+    }
+    let (mut x, mut y) = (n[0] / len, n[1] / len);
+    if n[2] < 0.0 {
+        let (ox, oy) = (x, y);
+        x = (1.0 - oy.abs()) * ox.signum();
+        y = (1.0 - ox.abs()) * oy.signum();
+    }
+    let q = |v: f32| (v.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+    (q(x), q(y))
+}
+
+/// Inverse of [`oct_encode`]; returns a unit normal.
+pub fn oct_decode(qx: i16, qy: i16) -> [f32; 3] {
+    let x = (qx as f32 / i16::MAX as f32).max(-1.0);
+    let y = (qy as f32 / i16::MAX as f32).max(-1.0);
+    let z = 1.0 - x.abs() - y.abs();
+    let t = (-z).max(0.0);
+    let x = x - t * x.signum();
+    let y = y - t * y.signum();
+    let len = (x * x + y * y + z * z).sqrt();
+    if len == 0.0 {
+        [0.0, 0.0, 1.0]
+    } else {
+        [x / len, y / len, z / len]
+    }
+}
+
+/// Read up to `count` materials (96 bytes each). Stops early on a short section.
+pub(crate) fn read_materials(c: &mut Cursor, count: usize) -> Vec<MaterialData> {
+    let mut materials = Vec::with_capacity(count);
+    for _ in 0..count {
+        if c.remaining() < 96 {
+            break;
+        }
+        let color = [c.read_f32().unwrap(), c.read_f32().unwrap(), c.read_f32().unwrap(), c.read_f32().unwrap()];
+        let metallic = c.read_f32().unwrap();
+        let roughness = c.read_f32().unwrap();
+        let opacity = c.read_f32().unwrap();
+        let alpha_cutoff = c.read_f32().unwrap();
+        let emissive_factor = [c.read_f32().unwrap(), c.read_f32().unwrap(), c.read_f32().unwrap(), c.read_f32().unwrap()];
+        let clearcoat = c.read_f32().unwrap();
+        let clearcoat_roughness = c.read_f32().unwrap();
+        let subsurface = c.read_f32().unwrap();
+        // The Julia exporter writes parallax_height_scale here, not subsurface_color
+        // Reread: subsurface_color is 3 floats then parallax. Let me check.
+        // Actually from scene_format.rs struct: subsurface_color: [f32; 3], parallax_height_scale: f32
+        // But the Julia exporter writes: clearcoat, clearcoat_roughness, subsurface, parallax (4 floats)
+        // There's a mismatch. The Julia exporter skips subsurface_color.
+        // To match the Julia exporter, we read the 4th float as parallax_height_scale.
+        let parallax_height_scale = c.read_f32().unwrap();
+        let albedo_texture_index = c.read_i32().unwrap();
+        let normal_texture_index = c.read_i32().unwrap();
+        let metallic_roughness_texture_index = c.read_i32().unwrap();
+        let ao_texture_index = c.read_i32().unwrap();
+        let emissive_texture_index = c.read_i32().unwrap();
+        let height_texture_index = c.read_i32().unwrap();
+        let clearcoat_texture_index = c.read_i32().unwrap();
+        let _pad = c.read_i32().unwrap();
+
+        materials.push(MaterialData {
+            color,
+            metallic,
+            roughness,
+            opacity,
+            alpha_cutoff,
+            emissive_factor,
+            clearcoat,
+            clearcoat_roughness,
+            subsurface,
+            subsurface_color: [0.0; 3],
+            parallax_height_scale,
+            albedo_texture_index,
+            normal_texture_index,
+            metallic_roughness_texture_index,
+            ao_texture_index,
+            emissive_texture_index,
+            height_texture_index,
+            clearcoat_texture_index,
+            _pad,
+        });
+    }
+    materials
+}
+
+/// Read `count` textures (24-byte header + payload each).
+pub(crate) fn read_textures(c: &mut Cursor, count: usize) -> Result<Vec<TextureParsed>, String> {
+    let mut textures = Vec::with_capacity(count);
+    for _ in 0..count {
+        let width = c.read_u32().ok_or("Truncated texture header")?;
+        let height = c.read_u32().ok_or("Truncated texture header")?;
+        let channels = c.read_u32().ok_or("Truncated texture header")?;
+        let compression = c.read_u32().ok_or("Truncated texture header")?;
+        let data_size = c.read_u64().ok_or("Truncated texture header")? as usize;
+        let data = if data_size > 0 {
+            c.read_bytes(data_size).ok_or("Truncated texture data")?.to_vec()
+        } else {
+            Vec::new()
+        };
+        textures.push(TextureParsed { width, height, channels, compression, data });
+    }
+    Ok(textures)
+}
+
+// ── Little-endian binary writer (inverse of `Cursor`) ──
+
+pub(crate) struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    pub(crate) fn new() -> Self {
+        Self { buf: Vec::new() }
+    }
+
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+
+    pub(crate) fn write_u8(&mut self, v: u8) {
+        self.buf.push(v);
+    }
+
+    pub(crate) fn write_u16(&mut self, v: u16) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub(crate) fn write_u32(&mut self, v: u32) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub(crate) fn write_u64(&mut self, v: u64) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub(crate) fn write_i32(&mut self, v: i32) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub(crate) fn write_i64(&mut self, v: i64) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub(crate) fn write_f32(&mut self, v: f32) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub(crate) fn write_f64(&mut self, v: f64) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub(crate) fn write_bytes(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Length-prefixed (u16) UTF-8 string, as used for clip, bone and ref names.
+    pub(crate) fn write_str16(&mut self, s: &str) {
+        self.write_u16(s.len() as u16);
+        self.write_bytes(s.as_bytes());
+    }
+}
+
+/// The encoding `write_meshes` stores for `m`: u16 indices are only honoured
+/// when every index fits.
+fn stored_encoding(m: &MeshParsed) -> MeshEncoding {
+    let mut encoding = m.encoding;
+    if encoding.has(MeshEncoding::INDICES_U16) && m.indices.iter().any(|&i| i > u16::MAX as u32) {
+        encoding.0 &= !MeshEncoding::INDICES_U16;
+    }
+    encoding
+}
+
+/// Whether `write_meshes` stores any of `meshes` with a non-zero encoding,
+/// which version 1 readers can't decode.
+pub(crate) fn meshes_encoded(meshes: &[MeshParsed]) -> bool {
+    meshes.iter().any(|m| stored_encoding(m) != MeshEncoding::default())
+}
+
+/// Write meshes in ORSB mesh-section encoding.
+pub(crate) fn write_meshes(w: &mut Writer, meshes: &[MeshParsed]) {
+    for m in meshes {
+        let has_bones = m.bone_weights.is_some() && m.bone_indices.is_some();
+        let nv = m.positions.len() / 3;
+        let encoding = stored_encoding(m);
+
+        w.write_u32(nv as u32);
+        w.write_u32(m.indices.len() as u32);
+        w.write_u32(has_bones as u32);
+        w.write_u32(encoding.0);
+        m.positions.iter().for_each(|&v| w.write_f32(v));
+
+        if encoding.has(MeshEncoding::NORMALS_OCT16) {
+            for n in m.normals.chunks_exact(3) {
+                let (x, y) = oct_encode([n[0], n[1], n[2]]);
+                w.write_u16(x as u16);
+                w.write_u16(y as u16);
+            }
+        } else {
+            m.normals.iter().for_each(|&v| w.write_f32(v));
+        }
+
+        if encoding.has(MeshEncoding::UVS_UNORM16) {
+            let mut lo = [f32::MAX; 2];
+            let mut hi = [f32::MIN; 2];
+            for uv in m.uvs.chunks_exact(2) {
+                for k in 0..2 {
+                    lo[k] = lo[k].min(uv[k]);
+                    hi[k] = hi[k].max(uv[k]);
+                }
+            }
+            if m.uvs.is_empty() {
+                lo = [0.0; 2];
+                hi = [0.0; 2];
+            }
+            [lo[0], lo[1], hi[0], hi[1]].iter().for_each(|&v| w.write_f32(v));
+            for (i, &v) in m.uvs.iter().enumerate() {
+                let range = hi[i % 2] - lo[i % 2];
+                let t = if range > 0.0 { (v - lo[i % 2]) / range } else { 0.0 };
+                w.write_u16((t.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16);
+            }
+        } else {
+            m.uvs.iter().for_each(|&v| w.write_f32(v));
+        }
+
+        if encoding.has(MeshEncoding::INDICES_U16) {
+            m.indices.iter().for_each(|&i| w.write_u16(i as u16));
+            if m.indices.len() % 2 == 1 {
+                w.write_u16(0); // pad to 4 bytes
+            }
+        } else {
+            m.indices.iter().for_each(|&i| w.write_u32(i));
+        }
+
+        if let (Some(bw), Some(bi)) = (&m.bone_weights, &m.bone_indices) {
+            bw.iter().for_each(|&v| w.write_f32(v));
+            bi.iter().for_each(|&v| w.write_u16(v));
+        }
+    }
+}
+
+/// Write materials (96 bytes each, Julia exporter layout).
+pub(crate) fn write_materials(w: &mut Writer, materials: &[MaterialData]) {
+    for m in materials {
+        m.color.iter().for_each(|&v| w.write_f32(v));
+        w.write_f32(m.metallic);
+        w.write_f32(m.roughness);
+        w.write_f32(m.opacity);
+        w.write_f32(m.alpha_cutoff);
+        m.emissive_factor.iter().for_each(|&v| w.write_f32(v));
+        w.write_f32(m.clearcoat);
+        w.write_f32(m.clearcoat_roughness);
+        w.write_f32(m.subsurface);
+        w.write_f32(m.parallax_height_scale);
+        w.write_i32(m.albedo_texture_index);
+        w.write_i32(m.normal_texture_index);
+        w.write_i32(m.metallic_roughness_texture_index);
+        w.write_i32(m.ao_texture_index);
+        w.write_i32(m.emissive_texture_index);
+        w.write_i32(m.height_texture_index);
+        w.write_i32(m.clearcoat_texture_index);
+        w.write_i32(m._pad);
+    }
+}
+
+/// Write textures (24-byte header + payload each).
+pub(crate) fn write_textures(w: &mut Writer, textures: &[TextureParsed]) {
+    for t in textures {
+        w.write_u32(t.width);
+        w.write_u32(t.height);
+        w.write_u32(t.channels);
+        w.write_u32(t.compression);
+        w.write_u64(t.data.len() as u64);
+        w.write_bytes(&t.data);
+    }
+}
+
+/// Serialize a `ParsedScene` to ORSB bytes — the inverse of `parse_orsb`.
+///
+/// Section counts in the header are taken from the scene's vectors, not from
+/// `scene.header`; only the header flags are carried over. The version is 1
+/// unless a mesh is stored encoded. A missing physics
+/// config is written with the engine defaults because the parser expects the
+/// section to precede scripts.
+pub fn write_orsb(scene: &ParsedScene) -> Vec<u8> {
+    let mut w = Writer::new();
+
+    // Header
+    w.write_bytes(&ORSB_MAGIC);
+    w.write_u32(if meshes_encoded(&scene.meshes) { ORSB_VERSION_MESH_ENCODING } else { ORSB_VERSION });
+    w.write_u32(scene.header.flags);
+    w.write_u32(scene.entity_ids.len() as u32);
+    w.write_u32(scene.meshes.len() as u32);
+    w.write_u32(scene.textures.len() as u32);
+    w.write_u32(scene.materials.len() as u32);
+    w.write_u32(scene.animations.len() as u32);
+
+    // Entity graph
+    let opt = |v: Option<usize>| v.map(|i| i as u32).unwrap_or(u32::MAX);
+    for i in 0..scene.entity_ids.len() {
+        w.write_u64(scene.entity_ids[i]);
+        w.write_u32(opt(scene.parent_indices[i]));
+        w.write_u64(scene.component_masks[i].0);
+        w.write_u32(opt(scene.mesh_indices[i]));
+        w.write_u32(opt(scene.material_indices[i]));
+    }
+
+    // Transforms
+    for t in &scene.transforms {
+        t.position.iter().for_each(|&v| w.write_f64(v));
+        t.rotation.iter().for_each(|&v| w.write_f64(v));
+        t.scale.iter().for_each(|&v| w.write_f64(v));
+    }
+
+    write_meshes(&mut w, &scene.meshes);
+    write_materials(&mut w, &scene.materials);
+    write_textures(&mut w, &scene.textures);
+
+    // Lights
+    w.write_u32(scene.point_lights.len() as u32);
+    for l in &scene.point_lights {
+        l.position.iter().for_each(|&v| w.write_f32(v));
+        l.color.iter().for_each(|&v| w.write_f32(v));
+        w.write_f32(l.intensity);
+        w.write_f32(l.range);
+    }
+    w.write_u32(scene.dir_lights.len() as u32);
+    for l in &scene.dir_lights {
+        l.direction.iter().for_each(|&v| w.write_f32(v));
+        l.color.iter().for_each(|&v| w.write_f32(v));
+        w.write_f32(l.intensity);
+        w.write_f32(0.0); // padding
+    }
+
+    // Cameras
+    w.write_u32(scene.cameras.len() as u32);
+    for cam in &scene.cameras {
+        w.write_f32(cam.fov);
+        w.write_f32(cam.near);
+        w.write_f32(cam.far);
+        w.write_f32(cam.aspect);
+    }
+
+    // Colliders
+    w.write_u32(scene.colliders.len() as u32);
+    for col in &scene.colliders {
+        w.write_u8(col.shape_type);
+        col.shape_data.iter().for_each(|&v| w.write_f32(v));
+        col.offset.iter().for_each(|&v| w.write_f32(v));
+        w.write_u8(col.is_trigger as u8);
+        w.write_bytes(&[0; 3]); // padding
+    }
+
+    // RigidBodies
+    w.write_u32(scene.rigidbodies.len() as u32);
+    for rb in &scene.rigidbodies {
+        w.write_u8(rb.body_type);
+        w.write_u8(rb.ccd_mode);
+        w.write_bytes(&[0; 2]); // padding
+        w.write_f64(rb.mass);
+        w.write_f32(rb.restitution);
+        w.write_f64(rb.friction);
+        w.write_f64(rb.linear_damping);
+        w.write_f64(rb.angular_damping);
+    }
+
+    // Animations
+    w.write_u32(scene.animations.len() as u32);
+    for anim in &scene.animations {
+        w.write_u32(anim.clips.len() as u32);
+        for clip in &anim.clips {
+            w.write_str16(&clip.name);
+            w.write_u32(clip.channels.len() as u32);
+            w.write_f32(clip.duration);
+            for ch in &clip.channels {
+                w.write_u32(ch.target_entity_index);
+                w.write_u8(ch.target_property as u8);
+                w.write_u8(ch.interpolation as u8);
+                w.write_u32(ch.times.len() as u32);
+                ch.times.iter().for_each(|&v| w.write_f32(v));
+                ch.values.iter().for_each(|&v| w.write_f64(v));
+            }
+        }
+        w.write_i32(anim.active_clip);
+        w.write_u8(anim.playing as u8);
+        w.write_u8(anim.looping as u8);
+        w.write_f32(anim.speed);
+    }
+
+    // Skeletons
+    w.write_u32(scene.skeletons.len() as u32);
+    for skel in &scene.skeletons {
+        w.write_u32(skel.bones.len() as u32);
+        for bone in &skel.bones {
+            w.write_u32(bone.entity_index);
+            bone.inverse_bind_matrix.iter().flatten().for_each(|&v| w.write_f32(v));
+            w.write_u32(bone.bone_index);
+            w.write_str16(&bone.name);
+        }
+    }
+
+    // Particles
+    w.write_u32(scene.particles.len() as u32);
+    for p in &scene.particles {
+        w.write_u32(p.max_particles);
+        w.write_f32(p.emission_rate);
+        w.write_u32(p.burst_count);
+        w.write_f32(p.lifetime_min);
+        w.write_f32(p.lifetime_max);
+        p.velocity_min.iter().for_each(|&v| w.write_f32(v));
+        p.velocity_max.iter().for_each(|&v| w.write_f32(v));
+        w.write_f32(p.gravity_modifier);
+        w.write_f32(p.damping);
+        w.write_f32(p.start_size_min);
+        w.write_f32(p.start_size_max);
+        w.write_f32(p.end_size);
+        p.start_color.iter().for_each(|&v| w.write_f32(v));
+        p.end_color.iter().for_each(|&v| w.write_f32(v));
+        w.write_f32(p.start_alpha);
+        w.write_f32(p.end_alpha);
+        w.write_u8(p.additive as u8);
+        w.write_bytes(&[0; 3]); // padding
+    }
+
+    // Physics config
+    let pc = scene.physics_config.unwrap_or_default();
+    pc.gravity.iter().for_each(|&v| w.write_f64(v));
+    w.write_f64(pc.fixed_dt);
+    w.write_u32(pc.max_substeps);
+    w.write_u32(pc.solver_iterations);
+    w.write_f32(pc.position_correction);
+    w.write_f32(pc.slop);
+
+    // Scripts
+    w.write_u32(scene.scripts.len() as u32);
+    for sc in &scene.scripts {
+        w.write_u32(sc.entity_index);
+        w.write_u8(sc.callback_type);
+        w.write_u32(sc.rhai_source.len() as u32);
+        w.write_bytes(sc.rhai_source.as_bytes());
+    }
+
+    // Game state (refs)
+    w.write_u32(scene.game_refs.len() as u32);
+    for r in &scene.game_refs {
+        w.write_str16(&r.name);
+        w.write_u8(r.value_type);
+        match r.value_type {
+            1 => w.write_u8(r.default_bool.unwrap_or(false) as u8),
+            2 => w.write_i64(r.default_i64.unwrap_or(0)),
+            3 => {
+                let s = r.default_string.as_deref().unwrap_or("");
+                w.write_u32(s.len() as u32);
+                w.write_bytes(s.as_bytes());
+            }
+            _ => w.write_f64(r.default_f64.unwrap_or(0.0)),
+        }
+    }
+
+    // Terrains
+    w.write_u32(scene.terrains.len() as u32);
+    for t in &scene.terrains {
+        w.write_u32(t.entity_index);
+        t.size.iter().for_each(|&v| w.write_f32(v));
+        w.write_f32(t.max_height);
+        w.write_u32(t.chunk_size);
+        w.write_u32(t.num_lod_levels);
+        w.write_u32(t.heightmap_width);
+        w.write_u32(t.heightmap_depth);
+        w.write_u32(t.splatmap_width);
+        w.write_u32(t.splatmap_height);
+        w.write_u32(t.layers.len() as u32);
+        for l in &t.layers {
+            w.write_i32(l.albedo_texture_index);
+            w.write_i32(l.normal_texture_index);
+            w.write_f32(l.uv_scale);
+            w.write_u32(0); // padding
+        }
+        t.heights.iter().for_each(|&v| w.write_f32(v));
+        w.write_bytes(&t.splatmap);
+    }
+
+    w.into_bytes()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1228,4 +1944,151 @@ mod tests {
         assert_eq!(scene.dir_lights[0].direction[1], -1.0);
         assert_eq!(scene.dir_lights[0].intensity, 5.0);
     }
+
+    #[test]
+    fn test_write_orsb_roundtrip() {
+        let mut data = build_header(2, 1, 0, 0);
+        write_entity(&mut data, 7, u32::MAX, ComponentMask::TRANSFORM | ComponentMask::MESH, 0, u32::MAX);
+        write_entity(&mut data, 8, 0, ComponentMask::TRANSFORM, u32::MAX, u32::MAX);
+        write_transform(&mut data, 1.0, 2.0, 3.0);
+        write_transform(&mut data, 4.0, 5.0, 6.0);
+        // Mesh: 3 verts, 3 indices, no bones
+        for v in &[3u32, 3, 0, 0] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        for i in 0..24 {
+            data.extend_from_slice(&(i as f32).to_le_bytes());
+        }
+        for i in &[0u32, 2, 1] {
+            data.extend_from_slice(&i.to_le_bytes());
+        }
+        write_empty_trailing(&mut data);
+
+        let mut scene = parse_orsb(&data).unwrap();
+        scene.scripts.push(ScriptParsed { entity_index: 1, callback_type: 1, rhai_source: "let x = 1;".into() });
+        scene.game_refs.push(GameRefParsed {
+            name: "score".into(),
+            value_type: 2,
+            default_f64: None,
+            default_bool: None,
+            default_i64: Some(42),
+            default_string: None,
+        });
+
+        let reparsed = parse_orsb(&write_orsb(&scene)).unwrap();
+        assert_eq!(reparsed.entity_ids, vec![7, 8]);
+        assert_eq!(reparsed.parent_indices, vec![None, Some(0)]);
+        assert_eq!(reparsed.transforms[1].position, [4.0, 5.0, 6.0]);
+        assert_eq!(reparsed.meshes[0].positions, scene.meshes[0].positions);
+        assert_eq!(reparsed.meshes[0].indices, vec![0, 2, 1]);
+        assert_eq!(reparsed.scripts[0].rhai_source, "let x = 1;");
+        assert_eq!(reparsed.game_refs[0].default_i64, Some(42));
+        assert!(reparsed.physics_config.is_some());
+        assert!(reparsed.terrains.is_empty());
+    }
+
+    #[test]
+    fn test_encoded_meshes_write_version_2() {
+        let triangle = |indices: Vec<u32>, encoding: u32| MeshParsed {
+            positions: vec![0.0; 9],
+            normals: [0.0, 1.0, 0.0].repeat(3),
+            uvs: vec![0.0; 6],
+            indices,
+            bone_weights: None,
+            bone_indices: None,
+            encoding: MeshEncoding(encoding),
+        };
+        let version = |meshes: Vec<MeshParsed>| {
+            let data = write_orsb(&ParsedScene { meshes, ..Default::default() });
+            let header = parse_header(&data).unwrap();
+            assert!(parse_orsb(&data).is_ok());
+            header.version
+        };
+
+        assert_eq!(version(vec![triangle(vec![0, 1, 2], 0)]), ORSB_VERSION);
+        assert_eq!(version(vec![triangle(vec![0, 1, 2], 0), triangle(vec![0, 1, 2], MeshEncoding::INDICES_U16)]), ORSB_VERSION_MESH_ENCODING);
+        // u16 indices that don't fit are stored unencoded.
+        assert_eq!(version(vec![triangle(vec![0, 1, 70_000], MeshEncoding::INDICES_U16)]), ORSB_VERSION);
+    }
+
+    #[test]
+    fn test_section_layout_covers_file() {
+        let mut scene = ParsedScene::default();
+        scene.scripts.push(ScriptParsed { entity_index: 0, callback_type: 0, rhai_source: "print(1);".into() });
+        let data = write_orsb(&scene);
+
+        let (_, sections) = parse_orsb_with_layout(&data).unwrap();
+        assert_eq!(sections[0].section, SectionType::EntityGraph);
+        assert_eq!(sections[0].offset, 32);
+        assert_eq!(sections.last().unwrap().section, SectionType::Terrains);
+        // Sections are contiguous and end at the end of the file.
+        for pair in sections.windows(2) {
+            assert_eq!(pair[0].offset + pair[0].size, pair[1].offset);
+        }
+        let last = sections.last().unwrap();
+        assert_eq!(last.offset + last.size, data.len());
+        let scripts = sections.iter().find(|s| s.section == SectionType::Scripts).unwrap();
+        assert_eq!(scripts.size, 4 + 4 + 1 + 4 + "print(1);".len());
+    }
+
+    #[test]
+    fn test_terrain_roundtrip() {
+        let mut scene = ParsedScene::default();
+        scene.terrains.push(TerrainParsed {
+            entity_index: 0,
+            size: [64.0, 32.0],
+            max_height: 10.0,
+            chunk_size: 3,
+            num_lod_levels: 2,
+            heightmap_width: 3,
+            heightmap_depth: 2,
+            heights: vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0],
+            splatmap_width: 1,
+            splatmap_height: 1,
+            splatmap: vec![255, 0, 0, 0],
+            layers: vec![TerrainLayerParsed { albedo_texture_index: 2, normal_texture_index: -1, uv_scale: 8.0 }],
+        });
+
+        let reparsed = parse_orsb(&write_orsb(&scene)).unwrap();
+        let t = &reparsed.terrains[0];
+        assert_eq!(t.size, [64.0, 32.0]);
+        assert_eq!((t.heightmap_width, t.heightmap_depth), (3, 2));
+        assert_eq!(t.heights[4], 4.0);
+        assert_eq!(t.splatmap, vec![255, 0, 0, 0]);
+        assert_eq!(t.layers[0].albedo_texture_index, 2);
+        assert_eq!(t.layers[0].uv_scale, 8.0);
+    }
+
+    #[test]
+    fn test_terrain_rejects_too_many_layers() {
+        let mut data = write_orsb(&ParsedScene::default());
+        data.truncate(data.len() - 4); // drop the empty terrain count
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes()); // entity
+        for v in [1.0f32, 1.0, 1.0] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        for v in [2u32, 1, 2, 2, 0, 0, 5] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        assert!(parse_orsb(&data).is_err());
+    }
+
+    #[test]
+    fn test_terrain_rejects_bad_lod_count() {
+        for num_lod_levels in [0, MAX_TERRAIN_LOD_LEVELS + 1, 64] {
+            let mut data = write_orsb(&ParsedScene::default());
+            data.truncate(data.len() - 4); // drop the empty terrain count
+            data.extend_from_slice(&1u32.to_le_bytes());
+            data.extend_from_slice(&0u32.to_le_bytes()); // entity
+            for v in [1.0f32, 1.0, 1.0] {
+                data.extend_from_slice(&v.to_le_bytes());
+            }
+            for v in [2u32, num_lod_levels, 2, 2, 0, 0, 0] {
+                data.extend_from_slice(&v.to_le_bytes());
+            }
+            data.extend_from_slice(&[0u8; 16]); // heights
+            assert_eq!(parse_orsb(&data).unwrap_err(), "Invalid terrain parameters", "{num_lod_levels} LODs");
+        }
+    }
 }
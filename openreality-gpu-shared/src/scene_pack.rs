@@ -0,0 +1,348 @@
+//! Content-addressed asset deduplication for ORSB scenes and bundles.
+//!
+//! Meshes, materials and textures are keyed by a hash of their ORSB encoding,
+//! so two entities that were exported with identical geometry or pixels end up
+//! sharing one pool entry. Hash hits are confirmed byte-for-byte before two
+//! assets are merged. Textures are pooled first so that materials which only
+//! differed by pointing at duplicate textures collapse as well.
+
+use std::collections::HashMap;
+
+use crate::scene_format::*;
+
+/// FNV-1a 64-bit hash of an asset payload. Stable across runs and platforms.
+pub fn content_hash(bytes: &[u8]) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    bytes.iter().fold(OFFSET, |h, &b| (h ^ b as u64).wrapping_mul(PRIME))
+}
+
+fn encode_mesh(mesh: &MeshParsed) -> Vec<u8> {
+    let mut w = Writer::new();
+    write_meshes(&mut w, std::slice::from_ref(mesh));
+    w.into_bytes()
+}
+
+fn encode_material(material: &MaterialData) -> Vec<u8> {
+    let mut w = Writer::new();
+    write_materials(&mut w, std::slice::from_ref(material));
+    w.into_bytes()
+}
+
+fn encode_texture(texture: &TextureParsed) -> Vec<u8> {
+    let mut w = Writer::new();
+    write_textures(&mut w, std::slice::from_ref(texture));
+    w.into_bytes()
+}
+
+/// Before/after counts for one asset kind.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AssetSavings {
+    pub count_before: usize,
+    pub count_after: usize,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+impl AssetSavings {
+    pub fn duplicates_removed(&self) -> usize {
+        self.count_before - self.count_after
+    }
+
+    pub fn bytes_saved(&self) -> u64 {
+        self.bytes_before - self.bytes_after
+    }
+}
+
+/// What a packing pass collapsed. Byte sizes are encoded ORSB payload sizes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DedupReport {
+    pub meshes: AssetSavings,
+    pub materials: AssetSavings,
+    pub textures: AssetSavings,
+}
+
+impl DedupReport {
+    pub fn bytes_before(&self) -> u64 {
+        self.meshes.bytes_before + self.materials.bytes_before + self.textures.bytes_before
+    }
+
+    pub fn bytes_after(&self) -> u64 {
+        self.meshes.bytes_after + self.materials.bytes_after + self.textures.bytes_after
+    }
+
+    pub fn bytes_saved(&self) -> u64 {
+        self.bytes_before() - self.bytes_after()
+    }
+}
+
+/// Hash-indexed list of unique assets of one kind.
+struct ContentStore<T> {
+    items: Vec<T>,
+    by_hash: HashMap<u64, Vec<usize>>,
+    encode: fn(&T) -> Vec<u8>,
+    savings: AssetSavings,
+}
+
+impl<T> ContentStore<T> {
+    fn new(encode: fn(&T) -> Vec<u8>) -> Self {
+        Self { items: Vec::new(), by_hash: HashMap::new(), encode, savings: AssetSavings::default() }
+    }
+
+    /// Insert an asset, returning the index of the stored copy.
+    fn insert(&mut self, item: T) -> usize {
+        let bytes = (self.encode)(&item);
+        self.savings.count_before += 1;
+        self.savings.bytes_before += bytes.len() as u64;
+
+        let bucket = self.by_hash.entry(content_hash(&bytes)).or_default();
+        if let Some(&existing) = bucket.iter().find(|&&i| (self.encode)(&self.items[i]) == bytes) {
+            return existing;
+        }
+
+        bucket.push(self.items.len());
+        self.items.push(item);
+        self.savings.count_after += 1;
+        self.savings.bytes_after += bytes.len() as u64;
+        self.items.len() - 1
+    }
+}
+
+/// Maps asset indices from where they were read to where they now live.
+/// Indices without an entry are left unchanged.
+#[derive(Clone, Debug, Default)]
+pub struct AssetRemap {
+    pub meshes: Vec<usize>,
+    pub materials: Vec<usize>,
+    pub textures: Vec<usize>,
+}
+
+impl AssetRemap {
+    /// Rewrite `scene`'s entity mesh/material indices and terrain texture
+    /// indices.
+    pub fn apply(&self, scene: &mut ParsedScene) {
+        for t in &mut scene.terrains {
+            t.texture_indices_mut().for_each(|idx| remap_index(idx, &self.textures));
+        }
+        for mi in scene.mesh_indices.iter_mut().flatten() {
+            if let Some(&new_idx) = self.meshes.get(*mi) {
+                *mi = new_idx;
+            }
+        }
+        for mi in scene.material_indices.iter_mut().flatten() {
+            if let Some(&new_idx) = self.materials.get(*mi) {
+                *mi = new_idx;
+            }
+        }
+    }
+}
+
+/// Rewrite a texture index (`-1` for none) through `map`.
+pub(crate) fn remap_index(idx: &mut i32, map: &[usize]) {
+    if *idx >= 0 {
+        if let Some(&new_idx) = map.get(*idx as usize) {
+            *idx = new_idx as i32;
+        }
+    }
+}
+
+/// Content-addressed pool of meshes, materials and textures.
+///
+/// Scenes are moved into the pool one at a time with [`AssetPool::absorb`];
+/// assets already present are reused and the scene's indices are rewritten to
+/// point at the pool.
+pub struct AssetPool {
+    meshes: ContentStore<MeshParsed>,
+    materials: ContentStore<MaterialData>,
+    textures: ContentStore<TextureParsed>,
+}
+
+impl Default for AssetPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AssetPool {
+    pub fn new() -> Self {
+        Self {
+            meshes: ContentStore::new(encode_mesh),
+            materials: ContentStore::new(encode_material),
+            textures: ContentStore::new(encode_texture),
+        }
+    }
+
+    /// Move `scene`'s assets into the pool. Afterwards the scene carries no
+    /// assets and its entity mesh/material indices refer to pool entries.
+    pub fn absorb(&mut self, scene: &mut ParsedScene) {
+        let remap = self.absorb_assets(
+            std::mem::take(&mut scene.meshes),
+            std::mem::take(&mut scene.materials),
+            std::mem::take(&mut scene.textures),
+        );
+        remap.apply(scene);
+        scene.header.num_meshes = 0;
+        scene.header.num_textures = 0;
+        scene.header.num_materials = 0;
+    }
+
+    /// Move a set of assets that several scenes may share into the pool.
+    /// Material texture indices are rewritten here; the returned remap
+    /// rewrites the scenes that refer to the assets.
+    pub fn absorb_assets(
+        &mut self,
+        meshes: Vec<MeshParsed>,
+        materials: Vec<MaterialData>,
+        textures: Vec<TextureParsed>,
+    ) -> AssetRemap {
+        let textures: Vec<usize> = textures.into_iter().map(|t| self.textures.insert(t)).collect();
+        let materials = materials
+            .into_iter()
+            .map(|mut m| {
+                m.texture_indices_mut().into_iter().for_each(|idx| remap_index(idx, &textures));
+                self.materials.insert(m)
+            })
+            .collect();
+        let meshes = meshes.into_iter().map(|m| self.meshes.insert(m)).collect();
+        AssetRemap { meshes, materials, textures }
+    }
+
+    /// Savings accumulated over every scene absorbed so far.
+    pub fn report(&self) -> DedupReport {
+        DedupReport {
+            meshes: self.meshes.savings,
+            materials: self.materials.savings,
+            textures: self.textures.savings,
+        }
+    }
+
+    /// Take the unique assets out of the pool.
+    pub fn into_assets(self) -> (Vec<MeshParsed>, Vec<MaterialData>, Vec<TextureParsed>) {
+        (self.meshes.items, self.materials.items, self.textures.items)
+    }
+}
+
+/// Collapse duplicate meshes, materials and textures within one scene,
+/// rewriting entity and material indices in place.
+pub fn dedup_scene(scene: &mut ParsedScene) -> DedupReport {
+    let mut pool = AssetPool::new();
+    pool.absorb(scene);
+    let report = pool.report();
+
+    let (meshes, materials, textures) = pool.into_assets();
+    scene.header.num_meshes = meshes.len() as u32;
+    scene.header.num_materials = materials.len() as u32;
+    scene.header.num_textures = textures.len() as u32;
+    scene.meshes = meshes;
+    scene.materials = materials;
+    scene.textures = textures;
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{material, texture};
+
+    fn quad(offset: f32) -> MeshParsed {
+        MeshParsed {
+            positions: vec![offset, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0],
+            normals: [0.0, 0.0, 1.0].repeat(4),
+            uvs: vec![0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0],
+            indices: vec![0, 1, 2, 0, 2, 3],
+            bone_weights: None,
+            bone_indices: None,
+            encoding: MeshEncoding::default(),
+        }
+    }
+
+    /// Three entities, each exported with its own copy of the assets it uses.
+    fn duplicated_scene() -> ParsedScene {
+        let mask = ComponentMask(ComponentMask::TRANSFORM | ComponentMask::MESH | ComponentMask::MATERIAL);
+        let identity = TransformData { position: [0.0; 3], rotation: [1.0, 0.0, 0.0, 0.0], scale: [1.0; 3] };
+        let mut scene = ParsedScene {
+            entity_ids: vec![1, 2, 3, 4],
+            parent_indices: vec![None; 4],
+            component_masks: vec![mask, mask, mask, ComponentMask(ComponentMask::TRANSFORM)],
+            mesh_indices: vec![Some(0), Some(1), Some(2), None],
+            material_indices: vec![Some(0), Some(1), Some(2), None],
+            transforms: vec![identity; 4],
+            meshes: vec![quad(0.0), quad(0.0), quad(0.5)],
+            // Materials 0 and 1 differ only by pointing at duplicate textures.
+            materials: vec![material(0), material(1), material(2)],
+            textures: vec![texture(9), texture(9), texture(3)],
+            ..Default::default()
+        };
+        scene.header.num_entities = 4;
+        scene.header.num_meshes = 3;
+        scene.header.num_materials = 3;
+        scene.header.num_textures = 3;
+        scene
+    }
+
+    #[test]
+    fn test_content_hash_fnv1a() {
+        assert_eq!(content_hash(&[]), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(content_hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_ne!(content_hash(b"ab"), content_hash(b"ba"));
+    }
+
+    #[test]
+    fn test_dedup_scene_collapses_duplicates() {
+        let mut scene = duplicated_scene();
+        let report = dedup_scene(&mut scene);
+
+        assert_eq!(scene.meshes.len(), 2);
+        assert_eq!(scene.textures.len(), 2);
+        assert_eq!(scene.materials.len(), 2);
+        assert_eq!(scene.header.num_meshes, 2);
+        assert_eq!(scene.header.num_textures, 2);
+        assert_eq!(scene.header.num_materials, 2);
+        assert_eq!(scene.mesh_indices, vec![Some(0), Some(0), Some(1), None]);
+        assert_eq!(scene.material_indices, vec![Some(0), Some(0), Some(1), None]);
+        assert_eq!(scene.materials[1].albedo_texture_index, 1);
+        assert_eq!(scene.textures[1].data, vec![3; 4]);
+
+        assert_eq!(report.meshes.duplicates_removed(), 1);
+        assert_eq!(report.textures.duplicates_removed(), 1);
+        assert_eq!(report.materials.duplicates_removed(), 1);
+        assert_eq!(report.meshes.bytes_saved(), report.meshes.bytes_before / 3);
+        assert!(report.bytes_saved() > 0);
+    }
+
+    #[test]
+    fn test_dedup_scene_roundtrips_through_orsb() {
+        let mut scene = duplicated_scene();
+        dedup_scene(&mut scene);
+        let parsed = parse_orsb(&write_orsb(&scene)).unwrap();
+        assert_eq!(parsed.meshes.len(), 2);
+        assert_eq!(parsed.mesh_indices, scene.mesh_indices);
+        assert_eq!(parsed.material_indices, scene.material_indices);
+    }
+
+    #[test]
+    fn test_dedup_scene_without_duplicates_is_noop() {
+        let mut scene = duplicated_scene();
+        dedup_scene(&mut scene);
+        let before = write_orsb(&scene);
+        let report = dedup_scene(&mut scene);
+        assert_eq!(report.bytes_saved(), 0);
+        assert_eq!(write_orsb(&scene), before);
+    }
+
+    #[test]
+    fn test_asset_pool_shares_across_scenes() {
+        let mut pool = AssetPool::new();
+        let mut a = duplicated_scene();
+        let mut b = duplicated_scene();
+        pool.absorb(&mut a);
+        pool.absorb(&mut b);
+
+        let report = pool.report();
+        assert_eq!(report.meshes.count_before, 6);
+        assert_eq!(report.meshes.count_after, 2);
+        assert!(b.meshes.is_empty());
+        assert_eq!(b.mesh_indices, a.mesh_indices);
+        assert_eq!(b.header.num_meshes, 0);
+    }
+}
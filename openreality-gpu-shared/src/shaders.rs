@@ -8,6 +8,7 @@ pub const GBUFFER_INSTANCED_VERT: &str = include_str!("../shaders/gbuffer_instan
 pub const GBUFFER_FRAG: &str = include_str!("../shaders/gbuffer_frag.wgsl");
 pub const DEFERRED_LIGHTING_FRAG: &str = include_str!("../shaders/deferred_lighting.wgsl");
 pub const SHADOW_DEPTH_VERT: &str = include_str!("../shaders/shadow_depth.wgsl");
+pub const SHADOW_DEPTH_SKINNED_VERT: &str = include_str!("../shaders/shadow_depth_skinned.wgsl");
 pub const SSAO_FRAG: &str = include_str!("../shaders/ssao.wgsl");
 pub const SSAO_BLUR_FRAG: &str = include_str!("../shaders/ssao_blur.wgsl");
 pub const SSR_FRAG: &str = include_str!("../shaders/ssr.wgsl");
@@ -24,3 +25,8 @@ pub const FORWARD_PBR_SHADER: &str = include_str!("../shaders/forward_pbr.wgsl")
 pub const DOF_SHADER: &str = include_str!("../shaders/dof.wgsl");
 pub const MOTION_BLUR_SHADER: &str = include_str!("../shaders/motion_blur.wgsl");
 pub const DEBUG_LINES_SHADER: &str = include_str!("../shaders/debug_lines.wgsl");
+pub const IBL_BAKE_SHADER: &str = include_str!("../shaders/ibl_bake.wgsl");
+pub const SKY_SHADER: &str = include_str!("../shaders/sky.wgsl");
+pub const AUTO_EXPOSURE_SHADER: &str = include_str!("../shaders/auto_exposure.wgsl");
+pub const SELECTION_SHADER: &str = include_str!("../shaders/selection.wgsl");
+pub const DEBUG_VIEW_SHADER: &str = include_str!("../shaders/debug_view.wgsl");
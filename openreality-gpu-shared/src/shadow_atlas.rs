@@ -0,0 +1,212 @@
+//! Shadow atlas layout for point and spot lights.
+//!
+//! Local light shadows share one square depth texture. Each shadowed light
+//! gets power-of-two tiles sized by how much of the screen the light covers:
+//! one tile for a spot light, six (the cube faces) for a point light. Tiles
+//! are handed out largest first, so they pack without gaps and a light only
+//! misses out once the atlas is full.
+
+use glam::{Mat4, Vec3};
+
+/// Smallest tile a shadowed light is given before it is dropped.
+pub const MIN_SHADOW_TILE: u32 = 64;
+
+/// Faces rendered for a point light (+X, -X, +Y, -Y, +Z, -Z).
+pub const POINT_SHADOW_FACES: u32 = 6;
+
+/// A square region of the atlas, in texels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AtlasTile {
+    pub x: u32,
+    pub y: u32,
+    pub size: u32,
+}
+
+impl AtlasTile {
+    /// UV offset (xy) and size (zw) of the tile in an atlas of `atlas_size` texels.
+    pub fn uv_rect(&self, atlas_size: u32) -> [f32; 4] {
+        let s = atlas_size as f32;
+        [self.x as f32 / s, self.y as f32 / s, self.size as f32 / s, self.size as f32 / s]
+    }
+}
+
+/// Tile size for a light of `range` whose centre is `distance` from the
+/// camera: the light sphere's height on screen in pixels, rounded up to a
+/// power of two and clamped to [`MIN_SHADOW_TILE`, `max_tile`].
+/// `proj_y_scale` is the projection's `y_axis.y` (cot of half the vertical FOV).
+pub fn shadow_tile_size(range: f32, distance: f32, proj_y_scale: f32, screen_height: u32, max_tile: u32) -> u32 {
+    let coverage = if distance <= range {
+        1.0
+    } else {
+        // tan of the sphere's angular radius, relative to the half FOV
+        (range / (distance * distance - range * range).sqrt() * proj_y_scale).min(1.0)
+    };
+    let pixels = (coverage * screen_height as f32).ceil().max(1.0) as u32;
+    pixels.next_power_of_two().clamp(MIN_SHADOW_TILE, max_tile.max(MIN_SHADOW_TILE))
+}
+
+/// Allocate `faces` tiles of `tile_size` for each request, in order. A
+/// request that doesn't fit is retried at half size down to
+/// [`MIN_SHADOW_TILE`], and gets `None` if even that fails. Callers should
+/// pass requests largest first: power-of-two squares then pack exactly.
+pub fn allocate_shadow_tiles(atlas_size: u32, requests: &[(u32, u32)]) -> Vec<Option<Vec<AtlasTile>>> {
+    let mut free = vec![AtlasTile { x: 0, y: 0, size: atlas_size }];
+    let mut free_area = atlas_size as u64 * atlas_size as u64;
+
+    requests.iter().map(|&(tile_size, faces)| {
+        let mut size = tile_size.min(atlas_size);
+        while size >= MIN_SHADOW_TILE {
+            let needed = faces as u64 * size as u64 * size as u64;
+            // Free squares are never smaller than earlier (larger) requests,
+            // so the area check guarantees every face finds a square.
+            if needed <= free_area && free.iter().all(|t| t.size >= size) {
+                free_area -= needed;
+                return Some((0..faces).map(|_| take_tile(&mut free, size)).collect());
+            }
+            size /= 2;
+        }
+        None
+    }).collect()
+}
+
+/// Take a `size` square from the smallest free square that holds it, splitting
+/// the remainder into quadrants.
+fn take_tile(free: &mut Vec<AtlasTile>, size: u32) -> AtlasTile {
+    let index = free.iter().enumerate()
+        .filter(|(_, t)| t.size >= size)
+        .min_by_key(|(_, t)| t.size)
+        .map(|(i, _)| i)
+        .expect("free area checked by caller");
+    let mut tile = free.swap_remove(index);
+    while tile.size > size {
+        let half = tile.size / 2;
+        free.push(AtlasTile { x: tile.x + half, y: tile.y, size: half });
+        free.push(AtlasTile { x: tile.x, y: tile.y + half, size: half });
+        free.push(AtlasTile { x: tile.x + half, y: tile.y + half, size: half });
+        tile.size = half;
+    }
+    tile
+}
+
+/// Near plane of local light shadow projections.
+fn shadow_near(range: f32) -> f32 {
+    (range * 0.01).max(0.01)
+}
+
+/// View-projections of a point light's six cube faces, in the order the
+/// shaders' `cube_face` picks them (+X, -X, +Y, -Y, +Z, -Z).
+pub fn point_shadow_matrices(position: Vec3, range: f32) -> [Mat4; 6] {
+    let proj = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, shadow_near(range), range);
+    [
+        (Vec3::X, Vec3::NEG_Y),
+        (Vec3::NEG_X, Vec3::NEG_Y),
+        (Vec3::Y, Vec3::Z),
+        (Vec3::NEG_Y, Vec3::NEG_Z),
+        (Vec3::Z, Vec3::NEG_Y),
+        (Vec3::NEG_Z, Vec3::NEG_Y),
+    ].map(|(dir, up)| proj * Mat4::look_to_rh(position, dir, up))
+}
+
+/// Field of view of a spot light's shadow frustum: its full outer cone,
+/// limited to what a single perspective tile can cover.
+pub fn spot_shadow_fov(outer_cos: f32) -> f32 {
+    (2.0 * outer_cos.clamp(-1.0, 1.0).acos()).clamp(0.01, 3.0)
+}
+
+/// View-projection of a spot light's shadow frustum, covering its outer cone.
+pub fn spot_shadow_matrix(position: Vec3, direction: Vec3, outer_cos: f32, range: f32) -> Mat4 {
+    let dir = direction.try_normalize().unwrap_or(Vec3::NEG_Y);
+    let up = if dir.y.abs() > 0.99 { Vec3::Z } else { Vec3::Y };
+    let proj = Mat4::perspective_rh(spot_shadow_fov(outer_cos), 1.0, shadow_near(range), range);
+    proj * Mat4::look_to_rh(position, dir, up)
+}
+
+/// Texel width at unit distance for a tile of `tile_size` covering `fov` radians.
+pub fn texel_angle(fov: f32, tile_size: u32) -> f32 {
+    2.0 * (fov * 0.5).tan() / tile_size.max(1) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overlaps(a: &AtlasTile, b: &AtlasTile) -> bool {
+        a.x < b.x + b.size && b.x < a.x + a.size && a.y < b.y + b.size && b.y < a.y + a.size
+    }
+
+    #[test]
+    fn test_tile_size_follows_screen_coverage() {
+        let proj_y = 1.0 / (30f32.to_radians()).tan();
+        let near = shadow_tile_size(2.0, 5.0, proj_y, 1080, 1024);
+        let far = shadow_tile_size(2.0, 50.0, proj_y, 1080, 1024);
+        assert!(near > far, "{near} vs {far}");
+        assert!(near.is_power_of_two() && far.is_power_of_two());
+        assert_eq!(shadow_tile_size(2.0, 1.0, proj_y, 1080, 1024), 1024);
+        assert_eq!(shadow_tile_size(0.1, 500.0, proj_y, 1080, 1024), MIN_SHADOW_TILE);
+    }
+
+    #[test]
+    fn test_allocation_packs_without_overlap() {
+        let requests = [(1024, 6), (512, 1), (512, 6), (256, 6), (128, 1)];
+        let result = allocate_shadow_tiles(4096, &requests);
+        let tiles: Vec<AtlasTile> = result.iter().flatten().flatten().copied().collect();
+        assert_eq!(tiles.len(), 6 + 1 + 6 + 6 + 1);
+        for (i, a) in tiles.iter().enumerate() {
+            assert!(a.x + a.size <= 4096 && a.y + a.size <= 4096);
+            for b in &tiles[i + 1..] {
+                assert!(!overlaps(a, b), "{a:?} overlaps {b:?}");
+            }
+        }
+        for (tiles, &(size, faces)) in result.iter().zip(&requests) {
+            let tiles = tiles.as_ref().unwrap();
+            assert_eq!(tiles.len(), faces as usize);
+            assert!(tiles.iter().all(|t| t.size == size));
+        }
+    }
+
+    #[test]
+    fn test_allocation_shrinks_then_drops() {
+        // Six 512 faces need more than a 1024 atlas, so the cube drops to 256.
+        let result = allocate_shadow_tiles(1024, &[(512, 6), (512, 6), (64, 1)]);
+        let first = result[0].as_ref().unwrap();
+        assert!(first.iter().all(|t| t.size == 256));
+        // 1024^2 - 6 * 256^2 leaves room for a second cube at 256.
+        assert!(result[1].as_ref().unwrap().iter().all(|t| t.size == 256));
+        assert!(result[2].is_some());
+
+        let full = allocate_shadow_tiles(128, &[(128, 1), (64, 1)]);
+        assert!(full[0].is_some());
+        assert!(full[1].is_none());
+    }
+
+    #[test]
+    fn test_cube_faces_cover_major_axis() {
+        let light = Vec3::new(1.0, 2.0, -3.0);
+        let faces = point_shadow_matrices(light, 10.0);
+        let dirs = [Vec3::X, Vec3::NEG_X, Vec3::Y, Vec3::NEG_Y, Vec3::Z, Vec3::NEG_Z];
+        for (face, dir) in dirs.iter().enumerate() {
+            // Points around the face's axis, up to the 45-degree edges.
+            for offset in [Vec3::ZERO, Vec3::new(0.9, 0.9, 0.9), Vec3::new(-0.9, 0.5, -0.3)] {
+                let p = light + (*dir + offset - *dir * offset.dot(*dir)) * 4.0;
+                let ndc = faces[face].project_point3(p);
+                assert!(ndc.x.abs() <= 1.0 && ndc.y.abs() <= 1.0, "face {face}: {ndc:?}");
+                assert!((0.0..=1.0).contains(&ndc.z), "face {face}: {ndc:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_spot_matrix_covers_cone() {
+        let pos = Vec3::new(0.0, 4.0, 0.0);
+        let dir = Vec3::new(0.3, -1.0, 0.1).normalize();
+        let outer_cos = 35f32.to_radians().cos();
+        let m = spot_shadow_matrix(pos, dir, outer_cos, 8.0);
+        let on_axis = m.project_point3(pos + dir * 5.0);
+        assert!(on_axis.x.abs() < 1e-4 && on_axis.y.abs() < 1e-4);
+        // A point just inside the cone edge stays inside the frustum.
+        let side = dir.cross(Vec3::Y).normalize();
+        let edge = (dir * outer_cos + side * (1.0 - outer_cos * outer_cos).sqrt()) * 0.99 + dir * 0.01;
+        let ndc = m.project_point3(pos + edge.normalize() * 5.0);
+        assert!(ndc.x.abs() <= 1.0 && ndc.y.abs() <= 1.0, "{ndc:?}");
+    }
+}
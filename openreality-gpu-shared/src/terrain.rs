@@ -0,0 +1,289 @@
+//! Terrain chunk mesh generation and LOD selection for ORSB terrains.
+//!
+//! Mirrors the Julia terrain renderer: the heightmap is split into a grid of
+//! chunks with `chunk_size` vertices per edge, and LOD `n` keeps every
+//! `2^n`-th vertex. Each chunk LOD also gets a skirt hanging below its border
+//! so that neighbouring chunks at different LODs don't show cracks.
+
+use glam::Vec3;
+
+use crate::scene_format::{MeshEncoding, MeshParsed, TerrainParsed};
+
+/// Distance (in chunk extents) up to which chunks render at LOD 0; each
+/// further LOD doubles it.
+pub const LOD_DISTANCE_FACTOR: f32 = 1.5;
+
+/// One terrain chunk with its level-of-detail meshes (LOD 0 = full detail).
+#[derive(Clone, Debug)]
+pub struct TerrainChunk {
+    pub lods: Vec<MeshParsed>,
+    pub aabb_min: [f32; 3],
+    pub aabb_max: [f32; 3],
+}
+
+impl TerrainChunk {
+    /// Pick a LOD from the horizontal distance between the camera and the
+    /// chunk centre.
+    pub fn select_lod(&self, camera_pos: [f32; 3]) -> usize {
+        select_lod(self.aabb_min, self.aabb_max, self.lods.len(), camera_pos)
+    }
+}
+
+/// LOD selection for a chunk given only its bounds, for callers that keep
+/// the chunk meshes elsewhere (e.g. already uploaded to the GPU).
+pub fn select_lod(aabb_min: [f32; 3], aabb_max: [f32; 3], num_lods: usize, camera_pos: [f32; 3]) -> usize {
+    let (min, max) = (Vec3::from(aabb_min), Vec3::from(aabb_max));
+    let center = (min + max) * 0.5;
+    let extent = (max.x - min.x).max(max.z - min.z).max(1e-3);
+    let dist = glam::Vec2::new(camera_pos[0] - center.x, camera_pos[2] - center.z).length();
+
+    let mut lod = 0;
+    let mut threshold = extent * LOD_DISTANCE_FACTOR;
+    while dist > threshold && lod + 1 < num_lods {
+        lod += 1;
+        threshold *= 2.0;
+    }
+    lod
+}
+
+/// Heightmap sampling helpers over a `TerrainParsed`.
+struct Grid<'a> {
+    t: &'a TerrainParsed,
+    w: usize,
+    d: usize,
+    cell: [f32; 2],
+    origin: [f32; 2],
+}
+
+impl<'a> Grid<'a> {
+    fn new(t: &'a TerrainParsed) -> Self {
+        let (w, d) = (t.heightmap_width as usize, t.heightmap_depth as usize);
+        Self {
+            t,
+            w,
+            d,
+            cell: [t.size[0] / (w - 1) as f32, t.size[1] / (d - 1) as f32],
+            origin: [-t.size[0] * 0.5, -t.size[1] * 0.5],
+        }
+    }
+
+    fn height(&self, x: usize, z: usize) -> f32 {
+        self.t.heights[z.min(self.d - 1) * self.w + x.min(self.w - 1)]
+    }
+
+    fn position(&self, x: usize, z: usize) -> [f32; 3] {
+        [
+            self.origin[0] + x as f32 * self.cell[0],
+            self.height(x, z),
+            self.origin[1] + z as f32 * self.cell[1],
+        ]
+    }
+
+    /// Central-difference normal with clamped borders.
+    fn normal(&self, x: usize, z: usize) -> [f32; 3] {
+        let hl = self.height(x.saturating_sub(1), z);
+        let hr = self.height(x + 1, z);
+        let hd = self.height(x, z.saturating_sub(1));
+        let hu = self.height(x, z + 1);
+        let n = Vec3::new((hl - hr) / (2.0 * self.cell[0]), 1.0, (hd - hu) / (2.0 * self.cell[1]));
+        n.try_normalize().unwrap_or(Vec3::Y).to_array()
+    }
+
+    fn uv(&self, x: usize, z: usize) -> [f32; 2] {
+        [x as f32 / (self.w - 1) as f32, z as f32 / (self.d - 1) as f32]
+    }
+}
+
+/// Sample coordinates from `start` to `end` inclusive with the given step,
+/// always keeping the end point so chunk borders line up across LODs.
+fn lod_coords(start: usize, end: usize, step: usize) -> Vec<usize> {
+    let mut v: Vec<usize> = (start..=end).step_by(step).collect();
+    if *v.last().unwrap() != end {
+        v.push(end);
+    }
+    v
+}
+
+/// Build every chunk of a terrain. Returns an empty list if the heightmap
+/// doesn't match its declared dimensions.
+pub fn build_terrain_chunks(t: &TerrainParsed) -> Vec<TerrainChunk> {
+    let (w, d) = (t.heightmap_width as usize, t.heightmap_depth as usize);
+    if w < 2 || d < 2 || t.chunk_size < 2 || t.heights.len() != w * d {
+        return Vec::new();
+    }
+    let grid = Grid::new(t);
+    let cells = t.chunk_size as usize - 1;
+    let skirt = t.max_height.abs() * 0.02 + grid.cell[0].max(grid.cell[1]);
+    // Past the LOD whose step spans the whole chunk, every LOD is identical.
+    let num_lods = t.num_lod_levels.clamp(1, cells.next_power_of_two().ilog2() + 1) as usize;
+
+    let mut chunks = Vec::new();
+    for z0 in (0..d - 1).step_by(cells) {
+        for x0 in (0..w - 1).step_by(cells) {
+            let (x1, z1) = ((x0 + cells).min(w - 1), (z0 + cells).min(d - 1));
+
+            let mut min_h = f32::MAX;
+            let mut max_h = f32::MIN;
+            for z in z0..=z1 {
+                for x in x0..=x1 {
+                    min_h = min_h.min(grid.height(x, z));
+                    max_h = max_h.max(grid.height(x, z));
+                }
+            }
+            let lo = grid.position(x0, z0);
+            let hi = grid.position(x1, z1);
+
+            let lods = (0..num_lods)
+                .map(|lod| build_chunk_lod(&grid, x0, z0, x1, z1, (1usize << lod).min(cells), skirt))
+                .collect();
+            chunks.push(TerrainChunk {
+                lods,
+                aabb_min: [lo[0], min_h - skirt, lo[2]],
+                aabb_max: [hi[0], max_h, hi[2]],
+            });
+        }
+    }
+    chunks
+}
+
+fn build_chunk_lod(grid: &Grid, x0: usize, z0: usize, x1: usize, z1: usize, step: usize, skirt: f32) -> MeshParsed {
+    let xs = lod_coords(x0, x1, step);
+    let zs = lod_coords(z0, z1, step);
+    let (nx, nz) = (xs.len(), zs.len());
+
+    let mut mesh = MeshParsed {
+        positions: Vec::with_capacity(nx * nz * 3),
+        normals: Vec::with_capacity(nx * nz * 3),
+        uvs: Vec::with_capacity(nx * nz * 2),
+        indices: Vec::with_capacity((nx - 1) * (nz - 1) * 6),
+        bone_weights: None,
+        bone_indices: None,
+        encoding: MeshEncoding::default(),
+    };
+    for &z in &zs {
+        for &x in &xs {
+            mesh.positions.extend_from_slice(&grid.position(x, z));
+            mesh.normals.extend_from_slice(&grid.normal(x, z));
+            mesh.uvs.extend_from_slice(&grid.uv(x, z));
+        }
+    }
+
+    // Counter-clockwise when seen from above.
+    let at = |i: usize, j: usize| (j * nx + i) as u32;
+    for j in 0..nz - 1 {
+        for i in 0..nx - 1 {
+            let (v00, v10, v01, v11) = (at(i, j), at(i + 1, j), at(i, j + 1), at(i + 1, j + 1));
+            mesh.indices.extend_from_slice(&[v00, v01, v10, v10, v01, v11]);
+        }
+    }
+
+    // Skirt: walk the border so that the outside is always on the same side,
+    // then drop a copy of each border vertex by `skirt`.
+    let mut border: Vec<u32> = Vec::with_capacity(2 * (nx + nz));
+    border.extend((0..nx).map(|i| at(i, 0)));
+    border.extend((1..nz).map(|j| at(nx - 1, j)));
+    border.extend((0..nx - 1).rev().map(|i| at(i, nz - 1)));
+    border.extend((0..nz - 1).rev().map(|j| at(0, j)));
+
+    let first_skirt = (mesh.positions.len() / 3) as u32;
+    for &v in &border {
+        let v = v as usize;
+        let p = [mesh.positions[v * 3], mesh.positions[v * 3 + 1] - skirt, mesh.positions[v * 3 + 2]];
+        mesh.positions.extend_from_slice(&p);
+        mesh.normals.extend_from_within(v * 3..v * 3 + 3);
+        mesh.uvs.extend_from_within(v * 2..v * 2 + 2);
+    }
+    for k in 0..border.len() - 1 {
+        let (a, b) = (border[k], border[k + 1]);
+        let (a2, b2) = (first_skirt + k as u32, first_skirt + k as u32 + 1);
+        mesh.indices.extend_from_slice(&[a, b, a2, b, b2, a2]);
+    }
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terrain(w: u32, d: u32, chunk_size: u32, lods: u32) -> TerrainParsed {
+        TerrainParsed {
+            entity_index: 0,
+            size: [(w - 1) as f32, (d - 1) as f32],
+            max_height: 4.0,
+            chunk_size,
+            num_lod_levels: lods,
+            heightmap_width: w,
+            heightmap_depth: d,
+            heights: (0..w * d).map(|i| (i % w) as f32 * 0.1).collect(),
+            splatmap_width: 0,
+            splatmap_height: 0,
+            splatmap: Vec::new(),
+            layers: Vec::new(),
+        }
+    }
+
+    fn mesh_normal(m: &MeshParsed, tri: usize) -> Vec3 {
+        let p = |i: u32| Vec3::from_slice(&m.positions[i as usize * 3..i as usize * 3 + 3]);
+        let t = &m.indices[tri * 3..tri * 3 + 3];
+        (p(t[1]) - p(t[0])).cross(p(t[2]) - p(t[0]))
+    }
+
+    #[test]
+    fn test_chunk_grid() {
+        let chunks = build_terrain_chunks(&terrain(17, 9, 9, 3));
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].lods.len(), 3);
+        assert_eq!(chunks[0].aabb_min[0], -8.0);
+        assert_eq!(chunks[1].aabb_max[0], 8.0);
+        // LOD 0: 9x9 grid plus a closed 33-vertex skirt loop.
+        assert_eq!(chunks[0].lods[0].positions.len() / 3, 81 + 33);
+        // LOD 2 keeps every 4th vertex: 3x3.
+        assert_eq!(chunks[0].lods[2].positions.len() / 3, 9 + 9);
+    }
+
+    #[test]
+    fn test_partial_chunk_keeps_border() {
+        // 12 cells don't divide into chunks of 8: the second chunk is 4 cells.
+        let chunks = build_terrain_chunks(&terrain(13, 5, 9, 4));
+        assert_eq!(chunks.len(), 2);
+        let lod = &chunks[1].lods[3];
+        let max_x = lod.positions.chunks_exact(3).map(|p| p[0]).fold(f32::MIN, f32::max);
+        assert_eq!(max_x, 6.0);
+    }
+
+    #[test]
+    fn test_surface_faces_up_and_skirt_faces_out() {
+        let chunks = build_terrain_chunks(&terrain(9, 9, 9, 1));
+        let m = &chunks[0].lods[0];
+        assert!(mesh_normal(m, 0).y > 0.0);
+        // First skirt triangle hangs off the -Z border.
+        let first_skirt_tri = 8 * 8 * 2;
+        assert!(mesh_normal(m, first_skirt_tri).z < 0.0);
+        // Last skirt triangle hangs off the -X border.
+        let last = m.indices.len() / 3 - 1;
+        assert!(mesh_normal(m, last).x < 0.0);
+    }
+
+    #[test]
+    fn test_select_lod_by_distance() {
+        let chunks = build_terrain_chunks(&terrain(9, 9, 9, 3));
+        let c = &chunks[0];
+        assert_eq!(c.select_lod([0.0, 100.0, 0.0]), 0);
+        assert_eq!(c.select_lod([20.0, 0.0, 0.0]), 1);
+        assert_eq!(c.select_lod([1000.0, 0.0, 0.0]), 2);
+    }
+
+    #[test]
+    fn test_mismatched_heightmap_yields_no_chunks() {
+        let mut t = terrain(9, 9, 9, 1);
+        t.heights.pop();
+        assert!(build_terrain_chunks(&t).is_empty());
+    }
+
+    #[test]
+    fn test_lod_count_clamped_to_chunk() {
+        // 8 cells per chunk: steps 1, 2, 4 and 8 are the only distinct LODs.
+        let chunks = build_terrain_chunks(&terrain(9, 9, 9, 64));
+        assert_eq!(chunks[0].lods.len(), 4);
+    }
+}
@@ -0,0 +1,33 @@
+//! Asset fixtures shared by the scene format unit tests.
+
+use crate::scene_format::{MaterialData, TextureParsed};
+
+/// Plain white material sampling `albedo_texture_index` (-1 for none).
+pub(crate) fn material(albedo_texture_index: i32) -> MaterialData {
+    MaterialData {
+        color: [1.0, 1.0, 1.0, 1.0],
+        metallic: 0.0,
+        roughness: 0.5,
+        opacity: 1.0,
+        alpha_cutoff: 0.0,
+        emissive_factor: [0.0; 4],
+        clearcoat: 0.0,
+        clearcoat_roughness: 0.0,
+        subsurface: 0.0,
+        subsurface_color: [0.0; 3],
+        parallax_height_scale: 0.0,
+        albedo_texture_index,
+        normal_texture_index: -1,
+        metallic_roughness_texture_index: -1,
+        ao_texture_index: -1,
+        emissive_texture_index: -1,
+        height_texture_index: -1,
+        clearcoat_texture_index: -1,
+        _pad: 0,
+    }
+}
+
+/// 1x1 RGBA texture with every byte set to `fill`.
+pub(crate) fn texture(fill: u8) -> TextureParsed {
+    TextureParsed { width: 1, height: 1, channels: 4, compression: 0, data: vec![fill; 4] }
+}
@@ -50,7 +50,9 @@ pub struct PerObjectUniforms {
     pub normal_matrix_col0: [f32; 4],
     pub normal_matrix_col1: [f32; 4],
     pub normal_matrix_col2: [f32; 4],
-    pub _pad: [f32; 4],
+    /// Written to the entity-ID target for picking; 0 means "no entity".
+    pub entity_id: u32,
+    pub _pad: [u32; 3],
 }
 
 /// Point light data.
@@ -65,6 +67,92 @@ pub struct PointLightData {
     pub _pad2: f32,
 }
 
+/// Spot light data. The cone points along `direction`; light fades from full
+/// strength at `inner_cos` to nothing at `outer_cos` (cosines of the cone
+/// half-angles).
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct SpotLightData {
+    pub position: [f32; 4],
+    pub direction: [f32; 4],
+    pub color: [f32; 4],
+    pub intensity: f32,
+    pub range: f32,
+    pub inner_cos: f32,
+    pub outer_cos: f32,
+}
+
+/// A point or spot light as stored in the clustered light buffer
+/// (`LocalLight` in the lighting and forward shaders).
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct LocalLightData {
+    pub position: [f32; 4],
+    pub color: [f32; 4],
+    /// Spot axis; unused for point lights.
+    pub direction: [f32; 4],
+    pub intensity: f32,
+    pub range: f32,
+    pub inner_cos: f32,
+    pub outer_cos: f32,
+    pub is_spot: u32,
+    /// First `ShadowTileData` of the light (six consecutive cube faces for a
+    /// point light, one tile for a spot light), or -1 when unshadowed.
+    pub shadow_index: i32,
+    pub _pad1: f32,
+    pub _pad2: f32,
+}
+
+impl From<&PointLightData> for LocalLightData {
+    fn from(light: &PointLightData) -> Self {
+        Self {
+            position: light.position,
+            color: light.color,
+            direction: [0.0, -1.0, 0.0, 0.0],
+            intensity: light.intensity,
+            range: light.range,
+            inner_cos: -1.0,
+            outer_cos: -1.0,
+            is_spot: 0,
+            shadow_index: -1,
+            _pad1: 0.0,
+            _pad2: 0.0,
+        }
+    }
+}
+
+impl From<&SpotLightData> for LocalLightData {
+    fn from(light: &SpotLightData) -> Self {
+        Self {
+            position: light.position,
+            color: light.color,
+            direction: light.direction,
+            intensity: light.intensity,
+            range: light.range,
+            inner_cos: light.inner_cos,
+            outer_cos: light.outer_cos,
+            is_spot: 1,
+            shadow_index: -1,
+            _pad1: 0.0,
+            _pad2: 0.0,
+        }
+    }
+}
+
+/// One shadow-atlas tile of a point or spot light (`ShadowTile` in the shaders).
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct ShadowTileData {
+    pub light_view_proj: [[f32; 4]; 4],
+    /// Atlas UV offset (xy) and size (zw) of the tile.
+    pub atlas_rect: [f32; 4],
+    /// Width of one texel at unit distance from the light, for normal-offset bias.
+    pub texel_angle: f32,
+    pub _pad1: f32,
+    pub _pad2: f32,
+    pub _pad3: f32,
+}
+
 /// Directional light data.
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
@@ -78,6 +166,10 @@ pub struct DirLightData {
 }
 
 /// Light uniform buffer — matches GPU bind group 1, binding 0 in lighting pass.
+///
+/// Shaders shade point lights from the clustered storage buffers (see
+/// `clusters`); `point_lights` and `num_point_lights` keep the layout the
+/// Julia backend uploads, and the FFI bins them into clusters.
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
 pub struct LightUniforms {
@@ -89,6 +181,19 @@ pub struct LightUniforms {
     pub ibl_intensity: f32,
 }
 
+/// Froxel grid for clustered point lights (matches `ClusterParams` in the
+/// lighting and forward shaders). Clusters are `grid_size[0] x grid_size[1]`
+/// screen tiles by `grid_size[2]` log-spaced depth slices over [z_near, z_far].
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct ClusterUniforms {
+    pub grid_size: [u32; 3],
+    pub num_lights: u32,
+    pub screen_size: [f32; 2],
+    pub z_near: f32,
+    pub z_far: f32,
+}
+
 /// SSAO parameters.
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
@@ -148,9 +253,42 @@ pub struct PostProcessParams {
     pub color_brightness: f32,
     pub color_contrast: f32,
     pub color_saturation: f32,
+    /// `EXPOSURE_MODE_MANUAL` or `EXPOSURE_MODE_AUTO`.
+    pub exposure_mode: i32,
+    /// Manual: exposure in stops (0 leaves radiance unscaled). Auto: compensation
+    /// added to the metered exposure.
+    pub exposure_ev: f32,
+    /// Auto: rate (1/s) at which the adapted luminance approaches the metered one.
+    pub adaptation_speed: f32,
+    /// Seconds since the previous frame, for adaptation.
+    pub delta_time: f32,
     pub _pad1: f32,
 }
 
+pub const EXPOSURE_MODE_MANUAL: i32 = 0;
+pub const EXPOSURE_MODE_AUTO: i32 = 1;
+
+/// Bins in the auto-exposure luminance histogram (`auto_exposure.wgsl`).
+pub const LUMINANCE_HISTOGRAM_BINS: usize = 256;
+
+/// 3D LUT colour grading applied in the present pass (`present.wgsl`).
+/// Two LUTs are bound so scripts can cross-fade between looks.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct ColorGradingUniforms {
+    /// xyz = input domain minimum of LUT A, w = its lattice size.
+    pub domain_min_a: [f32; 4],
+    /// xyz = input domain maximum of LUT A.
+    pub domain_max_a: [f32; 4],
+    pub domain_min_b: [f32; 4],
+    pub domain_max_b: [f32; 4],
+    /// 0 = LUT A only, 1 = LUT B only.
+    pub blend: f32,
+    /// 0 disables grading, 1 applies the blended LUT fully.
+    pub intensity: f32,
+    pub _pad: [f32; 2],
+}
+
 /// Bone matrix uniforms for skeletal animation.
 /// 128 bones * mat4x4 = 8192 bytes + 16-byte header = 8208 bytes.
 #[repr(C)]
@@ -227,6 +365,81 @@ pub struct MotionBlurParams {
     pub _pad2: f32,
 }
 
+/// Terrain splat-map blending parameters (matches `TerrainParams` in terrain_gbuffer.wgsl).
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct TerrainParams {
+    pub num_layers: i32,
+    pub layer_uv_scales: [f32; 4],
+    pub _pad1: f32,
+    pub _pad2: f32,
+    pub _pad3: f32,
+}
+
+/// Background sky parameters (matches `SkyUniforms` in sky.wgsl).
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct SkyUniforms {
+    /// xyz: unit vector toward the sun.
+    pub sun_direction: [f32; 4],
+    pub sun_intensity: f32,
+    /// Aerosol (Mie) density multiplier.
+    pub turbidity: f32,
+    /// `SKY_MODE_ENVIRONMENT` or `SKY_MODE_ATMOSPHERE`.
+    pub mode: i32,
+    /// Cube face rendered by `fs_sky_cube` (IBL bakes only).
+    pub face: u32,
+    pub environment_intensity: f32,
+    /// Non-zero to draw the sun disk.
+    pub sun_disk: i32,
+    pub _pad1: f32,
+    pub _pad2: f32,
+}
+
+pub const SKY_MODE_ENVIRONMENT: i32 = 0;
+pub const SKY_MODE_ATMOSPHERE: i32 = 1;
+
+/// Selection highlight drawn over the presented frame (`selection.wgsl`).
+/// The selected entity IDs are bound as a separate storage array.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct SelectionUniforms {
+    pub outline_color: [f32; 4],
+    /// Blended over selected pixels; alpha is the tint strength.
+    pub tint_color: [f32; 4],
+    /// Outline width in pixels, at most `MAX_SELECTION_OUTLINE_WIDTH`.
+    pub outline_width: f32,
+    /// Number of selected IDs.
+    pub count: u32,
+    pub _pad1: f32,
+    pub _pad2: f32,
+}
+
+/// Widest outline `selection.wgsl` searches for.
+pub const MAX_SELECTION_OUTLINE_WIDTH: f32 = 8.0;
+
+/// How `debug_view.wgsl` remaps an intermediate target for display.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct DebugViewUniforms {
+    /// One of the `DEBUG_REMAP_*` modes.
+    pub remap: u32,
+    /// Channel shown as grey by `DEBUG_REMAP_CHANNEL`.
+    pub channel: u32,
+    /// Camera clip planes, for `DEBUG_REMAP_DEPTH`.
+    pub near: f32,
+    pub far: f32,
+}
+
+/// RGB shown as is.
+pub const DEBUG_REMAP_COLOR: u32 = 0;
+/// One channel shown as grey.
+pub const DEBUG_REMAP_CHANNEL: u32 = 1;
+/// Hardware depth linearized between the camera planes.
+pub const DEBUG_REMAP_DEPTH: u32 = 2;
+/// HDR radiance compressed with Reinhard.
+pub const DEBUG_REMAP_HDR: u32 = 3;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -246,7 +459,7 @@ mod tests {
 
     #[test]
     fn test_per_object_uniforms_size() {
-        // model (64) + 3 normal cols (48) + pad (16) = 128
+        // model (64) + 3 normal cols (48) + entity id + pad (16) = 128
         assert_eq!(size_of::<PerObjectUniforms>(), 128);
     }
 
@@ -256,6 +469,16 @@ mod tests {
         assert_eq!(size_of::<PointLightData>(), 48);
     }
 
+    #[test]
+    fn test_local_light_sizes() {
+        // position+direction+color (48) + intensity/range/cone (16) = 64
+        assert_eq!(size_of::<SpotLightData>(), 64);
+        // position+color+direction (48) + 8 scalars (32) = 80
+        assert_eq!(size_of::<LocalLightData>(), 80);
+        // matrix (64) + atlas_rect (16) + texel_angle+pads (16) = 96
+        assert_eq!(size_of::<ShadowTileData>(), 96);
+    }
+
     #[test]
     fn test_dir_light_data_size() {
         // direction (16) + color (16) + intensity+pads (16) = 48
@@ -268,6 +491,12 @@ mod tests {
         assert_eq!(size_of::<LightUniforms>(), 16 * 48 + 4 * 48 + 16);
     }
 
+    #[test]
+    fn test_cluster_uniforms_size() {
+        // grid_size+num_lights (16) + screen_size+z_near+z_far (16) = 32
+        assert_eq!(size_of::<ClusterUniforms>(), 32);
+    }
+
     #[test]
     fn test_shadow_uniforms_size() {
         // 4 cascades * 80 + 16 header = 336
@@ -275,6 +504,41 @@ mod tests {
         assert_eq!(size_of::<ShadowUniforms>(), 4 * 80 + 16);
     }
 
+    #[test]
+    fn test_terrain_params_size() {
+        // num_layers + 4 uv scales + 3 pads = 32
+        assert_eq!(size_of::<TerrainParams>(), 32);
+    }
+
+    #[test]
+    fn test_sky_uniforms_size() {
+        // sun_direction (16) + 4 scalars (16) + 2 scalars + pads (16) = 48
+        assert_eq!(size_of::<SkyUniforms>(), 48);
+    }
+
+    #[test]
+    fn test_post_process_params_size() {
+        // 11 bloom/tone mapping/vignette/grading scalars (44) + 4 exposure scalars + pad (20) = 64
+        assert_eq!(size_of::<PostProcessParams>(), 64);
+    }
+
+    #[test]
+    fn test_color_grading_uniforms_size() {
+        // 4 domain vec4s (64) + blend + intensity + 2 pads (16) = 80
+        assert_eq!(size_of::<ColorGradingUniforms>(), 80);
+    }
+
+    #[test]
+    fn test_selection_uniforms_size() {
+        // 2 colour vec4s (32) + width + count + 2 pads (16) = 48
+        assert_eq!(size_of::<SelectionUniforms>(), 48);
+    }
+
+    #[test]
+    fn test_debug_view_uniforms_size() {
+        assert_eq!(size_of::<DebugViewUniforms>(), 16);
+    }
+
     #[test]
     fn test_pod_zeroable_roundtrip() {
         let uniform: PerFrameUniforms = Zeroable::zeroed();
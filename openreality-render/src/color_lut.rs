@@ -0,0 +1,87 @@
+//! 3D LUT colour grading resources for the present pass.
+//!
+//! A parsed `.cube` table is uploaded as a 3D texture indexed by (r, g, b).
+//! The present shader cross-fades between two bound LUTs; an empty slot grades
+//! with the identity LUT, so fading toward it fades the look out.
+
+use wgpu::util::DeviceExt;
+
+use openreality_gpu_shared::cube_lut::CubeLut;
+use openreality_gpu_shared::uniforms::ColorGradingUniforms;
+
+/// LUT texel format. Grading is applied after tone mapping, so entries are
+/// display-referred values in [0, 1].
+pub const COLOR_LUT_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+/// A colour lookup table on the GPU.
+pub struct ColorLut {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub size: u32,
+    pub domain_min: [f32; 3],
+    pub domain_max: [f32; 3],
+}
+
+impl ColorLut {
+    /// Upload a parsed `.cube` table.
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, lut: &CubeLut) -> Result<Self, String> {
+        let max_dimension = device.limits().max_texture_dimension_3d;
+        if lut.size > max_dimension {
+            return Err(format!("LUT size {} exceeds the {max_dimension} 3D texture limit", lut.size));
+        }
+        let texels: Vec<[u8; 4]> = lut
+            .table
+            .iter()
+            .map(|rgb| {
+                let unorm = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+                [unorm(rgb[0]), unorm(rgb[1]), unorm(rgb[2]), 255]
+            })
+            .collect();
+        let texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Color Grading LUT"),
+                size: wgpu::Extent3d { width: lut.size, height: lut.size, depth_or_array_layers: lut.size },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D3,
+                format: COLOR_LUT_FORMAT,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            bytemuck::cast_slice(&texels),
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Ok(Self { texture, view, size: lut.size, domain_min: lut.domain_min, domain_max: lut.domain_max })
+    }
+
+    /// The smallest identity LUT, bound for empty slots.
+    pub fn identity(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        Self::new(device, queue, &CubeLut::identity(2)).expect("identity LUT fits any device")
+    }
+}
+
+/// Grading uniforms for the LUTs in slots A and B (`None` grades with the
+/// identity). With both slots empty grading is switched off entirely.
+pub fn color_grading_uniforms(luts: [Option<&ColorLut>; 2], blend: f32, intensity: f32) -> ColorGradingUniforms {
+    let domain = |lut: Option<&ColorLut>| match lut {
+        Some(lut) => (
+            [lut.domain_min[0], lut.domain_min[1], lut.domain_min[2], lut.size as f32],
+            [lut.domain_max[0], lut.domain_max[1], lut.domain_max[2], 0.0],
+        ),
+        None => ([0.0, 0.0, 0.0, 2.0], [1.0, 1.0, 1.0, 0.0]),
+    };
+    let (domain_min_a, domain_max_a) = domain(luts[0]);
+    let (domain_min_b, domain_max_b) = domain(luts[1]);
+    let enabled = luts.iter().any(Option::is_some);
+    ColorGradingUniforms {
+        domain_min_a,
+        domain_max_a,
+        domain_min_b,
+        domain_max_b,
+        blend: blend.clamp(0.0, 1.0),
+        intensity: if enabled { intensity.clamp(0.0, 1.0) } else { 0.0 },
+        _pad: [0.0; 2],
+    }
+}
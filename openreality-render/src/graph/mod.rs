@@ -0,0 +1,411 @@
+//! Render graph — passes declare the targets they read and write, and the
+//! graph decides what actually runs and where transient targets live.
+//!
+//! Passes run in declaration order. `compile()` walks the declared reads and
+//! writes backwards from the outputs to cull passes whose results nobody
+//! consumes, then assigns every transient target a pooled texture; transients
+//! with the same description whose lifetimes don't overlap share a texture.
+//! Persistent targets (G-Buffer, history, swapchain) are imported by reference.
+
+pub mod nodes;
+
+use crate::profiler::FrameProfiler;
+
+/// Handle to a texture declared in a `RenderGraph`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ResourceId(usize);
+
+/// Size and format of a transient target. Transients alias only when these match.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TextureDesc {
+    pub width: u32,
+    pub height: u32,
+    pub format: wgpu::TextureFormat,
+}
+
+enum Resource<'a> {
+    Imported {
+        label: &'static str,
+        view: &'a wgpu::TextureView,
+        texture: Option<&'a wgpu::Texture>,
+    },
+    Transient {
+        label: &'static str,
+        desc: TextureDesc,
+    },
+}
+
+impl Resource<'_> {
+    fn label(&self) -> &'static str {
+        match self {
+            Resource::Imported { label, .. } | Resource::Transient { label, .. } => label,
+        }
+    }
+}
+
+type PassFn<'a> = Box<dyn FnOnce(&mut wgpu::CommandEncoder, &PassResources<'_>) + 'a>;
+
+struct PassNode<'a> {
+    name: &'static str,
+    reads: Vec<ResourceId>,
+    writes: Vec<ResourceId>,
+    exec: PassFn<'a>,
+}
+
+/// Result of `RenderGraph::compile`: what runs, and which pooled texture
+/// backs each transient.
+#[derive(Debug, PartialEq)]
+pub struct GraphPlan {
+    /// Indices of the passes to run, in execution order.
+    pub order: Vec<usize>,
+    /// Physical slot per resource; `None` for imports and unused transients.
+    pub slots: Vec<Option<usize>>,
+    /// Description of each physical slot.
+    pub slot_descs: Vec<TextureDesc>,
+}
+
+/// Views (and textures, where available) for the resources of a running pass.
+pub struct PassResources<'r> {
+    entries: Vec<Option<(&'r wgpu::TextureView, Option<&'r wgpu::Texture>)>>,
+    labels: Vec<&'static str>,
+}
+
+impl<'r> PassResources<'r> {
+    pub fn view(&self, id: ResourceId) -> &'r wgpu::TextureView {
+        match self.entries[id.0] {
+            Some((view, _)) => view,
+            None => panic!("render graph resource '{}' has no backing texture", self.labels[id.0]),
+        }
+    }
+
+    /// Texture behind a resource, for copies. Panics for view-only imports
+    /// such as the swapchain.
+    pub fn texture(&self, id: ResourceId) -> &'r wgpu::Texture {
+        match self.entries[id.0] {
+            Some((_, Some(texture))) => texture,
+            _ => panic!("render graph resource '{}' has no texture to copy", self.labels[id.0]),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct RenderGraph<'a> {
+    resources: Vec<Resource<'a>>,
+    passes: Vec<PassNode<'a>>,
+    outputs: Vec<ResourceId>,
+}
+
+impl<'a> RenderGraph<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Import a texture owned outside the graph. Pass `None` for `texture`
+    /// when only a view exists (e.g. the swapchain).
+    pub fn import(&mut self, label: &'static str, view: &'a wgpu::TextureView, texture: Option<&'a wgpu::Texture>) -> ResourceId {
+        self.resources.push(Resource::Imported { label, view, texture });
+        ResourceId(self.resources.len() - 1)
+    }
+
+    /// Import the color attachment of a render target.
+    pub fn import_target(&mut self, label: &'static str, target: &'a crate::types::RenderTarget) -> ResourceId {
+        self.import(label, &target.color_view, Some(&target.color_texture))
+    }
+
+    /// Declare a transient target, allocated from the pool at execution and
+    /// only valid within this frame. It must be written before it is read.
+    pub fn create(&mut self, label: &'static str, desc: TextureDesc) -> ResourceId {
+        self.resources.push(Resource::Transient { label, desc });
+        ResourceId(self.resources.len() - 1)
+    }
+
+    /// Keep passes that write `id` (and everything they depend on).
+    pub fn mark_output(&mut self, id: ResourceId) {
+        if !self.outputs.contains(&id) {
+            self.outputs.push(id);
+        }
+    }
+
+    pub fn add_pass(
+        &mut self,
+        name: &'static str,
+        reads: &[ResourceId],
+        writes: &[ResourceId],
+        exec: impl FnOnce(&mut wgpu::CommandEncoder, &PassResources<'_>) + 'a,
+    ) {
+        self.passes.push(PassNode {
+            name,
+            reads: reads.to_vec(),
+            writes: writes.to_vec(),
+            exec: Box::new(exec),
+        });
+    }
+
+    pub fn pass_count(&self) -> usize {
+        self.passes.len()
+    }
+
+    /// Cull, order and allocate. Fails if a pass reads a transient that no
+    /// earlier pass writes.
+    pub fn compile(&self) -> Result<GraphPlan, String> {
+        let is_transient = |id: &ResourceId| matches!(self.resources[id.0], Resource::Transient { .. });
+
+        let mut written = vec![false; self.resources.len()];
+        for pass in &self.passes {
+            if let Some(r) = pass.reads.iter().find(|r| is_transient(r) && !written[r.0]) {
+                return Err(format!("pass '{}' reads '{}' before any pass writes it", pass.name, self.resources[r.0].label()));
+            }
+            for w in &pass.writes {
+                written[w.0] = true;
+            }
+        }
+
+        // Walk backwards from the outputs: a pass is live if something live
+        // (or an output) needs one of the targets it writes.
+        let mut needed = vec![false; self.resources.len()];
+        for o in &self.outputs {
+            needed[o.0] = true;
+        }
+        let mut live = vec![false; self.passes.len()];
+        for (i, pass) in self.passes.iter().enumerate().rev() {
+            if pass.writes.iter().any(|w| needed[w.0]) {
+                live[i] = true;
+                for r in &pass.reads {
+                    needed[r.0] = true;
+                }
+            }
+        }
+        let order: Vec<usize> = (0..self.passes.len()).filter(|&i| live[i]).collect();
+
+        // Lifetime of each transient over the execution order; outputs stay
+        // alive to the end.
+        let mut first = vec![usize::MAX; self.resources.len()];
+        let mut last = vec![0; self.resources.len()];
+        for (step, &i) in order.iter().enumerate() {
+            let pass = &self.passes[i];
+            for r in pass.reads.iter().chain(&pass.writes) {
+                first[r.0] = first[r.0].min(step);
+                last[r.0] = last[r.0].max(step);
+            }
+        }
+        for o in &self.outputs {
+            last[o.0] = order.len();
+        }
+
+        let mut transients: Vec<usize> = (0..self.resources.len())
+            .filter(|&r| is_transient(&ResourceId(r)) && first[r] != usize::MAX)
+            .collect();
+        transients.sort_by_key(|&r| first[r]);
+
+        let mut slots = vec![None; self.resources.len()];
+        let mut slot_descs: Vec<TextureDesc> = Vec::new();
+        let mut slot_free_after: Vec<usize> = Vec::new();
+        for r in transients {
+            let Resource::Transient { desc, .. } = self.resources[r] else { unreachable!() };
+            let slot = match (0..slot_descs.len()).find(|&s| slot_descs[s] == desc && slot_free_after[s] < first[r]) {
+                Some(s) => s,
+                None => {
+                    slot_descs.push(desc);
+                    slot_free_after.push(0);
+                    slot_descs.len() - 1
+                }
+            };
+            slot_free_after[slot] = last[r];
+            slots[r] = Some(slot);
+        }
+
+        Ok(GraphPlan { order, slots, slot_descs })
+    }
+
+    /// Compile and record every live pass into `encoder`.
+    pub fn execute(self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder, pool: &mut TransientPool) -> Result<(), String> {
+        self.record(device, encoder, pool, None)
+    }
+
+    /// `execute`, timing every live pass with `profiler` (which must be
+    /// between `begin_frame` and `end_frame`) and resolving its timestamps.
+    pub fn execute_profiled(
+        self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        pool: &mut TransientPool,
+        profiler: &mut FrameProfiler,
+    ) -> Result<(), String> {
+        self.record(device, encoder, pool, Some(profiler))
+    }
+
+    fn record(
+        self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        pool: &mut TransientPool,
+        mut profiler: Option<&mut FrameProfiler>,
+    ) -> Result<(), String> {
+        let plan = self.compile()?;
+        let textures = pool.acquire(device, &plan.slot_descs);
+
+        let entries = self.resources.iter().zip(&plan.slots)
+            .map(|(res, slot)| match (res, slot) {
+                (Resource::Imported { view, texture, .. }, _) => Some((*view, *texture)),
+                (Resource::Transient { .. }, Some(s)) => {
+                    let (texture, view) = &pool.textures[textures[*s]].1;
+                    Some((view, Some(texture)))
+                }
+                (Resource::Transient { .. }, None) => None,
+            })
+            .collect();
+        let resources = PassResources {
+            entries,
+            labels: self.resources.iter().map(|r| r.label()).collect(),
+        };
+
+        let mut passes: Vec<Option<PassNode>> = self.passes.into_iter().map(Some).collect();
+        for i in plan.order {
+            let pass = passes[i].take().expect("pass scheduled once");
+            if let Some(profiler) = profiler.as_deref_mut() {
+                profiler.begin_pass(encoder, pass.name);
+            }
+            (pass.exec)(encoder, &resources);
+            if let Some(profiler) = profiler.as_deref_mut() {
+                profiler.end_pass(encoder);
+            }
+        }
+        if let Some(profiler) = profiler {
+            profiler.resolve(encoder);
+        }
+        Ok(())
+    }
+}
+
+/// Textures backing transient targets, kept across frames so steady-state
+/// rendering allocates nothing. Clear it when the render size changes.
+#[derive(Default)]
+pub struct TransientPool {
+    textures: Vec<(TextureDesc, (wgpu::Texture, wgpu::TextureView))>,
+}
+
+impl TransientPool {
+    /// Pool index for each slot, creating textures that don't exist yet.
+    fn acquire(&mut self, device: &wgpu::Device, slots: &[TextureDesc]) -> Vec<usize> {
+        let mut taken = vec![false; self.textures.len()];
+        slots.iter().map(|desc| {
+            if let Some(i) = (0..self.textures.len()).find(|&i| !taken[i] && self.textures[i].0 == *desc) {
+                taken[i] = true;
+                return i;
+            }
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Render Graph Transient"),
+                size: wgpu::Extent3d { width: desc.width, height: desc.height, depth_or_array_layers: 1 },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: desc.format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            });
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            self.textures.push((*desc, (texture, view)));
+            taken.push(true);
+            self.textures.len() - 1
+        }).collect()
+    }
+
+    /// Number of pooled textures.
+    pub fn len(&self) -> usize {
+        self.textures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.textures.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.textures.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HDR: TextureDesc = TextureDesc { width: 64, height: 64, format: wgpu::TextureFormat::Rgba16Float };
+    const R16: TextureDesc = TextureDesc { width: 64, height: 64, format: wgpu::TextureFormat::R16Float };
+
+    fn noop(graph: &mut RenderGraph, name: &'static str, reads: &[ResourceId], writes: &[ResourceId]) {
+        graph.add_pass(name, reads, writes, |_, _| {});
+    }
+
+    #[test]
+    fn test_chain_aliases_non_overlapping_transients() {
+        let mut g = RenderGraph::new();
+        let a = g.create("a", HDR);
+        let b = g.create("b", HDR);
+        let c = g.create("c", HDR);
+        noop(&mut g, "write a", &[], &[a]);
+        noop(&mut g, "a -> b", &[a], &[b]);
+        noop(&mut g, "b -> c", &[b], &[c]);
+        g.mark_output(c);
+
+        let plan = g.compile().unwrap();
+        assert_eq!(plan.order, vec![0, 1, 2]);
+        // a dies at step 1, c is born at step 2: they share a texture.
+        assert_eq!(plan.slots[a.0], plan.slots[c.0]);
+        assert_ne!(plan.slots[a.0], plan.slots[b.0]);
+        assert_eq!(plan.slot_descs.len(), 2);
+    }
+
+    #[test]
+    fn test_unused_passes_are_culled() {
+        let mut g = RenderGraph::new();
+        let a = g.create("a", HDR);
+        let unused = g.create("unused", HDR);
+        let out = g.create("out", HDR);
+        noop(&mut g, "write a", &[], &[a]);
+        noop(&mut g, "dead end", &[a], &[unused]);
+        noop(&mut g, "a -> out", &[a], &[out]);
+        g.mark_output(out);
+
+        let plan = g.compile().unwrap();
+        assert_eq!(plan.order, vec![0, 2]);
+        assert_eq!(plan.slots[unused.0], None);
+    }
+
+    #[test]
+    fn test_different_descs_never_alias() {
+        let mut g = RenderGraph::new();
+        let ao = g.create("ao", R16);
+        let lit = g.create("lit", HDR);
+        let out = g.create("out", R16);
+        noop(&mut g, "ao", &[], &[ao]);
+        noop(&mut g, "lit", &[ao], &[lit]);
+        noop(&mut g, "out", &[lit], &[out]);
+        g.mark_output(out);
+
+        let plan = g.compile().unwrap();
+        assert_eq!(plan.slots[ao.0], plan.slots[out.0]);
+        assert_ne!(plan.slots[ao.0], plan.slots[lit.0]);
+        assert_eq!(plan.slot_descs, vec![R16, HDR]);
+    }
+
+    #[test]
+    fn test_read_before_write_is_an_error() {
+        let mut g = RenderGraph::new();
+        let a = g.create("a", HDR);
+        let b = g.create("b", HDR);
+        noop(&mut g, "blur", &[a], &[b]);
+        g.mark_output(b);
+        let err = g.compile().unwrap_err();
+        assert!(err.contains("'blur'") && err.contains("'a'"), "{err}");
+    }
+
+    #[test]
+    fn test_read_modify_write_keeps_earlier_writer() {
+        let mut g = RenderGraph::new();
+        let a = g.create("a", HDR);
+        noop(&mut g, "clear a", &[], &[a]);
+        noop(&mut g, "draw into a", &[a], &[a]);
+        g.mark_output(a);
+        assert_eq!(g.compile().unwrap().order, vec![0, 1]);
+    }
+}
@@ -0,0 +1,630 @@
+//! Standard deferred-pipeline nodes, shared by `SceneRenderer` and the FFI
+//! backend. Each `add_*` uploads its parameters and adds one or more passes
+//! to the graph; bind groups are built when the pass runs, so transient
+//! targets can be bound.
+
+use wgpu::util::DeviceExt;
+
+use super::{RenderGraph, ResourceId, TextureDesc};
+use crate::color_lut::{self, ColorLut};
+use crate::ibl::IBLEnvironment;
+use crate::light_clusters::LightClusterBuffers;
+use crate::passes;
+use crate::render_targets::{HDR_FORMAT, R16_FORMAT, RG16_FORMAT};
+use crate::settings::{DebugView, SelectionStyle};
+use crate::types::DeferredPipeline;
+use openreality_gpu_shared::uniforms::*;
+
+/// Graph handles for every target of a `DeferredPipeline`.
+#[derive(Clone, Copy, Debug)]
+pub struct DeferredTargets {
+    pub albedo_metallic: ResourceId,
+    pub normal_roughness: ResourceId,
+    pub emissive_ao: ResourceId,
+    pub advanced: ResourceId,
+    pub depth: ResourceId,
+    pub lighting: ResourceId,
+    pub ssao: ResourceId,
+    pub ssao_blur: ResourceId,
+    pub ssr: ResourceId,
+    pub taa_current: ResourceId,
+    pub taa_history: ResourceId,
+    pub bloom_extract: ResourceId,
+    pub bloom_blur_h: ResourceId,
+    pub bloom_blur_v: ResourceId,
+    pub dof_coc: ResourceId,
+    pub dof_blur_h: ResourceId,
+    pub dof_blur_v: ResourceId,
+    pub velocity: ResourceId,
+    pub motion_blur: ResourceId,
+    pub pp_a: ResourceId,
+    pub pp_b: ResourceId,
+    pub exposure: ResourceId,
+    /// Written by the G-Buffer and forward passes while picking is enabled.
+    pub entity_id: Option<ResourceId>,
+}
+
+impl DeferredTargets {
+    /// Import every target from the pipeline. Used when a frame is split
+    /// over several graphs (the FFI builds one per pass call), so
+    /// intermediate results must outlive the graph.
+    pub fn import<'a>(graph: &mut RenderGraph<'a>, dp: &'a DeferredPipeline) -> Self {
+        Self::build(graph, dp, None)
+    }
+
+    /// Import the persistent targets (G-Buffer, lighting, SSR, TAA history
+    /// and exposure) and declare the intra-frame ones as `width`×`height`
+    /// transients.
+    pub fn with_transients<'a>(graph: &mut RenderGraph<'a>, dp: &'a DeferredPipeline, width: u32, height: u32) -> Self {
+        Self::build(graph, dp, Some((width, height)))
+    }
+
+    fn build<'a>(graph: &mut RenderGraph<'a>, dp: &'a DeferredPipeline, transient_size: Option<(u32, u32)>) -> Self {
+        let gb = &dp.gbuffer;
+        let mut target = |label, rt: &'a crate::types::RenderTarget, format, half: bool| match transient_size {
+            Some((w, h)) => {
+                let (width, height) = if half { ((w / 2).max(1), (h / 2).max(1)) } else { (w, h) };
+                graph.create(label, TextureDesc { width, height, format })
+            }
+            None => graph.import_target(label, rt),
+        };
+        let ssao = target("SSAO AO", &dp.ssao_targets.ao, R16_FORMAT, false);
+        let ssao_blur = target("SSAO Blur", &dp.ssao_targets.blur, R16_FORMAT, false);
+        let bloom_extract = target("Bloom Extract", &dp.bloom_targets.extract, HDR_FORMAT, true);
+        let bloom_blur_h = target("Bloom Blur H", &dp.bloom_targets.blur_h, HDR_FORMAT, true);
+        let bloom_blur_v = target("Bloom Blur V", &dp.bloom_targets.blur_v, HDR_FORMAT, true);
+        let dof_coc = target("DOF CoC", &dp.dof_targets.coc, R16_FORMAT, false);
+        let dof_blur_h = target("DOF Blur H", &dp.dof_targets.blur_h, HDR_FORMAT, true);
+        let dof_blur_v = target("DOF Blur V", &dp.dof_targets.blur_v, HDR_FORMAT, true);
+        let velocity = target("Motion Blur Velocity", &dp.mblur_targets.velocity, RG16_FORMAT, false);
+        let motion_blur = target("Motion Blur Output", &dp.mblur_targets.blur, HDR_FORMAT, false);
+        let pp_a = target("PP Target A", &dp.pp_target_a, HDR_FORMAT, false);
+        let pp_b = target("PP Target B", &dp.pp_target_b, HDR_FORMAT, false);
+
+        Self {
+            albedo_metallic: graph.import("G-Buffer Albedo/Metallic", &gb.albedo_metallic_view, Some(&gb.albedo_metallic)),
+            normal_roughness: graph.import("G-Buffer Normal/Roughness", &gb.normal_roughness_view, Some(&gb.normal_roughness)),
+            emissive_ao: graph.import("G-Buffer Emissive/AO", &gb.emissive_ao_view, Some(&gb.emissive_ao)),
+            advanced: graph.import("G-Buffer Advanced", &gb.advanced_view, Some(&gb.advanced)),
+            depth: graph.import("G-Buffer Depth", &gb.depth_view, Some(&gb.depth)),
+            lighting: graph.import_target("Lighting Target", &dp.lighting_target),
+            ssr: graph.import_target("SSR", &dp.ssr_target),
+            taa_current: graph.import_target("TAA Current", &dp.taa_targets.current),
+            taa_history: graph.import("TAA History", &dp.taa_targets.history_view, Some(&dp.taa_targets.history_texture)),
+            exposure: graph.import("Exposure", &dp.exposure_view, Some(&dp.exposure_texture)),
+            entity_id: dp.entity_ids.as_ref().map(|ids| graph.import_target("Entity IDs", &ids.target)),
+            ssao,
+            ssao_blur,
+            bloom_extract,
+            bloom_blur_h,
+            bloom_blur_v,
+            dof_coc,
+            dof_blur_h,
+            dof_blur_v,
+            velocity,
+            motion_blur,
+            pp_a,
+            pp_b,
+        }
+    }
+
+    pub fn gbuffer(&self) -> [ResourceId; 5] {
+        [self.albedo_metallic, self.normal_roughness, self.emissive_ao, self.advanced, self.depth]
+    }
+}
+
+/// Everything the standard nodes need besides their parameters.
+#[derive(Clone, Copy)]
+pub struct NodeContext<'a> {
+    pub device: &'a wgpu::Device,
+    pub queue: &'a wgpu::Queue,
+    pub dp: &'a DeferredPipeline,
+    pub sampler: &'a wgpu::Sampler,
+    pub targets: DeferredTargets,
+}
+
+/// Clear a target, for disabled effects whose output is still sampled.
+pub fn add_clear(graph: &mut RenderGraph, label: &'static str, target: ResourceId, color: wgpu::Color) {
+    graph.add_pass(label, &[], &[target], move |encoder, res| {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(label),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: res.view(target),
+                resolve_target: None,
+                ops: wgpu::Operations { load: wgpu::LoadOp::Clear(color), store: wgpu::StoreOp::Store },
+            })],
+            depth_stencil_attachment: None,
+            ..Default::default()
+        });
+    });
+}
+
+/// Copy one full-size color target into another of the same size and format.
+pub fn add_copy(graph: &mut RenderGraph, label: &'static str, source: ResourceId, dest: ResourceId) {
+    graph.add_pass(label, &[source], &[dest], move |encoder, res| {
+        let dest = res.texture(dest);
+        encoder.copy_texture_to_texture(res.texture(source).as_image_copy(), dest.as_image_copy(), dest.size());
+    });
+}
+
+/// SSAO and its blur into `targets.ssao_blur`.
+pub fn add_ssao<'a>(graph: &mut RenderGraph<'a>, ctx: &NodeContext<'a>, params: &SSAOParams) {
+    let NodeContext { device, queue, dp, sampler, targets: t } = *ctx;
+    queue.write_buffer(&dp.ssao_params_buffer, 0, bytemuck::bytes_of(params));
+
+    graph.add_pass("SSAO", &[t.depth, t.normal_roughness], &[t.ssao], move |encoder, res| {
+        let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("SSAO BG"),
+            layout: &dp.ssao_bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: dp.ssao_params_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(res.view(t.depth)) },
+                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(res.view(t.normal_roughness)) },
+                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(&dp.ssao_noise_view) },
+                wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::Sampler(sampler) },
+                wgpu::BindGroupEntry { binding: 5, resource: wgpu::BindingResource::Sampler(&dp.depth_sampler) },
+            ],
+        });
+        passes::ssao::render_ssao_pass(encoder, res.view(t.ssao), &dp.ssao_pipeline, &bg);
+    });
+
+    // The blur shader reads its own BlurParams (screen size), not SSAOParams.
+    let blur_params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("SSAO Blur Params"),
+        contents: bytemuck::cast_slice(&[params.screen_width, params.screen_height, 0.0, 0.0]),
+        usage: wgpu::BufferUsages::UNIFORM,
+    });
+    graph.add_pass("SSAO Blur", &[t.ssao], &[t.ssao_blur], move |encoder, res| {
+        let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("SSAO Blur BG"),
+            layout: &dp.ssao_blur_bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: blur_params.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(res.view(t.ssao)) },
+                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(sampler) },
+            ],
+        });
+        passes::ssao::render_ssao_blur(encoder, res.view(t.ssao_blur), &dp.ssao_blur_pipeline, &bg);
+    });
+}
+
+/// Light data and shadow maps read by `add_lighting`.
+#[derive(Clone, Copy)]
+pub struct LightingInputs<'a> {
+    pub light_buffer: &'a wgpu::Buffer,
+    pub light_clusters: &'a LightClusterBuffers,
+    pub cascades: [ResourceId; 4],
+    /// Point/spot light shadow atlas, sampled through the cluster shadow tiles.
+    pub shadow_atlas: ResourceId,
+    /// Sampled when `LightUniforms::has_ibl` is set.
+    pub ibl: &'a IBLEnvironment,
+}
+
+/// Deferred lighting from the G-Buffer, blurred SSAO, SSR, the shadow cascades,
+/// the clustered point and spot lights with their shadow atlas, and the IBL
+/// environment.
+pub fn add_lighting<'a>(
+    graph: &mut RenderGraph<'a>,
+    ctx: &NodeContext<'a>,
+    per_frame_buffer: &'a wgpu::Buffer,
+    inputs: LightingInputs<'a>,
+) {
+    let NodeContext { device, dp, sampler, targets: t, .. } = *ctx;
+    let LightingInputs { light_buffer, light_clusters, cascades, shadow_atlas, ibl } = inputs;
+    let mut reads = t.gbuffer().to_vec();
+    reads.extend([t.ssao_blur, t.ssr]);
+    reads.extend(cascades);
+    reads.push(shadow_atlas);
+
+    graph.add_pass("Deferred Lighting", &reads, &[t.lighting], move |encoder, res| {
+        let lighting_bg = passes::lighting::create_lighting_bind_group(
+            device,
+            &dp.lighting_bgl,
+            per_frame_buffer,
+            &dp.gbuffer,
+            res.view(t.ssao_blur),
+            res.view(t.ssr),
+            sampler,
+            &dp.depth_sampler,
+        );
+        let [params, lights, ranges, indices] = light_clusters.entries(1);
+        let [irradiance, prefilter, brdf_lut, ibl_sampler] = ibl.entries(5);
+        let light_data_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Light Data BG"),
+            layout: &dp.light_data_bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: light_buffer.as_entire_binding() },
+                params,
+                lights,
+                ranges,
+                indices,
+                irradiance,
+                prefilter,
+                brdf_lut,
+                ibl_sampler,
+            ],
+        });
+        let shadow_bg = passes::lighting::create_lighting_shadow_bind_group(
+            device,
+            &dp.lighting_shadow_bgl,
+            &dp.shadow_uniform_buffer,
+            cascades.map(|c| res.view(c)),
+            &dp.shadow_comparison_sampler,
+            res.view(shadow_atlas),
+            light_clusters.shadow_tiles(),
+        );
+        passes::lighting::render_lighting_pass(encoder, res.view(t.lighting), &dp.lighting_pipeline, &lighting_bg, &light_data_bg, &shadow_bg);
+    });
+}
+
+/// The sky behind the lit scene: `targets.lighting` is shaded wherever
+/// `targets.depth` is still at the far plane. `environment` is sampled in
+/// `SKY_MODE_ENVIRONMENT`.
+pub fn add_background<'a>(
+    graph: &mut RenderGraph<'a>,
+    ctx: &NodeContext<'a>,
+    per_frame_buffer: &'a wgpu::Buffer,
+    sky: &SkyUniforms,
+    environment: &'a IBLEnvironment,
+) {
+    let NodeContext { device, queue, dp, targets: t, .. } = *ctx;
+    queue.write_buffer(&dp.sky_uniform_buffer, 0, bytemuck::bytes_of(sky));
+
+    graph.add_pass("Sky", &[t.depth, t.lighting], &[t.lighting], move |encoder, res| {
+        let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Sky BG"),
+            layout: &dp.sky_bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: per_frame_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: dp.sky_uniform_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(&environment.environment_view) },
+                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::Sampler(&environment.sampler) },
+            ],
+        });
+        passes::sky::render_sky_pass(encoder, res.view(t.lighting), res.view(t.depth), &dp.sky_pipeline, &bg);
+    });
+}
+
+/// Screen-space reflections of the lit scene into `targets.ssr`.
+pub fn add_ssr<'a>(graph: &mut RenderGraph<'a>, ctx: &NodeContext<'a>, params: &SSRParams) {
+    let NodeContext { device, queue, dp, sampler, targets: t } = *ctx;
+    queue.write_buffer(&dp.ssr_params_buffer, 0, bytemuck::bytes_of(params));
+
+    graph.add_pass("SSR", &[t.depth, t.normal_roughness, t.lighting], &[t.ssr], move |encoder, res| {
+        let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("SSR BG"),
+            layout: &dp.ssr_bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: dp.ssr_params_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(res.view(t.depth)) },
+                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(res.view(t.normal_roughness)) },
+                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(res.view(t.lighting)) },
+                wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::Sampler(sampler) },
+                wgpu::BindGroupEntry { binding: 5, resource: wgpu::BindingResource::Sampler(&dp.depth_sampler) },
+            ],
+        });
+        passes::ssr::render_ssr_pass(encoder, res.view(t.ssr), &dp.ssr_pipeline, &bg);
+    });
+}
+
+/// TAA resolve; the result becomes next frame's history and replaces the
+/// lighting target.
+pub fn add_taa<'a>(graph: &mut RenderGraph<'a>, ctx: &NodeContext<'a>, params: &TAAParams) {
+    let NodeContext { device, queue, dp, sampler, targets: t } = *ctx;
+    queue.write_buffer(&dp.taa_params_buffer, 0, bytemuck::bytes_of(params));
+
+    graph.add_pass("TAA", &[t.lighting, t.taa_history, t.depth], &[t.taa_current], move |encoder, res| {
+        let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("TAA BG"),
+            layout: &dp.taa_bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: dp.taa_params_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(res.view(t.lighting)) },
+                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(res.view(t.taa_history)) },
+                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(res.view(t.depth)) },
+                wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::Sampler(sampler) },
+                wgpu::BindGroupEntry { binding: 5, resource: wgpu::BindingResource::Sampler(&dp.depth_sampler) },
+            ],
+        });
+        passes::taa::render_taa_pass(encoder, res.view(t.taa_current), &dp.taa_pipeline, &bg);
+    });
+    add_copy(graph, "TAA History Copy", t.taa_current, t.taa_history);
+    add_copy(graph, "TAA Resolve Copy", t.taa_current, t.lighting);
+}
+
+/// Depth of field: CoC, separable bokeh blur, composite back into the lighting target.
+pub fn add_dof<'a>(graph: &mut RenderGraph<'a>, ctx: &NodeContext<'a>, coc: &DOFCoCParams, bokeh_radius: f32) {
+    let NodeContext { device, queue, dp, sampler, targets: t } = *ctx;
+    queue.write_buffer(&dp.dof_coc_params_buffer, 0, bytemuck::bytes_of(coc));
+
+    graph.add_pass("DOF CoC", &[t.depth], &[t.dof_coc], move |encoder, res| {
+        let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("DOF CoC BG"),
+            layout: &dp.dof_coc_bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: dp.dof_coc_params_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(res.view(t.depth)) },
+                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&dp.depth_sampler) },
+            ],
+        });
+        passes::dof::render_dof_coc(encoder, res.view(t.dof_coc), &dp.dof_coc_pipeline, &bg);
+    });
+
+    // H and V blurs differ only in `horizontal`; V gets its own buffer since
+    // writes are staged until submit.
+    let blur_params = |horizontal| DOFBlurParams { horizontal, bokeh_radius, _pad1: 0.0, _pad2: 0.0 };
+    queue.write_buffer(&dp.dof_blur_params_buffer, 0, bytemuck::bytes_of(&blur_params(1)));
+    let blur_v_params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("DOF Blur V Params"),
+        contents: bytemuck::bytes_of(&blur_params(0)),
+        usage: wgpu::BufferUsages::UNIFORM,
+    });
+    graph.add_pass("DOF Blur", &[t.lighting, t.dof_coc], &[t.dof_blur_h, t.dof_blur_v], move |encoder, res| {
+        for (params, source, target, label) in [
+            (&dp.dof_blur_params_buffer, t.lighting, t.dof_blur_h, "DOF Blur H BG"),
+            (&blur_v_params, t.dof_blur_h, t.dof_blur_v, "DOF Blur V BG"),
+        ] {
+            let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &dp.dof_blur_bgl,
+                entries: &[
+                    wgpu::BindGroupEntry { binding: 0, resource: params.as_entire_binding() },
+                    wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(res.view(source)) },
+                    wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(res.view(t.dof_coc)) },
+                    wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::Sampler(sampler) },
+                ],
+            });
+            passes::dof::render_dof_blur(encoder, res.view(target), &dp.dof_blur_pipeline, &bg);
+        }
+    });
+
+    graph.add_pass("DOF Composite", &[t.lighting, t.dof_blur_v, t.dof_coc], &[t.pp_a], move |encoder, res| {
+        let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("DOF Composite BG"),
+            layout: &dp.dof_composite_bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(res.view(t.lighting)) },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(res.view(t.dof_blur_v)) },
+                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(res.view(t.dof_coc)) },
+                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::Sampler(sampler) },
+            ],
+        });
+        passes::dof::render_dof_composite(encoder, res.view(t.pp_a), &dp.dof_composite_pipeline, &bg);
+    });
+    add_copy(graph, "DOF Copy", t.pp_a, t.lighting);
+}
+
+/// Camera motion blur: velocity from depth reprojection, then a directional
+/// blur back into the lighting target.
+pub fn add_motion_blur<'a>(graph: &mut RenderGraph<'a>, ctx: &NodeContext<'a>, velocity: &VelocityParams, blur: &MotionBlurParams) {
+    let NodeContext { device, queue, dp, sampler, targets: t } = *ctx;
+    queue.write_buffer(&dp.mblur_velocity_params_buffer, 0, bytemuck::bytes_of(velocity));
+    queue.write_buffer(&dp.mblur_blur_params_buffer, 0, bytemuck::bytes_of(blur));
+
+    graph.add_pass("Motion Blur Velocity", &[t.depth], &[t.velocity], move |encoder, res| {
+        let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("MBlur Velocity BG"),
+            layout: &dp.mblur_velocity_bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: dp.mblur_velocity_params_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(res.view(t.depth)) },
+                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&dp.depth_sampler) },
+            ],
+        });
+        passes::motion_blur::render_velocity_pass(encoder, res.view(t.velocity), &dp.mblur_velocity_pipeline, &bg);
+    });
+
+    graph.add_pass("Motion Blur", &[t.lighting, t.velocity], &[t.motion_blur], move |encoder, res| {
+        let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("MBlur Blur BG"),
+            layout: &dp.mblur_blur_bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: dp.mblur_blur_params_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(res.view(t.lighting)) },
+                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(res.view(t.velocity)) },
+                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::Sampler(sampler) },
+            ],
+        });
+        passes::motion_blur::render_blur_pass(encoder, res.view(t.motion_blur), &dp.mblur_blur_pipeline, &bg);
+    });
+    add_copy(graph, "Motion Blur Copy", t.motion_blur, t.lighting);
+}
+
+/// Bloom, auto exposure, tone-mapping composite and optional FXAA. Returns
+/// the target holding the final image. The composite always runs since it
+/// tone maps; without bloom it composites a cleared bloom target. Exposure is
+/// metered from the lighting target only in `EXPOSURE_MODE_AUTO`.
+pub fn add_postprocess<'a>(graph: &mut RenderGraph<'a>, ctx: &NodeContext<'a>, params: &PostProcessParams, bloom: bool, fxaa: bool) -> ResourceId {
+    let NodeContext { device, queue, dp, sampler, targets: t } = *ctx;
+    queue.write_buffer(&dp.pp_params_buffer, 0, bytemuck::bytes_of(params));
+
+    let effect = move |label: &'static str, layout: &'a wgpu::BindGroupLayout, pipeline: &'a wgpu::RenderPipeline, source: ResourceId, target: ResourceId| {
+        move |encoder: &mut wgpu::CommandEncoder, res: &super::PassResources<'_>| {
+            let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout,
+                entries: &[
+                    wgpu::BindGroupEntry { binding: 0, resource: dp.pp_params_buffer.as_entire_binding() },
+                    wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(res.view(source)) },
+                    wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(sampler) },
+                ],
+            });
+            passes::postprocess::render_fullscreen_effect(encoder, res.view(target), pipeline, &bg, label);
+        }
+    };
+
+    if bloom {
+        graph.add_pass("Bloom Extract", &[t.lighting], &[t.bloom_extract],
+            effect("Bloom Extract", &dp.bloom_extract_bgl, &dp.bloom_extract_pipeline, t.lighting, t.bloom_extract));
+        graph.add_pass("Bloom Blur H", &[t.bloom_extract], &[t.bloom_blur_h],
+            effect("Bloom Blur H", &dp.bloom_blur_bgl, &dp.bloom_blur_pipeline, t.bloom_extract, t.bloom_blur_h));
+        graph.add_pass("Bloom Blur V", &[t.bloom_blur_h], &[t.bloom_blur_v],
+            effect("Bloom Blur V", &dp.bloom_blur_bgl, &dp.bloom_blur_pipeline, t.bloom_blur_h, t.bloom_blur_v));
+    } else {
+        add_clear(graph, "Bloom Clear", t.bloom_blur_v, wgpu::Color::BLACK);
+    }
+
+    if params.exposure_mode == EXPOSURE_MODE_AUTO {
+        graph.add_pass("Auto Exposure", &[t.lighting], &[t.exposure], move |encoder, res| {
+            let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Auto Exposure BG"),
+                layout: &dp.auto_exposure_bgl,
+                entries: &[
+                    wgpu::BindGroupEntry { binding: 0, resource: dp.pp_params_buffer.as_entire_binding() },
+                    wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(res.view(t.lighting)) },
+                    wgpu::BindGroupEntry { binding: 2, resource: dp.exposure_histogram_buffer.as_entire_binding() },
+                    wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(res.view(t.exposure)) },
+                ],
+            });
+            let size = res.texture(t.lighting).size();
+            passes::auto_exposure::render_auto_exposure(
+                encoder, &dp.exposure_histogram_pipeline, &dp.exposure_average_pipeline, &bg, size.width, size.height,
+            );
+        });
+    }
+
+    graph.add_pass("Bloom Composite", &[t.lighting, t.bloom_blur_v, t.exposure], &[t.pp_a], move |encoder, res| {
+        let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Bloom Composite BG"),
+            layout: &dp.bloom_composite_bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: dp.pp_params_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(res.view(t.lighting)) },
+                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(res.view(t.bloom_blur_v)) },
+                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::Sampler(sampler) },
+                wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::TextureView(res.view(t.exposure)) },
+            ],
+        });
+        passes::postprocess::render_bloom_composite(encoder, res.view(t.pp_a), &dp.bloom_composite_pipeline, &bg);
+    });
+
+    if !fxaa {
+        return t.pp_a;
+    }
+    graph.add_pass("FXAA", &[t.pp_a], &[t.pp_b], move |encoder, res| {
+        let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("FXAA BG"),
+            layout: &dp.fxaa_bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(res.view(t.pp_a)) },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(sampler) },
+            ],
+        });
+        passes::postprocess::render_fxaa(encoder, res.view(t.pp_b), &dp.fxaa_pipeline, &bg);
+    });
+    t.pp_b
+}
+
+/// Present `source` to `surface`, grading it with the LUTs in slots A and B
+/// (see `color_lut::color_grading_uniforms`).
+pub fn add_present<'a>(
+    graph: &mut RenderGraph<'a>,
+    ctx: &NodeContext<'a>,
+    source: ResourceId,
+    surface: ResourceId,
+    luts: [Option<&'a ColorLut>; 2],
+    lut_blend: f32,
+    lut_intensity: f32,
+) {
+    let NodeContext { device, queue, dp, sampler, .. } = *ctx;
+    let grading = color_lut::color_grading_uniforms(luts, lut_blend, lut_intensity);
+    queue.write_buffer(&dp.color_grading_buffer, 0, bytemuck::bytes_of(&grading));
+
+    let [lut_a, lut_b] = luts.map(|lut| &lut.unwrap_or(&dp.identity_lut).view);
+    graph.add_pass("Present", &[source], &[surface], move |encoder, res| {
+        let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Present BG"),
+            layout: &dp.present_bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: dp.pp_params_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(res.view(source)) },
+                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(sampler) },
+                wgpu::BindGroupEntry { binding: 3, resource: dp.color_grading_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::TextureView(lut_a) },
+                wgpu::BindGroupEntry { binding: 5, resource: wgpu::BindingResource::TextureView(lut_b) },
+            ],
+        });
+        passes::present::render_present_pass(encoder, res.view(surface), &dp.present_pipeline, &bg);
+    });
+}
+
+/// Present an intermediate target instead of the final image (see
+/// `DebugView`). `near` and `far` are the camera planes, for linearizing depth.
+pub fn add_debug_view<'a>(
+    graph: &mut RenderGraph<'a>,
+    ctx: &NodeContext<'a>,
+    view: DebugView,
+    cascades: [ResourceId; 4],
+    surface: ResourceId,
+    near: f32,
+    far: f32,
+) {
+    let NodeContext { device, dp, targets: t, .. } = *ctx;
+    let source = match view {
+        DebugView::Final | DebugView::Lighting => t.lighting,
+        DebugView::Albedo | DebugView::Metallic => t.albedo_metallic,
+        DebugView::Normal | DebugView::Roughness => t.normal_roughness,
+        DebugView::Emissive | DebugView::Occlusion => t.emissive_ao,
+        DebugView::Depth => t.depth,
+        DebugView::Ssao => t.ssao_blur,
+        DebugView::Ssr => t.ssr,
+        DebugView::Bloom => t.bloom_blur_v,
+        DebugView::ShadowCascade(i) => cascades[(i as usize).min(3)],
+    };
+    let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Debug View Params"),
+        contents: bytemuck::bytes_of(&view.uniforms(near, far)),
+        usage: wgpu::BufferUsages::UNIFORM,
+    });
+
+    graph.add_pass("Debug View", &[source], &[surface], move |encoder, res| {
+        let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Debug View BG"),
+            layout: &dp.debug_view_bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: params.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(res.view(source)) },
+            ],
+        });
+        passes::present::render_present_pass(encoder, res.view(surface), &dp.debug_view_pipeline, &bg);
+    });
+}
+
+/// Highlight the entities whose IDs are in `selected` over `surface`: an
+/// outline around them and a tint across them. Does nothing without the
+/// entity-ID target (picking disabled) or with an empty selection.
+pub fn add_selection<'a>(
+    graph: &mut RenderGraph<'a>,
+    ctx: &NodeContext<'a>,
+    surface: ResourceId,
+    selected: &[u32],
+    style: &SelectionStyle,
+) {
+    let NodeContext { device, dp, targets: t, .. } = *ctx;
+    let Some(entity_id) = t.entity_id.filter(|_| !selected.is_empty()) else {
+        return;
+    };
+    let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Selection Params"),
+        contents: bytemuck::bytes_of(&style.uniforms(selected.len() as u32)),
+        usage: wgpu::BufferUsages::UNIFORM,
+    });
+    let ids = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Selected IDs"),
+        contents: bytemuck::cast_slice(selected),
+        usage: wgpu::BufferUsages::STORAGE,
+    });
+
+    graph.add_pass("Selection", &[entity_id, surface], &[surface], move |encoder, res| {
+        let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Selection BG"),
+            layout: &dp.selection_bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: params.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: ids.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(res.view(entity_id)) },
+            ],
+        });
+        passes::present::render_selection_pass(encoder, res.view(surface), &dp.selection_pipeline, &bg);
+    });
+}
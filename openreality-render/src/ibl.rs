@@ -1,255 +1,438 @@
-//! IBL (Image-Based Lighting) environment generation.
-//! Generates a procedural sky cubemap and computes irradiance/prefilter maps.
-//! This is a simplified version — for production, accept pre-processed HDR cubemaps.
+//! IBL (Image-Based Lighting) environment precomputation.
+//!
+//! An equirectangular HDR image or the analytic sky (`sky.wgsl`) is baked on
+//! the GPU (render passes in `ibl_bake.wgsl`) into the maps the lighting pass
+//! samples for ambient light: a radiance cubemap with a mip chain, a diffuse
+//! irradiance cubemap, a GGX prefiltered cubemap with one roughness level per
+//! mip, and the split-sum BRDF lookup table.
+
+use wgpu::util::DeviceExt;
+
+use openreality_gpu_shared::hdr::HdrImage;
+use openreality_gpu_shared::shaders;
+use openreality_gpu_shared::uniforms::SkyUniforms;
+
+use crate::pipeline;
+
+/// Largest face size of the radiance cubemap built from the equirect image.
+pub const ENVIRONMENT_MAX_SIZE: u32 = 512;
+/// Face size of the radiance cubemap baked from the analytic sky.
+pub const SKY_ENVIRONMENT_SIZE: u32 = 128;
+pub const IRRADIANCE_SIZE: u32 = 32;
+pub const PREFILTER_SIZE: u32 = 128;
+/// Prefiltered mips, from roughness 0 (mip 0) to 1 (last mip).
+pub const PREFILTER_MIPS: u32 = 5;
+pub const BRDF_LUT_SIZE: u32 = 256;
+
+/// Format of the environment, irradiance and prefiltered cubemaps.
+pub const IBL_CUBE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+/// BRDF LUT format: scale (R) and bias (G) applied to F0.
+pub const BRDF_LUT_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rg16Float;
+
+const IRRADIANCE_SAMPLES: u32 = 512;
+const PREFILTER_SAMPLES: u32 = 256;
+const BRDF_LUT_SAMPLES: u32 = 512;
+
+/// Matches `BakeParams` in `ibl_bake.wgsl`.
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct BakeParams {
+    face: u32,
+    roughness: f32,
+    source_size: f32,
+    sample_count: u32,
+}
 
 /// IBL environment state.
 pub struct IBLEnvironment {
+    /// Radiance cubemap with a full mip chain (also usable as a skybox).
+    pub environment_cubemap: wgpu::Texture,
+    pub environment_view: wgpu::TextureView,
     pub irradiance_cubemap: wgpu::Texture,
     pub irradiance_view: wgpu::TextureView,
     pub prefilter_cubemap: wgpu::Texture,
     pub prefilter_view: wgpu::TextureView,
     pub brdf_lut: wgpu::Texture,
     pub brdf_lut_view: wgpu::TextureView,
+    /// Trilinear, edge-clamped sampler for all of the above.
+    pub sampler: wgpu::Sampler,
 }
 
-/// Generate a BRDF integration LUT (2D texture).
-/// This is a precomputed lookup table for the split-sum approximation.
-pub fn generate_brdf_lut(device: &wgpu::Device, queue: &wgpu::Queue) -> (wgpu::Texture, wgpu::TextureView) {
-    let size = 256u32;
-    let mut data = vec![0u8; (size * size * 4) as usize]; // RG16F packed as RGBA8 for simplicity
+impl IBLEnvironment {
+    /// Bake an environment from an equirectangular HDR image (2:1, top row
+    /// looking up). The work is submitted to `queue` before returning.
+    pub fn from_equirect(device: &wgpu::Device, queue: &wgpu::Queue, image: &HdrImage) -> Result<Self, String> {
+        let max_dimension = device.limits().max_texture_dimension_2d;
+        if image.width == 0 || image.height == 0 {
+            return Err("environment image is empty".into());
+        }
+        if image.width > max_dimension || image.height > max_dimension {
+            return Err(format!(
+                "environment image {}x{} exceeds the {max_dimension} texture limit",
+                image.width, image.height
+            ));
+        }
 
-    for y in 0..size {
-        for x in 0..size {
-            let n_dot_v = (x as f32 + 0.5) / size as f32;
-            let roughness = (y as f32 + 0.5) / size as f32;
+        let equirect = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("IBL Equirect Source"),
+                size: wgpu::Extent3d { width: image.width, height: image.height, depth_or_array_layers: 1 },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                // RGBE bytes, decoded in the shader
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            bytemuck::cast_slice(&image.rgbe),
+        );
+        let equirect_view = equirect.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let env_size = (image.width / 4).next_power_of_two().clamp(16, ENVIRONMENT_MAX_SIZE);
+        Ok(Self::bake(device, queue, env_size, |baker, encoder, environment| {
+            for face in 0..6 {
+                let params = BakeParams { face, roughness: 0.0, source_size: 0.0, sample_count: 0 };
+                let bg = baker.equirect_bind_group(&params, &equirect_view);
+                baker.draw(encoder, &baker.equirect_pipeline, &bg, &face_view(environment, face, 0));
+            }
+        }))
+    }
 
-            let (scale, bias) = integrate_brdf(n_dot_v.max(0.001), roughness);
+    /// Bake an environment from the analytic atmosphere in `sky.wgsl`
+    /// (`SKY_MODE_ATMOSPHERE` uniforms; `face` is set per draw).
+    pub fn from_sky(device: &wgpu::Device, queue: &wgpu::Queue, sky: &SkyUniforms) -> Self {
+        let sky_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("IBL Sky BGL"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let sky_pipeline = pipeline::create_fullscreen_effect_pipeline(
+            device, "IBL Sky To Cube", shaders::SKY_SHADER, "fs_sky_cube", &sky_bgl, IBL_CUBE_FORMAT,
+        );
 
-            let idx = ((y * size + x) * 4) as usize;
-            data[idx] = (scale.clamp(0.0, 1.0) * 255.0) as u8;
-            data[idx + 1] = (bias.clamp(0.0, 1.0) * 255.0) as u8;
-            data[idx + 2] = 0;
-            data[idx + 3] = 255;
-        }
+        Self::bake(device, queue, SKY_ENVIRONMENT_SIZE, |baker, encoder, environment| {
+            for face in 0..6 {
+                let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("IBL Sky Uniforms"),
+                    contents: bytemuck::bytes_of(&SkyUniforms { face, ..*sky }),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+                let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("IBL Sky BG"),
+                    layout: &sky_bgl,
+                    entries: &[wgpu::BindGroupEntry { binding: 1, resource: buffer.as_entire_binding() }],
+                });
+                baker.draw(encoder, &sky_pipeline, &bg, &face_view(environment, face, 0));
+            }
+        })
     }
 
-    let texture = device.create_texture(&wgpu::TextureDescriptor {
-        label: Some("BRDF LUT"),
-        size: wgpu::Extent3d {
-            width: size,
-            height: size,
-            depth_or_array_layers: 1,
-        },
-        mip_level_count: 1,
-        sample_count: 1,
-        dimension: wgpu::TextureDimension::D2,
-        format: wgpu::TextureFormat::Rgba8Unorm,
-        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-        view_formats: &[],
-    });
-
-    queue.write_texture(
-        wgpu::ImageCopyTexture {
-            texture: &texture,
-            mip_level: 0,
-            origin: wgpu::Origin3d::ZERO,
-            aspect: wgpu::TextureAspect::All,
-        },
-        &data,
-        wgpu::ImageDataLayout {
-            offset: 0,
-            bytes_per_row: Some(4 * size),
-            rows_per_image: Some(size),
-        },
-        wgpu::Extent3d {
-            width: size,
-            height: size,
-            depth_or_array_layers: 1,
-        },
-    );
-
-    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-    (texture, view)
-}
+    /// Bake every map from an `env_size` environment cubemap whose mip 0 is
+    /// drawn by `draw_source`. The work is submitted to `queue` before returning.
+    fn bake(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        env_size: u32,
+        draw_source: impl FnOnce(&Baker, &mut wgpu::CommandEncoder, &wgpu::Texture),
+    ) -> Self {
+        let env_mips = env_size.ilog2() + 1;
+        let usage = wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING;
+        let environment_cubemap = create_cubemap(device, "IBL Environment Cubemap", env_size, env_mips, usage);
+        let irradiance_cubemap = create_cubemap(device, "IBL Irradiance Cubemap", IRRADIANCE_SIZE, 1, usage);
+        let prefilter_cubemap = create_cubemap(device, "IBL Prefilter Cubemap", PREFILTER_SIZE, PREFILTER_MIPS, usage);
+        let brdf_lut = create_brdf_lut(device, BRDF_LUT_SIZE, usage);
+
+        let sampler = create_sampler(device);
+        let baker = Baker::new(device, &sampler);
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("IBL Bake Encoder"),
+        });
+
+        // 1. Source -> environment mip 0, then box-filter the mip chain.
+        draw_source(&baker, &mut encoder, &environment_cubemap);
+        for mip in 1..env_mips {
+            let source = cube_view(&environment_cubemap, mip - 1, Some(1));
+            for face in 0..6 {
+                let params = BakeParams { face, roughness: 0.0, source_size: 0.0, sample_count: 0 };
+                let bg = baker.cube_bind_group(&params, &source);
+                baker.draw(&mut encoder, &baker.downsample_pipeline, &bg, &face_view(&environment_cubemap, face, mip));
+            }
+        }
 
-/// Generate a simple procedural sky cubemap (gradient sky).
-pub fn generate_procedural_sky_cubemap(
-    device: &wgpu::Device,
-    queue: &wgpu::Queue,
-    size: u32,
-) -> (wgpu::Texture, wgpu::TextureView) {
-    let texture = device.create_texture(&wgpu::TextureDescriptor {
-        label: Some("Procedural Sky Cubemap"),
-        size: wgpu::Extent3d {
-            width: size,
-            height: size,
-            depth_or_array_layers: 6,
-        },
-        mip_level_count: 1,
-        sample_count: 1,
-        dimension: wgpu::TextureDimension::D2,
-        format: wgpu::TextureFormat::Rgba8Unorm,
-        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-        view_formats: &[],
-    });
-
-    // Simple gradient: sky blue at top, horizon white, ground dark
-    let mut face_data = vec![0u8; (size * size * 4) as usize];
-
-    for face in 0..6u32 {
-        for y in 0..size {
-            for x in 0..size {
-                let dir = cubemap_direction(face, x, y, size);
-                let up = dir[1]; // Y component
-
-                // Sky gradient
-                let (r, g, b) = if up > 0.0 {
-                    // Sky: lerp from horizon white to zenith blue
-                    let t = up;
-                    (
-                        lerp(0.8, 0.3, t),
-                        lerp(0.85, 0.5, t),
-                        lerp(0.9, 0.9, t),
-                    )
-                } else {
-                    // Ground: dark gray
-                    let t = (-up).min(1.0);
-                    (
-                        lerp(0.5, 0.2, t),
-                        lerp(0.5, 0.2, t),
-                        lerp(0.5, 0.2, t),
-                    )
-                };
-
-                let idx = ((y * size + x) * 4) as usize;
-                face_data[idx] = (r * 255.0) as u8;
-                face_data[idx + 1] = (g * 255.0) as u8;
-                face_data[idx + 2] = (b * 255.0) as u8;
-                face_data[idx + 3] = 255;
+        // 2. Diffuse irradiance and specular prefilter convolutions of the full chain.
+        let environment_view = cube_view(&environment_cubemap, 0, None);
+        for face in 0..6 {
+            let params = BakeParams { face, roughness: 0.0, source_size: env_size as f32, sample_count: IRRADIANCE_SAMPLES };
+            let bg = baker.cube_bind_group(&params, &environment_view);
+            baker.draw(&mut encoder, &baker.irradiance_pipeline, &bg, &face_view(&irradiance_cubemap, face, 0));
+        }
+        for mip in 0..PREFILTER_MIPS {
+            let roughness = mip as f32 / (PREFILTER_MIPS - 1) as f32;
+            for face in 0..6 {
+                let params = BakeParams { face, roughness, source_size: env_size as f32, sample_count: PREFILTER_SAMPLES };
+                let bg = baker.cube_bind_group(&params, &environment_view);
+                baker.draw(&mut encoder, &baker.prefilter_pipeline, &bg, &face_view(&prefilter_cubemap, face, mip));
             }
         }
 
-        queue.write_texture(
-            wgpu::ImageCopyTexture {
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d {
-                    x: 0,
-                    y: 0,
-                    z: face,
-                },
-                aspect: wgpu::TextureAspect::All,
-            },
-            &face_data,
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * size),
-                rows_per_image: Some(size),
-            },
-            wgpu::Extent3d {
-                width: size,
-                height: size,
-                depth_or_array_layers: 1,
-            },
-        );
+        // 3. BRDF LUT (independent of the environment).
+        let params = BakeParams { face: 0, roughness: 0.0, source_size: 0.0, sample_count: BRDF_LUT_SAMPLES };
+        let bg = baker.params_bind_group(&params);
+        let brdf_lut_view = brdf_lut.create_view(&wgpu::TextureViewDescriptor::default());
+        baker.draw(&mut encoder, &baker.brdf_lut_pipeline, &bg, &brdf_lut_view);
+
+        queue.submit(std::iter::once(encoder.finish()));
+
+        Self {
+            irradiance_view: cube_view(&irradiance_cubemap, 0, None),
+            prefilter_view: cube_view(&prefilter_cubemap, 0, None),
+            environment_view,
+            environment_cubemap,
+            irradiance_cubemap,
+            prefilter_cubemap,
+            brdf_lut,
+            brdf_lut_view,
+            sampler,
+        }
     }
 
-    let view = texture.create_view(&wgpu::TextureViewDescriptor {
-        dimension: Some(wgpu::TextureViewDimension::Cube),
-        ..Default::default()
-    });
-
-    (texture, view)
-}
-
-// ---- Internal helpers ----
+    /// 1x1 black maps, bound when no environment is loaded (`has_ibl` is 0).
+    pub fn placeholder(device: &wgpu::Device) -> Self {
+        let usage = wgpu::TextureUsages::TEXTURE_BINDING;
+        let environment_cubemap = create_cubemap(device, "IBL Placeholder Cubemap", 1, 1, usage);
+        let irradiance_cubemap = create_cubemap(device, "IBL Placeholder Cubemap", 1, 1, usage);
+        let prefilter_cubemap = create_cubemap(device, "IBL Placeholder Cubemap", 1, 1, usage);
+        let brdf_lut = create_brdf_lut(device, 1, usage);
+        Self {
+            environment_view: cube_view(&environment_cubemap, 0, None),
+            irradiance_view: cube_view(&irradiance_cubemap, 0, None),
+            prefilter_view: cube_view(&prefilter_cubemap, 0, None),
+            brdf_lut_view: brdf_lut.create_view(&wgpu::TextureViewDescriptor::default()),
+            environment_cubemap,
+            irradiance_cubemap,
+            prefilter_cubemap,
+            brdf_lut,
+            sampler: create_sampler(device),
+        }
+    }
 
-fn lerp(a: f32, b: f32, t: f32) -> f32 {
-    a + (b - a) * t
+    /// Bind group entries for the irradiance map, prefiltered map, BRDF LUT
+    /// and sampler at `first_binding`..`first_binding + 4` (see
+    /// `pipeline::ibl_layout_entries`).
+    pub fn entries(&self, first_binding: u32) -> [wgpu::BindGroupEntry<'_>; 4] {
+        [
+            wgpu::BindGroupEntry { binding: first_binding, resource: wgpu::BindingResource::TextureView(&self.irradiance_view) },
+            wgpu::BindGroupEntry { binding: first_binding + 1, resource: wgpu::BindingResource::TextureView(&self.prefilter_view) },
+            wgpu::BindGroupEntry { binding: first_binding + 2, resource: wgpu::BindingResource::TextureView(&self.brdf_lut_view) },
+            wgpu::BindGroupEntry { binding: first_binding + 3, resource: wgpu::BindingResource::Sampler(&self.sampler) },
+        ]
+    }
 }
 
-/// Get the world-space direction for a cubemap texel.
-fn cubemap_direction(face: u32, x: u32, y: u32, size: u32) -> [f32; 3] {
-    let u = (x as f32 + 0.5) / size as f32 * 2.0 - 1.0;
-    let v = (y as f32 + 0.5) / size as f32 * 2.0 - 1.0;
-
-    let dir = match face {
-        0 => [1.0, -v, -u],   // +X
-        1 => [-1.0, -v, u],   // -X
-        2 => [u, 1.0, v],     // +Y
-        3 => [u, -1.0, -v],   // -Y
-        4 => [u, -v, 1.0],    // +Z
-        _ => [-u, -v, -1.0],  // -Z
-    };
-
-    let len = (dir[0] * dir[0] + dir[1] * dir[1] + dir[2] * dir[2]).sqrt();
-    [dir[0] / len, dir[1] / len, dir[2] / len]
+/// Pipelines and layouts of the bake passes, alive for one bake.
+struct Baker<'a> {
+    device: &'a wgpu::Device,
+    sampler: &'a wgpu::Sampler,
+    params_bgl: wgpu::BindGroupLayout,
+    equirect_bgl: wgpu::BindGroupLayout,
+    cube_bgl: wgpu::BindGroupLayout,
+    equirect_pipeline: wgpu::RenderPipeline,
+    downsample_pipeline: wgpu::RenderPipeline,
+    irradiance_pipeline: wgpu::RenderPipeline,
+    prefilter_pipeline: wgpu::RenderPipeline,
+    brdf_lut_pipeline: wgpu::RenderPipeline,
 }
 
-/// Numerically integrate the BRDF split-sum for a given NdotV and roughness.
-fn integrate_brdf(n_dot_v: f32, roughness: f32) -> (f32, f32) {
-    let v = [
-        (1.0 - n_dot_v * n_dot_v).sqrt(),
-        0.0f32,
-        n_dot_v,
-    ];
-
-    let mut a = 0.0f32;
-    let mut b = 0.0f32;
-    let sample_count = 64u32;
-
-    for i in 0..sample_count {
-        let xi = hammersley(i, sample_count);
-        let h = importance_sample_ggx(xi, roughness);
-
-        let l = [
-            2.0 * (v[0] * h[0] + v[1] * h[1] + v[2] * h[2]) * h[0] - v[0],
-            2.0 * (v[0] * h[0] + v[1] * h[1] + v[2] * h[2]) * h[1] - v[1],
-            2.0 * (v[0] * h[0] + v[1] * h[1] + v[2] * h[2]) * h[2] - v[2],
-        ];
-
-        let n_dot_l = l[2].max(0.0);
-        let n_dot_h = h[2].max(0.0);
-        let v_dot_h = (v[0] * h[0] + v[1] * h[1] + v[2] * h[2]).max(0.0);
-
-        if n_dot_l > 0.0 {
-            let g = geometry_smith_ibl(n_dot_v, n_dot_l, roughness);
-            let g_vis = (g * v_dot_h) / (n_dot_h * n_dot_v).max(0.001);
-            let fc = (1.0 - v_dot_h).powi(5);
-
-            a += (1.0 - fc) * g_vis;
-            b += fc * g_vis;
+impl<'a> Baker<'a> {
+    fn new(device: &'a wgpu::Device, sampler: &'a wgpu::Sampler) -> Self {
+        let params = wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let texture = |binding, filterable, view_dimension| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable },
+                view_dimension,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let bgl = |label, entries: &[wgpu::BindGroupLayoutEntry]| {
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor { label: Some(label), entries })
+        };
+        let params_bgl = bgl("IBL Bake Params BGL", &[params]);
+        let equirect_bgl = bgl("IBL Bake Equirect BGL", &[params, texture(1, false, wgpu::TextureViewDimension::D2)]);
+        let cube_bgl = bgl("IBL Bake Cube BGL", &[
+            params,
+            texture(2, true, wgpu::TextureViewDimension::Cube),
+            wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ]);
+
+        let bake_pipeline = |label, entry, layout, format| {
+            pipeline::create_fullscreen_effect_pipeline(device, label, shaders::IBL_BAKE_SHADER, entry, layout, format)
+        };
+        Self {
+            equirect_pipeline: bake_pipeline("IBL Equirect To Cube", "fs_equirect_to_cube", &equirect_bgl, IBL_CUBE_FORMAT),
+            downsample_pipeline: bake_pipeline("IBL Downsample", "fs_downsample", &cube_bgl, IBL_CUBE_FORMAT),
+            irradiance_pipeline: bake_pipeline("IBL Irradiance", "fs_irradiance", &cube_bgl, IBL_CUBE_FORMAT),
+            prefilter_pipeline: bake_pipeline("IBL Prefilter", "fs_prefilter", &cube_bgl, IBL_CUBE_FORMAT),
+            brdf_lut_pipeline: bake_pipeline("IBL BRDF LUT", "fs_brdf_lut", &params_bgl, BRDF_LUT_FORMAT),
+            device,
+            sampler,
+            params_bgl,
+            equirect_bgl,
+            cube_bgl,
         }
     }
 
-    (a / sample_count as f32, b / sample_count as f32)
+    fn params_buffer(&self, params: &BakeParams) -> wgpu::Buffer {
+        self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("IBL Bake Params"),
+            contents: bytemuck::bytes_of(params),
+            usage: wgpu::BufferUsages::UNIFORM,
+        })
+    }
+
+    fn params_bind_group(&self, params: &BakeParams) -> wgpu::BindGroup {
+        let buffer = self.params_buffer(params);
+        self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("IBL Bake BG"),
+            layout: &self.params_bgl,
+            entries: &[wgpu::BindGroupEntry { binding: 0, resource: buffer.as_entire_binding() }],
+        })
+    }
+
+    fn equirect_bind_group(&self, params: &BakeParams, equirect: &wgpu::TextureView) -> wgpu::BindGroup {
+        let buffer = self.params_buffer(params);
+        self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("IBL Bake Equirect BG"),
+            layout: &self.equirect_bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(equirect) },
+            ],
+        })
+    }
+
+    fn cube_bind_group(&self, params: &BakeParams, source: &wgpu::TextureView) -> wgpu::BindGroup {
+        let buffer = self.params_buffer(params);
+        self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("IBL Bake Cube BG"),
+            layout: &self.cube_bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(source) },
+                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::Sampler(self.sampler) },
+            ],
+        })
+    }
+
+    /// Draw a fullscreen triangle into `target`.
+    fn draw(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        pipeline: &wgpu::RenderPipeline,
+        bind_group: &wgpu::BindGroup,
+        target: &wgpu::TextureView,
+    ) {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("IBL Bake Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            ..Default::default()
+        });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
 }
 
-fn hammersley(i: u32, n: u32) -> [f32; 2] {
-    [i as f32 / n as f32, radical_inverse_vdc(i)]
+fn create_cubemap(device: &wgpu::Device, label: &str, size: u32, mips: u32, usage: wgpu::TextureUsages) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d { width: size, height: size, depth_or_array_layers: 6 },
+        mip_level_count: mips,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: IBL_CUBE_FORMAT,
+        usage,
+        view_formats: &[],
+    })
 }
 
-fn radical_inverse_vdc(mut bits: u32) -> f32 {
-    bits = (bits << 16) | (bits >> 16);
-    bits = ((bits & 0x55555555) << 1) | ((bits & 0xAAAAAAAA) >> 1);
-    bits = ((bits & 0x33333333) << 2) | ((bits & 0xCCCCCCCC) >> 2);
-    bits = ((bits & 0x0F0F0F0F) << 4) | ((bits & 0xF0F0F0F0) >> 4);
-    bits = ((bits & 0x00FF00FF) << 8) | ((bits & 0xFF00FF00) >> 8);
-    bits as f32 * 2.3283064365386963e-10
+fn create_brdf_lut(device: &wgpu::Device, size: u32, usage: wgpu::TextureUsages) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("BRDF LUT"),
+        size: wgpu::Extent3d { width: size, height: size, depth_or_array_layers: 1 },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: BRDF_LUT_FORMAT,
+        usage,
+        view_formats: &[],
+    })
 }
 
-fn importance_sample_ggx(xi: [f32; 2], roughness: f32) -> [f32; 3] {
-    let a = roughness * roughness;
-    let phi = 2.0 * std::f32::consts::PI * xi[0];
-    let cos_theta = ((1.0 - xi[1]) / (1.0 + (a * a - 1.0) * xi[1])).sqrt();
-    let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+fn create_sampler(device: &wgpu::Device) -> wgpu::Sampler {
+    device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some("IBL Sampler"),
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        mipmap_filter: wgpu::FilterMode::Linear,
+        ..Default::default()
+    })
+}
 
-    [phi.cos() * sin_theta, phi.sin() * sin_theta, cos_theta]
+/// Cube view from `base_mip` (through `mip_count` levels, or the rest of the chain).
+fn cube_view(texture: &wgpu::Texture, base_mip: u32, mip_count: Option<u32>) -> wgpu::TextureView {
+    texture.create_view(&wgpu::TextureViewDescriptor {
+        dimension: Some(wgpu::TextureViewDimension::Cube),
+        base_mip_level: base_mip,
+        mip_level_count: mip_count,
+        ..Default::default()
+    })
 }
 
-fn geometry_smith_ibl(n_dot_v: f32, n_dot_l: f32, roughness: f32) -> f32 {
-    let k = (roughness * roughness) / 2.0;
-    let ggx_v = n_dot_v / (n_dot_v * (1.0 - k) + k);
-    let ggx_l = n_dot_l / (n_dot_l * (1.0 - k) + k);
-    ggx_v * ggx_l
+/// Render-target view of one face at one mip level.
+fn face_view(texture: &wgpu::Texture, face: u32, mip: u32) -> wgpu::TextureView {
+    texture.create_view(&wgpu::TextureViewDescriptor {
+        label: Some("IBL Face View"),
+        dimension: Some(wgpu::TextureViewDimension::D2),
+        base_mip_level: mip,
+        mip_level_count: Some(1),
+        base_array_layer: face,
+        array_layer_count: Some(1),
+        ..Default::default()
+    })
 }
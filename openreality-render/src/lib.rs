@@ -7,8 +7,16 @@
 
 pub mod types;
 pub mod handle;
+pub mod object_buffer;
+pub mod light_clusters;
 pub mod pipeline;
 pub mod render_targets;
+pub mod offscreen;
 pub mod passes;
 pub mod ibl;
+pub mod color_lut;
+pub mod picking;
+pub mod profiler;
 pub mod scene_renderer;
+pub mod settings;
+pub mod graph;
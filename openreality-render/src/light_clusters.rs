@@ -0,0 +1,120 @@
+//! GPU buffers for clustered point and spot lights.
+//!
+//! The lighting and forward shaders read local lights from storage buffers
+//! rather than the fixed array in `LightUniforms`: all of the frame's point
+//! and spot lights, a `[offset, count]` range per cluster, the flattened
+//! per-cluster light indices built by `openreality_gpu_shared::clusters`, and
+//! the shadow-atlas tiles the lights' `shadow_index` points at. Growable
+//! buffers are never empty, since wgpu rejects zero-sized storage bindings.
+
+use openreality_gpu_shared::clusters::{LightClusters, CLUSTER_COUNT};
+use openreality_gpu_shared::uniforms::{ClusterUniforms, LocalLightData, ShadowTileData};
+
+/// Lights, cluster indices and shadow tiles allocated up front.
+pub const INITIAL_LIGHT_CAPACITY: u64 = 64;
+pub const INITIAL_INDEX_CAPACITY: u64 = 1024;
+pub const INITIAL_TILE_CAPACITY: u64 = 16;
+
+/// A storage buffer that is reallocated (doubling) when an upload outgrows it.
+struct GrowableBuffer {
+    buffer: wgpu::Buffer,
+    label: &'static str,
+    stride: u64,
+    capacity: u64,
+}
+
+impl GrowableBuffer {
+    fn new(device: &wgpu::Device, label: &'static str, stride: u64, capacity: u64) -> Self {
+        Self { buffer: storage_buffer(device, label, stride * capacity), label, stride, capacity }
+    }
+
+    fn write(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[u8]) {
+        let count = data.len() as u64 / self.stride;
+        if count > self.capacity {
+            self.capacity = count.next_power_of_two();
+            self.buffer = storage_buffer(device, self.label, self.stride * self.capacity);
+        }
+        if !data.is_empty() {
+            queue.write_buffer(&self.buffer, 0, data);
+        }
+    }
+}
+
+fn storage_buffer(device: &wgpu::Device, label: &str, size: u64) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(label),
+        size,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+pub struct LightClusterBuffers {
+    params: wgpu::Buffer,
+    ranges: wgpu::Buffer,
+    lights: GrowableBuffer,
+    indices: GrowableBuffer,
+    shadow_tiles: GrowableBuffer,
+}
+
+impl LightClusterBuffers {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let params = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Cluster Params"),
+            size: std::mem::size_of::<ClusterUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Self {
+            params,
+            ranges: storage_buffer(device, "Cluster Light Ranges", CLUSTER_COUNT as u64 * 8),
+            lights: GrowableBuffer::new(
+                device,
+                "Cluster Lights",
+                std::mem::size_of::<LocalLightData>() as u64,
+                INITIAL_LIGHT_CAPACITY,
+            ),
+            indices: GrowableBuffer::new(device, "Cluster Light Indices", 4, INITIAL_INDEX_CAPACITY),
+            shadow_tiles: GrowableBuffer::new(
+                device,
+                "Local Shadow Tiles",
+                std::mem::size_of::<ShadowTileData>() as u64,
+                INITIAL_TILE_CAPACITY,
+            ),
+        }
+    }
+
+    /// Upload this frame's lights, their cluster assignment and shadow tiles.
+    /// Bind groups referencing these buffers must be created after this call,
+    /// since the growable buffers may be reallocated.
+    pub fn upload(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        lights: &[LocalLightData],
+        clusters: &LightClusters,
+        shadow_tiles: &[ShadowTileData],
+    ) {
+        queue.write_buffer(&self.params, 0, bytemuck::bytes_of(&clusters.params));
+        queue.write_buffer(&self.ranges, 0, bytemuck::cast_slice(&clusters.ranges));
+        self.lights.write(device, queue, bytemuck::cast_slice(lights));
+        self.indices.write(device, queue, bytemuck::cast_slice(&clusters.indices));
+        self.shadow_tiles.write(device, queue, bytemuck::cast_slice(shadow_tiles));
+    }
+
+    /// Bind group entries for the cluster params, lights, ranges and indices
+    /// at `first_binding`..`first_binding + 4` (see `pipeline::cluster_layout_entries`).
+    pub fn entries(&self, first_binding: u32) -> [wgpu::BindGroupEntry<'_>; 4] {
+        [
+            wgpu::BindGroupEntry { binding: first_binding, resource: self.params.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: first_binding + 1, resource: self.lights.buffer.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: first_binding + 2, resource: self.ranges.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: first_binding + 3, resource: self.indices.buffer.as_entire_binding() },
+        ]
+    }
+
+    /// `ShadowTileData` array indexed by the lights' `shadow_index`.
+    pub fn shadow_tiles(&self) -> &wgpu::Buffer {
+        &self.shadow_tiles.buffer
+    }
+}
@@ -0,0 +1,147 @@
+//! Per-frame object uniform buffer bound with dynamic offsets.
+//!
+//! Each draw's `PerObjectUniforms` gets one aligned slot in a single buffer
+//! and passes pick it with `set_bind_group(.., &[offset])`. Slots are handed
+//! out linearly and rewound with `clear()` before each frame (or each FFI
+//! pass call); the whole frame is written with one `queue.write_buffer`, so
+//! shadow, G-buffer and forward draws no longer allocate a buffer and bind
+//! group apiece.
+
+use openreality_gpu_shared::uniforms::PerObjectUniforms;
+
+/// Slots allocated up front; the buffer doubles when a frame needs more.
+pub const INITIAL_OBJECT_CAPACITY: u64 = 256;
+
+const OBJECT_SIZE: u64 = std::mem::size_of::<PerObjectUniforms>() as u64;
+
+/// Distance between slots: the object size rounded up to the device's
+/// dynamic uniform offset alignment.
+pub fn slot_stride(alignment: u32) -> u64 {
+    let alignment = alignment.max(1) as u64;
+    OBJECT_SIZE.div_ceil(alignment) * alignment
+}
+
+/// Capacity (in slots) needed to hold `needed` objects, growing by powers of two.
+pub fn grown_capacity(capacity: u64, needed: u64) -> u64 {
+    if needed <= capacity {
+        capacity
+    } else {
+        needed.next_power_of_two().max(INITIAL_OBJECT_CAPACITY)
+    }
+}
+
+pub struct ObjectBuffer {
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    stride: u64,
+    capacity: u64,
+    /// CPU copy of this frame's slots, uploaded by `upload()`.
+    staging: Vec<u8>,
+}
+
+impl ObjectBuffer {
+    pub fn new(device: &wgpu::Device, layout: &wgpu::BindGroupLayout) -> Self {
+        let stride = slot_stride(device.limits().min_uniform_buffer_offset_alignment);
+        let (buffer, bind_group) = Self::allocate(device, layout, stride, INITIAL_OBJECT_CAPACITY);
+        Self {
+            buffer,
+            bind_group,
+            stride,
+            capacity: INITIAL_OBJECT_CAPACITY,
+            staging: Vec::new(),
+        }
+    }
+
+    fn allocate(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        stride: u64,
+        capacity: u64,
+    ) -> (wgpu::Buffer, wgpu::BindGroup) {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Per-Object Uniforms"),
+            size: stride * capacity,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Per-Object BG"),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(OBJECT_SIZE),
+                }),
+            }],
+        });
+        (buffer, bind_group)
+    }
+
+    /// Rewind to the first slot. Earlier uploads stay valid for work already
+    /// submitted, since `write_buffer` lands at the start of the next submit.
+    pub fn clear(&mut self) {
+        self.staging.clear();
+    }
+
+    /// Stage one object and return its dynamic offset.
+    pub fn push(&mut self, object: &PerObjectUniforms) -> u32 {
+        let offset = self.staging.len();
+        self.staging.extend_from_slice(bytemuck::bytes_of(object));
+        self.staging.resize(offset + self.stride as usize, 0);
+        offset as u32
+    }
+
+    pub fn len(&self) -> usize {
+        self.staging.len() / self.stride as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.staging.is_empty()
+    }
+
+    /// Write the staged slots to the GPU, growing the buffer (and replacing
+    /// the bind group) if this frame outgrew it. Call before recording draws.
+    pub fn upload(
+        &mut self,
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        queue: &wgpu::Queue,
+    ) {
+        if self.staging.is_empty() {
+            return;
+        }
+        let capacity = grown_capacity(self.capacity, self.len() as u64);
+        if capacity != self.capacity {
+            (self.buffer, self.bind_group) = Self::allocate(device, layout, self.stride, capacity);
+            self.capacity = capacity;
+        }
+        queue.write_buffer(&self.buffer, 0, &self.staging);
+    }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_stride_respects_alignment() {
+        assert_eq!(OBJECT_SIZE, 128);
+        assert_eq!(slot_stride(256), 256);
+        assert_eq!(slot_stride(64), 128);
+        assert_eq!(slot_stride(0), 128);
+    }
+
+    #[test]
+    fn test_grown_capacity_doubles() {
+        assert_eq!(grown_capacity(256, 10), 256);
+        assert_eq!(grown_capacity(256, 256), 256);
+        assert_eq!(grown_capacity(256, 257), 512);
+        assert_eq!(grown_capacity(512, 3000), 4096);
+    }
+}
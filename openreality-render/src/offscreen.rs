@@ -0,0 +1,250 @@
+//! Offscreen rendering and framebuffer readback.
+//!
+//! An `OffscreenTarget` stands in for a window surface: pass its `view` where
+//! a surface view is expected, then copy the pixels back to the CPU with
+//! `read_rgba8()`. HDR targets (the lighting buffer) read back as linear f32
+//! RGBA through `read_texture_f32()`. Readback blocks on `device.poll`, so it
+//! only completes on native backends; on the web the map never finishes
+//! before the call returns and an error is reported instead.
+
+/// Request an adapter and device without a surface, for screenshot tools and
+/// tests. `force_fallback_adapter` selects a software adapter (e.g. WARP,
+/// lavapipe, llvmpipe) when the platform provides one. Timestamp features
+/// are enabled where supported, for `FrameProfiler`.
+pub async fn request_headless_device(
+    instance: &wgpu::Instance,
+    force_fallback_adapter: bool,
+) -> Result<(wgpu::Adapter, wgpu::Device, wgpu::Queue), String> {
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface: None,
+            force_fallback_adapter,
+        })
+        .await
+        .ok_or("Failed to find suitable GPU adapter")?;
+
+    let (device, queue) = adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("OpenReality Headless Device"),
+                required_features: crate::profiler::supported_features(&adapter),
+                required_limits: wgpu::Limits::default(),
+                memory_hints: wgpu::MemoryHints::default(),
+            },
+            None,
+        )
+        .await
+        .map_err(|e| format!("Failed to create device: {e}"))?;
+
+    Ok((adapter, device, queue))
+}
+
+/// A color texture that frames are rendered into instead of a swapchain image.
+pub struct OffscreenTarget {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub width: u32,
+    pub height: u32,
+    pub format: wgpu::TextureFormat,
+}
+
+impl OffscreenTarget {
+    pub fn new(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Offscreen Target"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self {
+            texture,
+            view,
+            width,
+            height,
+            format,
+        }
+    }
+
+    /// Read the target back as tightly packed RGBA8 rows, top row first.
+    pub fn read_rgba8(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<Vec<u8>, String> {
+        let mut pixels = read_texture(device, queue, &self.texture)?;
+        match self.format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => {}
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => {
+                swizzle_bgra(&mut pixels)
+            }
+            other => return Err(format!("RGBA8 readback does not support {other:?}")),
+        }
+        Ok(pixels)
+    }
+}
+
+/// Bytes per row in a texture-to-buffer copy, padded to wgpu's 256-byte alignment.
+pub fn padded_bytes_per_row(unpadded: u32) -> u32 {
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    unpadded.div_ceil(align) * align
+}
+
+/// Copy mip 0 of a 2D color texture to the CPU, with row padding removed.
+/// The texture must have been created with `COPY_SRC`.
+pub fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) -> Result<Vec<u8>, String> {
+    let format = texture.format();
+    let texel_size = format
+        .block_copy_size(None)
+        .ok_or_else(|| format!("Cannot read back {format:?}"))?;
+    let (width, height) = (texture.width(), texture.height());
+    let unpadded = width * texel_size;
+    let padded = padded_bytes_per_row(unpadded);
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Readback Buffer"),
+        size: padded as u64 * height as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Readback Encoder"),
+    });
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded),
+                rows_per_image: Some(height),
+            },
+        },
+        wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+    );
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    let (tx, rx) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = tx.send(result);
+    });
+    device.poll(wgpu::Maintain::Wait);
+    rx.try_recv()
+        .map_err(|_| "Readback did not complete (blocking readback needs a native backend)".to_string())?
+        .map_err(|e| format!("Failed to map readback buffer: {e}"))?;
+
+    let pixels = unpad_rows(&slice.get_mapped_range(), unpadded as usize, padded as usize, height as usize);
+    buffer.unmap();
+    Ok(pixels)
+}
+
+/// Read an `Rgba16Float` or `Rgba32Float` texture as linear f32 RGBA.
+pub fn read_texture_f32(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) -> Result<Vec<f32>, String> {
+    match texture.format() {
+        wgpu::TextureFormat::Rgba16Float => {
+            let bytes = read_texture(device, queue, texture)?;
+            Ok(bytes
+                .chunks_exact(2)
+                .map(|h| f16_to_f32(u16::from_le_bytes([h[0], h[1]])))
+                .collect())
+        }
+        wgpu::TextureFormat::Rgba32Float => {
+            let bytes = read_texture(device, queue, texture)?;
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|f| f32::from_le_bytes([f[0], f[1], f[2], f[3]]))
+                .collect())
+        }
+        other => Err(format!("HDR readback does not support {other:?}")),
+    }
+}
+
+/// Drop the per-row padding a buffer copy adds.
+fn unpad_rows(data: &[u8], unpadded: usize, padded: usize, rows: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(unpadded * rows);
+    for row in data.chunks(padded).take(rows) {
+        out.extend_from_slice(&row[..unpadded]);
+    }
+    out
+}
+
+fn swizzle_bgra(pixels: &mut [u8]) {
+    for px in pixels.chunks_exact_mut(4) {
+        px.swap(0, 2);
+    }
+}
+
+/// Convert IEEE 754 half-precision bits to f32.
+pub fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f32;
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => sign * f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_padded_bytes_per_row() {
+        assert_eq!(padded_bytes_per_row(4), 256);
+        assert_eq!(padded_bytes_per_row(256), 256);
+        assert_eq!(padded_bytes_per_row(100 * 4), 512);
+        assert_eq!(padded_bytes_per_row(100 * 8), 1024);
+    }
+
+    #[test]
+    fn test_unpad_rows_and_swizzle() {
+        let mut data = vec![0u8; 512];
+        data[..4].copy_from_slice(&[1, 2, 3, 4]);
+        data[256..260].copy_from_slice(&[5, 6, 7, 8]);
+        let mut pixels = unpad_rows(&data, 4, 256, 2);
+        assert_eq!(pixels, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        swizzle_bgra(&mut pixels);
+        assert_eq!(pixels, vec![3, 2, 1, 4, 7, 6, 5, 8]);
+    }
+
+    #[test]
+    fn test_f16_to_f32() {
+        assert_eq!(f16_to_f32(0x0000), 0.0);
+        assert_eq!(f16_to_f32(0x3c00), 1.0);
+        assert_eq!(f16_to_f32(0xc000), -2.0);
+        assert_eq!(f16_to_f32(0x3800), 0.5);
+        assert_eq!(f16_to_f32(0x7bff), 65504.0);
+        assert_eq!(f16_to_f32(0x0001), 2f32.powi(-24));
+        assert_eq!(f16_to_f32(0x7c00), f32::INFINITY);
+        assert!(f16_to_f32(0x7e00).is_nan());
+    }
+}
@@ -0,0 +1,26 @@
+//! Auto-exposure pass — meter scene luminance on the GPU.
+
+/// Workgroup size of `cs_histogram` in each dimension.
+const HISTOGRAM_TILE: u32 = 16;
+
+/// Bin the luminance of a `width`×`height` HDR image, then average the
+/// histogram into the adapted exposure. Both stages share `bind_group`.
+pub fn render_auto_exposure(
+    encoder: &mut wgpu::CommandEncoder,
+    histogram_pipeline: &wgpu::ComputePipeline,
+    average_pipeline: &wgpu::ComputePipeline,
+    bind_group: &wgpu::BindGroup,
+    width: u32,
+    height: u32,
+) {
+    let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+        label: Some("Auto Exposure"),
+        timestamp_writes: None,
+    });
+
+    pass.set_bind_group(0, bind_group, &[]);
+    pass.set_pipeline(histogram_pipeline);
+    pass.dispatch_workgroups(width.div_ceil(HISTOGRAM_TILE), height.div_ceil(HISTOGRAM_TILE), 1);
+    pass.set_pipeline(average_pipeline);
+    pass.dispatch_workgroups(1, 1, 1);
+}
@@ -1,18 +1,16 @@
 //! Depth of Field pass — CoC computation, separable bokeh blur, composite.
 
-use crate::types::RenderTarget;
-
 /// Render CoC (circle of confusion) from depth buffer.
 pub fn render_dof_coc(
     encoder: &mut wgpu::CommandEncoder,
-    target: &RenderTarget,
+    target: &wgpu::TextureView,
     pipeline: &wgpu::RenderPipeline,
     bind_group: &wgpu::BindGroup,
 ) {
     let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
         label: Some("DOF CoC Pass"),
         color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-            view: &target.color_view,
+            view: target,
             resolve_target: None,
             ops: wgpu::Operations {
                 load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
@@ -31,14 +29,14 @@ pub fn render_dof_coc(
 /// Render separable blur pass (run once for horizontal, once for vertical).
 pub fn render_dof_blur(
     encoder: &mut wgpu::CommandEncoder,
-    target: &RenderTarget,
+    target: &wgpu::TextureView,
     pipeline: &wgpu::RenderPipeline,
     bind_group: &wgpu::BindGroup,
 ) {
     let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
         label: Some("DOF Blur Pass"),
         color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-            view: &target.color_view,
+            view: target,
             resolve_target: None,
             ops: wgpu::Operations {
                 load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
@@ -57,14 +55,14 @@ pub fn render_dof_blur(
 /// Render DOF composite — blend sharp and blurred based on CoC.
 pub fn render_dof_composite(
     encoder: &mut wgpu::CommandEncoder,
-    target: &RenderTarget,
+    target: &wgpu::TextureView,
     pipeline: &wgpu::RenderPipeline,
     bind_group: &wgpu::BindGroup,
 ) {
     let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
         label: Some("DOF Composite Pass"),
         color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-            view: &target.color_view,
+            view: target,
             resolve_target: None,
             ops: wgpu::Operations {
                 load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
@@ -1,7 +1,7 @@
 //! Forward PBR pass — render transparent objects with blending.
 
 use crate::types::RenderTarget;
-use crate::passes::gbuffer::GBufferEntity;
+use crate::passes::gbuffer::{entity_id_attachment, GBufferEntity};
 use openreality_gpu_shared::uniforms::MaterialUniforms;
 
 /// Render transparent entities with the forward PBR pipeline.
@@ -10,28 +10,31 @@ pub fn render_forward_pass(
     encoder: &mut wgpu::CommandEncoder,
     target: &RenderTarget,
     depth_view: &wgpu::TextureView,
+    entity_ids: Option<&wgpu::TextureView>,
     pipeline: &wgpu::RenderPipeline,
     per_frame_bg: &wgpu::BindGroup,
     light_shadow_bg: &wgpu::BindGroup,
-    per_object_bgl: &wgpu::BindGroupLayout,
+    objects: &wgpu::BindGroup,
     material_bgl: &wgpu::BindGroupLayout,
     device: &wgpu::Device,
     queue: &wgpu::Queue,
-    per_object_buffer: &wgpu::Buffer,
     entities: &[GBufferEntity<'_>],
     default_texture_view: &wgpu::TextureView,
     default_sampler: &wgpu::Sampler,
 ) {
+    let mut color_attachments = vec![Some(wgpu::RenderPassColorAttachment {
+        view: &target.color_view,
+        resolve_target: None,
+        ops: wgpu::Operations {
+            load: wgpu::LoadOp::Load, // Preserve lighting result
+            store: wgpu::StoreOp::Store,
+        },
+    })];
+    color_attachments.extend(entity_ids.map(|view| entity_id_attachment(view, false)));
+
     let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
         label: Some("Forward Transparent Pass"),
-        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-            view: &target.color_view,
-            resolve_target: None,
-            ops: wgpu::Operations {
-                load: wgpu::LoadOp::Load, // Preserve lighting result
-                store: wgpu::StoreOp::Store,
-            },
-        })],
+        color_attachments: &color_attachments,
         depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
             view: depth_view,
             depth_ops: Some(wgpu::Operations {
@@ -48,17 +51,6 @@ pub fn render_forward_pass(
     pass.set_bind_group(3, light_shadow_bg, &[]);
 
     for entity in entities {
-        queue.write_buffer(per_object_buffer, 0, bytemuck::bytes_of(&entity.per_object));
-
-        let obj_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Forward Per-Object BG"),
-            layout: per_object_bgl,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: per_object_buffer.as_entire_binding(),
-            }],
-        });
-
         let mat_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Forward Material UBO"),
             size: std::mem::size_of::<MaterialUniforms>() as u64,
@@ -89,12 +81,12 @@ pub fn render_forward_pass(
         });
 
         pass.set_bind_group(1, &mat_bg, &[]);
-        pass.set_bind_group(2, &obj_bg, &[]);
+        pass.set_bind_group(2, objects, &[entity.object_offset]);
 
         pass.set_vertex_buffer(0, entity.mesh.vertex_buffer.slice(..));
         pass.set_vertex_buffer(1, entity.mesh.normal_buffer.slice(..));
         pass.set_vertex_buffer(2, entity.mesh.uv_buffer.slice(..));
-        pass.set_index_buffer(entity.mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        pass.set_index_buffer(entity.mesh.index_buffer.slice(..), entity.mesh.index_format);
         pass.draw_indexed(0..entity.mesh.index_count, 0, 0..1);
     }
 }
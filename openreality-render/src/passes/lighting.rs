@@ -1,19 +1,20 @@
 //! Deferred lighting pass — fullscreen PBR lighting with Cook-Torrance BRDF.
 
-use crate::types::{GBuffer, RenderTarget};
+use crate::types::GBuffer;
 
 /// Render the deferred lighting pass into the lighting target.
 pub fn render_lighting_pass(
     encoder: &mut wgpu::CommandEncoder,
-    target: &RenderTarget,
+    target: &wgpu::TextureView,
     pipeline: &wgpu::RenderPipeline,
     lighting_bg: &wgpu::BindGroup,
     light_data_bg: &wgpu::BindGroup,
+    shadow_bg: &wgpu::BindGroup,
 ) {
     let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
         label: Some("Deferred Lighting Pass"),
         color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-            view: &target.color_view,
+            view: target,
             resolve_target: None,
             ops: wgpu::Operations {
                 load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
@@ -27,6 +28,7 @@ pub fn render_lighting_pass(
     pass.set_pipeline(pipeline);
     pass.set_bind_group(0, lighting_bg, &[]);
     pass.set_bind_group(1, light_data_bg, &[]);
+    pass.set_bind_group(2, shadow_bg, &[]);
 
     // Full-screen triangle via vertex index (no vertex buffer needed)
     pass.draw(0..3, 0..1);
@@ -90,3 +92,55 @@ pub fn create_lighting_bind_group(
         ],
     })
 }
+
+/// Create the lighting shadow bind group. Unused cascade slots must still be
+/// bound to some depth texture; `ShadowUniforms::num_cascades` decides which
+/// are sampled. The same goes for the shadow atlas when no light has tiles.
+pub fn create_lighting_shadow_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    shadow_uniform_buffer: &wgpu::Buffer,
+    cascade_views: [&wgpu::TextureView; 4],
+    comparison_sampler: &wgpu::Sampler,
+    shadow_atlas_view: &wgpu::TextureView,
+    shadow_tiles: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Lighting Shadow Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: shadow_uniform_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(cascade_views[0]),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::TextureView(cascade_views[1]),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::TextureView(cascade_views[2]),
+            },
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: wgpu::BindingResource::TextureView(cascade_views[3]),
+            },
+            wgpu::BindGroupEntry {
+                binding: 5,
+                resource: wgpu::BindingResource::Sampler(comparison_sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 6,
+                resource: wgpu::BindingResource::TextureView(shadow_atlas_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 7,
+                resource: shadow_tiles.as_entire_binding(),
+            },
+        ],
+    })
+}
@@ -15,3 +15,5 @@ pub mod terrain;
 pub mod dof;
 pub mod motion_blur;
 pub mod debug_lines;
+pub mod sky;
+pub mod auto_exposure;
@@ -1,18 +1,16 @@
 //! Motion Blur pass — velocity buffer from reprojection + directional blur.
 
-use crate::types::RenderTarget;
-
 /// Render per-pixel velocity buffer from depth + camera reprojection.
 pub fn render_velocity_pass(
     encoder: &mut wgpu::CommandEncoder,
-    target: &RenderTarget,
+    target: &wgpu::TextureView,
     pipeline: &wgpu::RenderPipeline,
     bind_group: &wgpu::BindGroup,
 ) {
     let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
         label: Some("Motion Blur Velocity Pass"),
         color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-            view: &target.color_view,
+            view: target,
             resolve_target: None,
             ops: wgpu::Operations {
                 load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
@@ -31,14 +29,14 @@ pub fn render_velocity_pass(
 /// Render directional blur along velocity vectors.
 pub fn render_blur_pass(
     encoder: &mut wgpu::CommandEncoder,
-    target: &RenderTarget,
+    target: &wgpu::TextureView,
     pipeline: &wgpu::RenderPipeline,
     bind_group: &wgpu::BindGroup,
 ) {
     let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
         label: Some("Motion Blur Pass"),
         color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-            view: &target.color_view,
+            view: target,
             resolve_target: None,
             ops: wgpu::Operations {
                 load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
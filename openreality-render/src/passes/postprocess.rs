@@ -1,11 +1,9 @@
 //! Post-processing pass — bloom extract/blur/composite, tone mapping, FXAA.
 
-use crate::types::RenderTarget;
-
 /// Render a generic fullscreen effect (bloom extract, blur, composite, FXAA, etc.).
 pub fn render_fullscreen_effect(
     encoder: &mut wgpu::CommandEncoder,
-    target: &RenderTarget,
+    target: &wgpu::TextureView,
     pipeline: &wgpu::RenderPipeline,
     bind_group: &wgpu::BindGroup,
     label: &str,
@@ -13,7 +11,7 @@ pub fn render_fullscreen_effect(
     let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
         label: Some(label),
         color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-            view: &target.color_view,
+            view: target,
             resolve_target: None,
             ops: wgpu::Operations {
                 load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
@@ -32,7 +30,7 @@ pub fn render_fullscreen_effect(
 /// Render bloom extract pass (threshold high-intensity pixels).
 pub fn render_bloom_extract(
     encoder: &mut wgpu::CommandEncoder,
-    target: &RenderTarget,
+    target: &wgpu::TextureView,
     pipeline: &wgpu::RenderPipeline,
     bind_group: &wgpu::BindGroup,
 ) {
@@ -42,7 +40,7 @@ pub fn render_bloom_extract(
 /// Render bloom blur pass (separable Gaussian blur).
 pub fn render_bloom_blur(
     encoder: &mut wgpu::CommandEncoder,
-    target: &RenderTarget,
+    target: &wgpu::TextureView,
     pipeline: &wgpu::RenderPipeline,
     bind_group: &wgpu::BindGroup,
     label: &str,
@@ -53,7 +51,7 @@ pub fn render_bloom_blur(
 /// Render bloom composite pass (add bloom to scene).
 pub fn render_bloom_composite(
     encoder: &mut wgpu::CommandEncoder,
-    target: &RenderTarget,
+    target: &wgpu::TextureView,
     pipeline: &wgpu::RenderPipeline,
     bind_group: &wgpu::BindGroup,
 ) {
@@ -63,7 +61,7 @@ pub fn render_bloom_composite(
 /// Render FXAA pass.
 pub fn render_fxaa(
     encoder: &mut wgpu::CommandEncoder,
-    target: &RenderTarget,
+    target: &wgpu::TextureView,
     pipeline: &wgpu::RenderPipeline,
     bind_group: &wgpu::BindGroup,
 ) {
@@ -1,4 +1,5 @@
-//! Cascaded shadow map depth pass.
+//! Shadow depth passes: cascaded directional shadows and the local light
+//! shadow atlas.
 
 use crate::types::{CascadedShadowMap, GPUMesh};
 
@@ -9,11 +10,8 @@ pub fn render_shadow_cascade(
     cascade_index: usize,
     pipeline: &wgpu::RenderPipeline,
     per_frame_bg: &wgpu::BindGroup,
-    per_object_bgl: &wgpu::BindGroupLayout,
-    device: &wgpu::Device,
-    queue: &wgpu::Queue,
-    per_object_buffer: &wgpu::Buffer,
-    meshes: &[(u64, &GPUMesh, [[f32; 4]; 4])], // (entity, mesh, model_matrix)
+    objects: &wgpu::BindGroup,
+    meshes: &[(u64, &GPUMesh, u32)], // (entity, mesh, object_offset)
 ) {
     let depth_view = &csm.depth_views[cascade_index];
 
@@ -34,36 +32,121 @@ pub fn render_shadow_cascade(
     pass.set_pipeline(pipeline);
     pass.set_bind_group(0, per_frame_bg, &[]);
 
-    for (_, mesh, model) in meshes {
-        // Create per-entity object buffer (can't reuse a single buffer because
-        // queue.write_buffer is staged and only the last write would survive).
-        let obj = openreality_gpu_shared::uniforms::PerObjectUniforms {
-            model: *model,
-            normal_matrix_col0: [0.0; 4], // Not needed for depth-only
-            normal_matrix_col1: [0.0; 4],
-            normal_matrix_col2: [0.0; 4],
-            _pad: [0.0; 4],
-        };
-        let obj_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Shadow Per-Object UBO"),
-            size: std::mem::size_of::<openreality_gpu_shared::uniforms::PerObjectUniforms>() as u64,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-        queue.write_buffer(&obj_buffer, 0, bytemuck::bytes_of(&obj));
+    for (_, mesh, object_offset) in meshes {
+        pass.set_bind_group(1, objects, &[*object_offset]);
 
-        let obj_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Shadow Per-Object BG"),
-            layout: per_object_bgl,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: obj_buffer.as_entire_binding(),
-            }],
-        });
-        pass.set_bind_group(1, &obj_bg, &[]);
+        pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+        pass.set_index_buffer(mesh.index_buffer.slice(..), mesh.index_format);
+        pass.draw_indexed(0..mesh.index_count, 0, 0..1);
+    }
+}
+
+/// Render skinned shadow casters into one cascade. Runs after
+/// `render_shadow_cascade` (which clears the depth) and keeps its result.
+pub fn render_shadow_cascade_skinned(
+    encoder: &mut wgpu::CommandEncoder,
+    csm: &CascadedShadowMap,
+    cascade_index: usize,
+    pipeline: &wgpu::RenderPipeline,
+    per_frame_bg: &wgpu::BindGroup,
+    objects: &wgpu::BindGroup,
+    meshes: &[(&GPUMesh, u32, &wgpu::BindGroup)], // (mesh, object_offset, bone_bg)
+) {
+    let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some(&format!("Shadow Cascade {cascade_index} Skinned")),
+        color_attachments: &[],
+        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+            view: &csm.depth_views[cascade_index],
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            }),
+            stencil_ops: None,
+        }),
+        ..Default::default()
+    });
+
+    pass.set_pipeline(pipeline);
+    pass.set_bind_group(0, per_frame_bg, &[]);
+
+    for (mesh, object_offset, bone_bg) in meshes {
+        let (Some(bw), Some(bi)) = (&mesh.bone_weight_buffer, &mesh.bone_index_buffer) else {
+            continue;
+        };
+        pass.set_bind_group(1, objects, &[*object_offset]);
+        pass.set_bind_group(2, *bone_bg, &[]);
 
         pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
-        pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        pass.set_vertex_buffer(1, bw.slice(..));
+        pass.set_vertex_buffer(2, bi.slice(..));
+        pass.set_index_buffer(mesh.index_buffer.slice(..), mesh.index_format);
         pass.draw_indexed(0..mesh.index_count, 0, 0..1);
     }
 }
+
+/// One shadow-atlas tile to render: its square viewport, the per-frame bind
+/// group holding the tile's light view-projection, and its casters.
+pub struct ShadowTileDraw<'a> {
+    /// x, y and size in atlas texels.
+    pub viewport: [u32; 3],
+    pub per_frame_bg: wgpu::BindGroup,
+    pub meshes: Vec<(&'a GPUMesh, u32)>, // (mesh, object_offset)
+    pub skinned: Vec<(&'a GPUMesh, u32, &'a wgpu::BindGroup)>, // (mesh, object_offset, bone_bg)
+}
+
+/// Render point and spot light shadow tiles into the atlas, clearing it first.
+pub fn render_shadow_atlas(
+    encoder: &mut wgpu::CommandEncoder,
+    atlas_view: &wgpu::TextureView,
+    pipeline: &wgpu::RenderPipeline,
+    skinned_pipeline: &wgpu::RenderPipeline,
+    objects: &wgpu::BindGroup,
+    tiles: &[ShadowTileDraw],
+) {
+    let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Shadow Atlas"),
+        color_attachments: &[],
+        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+            view: atlas_view,
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Clear(1.0),
+                store: wgpu::StoreOp::Store,
+            }),
+            stencil_ops: None,
+        }),
+        ..Default::default()
+    });
+
+    for tile in tiles {
+        let [x, y, size] = tile.viewport;
+        pass.set_viewport(x as f32, y as f32, size as f32, size as f32, 0.0, 1.0);
+        pass.set_scissor_rect(x, y, size, size);
+
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &tile.per_frame_bg, &[]);
+        for (mesh, object_offset) in &tile.meshes {
+            pass.set_bind_group(1, objects, &[*object_offset]);
+            pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+            pass.set_index_buffer(mesh.index_buffer.slice(..), mesh.index_format);
+            pass.draw_indexed(0..mesh.index_count, 0, 0..1);
+        }
+
+        if tile.skinned.is_empty() {
+            continue;
+        }
+        pass.set_pipeline(skinned_pipeline);
+        pass.set_bind_group(0, &tile.per_frame_bg, &[]);
+        for (mesh, object_offset, bone_bg) in &tile.skinned {
+            let (Some(bw), Some(bi)) = (&mesh.bone_weight_buffer, &mesh.bone_index_buffer) else {
+                continue;
+            };
+            pass.set_bind_group(1, objects, &[*object_offset]);
+            pass.set_bind_group(2, *bone_bg, &[]);
+            pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+            pass.set_vertex_buffer(1, bw.slice(..));
+            pass.set_vertex_buffer(2, bi.slice(..));
+            pass.set_index_buffer(mesh.index_buffer.slice(..), mesh.index_format);
+            pass.draw_indexed(0..mesh.index_count, 0, 0..1);
+        }
+    }
+}
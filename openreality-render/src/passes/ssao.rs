@@ -1,18 +1,16 @@
 //! SSAO pass — screen-space ambient occlusion with blur.
 
-use crate::types::RenderTarget;
-
 /// Render SSAO from G-Buffer depth + normals.
 pub fn render_ssao_pass(
     encoder: &mut wgpu::CommandEncoder,
-    target: &RenderTarget,
+    target: &wgpu::TextureView,
     pipeline: &wgpu::RenderPipeline,
     bind_group: &wgpu::BindGroup,
 ) {
     let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
         label: Some("SSAO Pass"),
         color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-            view: &target.color_view,
+            view: target,
             resolve_target: None,
             ops: wgpu::Operations {
                 load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
@@ -31,14 +29,14 @@ pub fn render_ssao_pass(
 /// Render SSAO blur pass.
 pub fn render_ssao_blur(
     encoder: &mut wgpu::CommandEncoder,
-    target: &RenderTarget,
+    target: &wgpu::TextureView,
     pipeline: &wgpu::RenderPipeline,
     bind_group: &wgpu::BindGroup,
 ) {
     let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
         label: Some("SSAO Blur Pass"),
         color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-            view: &target.color_view,
+            view: target,
             resolve_target: None,
             ops: wgpu::Operations {
                 load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
@@ -0,0 +1,157 @@
+//! Entity-ID picking.
+//!
+//! When enabled, the G-Buffer and forward passes also write each pixel's
+//! `PerObjectUniforms::entity_id` to an R32Uint target (0 where nothing was
+//! drawn). A pick copies one texel of the last rendered frame to a staging
+//! buffer and maps it asynchronously: await the returned `PickRequest` on the
+//! web, or `device.poll` and check `try_take()` on native.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use crate::pipeline;
+use crate::render_targets::{self, ENTITY_ID_FORMAT};
+use crate::types::{DeferredPipeline, RenderTarget};
+
+/// The entity-ID target and the geometry pipeline variants that write it.
+pub struct EntityIds {
+    pub target: RenderTarget,
+    pub gbuffer_pipeline: wgpu::RenderPipeline,
+    pub gbuffer_skinned_pipeline: wgpu::RenderPipeline,
+    pub gbuffer_instanced_pipeline: wgpu::RenderPipeline,
+    pub terrain_pipeline: wgpu::RenderPipeline,
+    pub forward_pipeline: wgpu::RenderPipeline,
+}
+
+impl EntityIds {
+    /// Create the target at the G-Buffer's size, with pipelines sharing the
+    /// plain variants' bind group layouts.
+    pub fn new(
+        device: &wgpu::Device,
+        dp: &DeferredPipeline,
+        per_frame_bgl: &wgpu::BindGroupLayout,
+        material_bgl: &wgpu::BindGroupLayout,
+    ) -> Self {
+        Self {
+            target: create_entity_id_target(device, dp.gbuffer.width, dp.gbuffer.height),
+            gbuffer_pipeline: pipeline::create_gbuffer_pipeline(device, per_frame_bgl, material_bgl, &dp.per_object_bgl, true),
+            gbuffer_skinned_pipeline: pipeline::create_gbuffer_skinned_pipeline(
+                device, per_frame_bgl, material_bgl, &dp.per_object_bgl, &dp.bone_bgl, true,
+            ),
+            gbuffer_instanced_pipeline: pipeline::create_gbuffer_instanced_pipeline(device, per_frame_bgl, material_bgl, true),
+            terrain_pipeline: pipeline::create_terrain_pipeline(device, per_frame_bgl, &dp.terrain_bgl, true),
+            forward_pipeline: pipeline::create_forward_pipeline(
+                device, per_frame_bgl, material_bgl, &dp.per_object_bgl, &dp.forward_light_shadow_bgl, true,
+            ),
+        }
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.target = create_entity_id_target(device, width, height);
+    }
+
+    /// Start reading the entity ID at pixel (`x`, `y`), top-left origin.
+    /// Positions outside the target resolve to `None` without touching the GPU.
+    pub fn pick(&self, device: &wgpu::Device, queue: &wgpu::Queue, x: u32, y: u32) -> PickRequest {
+        if x >= self.target.width || y >= self.target.height {
+            return PickRequest::resolved(None);
+        }
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Pick Readback Buffer"),
+            size: 4,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Pick Encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &self.target.color_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                // A single texel needs no row pitch.
+                layout: wgpu::ImageDataLayout { offset: 0, bytes_per_row: None, rows_per_image: None },
+            },
+            wgpu::Extent3d { width: 1, height: 1, depth_or_array_layers: 1 },
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let state = Arc::new(Mutex::new(MapState::default()));
+        let callback_state = Arc::clone(&state);
+        buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            let mut state = callback_state.lock().unwrap();
+            state.mapped = Some(result.is_ok());
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        });
+
+        PickRequest { readback: Some((buffer, state)), result: None }
+    }
+}
+
+fn create_entity_id_target(device: &wgpu::Device, width: u32, height: u32) -> RenderTarget {
+    render_targets::create_render_target(device, width, height, "Entity IDs", ENTITY_ID_FORMAT, false)
+}
+
+#[derive(Default)]
+struct MapState {
+    /// Set by the map callback: whether the staging buffer mapped.
+    mapped: Option<bool>,
+    waker: Option<Waker>,
+}
+
+/// A pending pick. Resolves to the entity ID under the pixel, or `None` for
+/// background, positions outside the target, failed readbacks, or when
+/// picking is disabled.
+pub struct PickRequest {
+    readback: Option<(wgpu::Buffer, Arc<Mutex<MapState>>)>,
+    result: Option<u32>,
+}
+
+impl PickRequest {
+    pub fn resolved(result: Option<u32>) -> Self {
+        Self { readback: None, result }
+    }
+
+    /// The result once the readback has finished, without blocking. Native
+    /// callers must `device.poll` for the map to complete.
+    pub fn try_take(&mut self) -> Option<Option<u32>> {
+        if let Some((buffer, state)) = &self.readback {
+            let mapped = state.lock().unwrap().mapped?;
+            if mapped {
+                let id = {
+                    let data = buffer.slice(..).get_mapped_range();
+                    u32::from_le_bytes([data[0], data[1], data[2], data[3]])
+                };
+                buffer.unmap();
+                self.result = Some(id).filter(|&id| id != 0);
+            }
+            self.readback = None;
+        }
+        Some(self.result)
+    }
+}
+
+impl Future for PickRequest {
+    type Output = Option<u32>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<u32>> {
+        if let Some((_, state)) = &self.readback {
+            let mut state = state.lock().unwrap();
+            if state.mapped.is_none() {
+                state.waker = Some(cx.waker().clone());
+                return Poll::Pending;
+            }
+        }
+        Poll::Ready(self.try_take().flatten())
+    }
+}
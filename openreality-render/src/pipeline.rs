@@ -2,8 +2,9 @@
 //! Each function creates a wgpu::RenderPipeline with appropriate shader, bind group layouts,
 //! and vertex buffer layouts.
 
-use crate::render_targets::{DEPTH_FORMAT, HDR_FORMAT};
+use crate::render_targets::{DEPTH_FORMAT, ENTITY_ID_FORMAT, EXPOSURE_FORMAT, HDR_FORMAT, RG16_FORMAT};
 use openreality_gpu_shared::shaders;
+use openreality_gpu_shared::uniforms::PerObjectUniforms;
 
 /// Shared fullscreen quad vertex state (used by vertex-index-based full-screen triangle).
 fn fullscreen_vertex_state(module: &wgpu::ShaderModule) -> wgpu::VertexState<'_> {
@@ -41,11 +42,31 @@ fn depth_stencil_readonly() -> wgpu::DepthStencilState {
 // G-Buffer Pipeline
 // ============================================================
 
+/// Color targets shared by every pipeline that writes the G-Buffer, plus the
+/// entity-ID target at location 4 when `entity_ids` is set.
+fn gbuffer_targets(entity_ids: bool) -> Vec<Option<wgpu::ColorTargetState>> {
+    let mut targets = vec![
+        Some(wgpu::ColorTargetState { format: HDR_FORMAT, blend: None, write_mask: wgpu::ColorWrites::ALL }),
+        Some(wgpu::ColorTargetState { format: HDR_FORMAT, blend: None, write_mask: wgpu::ColorWrites::ALL }),
+        Some(wgpu::ColorTargetState { format: HDR_FORMAT, blend: None, write_mask: wgpu::ColorWrites::ALL }),
+        Some(wgpu::ColorTargetState { format: RG16_FORMAT, blend: None, write_mask: wgpu::ColorWrites::ALL }),
+    ];
+    if entity_ids {
+        targets.push(entity_id_target());
+    }
+    targets
+}
+
+fn entity_id_target() -> Option<wgpu::ColorTargetState> {
+    Some(wgpu::ColorTargetState { format: ENTITY_ID_FORMAT, blend: None, write_mask: wgpu::ColorWrites::ALL })
+}
+
 pub fn create_gbuffer_pipeline(
     device: &wgpu::Device,
     per_frame_bgl: &wgpu::BindGroupLayout,
     material_bgl: &wgpu::BindGroupLayout,
     per_object_bgl: &wgpu::BindGroupLayout,
+    entity_ids: bool,
 ) -> wgpu::RenderPipeline {
     let vert_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: Some("GBuffer Vertex"),
@@ -107,28 +128,7 @@ pub fn create_gbuffer_pipeline(
             module: &frag_module,
             entry_point: Some("fs_main"),
             compilation_options: wgpu::PipelineCompilationOptions::default(),
-            targets: &[
-                Some(wgpu::ColorTargetState {
-                    format: HDR_FORMAT,
-                    blend: None,
-                    write_mask: wgpu::ColorWrites::ALL,
-                }),
-                Some(wgpu::ColorTargetState {
-                    format: HDR_FORMAT,
-                    blend: None,
-                    write_mask: wgpu::ColorWrites::ALL,
-                }),
-                Some(wgpu::ColorTargetState {
-                    format: HDR_FORMAT,
-                    blend: None,
-                    write_mask: wgpu::ColorWrites::ALL,
-                }),
-                Some(wgpu::ColorTargetState {
-                    format: HDR_FORMAT,
-                    blend: None,
-                    write_mask: wgpu::ColorWrites::ALL,
-                }),
-            ],
+            targets: &gbuffer_targets(entity_ids),
         }),
         primitive: wgpu::PrimitiveState {
             topology: wgpu::PrimitiveTopology::TriangleList,
@@ -172,6 +172,7 @@ pub fn create_gbuffer_skinned_pipeline(
     material_bgl: &wgpu::BindGroupLayout,
     per_object_bgl: &wgpu::BindGroupLayout,
     bone_bgl: &wgpu::BindGroupLayout,
+    entity_ids: bool,
 ) -> wgpu::RenderPipeline {
     let vert_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: Some("GBuffer Skinned Vertex"),
@@ -253,28 +254,7 @@ pub fn create_gbuffer_skinned_pipeline(
             module: &frag_module,
             entry_point: Some("fs_main"),
             compilation_options: wgpu::PipelineCompilationOptions::default(),
-            targets: &[
-                Some(wgpu::ColorTargetState {
-                    format: HDR_FORMAT,
-                    blend: None,
-                    write_mask: wgpu::ColorWrites::ALL,
-                }),
-                Some(wgpu::ColorTargetState {
-                    format: HDR_FORMAT,
-                    blend: None,
-                    write_mask: wgpu::ColorWrites::ALL,
-                }),
-                Some(wgpu::ColorTargetState {
-                    format: HDR_FORMAT,
-                    blend: None,
-                    write_mask: wgpu::ColorWrites::ALL,
-                }),
-                Some(wgpu::ColorTargetState {
-                    format: HDR_FORMAT,
-                    blend: None,
-                    write_mask: wgpu::ColorWrites::ALL,
-                }),
-            ],
+            targets: &gbuffer_targets(entity_ids),
         }),
         primitive: wgpu::PrimitiveState {
             topology: wgpu::PrimitiveTopology::TriangleList,
@@ -292,13 +272,15 @@ pub fn create_gbuffer_skinned_pipeline(
 // Instanced G-Buffer Pipeline
 // ============================================================
 
-/// Instance stride: model mat4 (4*vec4) + normal mat3 as 3*vec4 = 7*16 = 112 bytes.
-pub const INSTANCE_STRIDE: u64 = 112;
+/// Instance stride: a whole `PerObjectUniforms` — model mat4 (4*vec4) +
+/// normal mat3 as 3*vec4 + entity ID and padding = 8*16 = 128 bytes.
+pub const INSTANCE_STRIDE: u64 = std::mem::size_of::<PerObjectUniforms>() as u64;
 
 pub fn create_gbuffer_instanced_pipeline(
     device: &wgpu::Device,
     per_frame_bgl: &wgpu::BindGroupLayout,
     material_bgl: &wgpu::BindGroupLayout,
+    entity_ids: bool,
 ) -> wgpu::RenderPipeline {
     let vert_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: Some("GBuffer Instanced Vertex"),
@@ -354,7 +336,7 @@ pub fn create_gbuffer_instanced_pipeline(
                         shader_location: 2,
                     }],
                 },
-                // Per-instance data: model (4 vec4) + normal (3 vec4) = 112 bytes
+                // Per-instance data: model (4 vec4) + normal (3 vec4) + entity ID
                 wgpu::VertexBufferLayout {
                     array_stride: INSTANCE_STRIDE,
                     step_mode: wgpu::VertexStepMode::Instance,
@@ -401,6 +383,12 @@ pub fn create_gbuffer_instanced_pipeline(
                             offset: 96,
                             shader_location: 9,
                         },
+                        // entity_id
+                        wgpu::VertexAttribute {
+                            format: wgpu::VertexFormat::Uint32,
+                            offset: 112,
+                            shader_location: 10,
+                        },
                     ],
                 },
             ],
@@ -409,28 +397,7 @@ pub fn create_gbuffer_instanced_pipeline(
             module: &frag_module,
             entry_point: Some("fs_main"),
             compilation_options: wgpu::PipelineCompilationOptions::default(),
-            targets: &[
-                Some(wgpu::ColorTargetState {
-                    format: HDR_FORMAT,
-                    blend: None,
-                    write_mask: wgpu::ColorWrites::ALL,
-                }),
-                Some(wgpu::ColorTargetState {
-                    format: HDR_FORMAT,
-                    blend: None,
-                    write_mask: wgpu::ColorWrites::ALL,
-                }),
-                Some(wgpu::ColorTargetState {
-                    format: HDR_FORMAT,
-                    blend: None,
-                    write_mask: wgpu::ColorWrites::ALL,
-                }),
-                Some(wgpu::ColorTargetState {
-                    format: HDR_FORMAT,
-                    blend: None,
-                    write_mask: wgpu::ColorWrites::ALL,
-                }),
-            ],
+            targets: &gbuffer_targets(entity_ids),
         }),
         primitive: wgpu::PrimitiveState {
             topology: wgpu::PrimitiveTopology::TriangleList,
@@ -504,6 +471,85 @@ pub fn create_shadow_pipeline(
     })
 }
 
+/// Depth-only shadow pipeline for skinned meshes. Vertex buffers are
+/// position, bone weights and bone indices; bones are bind group 2.
+pub fn create_shadow_skinned_pipeline(
+    device: &wgpu::Device,
+    per_frame_bgl: &wgpu::BindGroupLayout,
+    per_object_bgl: &wgpu::BindGroupLayout,
+    bone_bgl: &wgpu::BindGroupLayout,
+) -> wgpu::RenderPipeline {
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Shadow Depth Skinned"),
+        source: wgpu::ShaderSource::Wgsl(shaders::SHADOW_DEPTH_SKINNED_VERT.into()),
+    });
+
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Shadow Skinned Pipeline Layout"),
+        bind_group_layouts: &[per_frame_bgl, per_object_bgl, bone_bgl],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Shadow Skinned Pipeline"),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: &module,
+            entry_point: Some("vs_main"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            buffers: &[
+                wgpu::VertexBufferLayout {
+                    array_stride: 12,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &[wgpu::VertexAttribute {
+                        format: wgpu::VertexFormat::Float32x3,
+                        offset: 0,
+                        shader_location: 0,
+                    }],
+                },
+                wgpu::VertexBufferLayout {
+                    array_stride: 16,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &[wgpu::VertexAttribute {
+                        format: wgpu::VertexFormat::Float32x4,
+                        offset: 0,
+                        shader_location: 1,
+                    }],
+                },
+                wgpu::VertexBufferLayout {
+                    array_stride: 8,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &[wgpu::VertexAttribute {
+                        format: wgpu::VertexFormat::Uint16x4,
+                        offset: 0,
+                        shader_location: 2,
+                    }],
+                },
+            ],
+        },
+        fragment: None, // Depth-only
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            cull_mode: Some(wgpu::Face::Front),
+            ..Default::default()
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState {
+                constant: 2,
+                slope_scale: 2.0,
+                clamp: 0.0,
+            },
+        }),
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
 // ============================================================
 // Deferred Lighting Pipeline
 // ============================================================
@@ -569,7 +615,7 @@ pub fn create_lighting_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGro
                 binding: 5,
                 visibility: wgpu::ShaderStages::FRAGMENT,
                 ty: wgpu::BindingType::Texture {
-                    sample_type: wgpu::TextureSampleType::Depth,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
                     view_dimension: wgpu::TextureViewDimension::D2,
                     multisampled: false,
                 },
@@ -615,11 +661,23 @@ pub fn create_lighting_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGro
     })
 }
 
-pub fn create_light_data_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
-    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-        label: Some("Light Data BGL"),
-        entries: &[wgpu::BindGroupLayoutEntry {
-            binding: 0,
+/// Clustered light bindings starting at `first_binding`: ClusterParams
+/// uniform, then read-only storage for the point and spot lights, per-cluster
+/// `[offset, count]` ranges and light indices (see `light_clusters`).
+pub fn cluster_layout_entries(first_binding: u32) -> [wgpu::BindGroupLayoutEntry; 4] {
+    let storage = |binding| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only: true },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    };
+    [
+        wgpu::BindGroupLayoutEntry {
+            binding: first_binding,
             visibility: wgpu::ShaderStages::FRAGMENT,
             ty: wgpu::BindingType::Buffer {
                 ty: wgpu::BufferBindingType::Uniform,
@@ -627,7 +685,170 @@ pub fn create_light_data_bind_group_layout(device: &wgpu::Device) -> wgpu::BindG
                 min_binding_size: None,
             },
             count: None,
-        }],
+        },
+        storage(first_binding + 1),
+        storage(first_binding + 2),
+        storage(first_binding + 3),
+    ]
+}
+
+/// Local light shadow bindings starting at `first_binding`: the shadow atlas
+/// (sampled with the shadow comparison sampler) and the read-only
+/// `ShadowTileData` array.
+pub fn local_shadow_layout_entries(first_binding: u32) -> [wgpu::BindGroupLayoutEntry; 2] {
+    [
+        wgpu::BindGroupLayoutEntry {
+            binding: first_binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Depth,
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        },
+        wgpu::BindGroupLayoutEntry {
+            binding: first_binding + 1,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        },
+    ]
+}
+
+/// Image-based lighting bindings starting at `first_binding`: the irradiance
+/// and prefiltered cubemaps, the BRDF LUT and their filtering sampler (see
+/// `ibl::IBLEnvironment::entries`).
+pub fn ibl_layout_entries(first_binding: u32) -> [wgpu::BindGroupLayoutEntry; 4] {
+    let texture = |binding, view_dimension| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            view_dimension,
+            multisampled: false,
+        },
+        count: None,
+    };
+    [
+        texture(first_binding, wgpu::TextureViewDimension::Cube),
+        texture(first_binding + 1, wgpu::TextureViewDimension::Cube),
+        texture(first_binding + 2, wgpu::TextureViewDimension::D2),
+        wgpu::BindGroupLayoutEntry {
+            binding: first_binding + 3,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            count: None,
+        },
+    ]
+}
+
+/// Light data bind group layout for the deferred lighting pass (group 1):
+/// LightUniforms at 0, the clustered point and spot lights at 1-4 and the
+/// IBL maps at 5-8.
+pub fn create_light_data_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let [params, lights, ranges, indices] = cluster_layout_entries(1);
+    let [irradiance, prefilter, brdf_lut, ibl_sampler] = ibl_layout_entries(5);
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Light Data BGL"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            params,
+            lights,
+            ranges,
+            indices,
+            irradiance,
+            prefilter,
+            brdf_lut,
+            ibl_sampler,
+        ],
+    })
+}
+
+/// Shadow bind group layout for the deferred lighting pass (group 2):
+/// ShadowUniforms, four cascade depth maps, a comparison sampler, and the
+/// local light shadow atlas with its tiles.
+pub fn create_lighting_shadow_bgl(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let [atlas, tiles] = local_shadow_layout_entries(6);
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Lighting Shadow BGL"),
+        entries: &[
+            // 0: ShadowUniforms
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            // 1-4: shadow cascade depth textures
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Depth,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Depth,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Depth,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 4,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Depth,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            // 5: comparison sampler
+            wgpu::BindGroupLayoutEntry {
+                binding: 5,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                count: None,
+            },
+            // 6-7: local light shadow atlas and tiles
+            atlas,
+            tiles,
+        ],
     })
 }
 
@@ -635,6 +856,7 @@ pub fn create_lighting_pipeline(
     device: &wgpu::Device,
     lighting_bgl: &wgpu::BindGroupLayout,
     light_data_bgl: &wgpu::BindGroupLayout,
+    shadow_bgl: &wgpu::BindGroupLayout,
 ) -> wgpu::RenderPipeline {
     let vert_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: Some("Fullscreen Quad Vert"),
@@ -648,7 +870,7 @@ pub fn create_lighting_pipeline(
 
     let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
         label: Some("Lighting Pipeline Layout"),
-        bind_group_layouts: &[lighting_bgl, light_data_bgl],
+        bind_group_layouts: &[lighting_bgl, light_data_bgl, shadow_bgl],
         push_constant_ranges: &[],
     });
 
@@ -730,7 +952,7 @@ pub fn create_effect_bind_group_layout(
 
 /// SSAO bind group layout — matches ssao.wgsl:
 ///   0: uniform SSAOParams
-///   1: texture_depth_2d (g_depth)
+///   1: texture_2d<f32> (g_depth, unfilterable)
 ///   2: texture_2d<f32>  (g_normal_roughness)
 ///   3: texture_2d<f32>  (noise_texture)
 ///   4: sampler           (tex_sampler)
@@ -753,7 +975,7 @@ pub fn create_ssao_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLa
                 binding: 1,
                 visibility: wgpu::ShaderStages::FRAGMENT,
                 ty: wgpu::BindingType::Texture {
-                    sample_type: wgpu::TextureSampleType::Depth,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
                     view_dimension: wgpu::TextureViewDimension::D2,
                     multisampled: false,
                 },
@@ -797,7 +1019,7 @@ pub fn create_ssao_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLa
 
 /// SSR bind group layout — matches ssr.wgsl:
 ///   0: uniform SSRParams
-///   1: texture_depth_2d (g_depth)
+///   1: texture_2d<f32> (g_depth, unfilterable)
 ///   2: texture_2d<f32>  (g_normal_roughness)
 ///   3: texture_2d<f32>  (lighting_result)
 ///   4: sampler           (tex_sampler)
@@ -820,7 +1042,7 @@ pub fn create_ssr_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLay
                 binding: 1,
                 visibility: wgpu::ShaderStages::FRAGMENT,
                 ty: wgpu::BindingType::Texture {
-                    sample_type: wgpu::TextureSampleType::Depth,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
                     view_dimension: wgpu::TextureViewDimension::D2,
                     multisampled: false,
                 },
@@ -866,7 +1088,7 @@ pub fn create_ssr_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLay
 ///   0: uniform TAAParams
 ///   1: texture_2d<f32>  (current_frame)
 ///   2: texture_2d<f32>  (history_frame)
-///   3: texture_depth_2d (depth_texture)
+///   3: texture_2d<f32> (depth_texture, unfilterable)
 ///   4: sampler           (tex_sampler)
 ///   5: sampler           (depth_sampler)
 pub fn create_taa_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
@@ -907,7 +1129,7 @@ pub fn create_taa_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLay
                 binding: 3,
                 visibility: wgpu::ShaderStages::FRAGMENT,
                 ty: wgpu::BindingType::Texture {
-                    sample_type: wgpu::TextureSampleType::Depth,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
                     view_dimension: wgpu::TextureViewDimension::D2,
                     multisampled: false,
                 },
@@ -929,6 +1151,49 @@ pub fn create_taa_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLay
     })
 }
 
+/// Bloom composite bind group layout — matches bloom_composite.wgsl:
+///   0: uniform PostProcessParams
+///   1: texture_2d<f32>  (scene_texture)
+///   2: texture_2d<f32>  (bloom_texture)
+///   3: sampler           (tex_sampler)
+///   4: texture_2d<f32> (exposure_texture, R32Float, unfilterable)
+pub fn create_bloom_composite_bgl(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let texture = |binding, filterable| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        },
+        count: None,
+    };
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Bloom Composite BGL"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            texture(1, true),
+            texture(2, true),
+            wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+            texture(4, false),
+        ],
+    })
+}
+
 /// FXAA bind group layout — matches fxaa.wgsl (no uniform buffer):
 ///   0: texture_2d<f32>  (input_texture)
 ///   1: sampler           (tex_sampler)
@@ -979,7 +1244,7 @@ pub fn create_dof_coc_bgl(device: &wgpu::Device) -> wgpu::BindGroupLayout {
                 binding: 1,
                 visibility: wgpu::ShaderStages::FRAGMENT,
                 ty: wgpu::BindingType::Texture {
-                    sample_type: wgpu::TextureSampleType::Depth,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
                     view_dimension: wgpu::TextureViewDimension::D2,
                     multisampled: false,
                 },
@@ -1108,7 +1373,7 @@ pub fn create_mblur_velocity_bgl(device: &wgpu::Device) -> wgpu::BindGroupLayout
                 binding: 1,
                 visibility: wgpu::ShaderStages::FRAGMENT,
                 ty: wgpu::BindingType::Texture {
-                    sample_type: wgpu::TextureSampleType::Depth,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
                     view_dimension: wgpu::TextureViewDimension::D2,
                     multisampled: false,
                 },
@@ -1216,11 +1481,180 @@ pub fn create_fullscreen_effect_pipeline(
     })
 }
 
+// ============================================================
+// Sky Background Pipeline
+// ============================================================
+
+/// Sky BGL: PerFrameUniforms at 0, SkyUniforms at 1, the environment cubemap
+/// at 2 and its filtering sampler at 3.
+pub fn create_sky_bgl(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let uniform = |binding| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    };
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Sky BGL"),
+        entries: &[
+            uniform(0),
+            uniform(1),
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::Cube,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+    })
+}
+
+/// Background pipeline: a far-plane triangle into the HDR lighting target,
+/// depth-tested (read-only) against the G-Buffer depth so only pixels with
+/// no geometry are shaded.
+pub fn create_sky_pipeline(device: &wgpu::Device, sky_bgl: &wgpu::BindGroupLayout) -> wgpu::RenderPipeline {
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Sky Shader"),
+        source: wgpu::ShaderSource::Wgsl(shaders::SKY_SHADER.into()),
+    });
+
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Sky Pipeline Layout"),
+        bind_group_layouts: &[sky_bgl],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Sky Pipeline"),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: &module,
+            entry_point: Some("vs_background"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &module,
+            entry_point: Some("fs_background"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: HDR_FORMAT,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: Some(wgpu::DepthStencilState {
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            ..depth_stencil_readonly()
+        }),
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
+// ============================================================
+// Auto Exposure Compute Pipelines
+// ============================================================
+
+/// Auto-exposure BGL — matches auto_exposure.wgsl:
+///   0: uniform PostProcessParams
+///   1: texture_2d<f32>  (scene_texture, read with textureLoad)
+///   2: storage buffer    (luminance histogram + adapted luminance)
+///   3: storage texture   (exposure_texture, R32Float, write-only)
+pub fn create_auto_exposure_bgl(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Auto Exposure BGL"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: false },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::StorageTexture {
+                    access: wgpu::StorageTextureAccess::WriteOnly,
+                    format: EXPOSURE_FORMAT,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                },
+                count: None,
+            },
+        ],
+    })
+}
+
+/// One auto-exposure stage: `cs_histogram` or `cs_average`.
+pub fn create_auto_exposure_pipeline(device: &wgpu::Device, bgl: &wgpu::BindGroupLayout, entry_point: &str) -> wgpu::ComputePipeline {
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Auto Exposure Shader"),
+        source: wgpu::ShaderSource::Wgsl(shaders::AUTO_EXPOSURE_SHADER.into()),
+    });
+
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Auto Exposure Pipeline Layout"),
+        bind_group_layouts: &[bgl],
+        push_constant_ranges: &[],
+    });
+
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some(entry_point),
+        layout: Some(&layout),
+        module: &module,
+        entry_point: Some(entry_point),
+        compilation_options: wgpu::PipelineCompilationOptions::default(),
+        cache: None,
+    })
+}
+
 // ============================================================
 // Forward PBR Pipeline (transparent objects)
 // ============================================================
 
 pub fn create_forward_light_shadow_bgl(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let [params, lights, ranges, indices] = cluster_layout_entries(7);
+    let [atlas, tiles] = local_shadow_layout_entries(11);
     device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
         label: Some("Forward Light+Shadow BGL"),
         entries: &[
@@ -1294,6 +1728,14 @@ pub fn create_forward_light_shadow_bgl(device: &wgpu::Device) -> wgpu::BindGroup
                 ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
                 count: None,
             },
+            // 7-10: clustered point and spot lights
+            params,
+            lights,
+            ranges,
+            indices,
+            // 11-12: local light shadow atlas and tiles
+            atlas,
+            tiles,
         ],
     })
 }
@@ -1304,6 +1746,7 @@ pub fn create_forward_pipeline(
     material_bgl: &wgpu::BindGroupLayout,
     per_object_bgl: &wgpu::BindGroupLayout,
     light_shadow_bgl: &wgpu::BindGroupLayout,
+    entity_ids: bool,
 ) -> wgpu::RenderPipeline {
     let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: Some("Forward PBR"),
@@ -1316,6 +1759,26 @@ pub fn create_forward_pipeline(
         push_constant_ranges: &[],
     });
 
+    let mut targets = vec![Some(wgpu::ColorTargetState {
+        format: HDR_FORMAT,
+        blend: Some(wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::SrcAlpha,
+                dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                operation: wgpu::BlendOperation::Add,
+            },
+            alpha: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                operation: wgpu::BlendOperation::Add,
+            },
+        }),
+        write_mask: wgpu::ColorWrites::ALL,
+    })];
+    if entity_ids {
+        targets.push(entity_id_target());
+    }
+
     device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
         label: Some("Forward PBR Pipeline"),
         layout: Some(&layout),
@@ -1357,22 +1820,7 @@ pub fn create_forward_pipeline(
             module: &module,
             entry_point: Some("fs_main"),
             compilation_options: wgpu::PipelineCompilationOptions::default(),
-            targets: &[Some(wgpu::ColorTargetState {
-                format: HDR_FORMAT,
-                blend: Some(wgpu::BlendState {
-                    color: wgpu::BlendComponent {
-                        src_factor: wgpu::BlendFactor::SrcAlpha,
-                        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                        operation: wgpu::BlendOperation::Add,
-                    },
-                    alpha: wgpu::BlendComponent {
-                        src_factor: wgpu::BlendFactor::One,
-                        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                        operation: wgpu::BlendOperation::Add,
-                    },
-                }),
-                write_mask: wgpu::ColorWrites::ALL,
-            })],
+            targets: &targets,
         }),
         primitive: wgpu::PrimitiveState {
             topology: wgpu::PrimitiveTopology::TriangleList,
@@ -1677,6 +2125,7 @@ pub fn create_terrain_pipeline(
     device: &wgpu::Device,
     per_frame_bgl: &wgpu::BindGroupLayout,
     terrain_bgl: &wgpu::BindGroupLayout,
+    entity_ids: bool,
 ) -> wgpu::RenderPipeline {
     let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: Some("Terrain GBuffer"),
@@ -1730,12 +2179,7 @@ pub fn create_terrain_pipeline(
             module: &module,
             entry_point: Some("fs_main"),
             compilation_options: wgpu::PipelineCompilationOptions::default(),
-            targets: &[
-                Some(wgpu::ColorTargetState { format: HDR_FORMAT, blend: None, write_mask: wgpu::ColorWrites::ALL }),
-                Some(wgpu::ColorTargetState { format: HDR_FORMAT, blend: None, write_mask: wgpu::ColorWrites::ALL }),
-                Some(wgpu::ColorTargetState { format: HDR_FORMAT, blend: None, write_mask: wgpu::ColorWrites::ALL }),
-                Some(wgpu::ColorTargetState { format: HDR_FORMAT, blend: None, write_mask: wgpu::ColorWrites::ALL }),
-            ],
+            targets: &gbuffer_targets(entity_ids),
         }),
         primitive: wgpu::PrimitiveState {
             topology: wgpu::PrimitiveTopology::TriangleList,
@@ -1783,6 +2227,37 @@ pub fn create_present_bgl(device: &wgpu::Device) -> wgpu::BindGroupLayout {
                 ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                 count: None,
             },
+            // Colour grading uniforms and the two LUTs
+            wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 4,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D3,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 5,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D3,
+                    multisampled: false,
+                },
+                count: None,
+            },
         ],
     })
 }
@@ -1802,6 +2277,125 @@ pub fn create_present_pipeline(
     )
 }
 
+/// Debug view BGL: DebugViewUniforms at 0 and the shown target at 1, as an
+/// unfilterable float texture so depth targets bind too.
+pub fn create_debug_view_bgl(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Debug View BGL"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+        ],
+    })
+}
+
+pub fn create_debug_view_pipeline(
+    device: &wgpu::Device,
+    debug_view_bgl: &wgpu::BindGroupLayout,
+    surface_format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    create_fullscreen_effect_pipeline(
+        device,
+        "Debug View Pipeline",
+        shaders::DEBUG_VIEW_SHADER,
+        "fs_main",
+        debug_view_bgl,
+        surface_format,
+    )
+}
+
+// ============================================================
+// Selection Highlight Pipeline
+// ============================================================
+
+/// Selection BGL: SelectionUniforms at 0, the selected IDs (storage) at 1 and
+/// the R32Uint entity-ID target at 2.
+pub fn create_selection_bgl(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let buffer = |binding, ty| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Buffer { ty, has_dynamic_offset: false, min_binding_size: None },
+        count: None,
+    };
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Selection BGL"),
+        entries: &[
+            buffer(0, wgpu::BufferBindingType::Uniform),
+            buffer(1, wgpu::BufferBindingType::Storage { read_only: true }),
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Uint,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+        ],
+    })
+}
+
+/// Fullscreen selection highlight, alpha blended over the presented frame.
+pub fn create_selection_pipeline(
+    device: &wgpu::Device,
+    selection_bgl: &wgpu::BindGroupLayout,
+    surface_format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    let vert_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Fullscreen Vert"),
+        source: wgpu::ShaderSource::Wgsl(shaders::FULLSCREEN_QUAD_VERT.into()),
+    });
+    let frag_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Selection Shader"),
+        source: wgpu::ShaderSource::Wgsl(shaders::SELECTION_SHADER.into()),
+    });
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Selection Pipeline Layout"),
+        bind_group_layouts: &[selection_bgl],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Selection Pipeline"),
+        layout: Some(&layout),
+        vertex: fullscreen_vertex_state(&vert_module),
+        fragment: Some(wgpu::FragmentState {
+            module: &frag_module,
+            entry_point: Some("fs_main"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: surface_format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
 // ============================================================
 // Debug Lines Pipeline
 // ============================================================
@@ -1878,6 +2472,26 @@ pub fn create_debug_lines_pipeline(
     })
 }
 
+/// Create the per-object bind group layout. The uniform is bound with a
+/// dynamic offset selecting one object's slot in an `ObjectBuffer`.
+pub fn create_per_object_bgl(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Per-Object BGL"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: true,
+                min_binding_size: wgpu::BufferSize::new(
+                    std::mem::size_of::<PerObjectUniforms>() as u64,
+                ),
+            },
+            count: None,
+        }],
+    })
+}
+
 /// Create the material bind group layout (group 1).
 /// Binding 0: MaterialUBO, Bindings 1-6: texture maps, Binding 7: sampler.
 pub fn create_material_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
@@ -0,0 +1,304 @@
+//! Per-pass frame timing.
+//!
+//! When the device has `TIMESTAMP_FEATURES`, a timestamp is written before
+//! and after every pass and read back asynchronously, so a frame's report
+//! arrives a frame or two later (`poll` collects it). Otherwise the CPU time
+//! spent recording each pass is measured instead and reported as soon as the
+//! frame ends.
+//!
+//! A frame may span several encoders: call `begin_frame`, time passes with
+//! `begin_pass`/`end_pass` (or `RenderGraph::execute_profiled`), `resolve`
+//! each encoder before finishing it, and `end_frame` after the last submit.
+
+use std::sync::{Arc, Mutex};
+
+/// Device features needed for GPU timestamps between passes.
+pub const TIMESTAMP_FEATURES: wgpu::Features =
+    wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS);
+
+/// Passes timed per frame; later passes go untimed.
+pub const MAX_TIMED_PASSES: u32 = 64;
+
+/// Frames whose timestamps can be in flight at once. Frames that start while
+/// all are still mapping go untimed.
+const READBACK_FRAMES: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimingSource {
+    /// GPU execution time, from timestamp queries.
+    Gpu,
+    /// CPU time spent recording the pass.
+    Cpu,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PassTiming {
+    pub name: &'static str,
+    pub ms: f64,
+}
+
+/// Pass durations of one frame, in execution order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameTimings {
+    pub source: TimingSource,
+    pub passes: Vec<PassTiming>,
+}
+
+impl FrameTimings {
+    pub fn total_ms(&self) -> f64 {
+        self.passes.iter().map(|p| p.ms).sum()
+    }
+}
+
+/// Device features to request for GPU timing: `TIMESTAMP_FEATURES` if the
+/// adapter has all of them, else none (a partial set can't time passes).
+pub fn supported_features(adapter: &wgpu::Adapter) -> wgpu::Features {
+    if adapter.features().contains(TIMESTAMP_FEATURES) {
+        TIMESTAMP_FEATURES
+    } else {
+        wgpu::Features::empty()
+    }
+}
+
+/// Milliseconds since an arbitrary epoch.
+#[cfg(not(target_arch = "wasm32"))]
+fn default_clock() -> f64 {
+    static EPOCH: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+    EPOCH.get_or_init(std::time::Instant::now).elapsed().as_secs_f64() * 1000.0
+}
+
+/// wasm32 has no std clock; `performance.now()` stands in.
+#[cfg(target_arch = "wasm32")]
+fn default_clock() -> f64 {
+    web_sys::window().and_then(|w| w.performance()).map_or(0.0, |p| p.now())
+}
+
+struct Readback {
+    buffer: wgpu::Buffer,
+    /// Passes timed into this buffer, two timestamps each.
+    names: Vec<&'static str>,
+    frame: u64,
+    /// Set while mapping; the callback stores whether the map succeeded.
+    mapping: Option<Arc<Mutex<Option<bool>>>>,
+}
+
+struct Timestamps {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readbacks: Vec<Readback>,
+    /// Nanoseconds per timestamp tick.
+    period: f32,
+}
+
+impl Timestamps {
+    fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let size = 2 * MAX_TIMED_PASSES as u64 * 8;
+        Self {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("Pass Timestamps"),
+                ty: wgpu::QueryType::Timestamp,
+                count: 2 * MAX_TIMED_PASSES,
+            }),
+            resolve_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Timestamp Resolve Buffer"),
+                size,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            readbacks: (0..READBACK_FRAMES).map(|_| Readback {
+                buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Timestamp Readback Buffer"),
+                    size,
+                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                }),
+                names: Vec::new(),
+                frame: 0,
+                mapping: None,
+            }).collect(),
+            period: queue.get_timestamp_period(),
+        }
+    }
+}
+
+enum OpenPass {
+    /// Query index of the pass's end timestamp.
+    Query(u32),
+    /// Pass name and CPU start time.
+    Cpu(&'static str, f64),
+}
+
+pub struct FrameProfiler {
+    gpu: Option<Timestamps>,
+    clock: fn() -> f64,
+    frame: u64,
+    /// Readback buffer of the frame being recorded; `None` when it goes
+    /// untimed on the GPU.
+    slot: Option<usize>,
+    /// GPU-timed passes of the frame being recorded.
+    names: Vec<&'static str>,
+    /// CPU-timed passes of the frame being recorded.
+    cpu_passes: Vec<PassTiming>,
+    open: Option<OpenPass>,
+    /// Queries of this frame already copied to the readback buffer.
+    resolved: u32,
+    last: Option<FrameTimings>,
+    last_frame: u64,
+}
+
+impl FrameProfiler {
+    /// Time with GPU timestamps if the device has `TIMESTAMP_FEATURES`, else
+    /// on the CPU.
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        Self {
+            gpu: device.features().contains(TIMESTAMP_FEATURES).then(|| Timestamps::new(device, queue)),
+            clock: default_clock,
+            frame: 0,
+            slot: None,
+            names: Vec::new(),
+            cpu_passes: Vec::new(),
+            open: None,
+            resolved: 0,
+            last: None,
+            last_frame: 0,
+        }
+    }
+
+    pub fn source(&self) -> TimingSource {
+        if self.gpu.is_some() { TimingSource::Gpu } else { TimingSource::Cpu }
+    }
+
+    /// Replace the CPU clock (milliseconds since any fixed epoch).
+    pub fn set_clock(&mut self, clock: fn() -> f64) {
+        self.clock = clock;
+    }
+
+    /// The most recent complete report.
+    pub fn timings(&self) -> Option<&FrameTimings> {
+        self.last.as_ref()
+    }
+
+    pub fn begin_frame(&mut self) {
+        self.frame += 1;
+        self.names.clear();
+        self.cpu_passes.clear();
+        self.open = None;
+        self.resolved = 0;
+        self.slot = self.gpu.as_ref().and_then(|gpu| gpu.readbacks.iter().position(|r| r.mapping.is_none()));
+    }
+
+    pub fn begin_pass(&mut self, encoder: &mut wgpu::CommandEncoder, name: &'static str) {
+        self.open = match &self.gpu {
+            Some(gpu) => {
+                let query = 2 * self.names.len() as u32;
+                (self.slot.is_some() && query < 2 * MAX_TIMED_PASSES).then(|| {
+                    encoder.write_timestamp(&gpu.query_set, query);
+                    self.names.push(name);
+                    OpenPass::Query(query + 1)
+                })
+            }
+            None => Some(OpenPass::Cpu(name, (self.clock)())),
+        };
+    }
+
+    pub fn end_pass(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        match (self.open.take(), &self.gpu) {
+            (Some(OpenPass::Query(query)), Some(gpu)) => encoder.write_timestamp(&gpu.query_set, query),
+            (Some(OpenPass::Cpu(name, start)), _) => {
+                self.cpu_passes.push(PassTiming { name, ms: ((self.clock)() - start).max(0.0) });
+            }
+            _ => {}
+        }
+    }
+
+    /// Copy the timestamps written into `encoder` to the frame's readback
+    /// buffer. Call once the encoder's last timed pass has ended.
+    pub fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let (Some(gpu), Some(slot)) = (&self.gpu, self.slot) else { return };
+        let (start, end) = (self.resolved, 2 * self.names.len() as u32);
+        if start == end {
+            return;
+        }
+        // Resolves must land 256-byte aligned, so go through the resolve buffer.
+        encoder.resolve_query_set(&gpu.query_set, start..end, &gpu.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &gpu.resolve_buffer, 0,
+            &gpu.readbacks[slot].buffer, start as u64 * 8,
+            (end - start) as u64 * 8,
+        );
+        self.resolved = end;
+    }
+
+    /// Finish the frame once its last encoder is submitted: CPU timings are
+    /// reported now, GPU timestamps start mapping.
+    pub fn end_frame(&mut self) {
+        let slot = self.slot.take();
+        let Some(gpu) = &mut self.gpu else {
+            self.last = Some(FrameTimings { source: TimingSource::Cpu, passes: std::mem::take(&mut self.cpu_passes) });
+            return;
+        };
+        // Passes never resolved have no timestamps to read.
+        self.names.truncate(self.resolved as usize / 2);
+        let Some(slot) = slot.filter(|_| !self.names.is_empty()) else { return };
+
+        let readback = &mut gpu.readbacks[slot];
+        let mapping = Arc::new(Mutex::new(None));
+        let callback_mapping = Arc::clone(&mapping);
+        readback.buffer.slice(..self.resolved as u64 * 8).map_async(wgpu::MapMode::Read, move |result| {
+            *callback_mapping.lock().unwrap() = Some(result.is_ok());
+        });
+        readback.names = std::mem::take(&mut self.names);
+        readback.frame = self.frame;
+        readback.mapping = Some(mapping);
+    }
+
+    /// Collect GPU timestamps that finished mapping, keeping the newest frame.
+    pub fn poll(&mut self, device: &wgpu::Device) {
+        let Some(gpu) = &mut self.gpu else { return };
+        device.poll(wgpu::Maintain::Poll);
+        for readback in &mut gpu.readbacks {
+            let Some(mapped) = readback.mapping.as_ref().and_then(|m| *m.lock().unwrap()) else { continue };
+            readback.mapping = None;
+            if !mapped {
+                continue;
+            }
+            let ticks: Vec<u64> = {
+                let data = readback.buffer.slice(..readback.names.len() as u64 * 16).get_mapped_range();
+                data.chunks_exact(8).map(|b| u64::from_le_bytes(b.try_into().unwrap())).collect()
+            };
+            readback.buffer.unmap();
+            if readback.frame > self.last_frame {
+                self.last_frame = readback.frame;
+                self.last = Some(FrameTimings {
+                    source: TimingSource::Gpu,
+                    passes: pass_durations(&readback.names, &ticks, gpu.period),
+                });
+            }
+        }
+    }
+}
+
+/// Durations from each pass's start and end ticks.
+fn pass_durations(names: &[&'static str], ticks: &[u64], period_ns: f32) -> Vec<PassTiming> {
+    names.iter().zip(ticks.chunks_exact(2)).map(|(&name, t)| PassTiming {
+        name,
+        // Timestamps aren't guaranteed monotonic across passes on every GPU.
+        ms: t[1].saturating_sub(t[0]) as f64 * period_ns as f64 / 1.0e6,
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pass_durations_from_ticks() {
+        let passes = pass_durations(&["G-Buffer", "Lighting"], &[1_000, 3_000_000, 3_000_000, 2_000], 2.0);
+        assert_eq!(passes[0].name, "G-Buffer");
+        assert!((passes[0].ms - 5.998).abs() < 1e-9);
+        // An end before its start clamps to zero.
+        assert_eq!(passes[1].ms, 0.0);
+        let timings = FrameTimings { source: TimingSource::Gpu, passes };
+        assert!((timings.total_ms() - 5.998).abs() < 1e-9);
+    }
+}
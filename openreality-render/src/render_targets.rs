@@ -2,6 +2,7 @@
 //! G-Buffer, lighting FBO, SSAO/SSR/TAA targets, bloom mip chain, DOF/motion blur targets.
 
 use crate::types::{GBuffer, RenderTarget};
+use openreality_gpu_shared::uniforms::LUMINANCE_HISTOGRAM_BINS;
 
 /// HDR color format used throughout the pipeline.
 pub const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
@@ -11,8 +12,16 @@ pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
 pub const R16_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R16Float;
 /// Two-channel float format (velocity buffer).
 pub const RG16_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rg16Float;
+/// Auto-exposure multiplier (1x1, written by a compute pass).
+pub const EXPOSURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Float;
+/// Per-pixel entity IDs written alongside the G-Buffer for picking.
+pub const ENTITY_ID_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Uint;
 
 /// Create G-Buffer with 4 color attachments + depth.
+///
+/// The advanced-material target only needs two channels; keeping it at
+/// 4 bytes per pixel leaves room in the 32-byte per-sample attachment
+/// budget for the optional entity-ID target.
 pub fn create_gbuffer(device: &wgpu::Device, width: u32, height: u32) -> GBuffer {
     let size = wgpu::Extent3d {
         width,
@@ -62,7 +71,7 @@ pub fn create_gbuffer(device: &wgpu::Device, width: u32, height: u32) -> GBuffer
         mip_level_count: 1,
         sample_count: 1,
         dimension: wgpu::TextureDimension::D2,
-        format: HDR_FORMAT,
+        format: RG16_FORMAT,
         usage,
         view_formats: &[],
     });
@@ -341,6 +350,39 @@ pub fn create_default_texture(device: &wgpu::Device, queue: &wgpu::Queue) -> (wg
     (texture, view)
 }
 
+/// Create the 1x1 auto-exposure target: storage-written by the metering
+/// pass, read by the bloom composite.
+pub fn create_exposure_target(device: &wgpu::Device) -> (wgpu::Texture, wgpu::TextureView) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Exposure"),
+        size: wgpu::Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: EXPOSURE_FORMAT,
+        usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    (texture, view)
+}
+
+/// Create the auto-exposure luminance histogram: `LUMINANCE_HISTOGRAM_BINS`
+/// counters followed by the adapted luminance, zeroed so the first metered
+/// frame snaps instead of adapting.
+pub fn create_exposure_histogram_buffer(device: &wgpu::Device) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Luminance Histogram"),
+        size: (LUMINANCE_HISTOGRAM_BINS as u64 + 1) * 4,
+        usage: wgpu::BufferUsages::STORAGE,
+        mapped_at_creation: false,
+    })
+}
+
 /// Create a fullscreen quad vertex buffer (2 triangles, pos2 + uv2).
 pub fn create_fullscreen_quad_vbo(device: &wgpu::Device) -> wgpu::Buffer {
     use wgpu::util::DeviceExt;
@@ -2,15 +2,50 @@
 //! full deferred PBR pipeline. Used by both native and WASM backends.
 
 use crate::types::*;
-use crate::{pipeline, render_targets};
+use crate::object_buffer::ObjectBuffer;
+use crate::ibl::IBLEnvironment;
+use crate::color_lut::ColorLut;
+use crate::picking::{EntityIds, PickRequest};
+use crate::profiler::{FrameProfiler, FrameTimings};
+use crate::light_clusters::LightClusterBuffers;
+use crate::{offscreen, pipeline, render_targets};
 use crate::passes;
+use crate::graph::nodes::{self, DeferredTargets, LightingInputs, NodeContext};
+use crate::graph::{RenderGraph, ResourceId, TransientPool};
+use crate::settings::{self, AtmosphereSettings, DebugView, RenderSettings};
 use bytemuck::Zeroable;
 use openreality_gpu_shared::uniforms::*;
 use openreality_gpu_shared::shaders;
+use openreality_gpu_shared::{clusters, math, terrain};
+use openreality_gpu_shared::shadow_atlas::{self, AtlasTile};
+use openreality_gpu_shared::scene_format::TerrainParsed;
+use openreality_gpu_shared::hdr::HdrImage;
+use openreality_gpu_shared::cube_lut::CubeLut;
+
+/// View distance covered by the shadow cascades (capped by the camera far plane).
+const CSM_MAX_DISTANCE: f32 = 150.0;
+/// PSSM blend between linear (0) and logarithmic (1) cascade splits.
+const CSM_SPLIT_LAMBDA: f32 = 0.75;
+/// Base depth bias applied when sampling the cascades, scaled by slope.
+const CSM_SHADOW_BIAS: f32 = 0.0005;
+/// Most point and spot lights given shadow-atlas tiles per frame.
+const MAX_SHADOWED_LOCAL_LIGHTS: usize = 8;
+/// Largest shadow-atlas tile, as a fraction of the atlas size.
+const LOCAL_SHADOW_MAX_TILE_DIVISOR: u32 = 4;
 
 /// GPU-uploaded mesh reference.
 pub struct UploadedMesh {
     pub gpu_mesh: GPUMesh,
+    /// Local-space bounds of the vertex positions, used for culling.
+    pub aabb_min: [f32; 3],
+    pub aabb_max: [f32; 3],
+}
+
+impl UploadedMesh {
+    /// World-space bounding sphere of the mesh under `model`.
+    pub fn bounding_sphere(&self, model: &glam::Mat4) -> (glam::Vec3, f32) {
+        math::aabb_bounding_sphere(model, self.aabb_min.into(), self.aabb_max.into())
+    }
 }
 
 /// GPU-uploaded texture reference.
@@ -18,6 +53,27 @@ pub struct UploadedTexture {
     pub gpu_texture: GPUTexture,
 }
 
+/// One terrain chunk uploaded to the GPU, one mesh per LOD.
+pub struct UploadedTerrainChunk {
+    pub lods: Vec<GPUMesh>,
+    pub aabb_min: [f32; 3],
+    pub aabb_max: [f32; 3],
+}
+
+/// GPU-uploaded terrain: chunk meshes plus the splat map and layer bind group.
+pub struct UploadedTerrain {
+    pub chunks: Vec<UploadedTerrainChunk>,
+    pub params_buffer: wgpu::Buffer,
+    pub splatmap: wgpu::Texture,
+    pub bind_group: wgpu::BindGroup,
+}
+
+/// Bone palette for one skeleton, bound at the skinned pipelines' bone group.
+pub struct SkeletonBuffer {
+    pub buffer: wgpu::Buffer,
+    pub bind_group: wgpu::BindGroup,
+}
+
 /// Per-entity rendering data (computed per frame).
 pub struct EntityRenderData {
     pub mesh_index: Option<usize>,
@@ -27,6 +83,31 @@ pub struct EntityRenderData {
     pub material: MaterialUniforms,
     pub is_transparent: bool,
     pub has_skinning: bool,
+    /// Skeleton whose bone palette deforms this entity (see `update_skeleton`).
+    pub skeleton_index: Option<usize>,
+}
+
+/// Frustum culling results of one `render_frame`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CullStats {
+    /// Entities with a mesh, opaque and transparent.
+    pub entities: u32,
+    /// Entities drawn for the camera (G-Buffer and forward passes).
+    pub camera_visible: u32,
+    /// Opaque entities tested against each shadow cascade.
+    pub shadow_casters: u32,
+    /// Shadow casters drawn into each cascade; unused cascades stay 0.
+    pub cascade_visible: [u32; 4],
+}
+
+impl CullStats {
+    pub fn camera_culled(&self) -> u32 {
+        self.entities - self.camera_visible
+    }
+
+    pub fn cascade_culled(&self, cascade: usize) -> u32 {
+        self.shadow_casters - self.cascade_visible[cascade]
+    }
 }
 
 /// Camera parameters for rendering.
@@ -42,6 +123,117 @@ pub struct CameraParams {
 pub struct SceneLights {
     pub dir_lights: Vec<DirLightData>,
     pub point_lights: Vec<PointLightData>,
+    pub spot_lights: Vec<SpotLightData>,
+}
+
+/// Smallest group of identical draws worth an instanced draw call.
+const MIN_INSTANCES: usize = 2;
+
+/// Entities drawn with one instanced G-Buffer draw.
+struct InstanceGroup<'e> {
+    /// First member; supplies the shared mesh, material and textures.
+    entity: &'e EntityRenderData,
+    first_instance: u32,
+    instance_count: u32,
+}
+
+/// Group static opaque draws that share mesh, material and textures. Returns
+/// the groups of at least `MIN_INSTANCES`, their per-instance data (each
+/// member's `PerObjectUniforms`, `pipeline::INSTANCE_STRIDE` bytes) and the
+/// remaining single draws.
+fn batch_instances<'e>(
+    draws: &[(&'e EntityRenderData, u32)],
+) -> (Vec<InstanceGroup<'e>>, Vec<u8>, Vec<(&'e EntityRenderData, u32)>) {
+    let mut group_of = std::collections::HashMap::new();
+    let mut members: Vec<Vec<(&'e EntityRenderData, u32)>> = Vec::new();
+    for &(e, offset) in draws {
+        let key = (e.mesh_index, e.texture_indices, bytemuck::bytes_of(&e.material));
+        let gi = *group_of.entry(key).or_insert_with(|| {
+            members.push(Vec::new());
+            members.len() - 1
+        });
+        members[gi].push((e, offset));
+    }
+
+    let (mut groups, mut data, mut singles) = (Vec::new(), Vec::new(), Vec::new());
+    for group in members {
+        if group.len() < MIN_INSTANCES {
+            singles.extend(group);
+            continue;
+        }
+        groups.push(InstanceGroup {
+            entity: group[0].0,
+            first_instance: (data.len() as u64 / pipeline::INSTANCE_STRIDE) as u32,
+            instance_count: group.len() as u32,
+        });
+        for (e, _) in group {
+            data.extend_from_slice(bytemuck::bytes_of(&e.per_object));
+        }
+    }
+    (groups, data, singles)
+}
+
+/// A shadow-atlas tile rendered this frame.
+struct LocalShadow {
+    view_proj: glam::Mat4,
+    tile: AtlasTile,
+    data: ShadowTileData,
+}
+
+/// Give the local lights covering the most screen shadow-atlas tiles, sized
+/// by that coverage. Lights outside the camera frustum cast nothing visible
+/// and are skipped. Sets each shadowed light's `shadow_index` and returns the
+/// tiles in index order (a point light's six faces are consecutive).
+fn assign_local_shadows(
+    lights: &mut [LocalLightData],
+    camera: &CameraParams,
+    camera_planes: &[[f32; 4]; 6],
+    atlas_size: u32,
+    screen_height: u32,
+) -> Vec<LocalShadow> {
+    let max_tile = atlas_size / LOCAL_SHADOW_MAX_TILE_DIVISOR;
+    let position = |l: &LocalLightData| glam::Vec3::new(l.position[0], l.position[1], l.position[2]);
+    let mut candidates: Vec<(usize, u32)> = lights.iter().enumerate()
+        .filter(|(_, l)| l.range > 0.0 && math::sphere_in_frustum(camera_planes, position(l), l.range))
+        .map(|(i, l)| {
+            let distance = position(l).distance(camera.position);
+            (i, shadow_atlas::shadow_tile_size(l.range, distance, camera.projection.y_axis.y, screen_height, max_tile))
+        })
+        .collect();
+    // Stable, so equally sized lights keep scene order.
+    candidates.sort_by_key(|&(_, size)| std::cmp::Reverse(size));
+    candidates.truncate(MAX_SHADOWED_LOCAL_LIGHTS);
+
+    let requests: Vec<(u32, u32)> = candidates.iter()
+        .map(|&(i, size)| (size, if lights[i].is_spot != 0 { 1 } else { shadow_atlas::POINT_SHADOW_FACES }))
+        .collect();
+    let mut shadows = Vec::new();
+    for (&(i, _), tiles) in candidates.iter().zip(shadow_atlas::allocate_shadow_tiles(atlas_size, &requests)) {
+        let Some(tiles) = tiles else { continue };
+        let light = &mut lights[i];
+        light.shadow_index = shadows.len() as i32;
+        let pos = position(light);
+        let (matrices, fov) = if light.is_spot != 0 {
+            let direction = glam::Vec3::new(light.direction[0], light.direction[1], light.direction[2]);
+            let matrix = shadow_atlas::spot_shadow_matrix(pos, direction, light.outer_cos, light.range);
+            (vec![matrix], shadow_atlas::spot_shadow_fov(light.outer_cos))
+        } else {
+            (shadow_atlas::point_shadow_matrices(pos, light.range).to_vec(), std::f32::consts::FRAC_PI_2)
+        };
+        shadows.extend(matrices.into_iter().zip(tiles).map(|(view_proj, tile)| LocalShadow {
+            view_proj,
+            tile,
+            data: ShadowTileData {
+                light_view_proj: view_proj.to_cols_array_2d(),
+                atlas_rect: tile.uv_rect(atlas_size),
+                texel_angle: shadow_atlas::texel_angle(fov, tile.size),
+                _pad1: 0.0,
+                _pad2: 0.0,
+                _pad3: 0.0,
+            },
+        }));
+    }
+    shadows
 }
 
 /// High-level scene renderer — owns all GPU resources for the deferred PBR pipeline.
@@ -54,17 +246,48 @@ pub struct SceneRenderer {
     // Shared GPU resources
     pub per_frame_buffer: wgpu::Buffer,
     pub per_frame_bgl: wgpu::BindGroupLayout,
-    pub per_object_buffer: wgpu::Buffer,
     pub material_bgl: wgpu::BindGroupLayout,
     pub light_buffer: wgpu::Buffer,
+    /// Point and spot lights binned into view clusters, read by lighting and forward shading.
+    pub light_clusters: LightClusterBuffers,
     pub default_sampler: wgpu::Sampler,
+    /// 1x1 depth texture bound in place of missing shadow cascades.
+    pub fallback_shadow_view: wgpu::TextureView,
 
     // Uploaded scene resources
     pub meshes: Vec<UploadedMesh>,
     pub textures: Vec<UploadedTexture>,
+    pub terrains: Vec<UploadedTerrain>,
+    pub skeletons: Vec<SkeletonBuffer>,
 
     // CSM (created on demand)
     pub csm: Option<CascadedShadowMap>,
+    /// Point and spot light shadows (created on demand).
+    pub shadow_atlas: Option<ShadowAtlas>,
+    /// Image-based ambient lighting (loaded on demand), scaled by `ibl_intensity`.
+    pub ibl: Option<IBLEnvironment>,
+    pub ibl_intensity: f32,
+    /// Black maps bound in place of a missing IBL environment.
+    pub fallback_ibl: IBLEnvironment,
+    /// Colour grading LUTs in slots A and B, blended by `settings.lut_blend`.
+    pub color_luts: [Option<ColorLut>; 2],
+
+    /// Post-processing toggles and parameters, read every frame.
+    pub settings: RenderSettings,
+    /// Culling results of the last `render_frame`.
+    pub cull_stats: CullStats,
+    /// Entity IDs highlighted with `settings.selection` (see `set_selection`).
+    selection: Vec<u32>,
+    /// Per-pass timing of each frame, when enabled with `set_profiling`.
+    pub profiler: Option<FrameProfiler>,
+    /// Frames rendered so far; drives the TAA jitter sequence.
+    frame_index: u64,
+    /// Unjittered view-projection of the previous frame, for TAA and motion blur.
+    prev_view_proj: Option<glam::Mat4>,
+    /// `time` of the previous frame, for exposure adaptation.
+    prev_time: Option<f32>,
+    /// Textures backing the render graph's transient targets.
+    transient_pool: TransientPool,
 
     // Dimensions
     pub width: u32,
@@ -107,14 +330,6 @@ impl SceneRenderer {
         // Material bind group layout (group 1)
         let material_bgl = pipeline::create_material_bind_group_layout(device);
 
-        // Per-object uniform buffer
-        let per_object_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Per-Object Uniforms"),
-            size: std::mem::size_of::<PerObjectUniforms>() as u64,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
         // Light buffer
         let light_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Light Uniforms"),
@@ -134,6 +349,19 @@ impl SceneRenderer {
             ..Default::default()
         });
 
+        // Fallback shadow map for forward shading without CSM. The G-Buffer
+        // depth can't stand in here since the forward pass renders against it.
+        let fallback_shadow_view = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Fallback Shadow Map"),
+            size: wgpu::Extent3d { width: 1, height: 1, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Depth32Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        }).create_view(&wgpu::TextureViewDescriptor::default());
+
         // Create the full deferred pipeline
         let deferred = Self::create_deferred_pipeline_inner(
             device, queue, width, height, surface_format,
@@ -144,13 +372,29 @@ impl SceneRenderer {
             deferred,
             per_frame_buffer,
             per_frame_bgl,
-            per_object_buffer,
             material_bgl,
             light_buffer,
+            light_clusters: LightClusterBuffers::new(device),
             default_sampler,
+            fallback_shadow_view,
             meshes: Vec::new(),
             textures: Vec::new(),
+            terrains: Vec::new(),
+            skeletons: Vec::new(),
             csm: None,
+            shadow_atlas: None,
+            ibl: None,
+            ibl_intensity: 1.0,
+            fallback_ibl: IBLEnvironment::placeholder(device),
+            color_luts: [None, None],
+            settings: RenderSettings::default(),
+            cull_stats: CullStats::default(),
+            selection: Vec::new(),
+            profiler: None,
+            frame_index: 0,
+            prev_view_proj: None,
+            prev_time: None,
+            transient_pool: TransientPool::default(),
             width,
             height,
             surface_format,
@@ -168,6 +412,22 @@ impl SceneRenderer {
         bone_weights: Option<&[f32]>,
         bone_indices: Option<&[u16]>,
     ) -> usize {
+        let gpu_mesh = Self::create_gpu_mesh(device, positions, normals, uvs, indices, bone_weights, bone_indices);
+        let (aabb_min, aabb_max) = math::positions_aabb(positions).unwrap_or_default();
+        let idx = self.meshes.len();
+        self.meshes.push(UploadedMesh { gpu_mesh, aabb_min, aabb_max });
+        idx
+    }
+
+    fn create_gpu_mesh(
+        device: &wgpu::Device,
+        positions: &[f32],
+        normals: &[f32],
+        uvs: &[f32],
+        indices: &[u32],
+        bone_weights: Option<&[f32]>,
+        bone_indices: Option<&[u16]>,
+    ) -> GPUMesh {
         use wgpu::util::DeviceExt;
 
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -185,11 +445,7 @@ impl SceneRenderer {
             contents: bytemuck::cast_slice(uvs),
             usage: wgpu::BufferUsages::VERTEX,
         });
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Mesh Indices"),
-            contents: bytemuck::cast_slice(indices),
-            usage: wgpu::BufferUsages::INDEX,
-        });
+        let (index_buffer, index_format) = GPUMesh::create_index_buffer(device, indices);
 
         let bone_weight_buffer = bone_weights.map(|bw| {
             device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -207,20 +463,17 @@ impl SceneRenderer {
         });
 
         let has_skinning = bone_weights.is_some() && bone_indices.is_some();
-        let idx = self.meshes.len();
-        self.meshes.push(UploadedMesh {
-            gpu_mesh: GPUMesh {
-                vertex_buffer,
-                normal_buffer,
-                uv_buffer,
-                index_buffer,
-                index_count: indices.len() as u32,
-                bone_weight_buffer,
-                bone_index_buffer,
-                has_skinning,
-            },
-        });
-        idx
+        GPUMesh {
+            vertex_buffer,
+            normal_buffer,
+            uv_buffer,
+            index_buffer,
+            index_count: indices.len() as u32,
+            index_format,
+            bone_weight_buffer,
+            bone_index_buffer,
+            has_skinning,
+        }
     }
 
     /// Upload a texture to the GPU (decodes PNG if needed).
@@ -307,6 +560,138 @@ impl SceneRenderer {
         idx
     }
 
+    /// Upload a terrain: builds its chunk LOD meshes and splat-map bind group.
+    ///
+    /// Layer textures refer to already uploaded scene textures, so call this
+    /// after `upload_texture`. Missing layers fall back to the default texture.
+    pub fn upload_terrain(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        terrain: &TerrainParsed,
+    ) -> usize {
+        use wgpu::util::DeviceExt;
+
+        let chunks = terrain::build_terrain_chunks(terrain)
+            .into_iter()
+            .map(|chunk| UploadedTerrainChunk {
+                lods: chunk.lods.iter().map(|m| {
+                    Self::create_gpu_mesh(device, &m.positions, &m.normals, &m.uvs, &m.indices, None, None)
+                }).collect(),
+                aabb_min: chunk.aabb_min,
+                aabb_max: chunk.aabb_max,
+            })
+            .collect();
+
+        let mut params = TerrainParams::zeroed();
+        params.num_layers = terrain.layers.len().min(4) as i32;
+        for (i, layer) in terrain.layers.iter().take(4).enumerate() {
+            params.layer_uv_scales[i] = layer.uv_scale;
+        }
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Terrain Params"),
+            contents: bytemuck::bytes_of(&params),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        // Splat weights are linear data, not colour. Without a splat map the
+        // first layer covers everything.
+        let has_splat = terrain.splatmap_width > 0
+            && terrain.splatmap_height > 0
+            && terrain.splatmap.len() == (terrain.splatmap_width * terrain.splatmap_height * 4) as usize;
+        let (splat_w, splat_h, splat_data) = if has_splat {
+            (terrain.splatmap_width, terrain.splatmap_height, terrain.splatmap.as_slice())
+        } else {
+            (1, 1, &[255u8, 0, 0, 0][..])
+        };
+        let splatmap = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Terrain Splatmap"),
+                size: wgpu::Extent3d { width: splat_w, height: splat_h, depth_or_array_layers: 1 },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            splat_data,
+        );
+        let splat_view = splatmap.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let dp = &self.deferred;
+        let layer_view = |i: usize| -> &wgpu::TextureView {
+            terrain.layers.get(i)
+                .map(|l| l.albedo_texture_index)
+                .filter(|&ti| ti >= 0 && (ti as usize) < self.textures.len())
+                .map(|ti| &self.textures[ti as usize].gpu_texture.view)
+                .unwrap_or(&dp.default_texture_view)
+        };
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Terrain BG"),
+            layout: &dp.terrain_bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: params_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&splat_view) },
+                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(layer_view(0)) },
+                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(layer_view(1)) },
+                wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::TextureView(layer_view(2)) },
+                wgpu::BindGroupEntry { binding: 5, resource: wgpu::BindingResource::TextureView(layer_view(3)) },
+                wgpu::BindGroupEntry { binding: 6, resource: wgpu::BindingResource::Sampler(&self.default_sampler) },
+            ],
+        });
+
+        let idx = self.terrains.len();
+        self.terrains.push(UploadedTerrain {
+            chunks,
+            params_buffer,
+            splatmap,
+            bind_group,
+        });
+        idx
+    }
+
+    /// Drop all uploaded terrains (e.g. when switching scenes).
+    pub fn clear_terrains(&mut self) {
+        self.terrains.clear();
+    }
+
+    /// Write the bone matrices of skeleton `index`, creating its uniform
+    /// buffer on first use. Matrices past `MAX_BONES` are dropped.
+    pub fn update_skeleton(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, index: usize, bone_matrices: &[glam::Mat4]) {
+        while self.skeletons.len() <= index {
+            let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&format!("Skeleton {} Bones", self.skeletons.len())),
+                size: std::mem::size_of::<BoneUniforms>() as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Skeleton Bone BG"),
+                layout: &self.deferred.bone_bgl,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }],
+            });
+            self.skeletons.push(SkeletonBuffer { buffer, bind_group });
+        }
+
+        let mut bones = BoneUniforms::zeroed();
+        bones.has_skinning = 1;
+        for (dst, m) in bones.bone_matrices.iter_mut().zip(bone_matrices) {
+            *dst = m.to_cols_array_2d();
+        }
+        queue.write_buffer(&self.skeletons[index].buffer, 0, bytemuck::bytes_of(&bones));
+    }
+
+    /// Drop all skeleton bone buffers (e.g. when switching scenes).
+    pub fn clear_skeletons(&mut self) {
+        self.skeletons.clear();
+    }
+
     /// Create cascaded shadow maps.
     pub fn create_csm(&mut self, device: &wgpu::Device, num_cascades: u32, resolution: u32) {
         let mut depth_textures = Vec::new();
@@ -349,19 +734,166 @@ impl SceneRenderer {
         });
     }
 
+    /// Create the shadow atlas for point and spot lights, `size` texels square.
+    pub fn create_shadow_atlas(&mut self, device: &wgpu::Device, size: u32) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Local Shadow Atlas"),
+            size: wgpu::Extent3d { width: size, height: size, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Depth32Float,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        self.shadow_atlas = Some(ShadowAtlas { texture, view, size });
+    }
+
+    /// Bake an IBL environment from an equirectangular HDR image and light the
+    /// scene with it.
+    pub fn create_ibl(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, image: &HdrImage) -> Result<(), String> {
+        self.ibl = Some(IBLEnvironment::from_equirect(device, queue, image)?);
+        Ok(())
+    }
+
+    /// Bake the analytic sky, for a sun in direction `to_sun`, into the IBL
+    /// environment. The sun disk is left out: the directional light already
+    /// supplies direct sunlight.
+    pub fn create_sky_ibl(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, atmosphere: &AtmosphereSettings, to_sun: glam::Vec3) {
+        let sky = AtmosphereSettings { sun_disk: false, ..*atmosphere }.sky_uniforms(to_sun);
+        self.ibl = Some(IBLEnvironment::from_sky(device, queue, &sky));
+    }
+
+    /// Load a colour grading LUT into slot 0 (A) or 1 (B), or clear the slot
+    /// with `None`.
+    pub fn set_color_lut(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, slot: usize, lut: Option<&CubeLut>) -> Result<(), String> {
+        let slot = self.color_luts.get_mut(slot).ok_or_else(|| format!("invalid LUT slot {slot}"))?;
+        *slot = lut.map(|lut| ColorLut::new(device, queue, lut)).transpose()?;
+        Ok(())
+    }
+
+    /// Enable or disable writing entity IDs for `pick`. Entities are
+    /// identified by `PerObjectUniforms::entity_id`; 0 is never picked.
+    pub fn set_picking(&mut self, device: &wgpu::Device, enabled: bool) {
+        if !enabled {
+            self.deferred.entity_ids = None;
+        } else if self.deferred.entity_ids.is_none() {
+            self.deferred.entity_ids = Some(EntityIds::new(device, &self.deferred, &self.per_frame_bgl, &self.material_bgl));
+        }
+    }
+
+    pub fn picking_enabled(&self) -> bool {
+        self.deferred.entity_ids.is_some()
+    }
+
+    /// Read the entity ID at pixel (`x`, `y`) of the last rendered frame,
+    /// top-left origin. Resolves to `None` when picking is disabled.
+    pub fn pick(&self, device: &wgpu::Device, queue: &wgpu::Queue, x: u32, y: u32) -> PickRequest {
+        match &self.deferred.entity_ids {
+            Some(ids) => ids.pick(device, queue, x, y),
+            None => PickRequest::resolved(None),
+        }
+    }
+
+    /// Highlight the entities with these `PerObjectUniforms::entity_id`s from
+    /// the next frame on, styled by `settings.selection`. Selection reads the
+    /// entity-ID target, so this enables picking; an empty set clears it.
+    pub fn set_selection(&mut self, device: &wgpu::Device, entity_ids: &[u32]) {
+        if !entity_ids.is_empty() {
+            self.set_picking(device, true);
+        }
+        self.selection = entity_ids.to_vec();
+    }
+
+    pub fn selection(&self) -> &[u32] {
+        &self.selection
+    }
+
+    /// Time every pass of each frame: on the GPU when the device was created
+    /// with `profiler::TIMESTAMP_FEATURES`, otherwise on the CPU.
+    pub fn set_profiling(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, enabled: bool) {
+        if !enabled {
+            self.profiler = None;
+        } else if self.profiler.is_none() {
+            self.profiler = Some(FrameProfiler::new(device, queue));
+        }
+    }
+
+    /// Pass timings of the latest profiled frame. GPU timings trail
+    /// `render_frame` by a frame or two.
+    pub fn frame_timings(&self) -> Option<&FrameTimings> {
+        self.profiler.as_ref()?.timings()
+    }
+
+    /// Resolve an entity's mesh and texture indices into a drawable entity.
+    /// Missing textures are left as `None` (the pass binds the default).
+    fn draw_entity<'a>(&'a self, e: &EntityRenderData, object_offset: u32) -> passes::gbuffer::GBufferEntity<'a> {
+        passes::gbuffer::GBufferEntity {
+            mesh: &self.meshes[e.mesh_index.unwrap()].gpu_mesh,
+            object_offset,
+            material: e.material,
+            texture_views: self.texture_views(e),
+        }
+    }
+
+    fn texture_views(&self, e: &EntityRenderData) -> [Option<&wgpu::TextureView>; 6] {
+        let mut texture_views = [None; 6];
+        for (view, &ti) in texture_views.iter_mut().zip(e.texture_indices.iter()) {
+            if ti >= 0 && (ti as usize) < self.textures.len() {
+                *view = Some(&self.textures[ti as usize].gpu_texture.view);
+            }
+        }
+        texture_views
+    }
+
+    /// Bone bind group for an entity that should be drawn skinned: the entity
+    /// and its mesh must both carry skinning data and the skeleton must exist.
+    fn skeleton_bind_group(&self, e: &EntityRenderData) -> Option<&wgpu::BindGroup> {
+        if !e.has_skinning || !self.meshes[e.mesh_index?].gpu_mesh.has_skinning {
+            return None;
+        }
+        self.skeletons.get(e.skeleton_index?).map(|s| &s.bind_group)
+    }
+
+    /// Whether an entity's bounding sphere touches the frustum `planes`.
+    /// Skinned entities always pass: their bind-pose bounds don't cover the
+    /// animated pose.
+    fn in_frustum(&self, e: &EntityRenderData, planes: &[[f32; 4]; 6]) -> bool {
+        if self.skeleton_bind_group(e).is_some() {
+            return true;
+        }
+        let model = glam::Mat4::from_cols_array_2d(&e.per_object.model);
+        let (center, radius) = self.meshes[e.mesh_index.unwrap()].bounding_sphere(&model);
+        math::sphere_in_frustum(planes, center, radius)
+    }
+
     /// Render a full frame using the deferred PBR pipeline.
     ///
     /// This drives the complete pass sequence:
-    /// 1. Shadow pass (CSM)
-    /// 2. G-Buffer pass (opaque geometry)
-    /// 3. Lighting pass (deferred)
-    /// 4. SSAO
-    /// 5. SSR
-    /// 6. TAA
-    /// 7. Bloom (extract → blur → composite)
-    /// 8. FXAA
-    /// 9. Forward pass (transparent geometry)
-    /// 10. Present (tone map to swapchain)
+    /// 1. Shadow pass (CSM and the point/spot light atlas, casters culled per
+    ///    cascade or tile)
+    /// 2. G-Buffer pass (static opaque geometry, instanced where entities
+    ///    share mesh and material, skinned geometry per skeleton, then
+    ///    terrain chunks), culled to the camera frustum
+    /// 3. SSAO
+    /// 4. Lighting pass (deferred)
+    /// 5. Forward pass (transparent geometry, sorted back-to-front)
+    /// 6. SSR (sampled by the next frame's lighting)
+    /// 7. TAA
+    /// 8. Depth of field
+    /// 9. Motion blur
+    /// 10. Bloom (extract → blur → composite, which also tone maps)
+    /// 11. FXAA
+    /// 12. Present, then the selection highlight (or `settings.debug_view`)
+    ///
+    /// The frame is built as a render graph: steps 3 and 6–11 follow
+    /// `self.settings` (the composite always runs), and intra-frame targets
+    /// are transients shared through `transient_pool`.
+    /// With TAA on, the camera projection is jittered by a sub-pixel offset
+    /// each frame; culling and shadows keep the unjittered matrices.
+    /// Culling counts are left in `self.cull_stats`, and pass timings in
+    /// `frame_timings()` when profiling.
     pub fn render_frame(
         &mut self,
         device: &wgpu::Device,
@@ -372,13 +904,80 @@ impl SceneRenderer {
         entities: &[EntityRenderData],
         time: f32,
     ) {
-        let dp = &self.deferred;
+        // The pool and profiler live on `self` but the graph's passes borrow `self`.
+        let mut pool = std::mem::take(&mut self.transient_pool);
+        let mut profiler = self.profiler.take();
+
+        // One object slot per entity, shared by the shadow, G-Buffer and forward passes.
+        let objects = &mut self.deferred.objects;
+        objects.clear();
+        let object_offsets: Vec<u32> = entities.iter().map(|e| objects.push(&e.per_object)).collect();
+        objects.upload(device, &self.deferred.per_object_bgl, queue);
+
+        // Separate opaque and transparent entities
+        let (transparent, opaque): (Vec<_>, Vec<_>) = entities.iter()
+            .zip(object_offsets)
+            .filter(|(e, _)| e.mesh_index.is_some())
+            .partition(|(e, _)| e.is_transparent);
+
+        // Camera culling. Shadow casters are culled per cascade instead, since
+        // objects outside the view can still shadow what's in it.
+        let view_proj = camera.projection * camera.view;
+        let camera_planes = math::extract_frustum_planes(&view_proj);
+        let mut cull_stats = CullStats {
+            entities: (opaque.len() + transparent.len()) as u32,
+            ..Default::default()
+        };
+        let visible_opaque: Vec<_> = opaque.iter().copied()
+            .filter(|(e, _)| self.in_frustum(e, &camera_planes))
+            .collect();
+        let transparent: Vec<_> = transparent.into_iter()
+            .filter(|(e, _)| self.in_frustum(e, &camera_planes))
+            .collect();
+        cull_stats.camera_visible = (visible_opaque.len() + transparent.len()) as u32;
+
+        // Static opaque entities sharing mesh + material are drawn instanced.
+        let static_opaque: Vec<_> = visible_opaque.iter().copied()
+            .filter(|(e, _)| self.skeleton_bind_group(e).is_none())
+            .collect();
+        let (instance_groups, instance_data, single_draws) = batch_instances(&static_opaque);
+        if !instance_data.is_empty() {
+            self.deferred.write_instances(device, queue, &instance_data);
+        }
+        let visible_opaque = &visible_opaque;
+        let (instance_groups, single_draws) = (&instance_groups, &single_draws);
+
+        // Bin point and spot lights into view clusters (any number of lights),
+        // after giving the most visible ones shadow-atlas tiles.
+        let mut local_lights: Vec<LocalLightData> = lights.point_lights.iter().map(LocalLightData::from)
+            .chain(lights.spot_lights.iter().map(LocalLightData::from))
+            .collect();
+        let local_shadows = match &self.shadow_atlas {
+            Some(atlas) => assign_local_shadows(&mut local_lights, camera, &camera_planes, atlas.size, self.height),
+            None => Vec::new(),
+        };
+        let shadow_tiles: Vec<ShadowTileData> = local_shadows.iter().map(|s| s.data).collect();
+        let clusters = clusters::build_light_clusters(
+            &camera.view, &camera.projection, camera.near, camera.far,
+            [self.width as f32, self.height as f32], &local_lights,
+        );
+        self.light_clusters.upload(device, queue, &local_lights, &clusters, &shadow_tiles);
+
+        let renderer = &*self;
+        let dp = &renderer.deferred;
+        let settings = &renderer.settings;
+        let (width, height) = (renderer.width, renderer.height);
 
         // Update per-frame uniforms
-        let inv_view_proj = (camera.projection * camera.view).inverse();
+        let projection = if settings.taa_enabled {
+            settings::jitter_projection(&camera.projection, settings::taa_jitter(renderer.frame_index), width, height)
+        } else {
+            camera.projection
+        };
+        let inv_view_proj = (projection * camera.view).inverse();
         let per_frame = PerFrameUniforms {
             view: camera.view.to_cols_array_2d(),
-            projection: camera.projection.to_cols_array_2d(),
+            projection: projection.to_cols_array_2d(),
             inv_view_proj: inv_view_proj.to_cols_array_2d(),
             camera_pos: [camera.position.x, camera.position.y, camera.position.z, 1.0],
             time,
@@ -387,7 +986,7 @@ impl SceneRenderer {
             _pad3: 0.0,
             _alignment_pad: [0.0; 8],
         };
-        queue.write_buffer(&self.per_frame_buffer, 0, bytemuck::bytes_of(&per_frame));
+        queue.write_buffer(&renderer.per_frame_buffer, 0, bytemuck::bytes_of(&per_frame));
 
         // Update light uniforms
         let mut light_uniforms = LightUniforms::zeroed();
@@ -395,221 +994,472 @@ impl SceneRenderer {
             light_uniforms.dir_lights[i] = *dl;
         }
         light_uniforms.num_dir_lights = lights.dir_lights.len().min(4) as i32;
-        for (i, pl) in lights.point_lights.iter().enumerate().take(16) {
-            light_uniforms.point_lights[i] = *pl;
-        }
-        light_uniforms.num_point_lights = lights.point_lights.len().min(16) as i32;
-        queue.write_buffer(&self.light_buffer, 0, bytemuck::bytes_of(&light_uniforms));
+        light_uniforms.num_point_lights = lights.point_lights.len() as i32;
+        light_uniforms.has_ibl = renderer.ibl.is_some() as i32;
+        light_uniforms.ibl_intensity = renderer.ibl_intensity;
+        queue.write_buffer(&renderer.light_buffer, 0, bytemuck::bytes_of(&light_uniforms));
 
         // Create per-frame bind group
         let per_frame_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Per-Frame BG"),
-            layout: &self.per_frame_bgl,
+            layout: &renderer.per_frame_bgl,
             entries: &[wgpu::BindGroupEntry {
                 binding: 0,
-                resource: self.per_frame_buffer.as_entire_binding(),
+                resource: renderer.per_frame_buffer.as_entire_binding(),
             }],
         });
-
-        // Separate opaque and transparent entities
-        let opaque: Vec<_> = entities.iter().filter(|e| !e.is_transparent && e.mesh_index.is_some()).collect();
-        let transparent: Vec<_> = entities.iter().filter(|e| e.is_transparent && e.mesh_index.is_some()).collect();
-
-        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Scene Render Encoder"),
+        let per_frame_bg = &per_frame_bg;
+
+
+        // Cascades follow the camera frustum; the first directional light casts.
+        let mut shadow_uniforms = ShadowUniforms::zeroed();
+        let mut cascade_matrices = Vec::new();
+        if let (Some(csm), Some(light)) = (&renderer.csm, lights.dir_lights.first()) {
+            let num_cascades = (csm.num_cascades as usize).min(csm.depth_views.len()).min(4);
+            let splits = math::compute_cascade_splits(camera.near, camera.far.min(CSM_MAX_DISTANCE), num_cascades, CSM_SPLIT_LAMBDA);
+            let light_dir = glam::Vec3::new(light.direction[0], light.direction[1], light.direction[2]);
+            cascade_matrices = math::compute_cascade_matrices(&camera.view, &camera.projection, &splits, light_dir, csm.resolution);
+            for (i, m) in cascade_matrices.iter().enumerate() {
+                shadow_uniforms.cascades[i].light_view_proj = m.to_cols_array_2d();
+                shadow_uniforms.cascades[i].split_depth = splits[i + 1];
+            }
+            shadow_uniforms.num_cascades = cascade_matrices.len() as i32;
+            shadow_uniforms.shadow_bias = CSM_SHADOW_BIAS;
+        }
+        queue.write_buffer(&dp.shadow_uniform_buffer, 0, bytemuck::bytes_of(&shadow_uniforms));
+
+        let mut graph = RenderGraph::new();
+        let targets = DeferredTargets::with_transients(&mut graph, dp, width, height);
+        let t = targets;
+        let ctx = NodeContext { device, queue, dp, sampler: &renderer.default_sampler, targets };
+        let surface = graph.import("Surface", surface_view, None);
+        let cascades: [ResourceId; 4] = std::array::from_fn(|i| {
+            let view = renderer.csm.as_ref()
+                .and_then(|csm| csm.depth_views.get(i))
+                .unwrap_or(&renderer.fallback_shadow_view);
+            graph.import("Shadow Cascade", view, None)
         });
+        let shadow_atlas = graph.import(
+            "Shadow Atlas",
+            renderer.shadow_atlas.as_ref().map_or(&renderer.fallback_shadow_view, |a| &a.view),
+            None,
+        );
+        graph.mark_output(surface);
+        // Read back by the next frame.
+        graph.mark_output(t.ssr);
+        graph.mark_output(t.taa_history);
 
         // --- 1. Shadow pass ---
-        if let Some(ref csm) = self.csm {
-            for cascade_idx in 0..csm.num_cascades as usize {
-                if cascade_idx < csm.depth_views.len() {
-                    let shadow_meshes: Vec<_> = opaque.iter().map(|e| {
-                        let mi = e.mesh_index.unwrap();
-                        (0u64, &self.meshes[mi].gpu_mesh, e.per_object.model)
-                    }).collect();
+        if let Some(csm) = &renderer.csm {
+            if !cascade_matrices.is_empty() {
+                cull_stats.shadow_casters = opaque.len() as u32;
+            }
+            for (cascade_idx, light_view_proj) in cascade_matrices.iter().enumerate() {
+                let cascade_planes = math::extract_frustum_planes(light_view_proj);
+                let casters: Vec<_> = opaque.iter().copied()
+                    .filter(|(e, _)| renderer.in_frustum(e, &cascade_planes))
+                    .collect();
+                cull_stats.cascade_visible[cascade_idx] = casters.len() as u32;
+
+                // Each cascade needs its own per-frame buffer: writes are
+                // staged until submit, so a shared buffer would only hold the last.
+                let cascade_frame = PerFrameUniforms {
+                    view: glam::Mat4::IDENTITY.to_cols_array_2d(),
+                    projection: light_view_proj.to_cols_array_2d(),
+                    ..per_frame
+                };
+                let cascade_frame_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Shadow Cascade Per-Frame"),
+                    size: std::mem::size_of::<PerFrameUniforms>() as u64,
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+                queue.write_buffer(&cascade_frame_buffer, 0, bytemuck::bytes_of(&cascade_frame));
+                let cascade_frame_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Shadow Cascade Per-Frame BG"),
+                    layout: &renderer.per_frame_bgl,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: cascade_frame_buffer.as_entire_binding(),
+                    }],
+                });
+
+                graph.add_pass("Shadow Cascade", &[], &[cascades[cascade_idx]], move |encoder, _| {
+                    let shadow_meshes: Vec<_> = casters.iter()
+                        .filter(|(e, _)| renderer.skeleton_bind_group(e).is_none())
+                        .map(|&(e, offset)| {
+                            let mi = e.mesh_index.unwrap();
+                            (0u64, &renderer.meshes[mi].gpu_mesh, offset)
+                        }).collect();
 
                     passes::shadow::render_shadow_cascade(
-                        &mut encoder,
+                        encoder,
                         csm,
                         cascade_idx,
                         &dp.shadow_pipeline,
-                        &per_frame_bg,
-                        &dp.per_object_bgl,
-                        device,
-                        queue,
-                        &self.per_object_buffer,
+                        &cascade_frame_bg,
+                        dp.objects.bind_group(),
                         &shadow_meshes,
                     );
-                }
+
+                    let skinned_meshes: Vec<_> = casters.iter()
+                        .filter_map(|&(e, offset)| {
+                            let bone_bg = renderer.skeleton_bind_group(e)?;
+                            Some((&renderer.meshes[e.mesh_index.unwrap()].gpu_mesh, offset, bone_bg))
+                        }).collect();
+                    if !skinned_meshes.is_empty() {
+                        passes::shadow::render_shadow_cascade_skinned(
+                            encoder,
+                            csm,
+                            cascade_idx,
+                            &dp.shadow_skinned_pipeline,
+                            &cascade_frame_bg,
+                            dp.objects.bind_group(),
+                            &skinned_meshes,
+                        );
+                    }
+                });
             }
         }
 
+        // Point and spot light tiles, casters culled per tile. Tiles share one
+        // per-frame buffer at aligned offsets.
+        if let (Some(atlas), false) = (&renderer.shadow_atlas, local_shadows.is_empty()) {
+            let align = device.limits().min_uniform_buffer_offset_alignment as u64;
+            let stride = (std::mem::size_of::<PerFrameUniforms>() as u64).next_multiple_of(align);
+            let tile_frame_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Shadow Tile Per-Frame"),
+                size: stride * local_shadows.len() as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let mut tile_draws = Vec::with_capacity(local_shadows.len());
+            for (i, shadow) in local_shadows.iter().enumerate() {
+                let tile_frame = PerFrameUniforms {
+                    view: glam::Mat4::IDENTITY.to_cols_array_2d(),
+                    projection: shadow.view_proj.to_cols_array_2d(),
+                    ..per_frame
+                };
+                queue.write_buffer(&tile_frame_buffer, i as u64 * stride, bytemuck::bytes_of(&tile_frame));
+                let tile_frame_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Shadow Tile Per-Frame BG"),
+                    layout: &renderer.per_frame_bgl,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                            buffer: &tile_frame_buffer,
+                            offset: i as u64 * stride,
+                            size: wgpu::BufferSize::new(std::mem::size_of::<PerFrameUniforms>() as u64),
+                        }),
+                    }],
+                });
+
+                let tile_planes = math::extract_frustum_planes(&shadow.view_proj);
+                let casters: Vec<_> = opaque.iter().copied()
+                    .filter(|(e, _)| renderer.in_frustum(e, &tile_planes))
+                    .collect();
+                tile_draws.push(passes::shadow::ShadowTileDraw {
+                    viewport: [shadow.tile.x, shadow.tile.y, shadow.tile.size],
+                    per_frame_bg: tile_frame_bg,
+                    meshes: casters.iter()
+                        .filter(|(e, _)| renderer.skeleton_bind_group(e).is_none())
+                        .map(|&(e, offset)| (&renderer.meshes[e.mesh_index.unwrap()].gpu_mesh, offset))
+                        .collect(),
+                    skinned: casters.iter()
+                        .filter_map(|&(e, offset)| {
+                            let bone_bg = renderer.skeleton_bind_group(e)?;
+                            Some((&renderer.meshes[e.mesh_index.unwrap()].gpu_mesh, offset, bone_bg))
+                        })
+                        .collect(),
+                });
+            }
+
+            graph.add_pass("Local Shadows", &[], &[shadow_atlas], move |encoder, _| {
+                passes::shadow::render_shadow_atlas(
+                    encoder,
+                    &atlas.view,
+                    &dp.shadow_pipeline,
+                    &dp.shadow_skinned_pipeline,
+                    dp.objects.bind_group(),
+                    &tile_draws,
+                );
+            });
+        }
+
         // --- 2. G-Buffer pass ---
-        let gbuffer_entities: Vec<passes::gbuffer::GBufferEntity> = opaque.iter().filter(|e| !e.has_skinning).map(|e| {
-            let mi = e.mesh_index.unwrap();
-            let mesh = &self.meshes[mi].gpu_mesh;
-            let tex_views: Vec<Option<&wgpu::TextureView>> = e.texture_indices.iter().map(|&ti| {
-                if ti >= 0 && (ti as usize) < self.textures.len() {
-                    Some(&self.textures[ti as usize].gpu_texture.view)
-                } else {
-                    None
+        let geometry = dp.geometry();
+        let mut gbuffer_writes = t.gbuffer().to_vec();
+        gbuffer_writes.extend(t.entity_id);
+        graph.add_pass("G-Buffer", &[], &gbuffer_writes, move |encoder, _| {
+            let gbuffer_entities: Vec<passes::gbuffer::GBufferEntity> = single_draws.iter()
+                .map(|&(e, offset)| renderer.draw_entity(e, offset))
+                .collect();
+
+            passes::gbuffer::render_gbuffer_pass(
+                encoder,
+                &dp.gbuffer,
+                geometry.entity_ids,
+                geometry.gbuffer,
+                per_frame_bg,
+                dp.objects.bind_group(),
+                &renderer.material_bgl,
+                device,
+                queue,
+                &gbuffer_entities,
+                &dp.default_texture_view,
+                &renderer.default_sampler,
+            );
+
+            if !instance_groups.is_empty() {
+                let batches: Vec<passes::gbuffer::InstanceBatch> = instance_groups.iter()
+                    .map(|g| passes::gbuffer::InstanceBatch {
+                        mesh: &renderer.meshes[g.entity.mesh_index.unwrap()].gpu_mesh,
+                        material: g.entity.material,
+                        texture_views: renderer.texture_views(g.entity),
+                        first_instance: g.first_instance,
+                        instance_count: g.instance_count,
+                    })
+                    .collect();
+                passes::gbuffer::render_gbuffer_instanced_pass(
+                    encoder,
+                    &dp.gbuffer,
+                    geometry.entity_ids,
+                    geometry.gbuffer_instanced,
+                    per_frame_bg,
+                    &renderer.material_bgl,
+                    device,
+                    queue,
+                    &batches,
+                    &dp.instance_vbo,
+                    &dp.default_texture_view,
+                    &renderer.default_sampler,
+                );
+            }
+
+            // Skinned meshes: one pass per skeleton so each binds its own bones.
+            for (si, skeleton) in renderer.skeletons.iter().enumerate() {
+                let skinned_entities: Vec<passes::gbuffer::GBufferEntity> = visible_opaque.iter()
+                    .filter(|(e, _)| e.skeleton_index == Some(si) && renderer.skeleton_bind_group(e).is_some())
+                    .map(|&(e, offset)| renderer.draw_entity(e, offset))
+                    .collect();
+                if skinned_entities.is_empty() {
+                    continue;
                 }
-            }).collect();
-            passes::gbuffer::GBufferEntity {
-                mesh,
-                per_object: e.per_object,
-                material: e.material,
-                texture_views: [
-                    tex_views.get(0).copied().flatten(),
-                    tex_views.get(1).copied().flatten(),
-                    tex_views.get(2).copied().flatten(),
-                    tex_views.get(3).copied().flatten(),
-                    tex_views.get(4).copied().flatten(),
-                    tex_views.get(5).copied().flatten(),
-                ],
+                passes::gbuffer::render_gbuffer_skinned_pass(
+                    encoder,
+                    &dp.gbuffer,
+                    geometry.entity_ids,
+                    geometry.gbuffer_skinned,
+                    per_frame_bg,
+                    dp.objects.bind_group(),
+                    &renderer.material_bgl,
+                    &skeleton.bind_group,
+                    device,
+                    queue,
+                    &skinned_entities,
+                    &dp.default_texture_view,
+                    &renderer.default_sampler,
+                );
             }
-        }).collect();
-
-        passes::gbuffer::render_gbuffer_pass(
-            &mut encoder,
-            &dp.gbuffer,
-            &dp.gbuffer_pipeline,
-            &per_frame_bg,
-            &dp.per_object_bgl,
-            &self.material_bgl,
-            device,
-            queue,
-            &self.per_object_buffer,
-            &gbuffer_entities,
-            &dp.default_texture_view,
-            &self.default_sampler,
-        );
 
-        // --- 3. Lighting pass ---
-        let lighting_bg = passes::lighting::create_lighting_bind_group(
-            device,
-            &dp.lighting_bgl,
-            &self.per_frame_buffer,
-            &dp.gbuffer,
-            &dp.ssao_targets.blur.color_view,
-            &dp.ssr_target.color_view,
-            &self.default_sampler,
-            &dp.depth_sampler,
-        );
-        let light_data_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Light Data BG"),
-            layout: &dp.light_data_bgl,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: self.light_buffer.as_entire_binding(),
-            }],
+            // Terrain chunks: cull against the camera frustum, pick LOD by distance.
+            if !renderer.terrains.is_empty() {
+                let planes = math::extract_frustum_planes(&(camera.projection * camera.view));
+                let camera_pos = camera.position.to_array();
+                for uploaded in &renderer.terrains {
+                    let chunks: Vec<&GPUMesh> = uploaded.chunks.iter()
+                        .filter(|c| math::aabb_in_frustum(&planes, c.aabb_min.into(), c.aabb_max.into()))
+                        .map(|c| &c.lods[terrain::select_lod(c.aabb_min, c.aabb_max, c.lods.len(), camera_pos)])
+                        .collect();
+                    if !chunks.is_empty() {
+                        passes::terrain::render_terrain_gbuffer(
+                            encoder,
+                            &dp.gbuffer,
+                            geometry.entity_ids,
+                            geometry.terrain,
+                            per_frame_bg,
+                            &uploaded.bind_group,
+                            &chunks,
+                        );
+                    }
+                }
+            }
         });
 
-        passes::lighting::render_lighting_pass(
-            &mut encoder,
-            &dp.lighting_target,
-            &dp.lighting_pipeline,
-            &lighting_bg,
-            &light_data_bg,
-        );
+        // --- 3. SSAO (sampled by lighting; white when disabled) ---
+        if settings.ssao_enabled {
+            nodes::add_ssao(&mut graph, &ctx, &settings.ssao_params(&projection, width, height));
+        } else {
+            nodes::add_clear(&mut graph, "SSAO Clear", t.ssao_blur, wgpu::Color::WHITE);
+        }
 
-        // --- 4. SSAO ---
-        let ssao_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("SSAO BG"),
-            layout: &dp.ssao_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.ssao_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.gbuffer.depth_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(&dp.gbuffer.normal_roughness_view) },
-                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(&dp.ssao_noise_view) },
-                wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::Sampler(&self.default_sampler) },
-                wgpu::BindGroupEntry { binding: 5, resource: wgpu::BindingResource::Sampler(&dp.depth_sampler) },
-            ],
+        // --- 4. Lighting pass ---
+        nodes::add_lighting(&mut graph, &ctx, &renderer.per_frame_buffer, LightingInputs {
+            light_buffer: &renderer.light_buffer,
+            light_clusters: &renderer.light_clusters,
+            cascades,
+            shadow_atlas,
+            ibl: renderer.ibl.as_ref().unwrap_or(&renderer.fallback_ibl),
         });
-        passes::ssao::render_ssao_pass(&mut encoder, &dp.ssao_targets.ao, &dp.ssao_pipeline, &ssao_bg);
 
-        // SSAO blur
-        let ssao_blur_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("SSAO Blur BG"),
-            layout: &dp.ssao_blur_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.ssao_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.ssao_targets.ao.color_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&self.default_sampler) },
-            ],
-        });
-        passes::ssao::render_ssao_blur(&mut encoder, &dp.ssao_targets.blur, &dp.ssao_blur_pipeline, &ssao_blur_bg);
+        // Sky behind the lit scene, lit by the first directional light (the
+        // environment background needs a loaded IBL environment).
+        let to_sun = lights.dir_lights.first().map_or(glam::Vec3::Y, |l| -glam::Vec3::from_slice(&l.direction[..3]));
+        let sky = settings.sky_uniforms(to_sun, renderer.ibl_intensity)
+            .filter(|sky| sky.mode != SKY_MODE_ENVIRONMENT || renderer.ibl.is_some());
+        if let Some(sky) = sky {
+            nodes::add_background(&mut graph, &ctx, &renderer.per_frame_buffer, &sky, renderer.ibl.as_ref().unwrap_or(&renderer.fallback_ibl));
+        }
 
-        // --- 5. Bloom (extract → blur H → blur V → composite) ---
-        let bloom_extract_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Bloom Extract BG"),
-            layout: &dp.bloom_extract_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.pp_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.lighting_target.color_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&self.default_sampler) },
-            ],
-        });
-        passes::postprocess::render_bloom_extract(&mut encoder, &dp.bloom_targets.extract, &dp.bloom_extract_pipeline, &bloom_extract_bg);
+        // --- 5. Forward pass (transparent geometry, back-to-front) ---
+        if !transparent.is_empty() {
+            let mut reads = vec![t.depth];
+            reads.extend(cascades);
+            reads.push(shadow_atlas);
+            let mut writes = vec![t.lighting];
+            writes.extend(t.entity_id);
+            graph.add_pass("Forward", &reads, &writes, move |encoder, res| {
+                // Skinned entities draw in bind pose: the forward layout already
+                // uses all four bind groups, leaving no slot for the bones.
+                let mut sorted: Vec<_> = transparent.iter().collect();
+                let view_depth = |e: &EntityRenderData| {
+                    let m = &e.per_object.model;
+                    camera.view.transform_point3(glam::Vec3::new(m[3][0], m[3][1], m[3][2])).z
+                };
+                // View space looks down -Z: most negative z is farthest.
+                sorted.sort_by(|a, b| view_depth(a.0).total_cmp(&view_depth(b.0)));
+                let forward_entities: Vec<passes::gbuffer::GBufferEntity> = sorted.iter()
+                    .map(|&&(e, offset)| renderer.draw_entity(e, offset))
+                    .collect();
+
+                let [params, cluster_lights, ranges, indices] = renderer.light_clusters.entries(7);
+                let light_shadow_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Forward Light+Shadow BG"),
+                    layout: &dp.forward_light_shadow_bgl,
+                    entries: &[
+                        wgpu::BindGroupEntry { binding: 0, resource: renderer.light_buffer.as_entire_binding() },
+                        wgpu::BindGroupEntry { binding: 1, resource: dp.shadow_uniform_buffer.as_entire_binding() },
+                        wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(res.view(cascades[0])) },
+                        wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(res.view(cascades[1])) },
+                        wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::TextureView(res.view(cascades[2])) },
+                        wgpu::BindGroupEntry { binding: 5, resource: wgpu::BindingResource::TextureView(res.view(cascades[3])) },
+                        wgpu::BindGroupEntry { binding: 6, resource: wgpu::BindingResource::Sampler(&dp.shadow_comparison_sampler) },
+                        params,
+                        cluster_lights,
+                        ranges,
+                        indices,
+                        wgpu::BindGroupEntry { binding: 11, resource: wgpu::BindingResource::TextureView(res.view(shadow_atlas)) },
+                        wgpu::BindGroupEntry { binding: 12, resource: renderer.light_clusters.shadow_tiles().as_entire_binding() },
+                    ],
+                });
+
+                passes::forward::render_forward_pass(
+                    encoder,
+                    &dp.lighting_target,
+                    &dp.gbuffer.depth_view,
+                    geometry.entity_ids,
+                    geometry.forward,
+                    per_frame_bg,
+                    &light_shadow_bg,
+                    dp.objects.bind_group(),
+                    &renderer.material_bgl,
+                    device,
+                    queue,
+                    &forward_entities,
+                    &dp.default_texture_view,
+                    &renderer.default_sampler,
+                );
+            });
+        }
 
-        let bloom_blur_h_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Bloom Blur H BG"),
-            layout: &dp.bloom_blur_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.pp_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.bloom_targets.extract.color_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&self.default_sampler) },
-            ],
-        });
-        passes::postprocess::render_bloom_blur(&mut encoder, &dp.bloom_targets.blur_h, &dp.bloom_blur_pipeline, &bloom_blur_h_bg, "Bloom Blur H");
+        // --- 6. SSR (reflections of this frame's lit scene, used next frame) ---
+        if settings.ssr_enabled {
+            nodes::add_ssr(&mut graph, &ctx, &settings.ssr_params(&projection, &camera.view, camera.position, width, height));
+        } else {
+            nodes::add_clear(&mut graph, "SSR Clear", t.ssr, wgpu::Color::TRANSPARENT);
+        }
 
-        let bloom_blur_v_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Bloom Blur V BG"),
-            layout: &dp.bloom_blur_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.pp_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.bloom_targets.blur_h.color_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&self.default_sampler) },
-            ],
-        });
-        passes::postprocess::render_bloom_blur(&mut encoder, &dp.bloom_targets.blur_v, &dp.bloom_blur_pipeline, &bloom_blur_v_bg, "Bloom Blur V");
+        // --- 7. TAA ---
+        if settings.taa_enabled {
+            nodes::add_taa(&mut graph, &ctx, &TAAParams {
+                prev_view_proj: renderer.prev_view_proj.unwrap_or(view_proj).to_cols_array_2d(),
+                feedback: settings.taa_feedback,
+                first_frame: dp.taa_first_frame as i32,
+                screen_width: width as f32,
+                screen_height: height as f32,
+            });
+        }
 
-        let bloom_composite_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Bloom Composite BG"),
-            layout: &dp.bloom_composite_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.pp_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.lighting_target.color_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(&dp.bloom_targets.blur_v.color_view) },
-                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::Sampler(&self.default_sampler) },
-            ],
-        });
-        passes::postprocess::render_bloom_composite(&mut encoder, &dp.pp_target_a, &dp.bloom_composite_pipeline, &bloom_composite_bg);
+        // --- 8. Depth of field ---
+        if settings.dof_enabled {
+            let coc = DOFCoCParams {
+                focus_distance: settings.dof_focus_distance,
+                focus_range: settings.dof_focus_range,
+                near_plane: camera.near,
+                far_plane: camera.far,
+            };
+            nodes::add_dof(&mut graph, &ctx, &coc, settings.dof_bokeh_radius);
+        }
 
-        // --- 6. FXAA ---
-        let fxaa_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("FXAA BG"),
-            layout: &dp.fxaa_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&dp.pp_target_a.color_view) },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(&self.default_sampler) },
-            ],
-        });
-        passes::postprocess::render_fxaa(&mut encoder, &dp.pp_target_b, &dp.fxaa_pipeline, &fxaa_bg);
+        // --- 9. Motion blur (needs last frame's camera) ---
+        if let Some(prev_view_proj) = renderer.prev_view_proj.filter(|_| settings.motion_blur_enabled) {
+            let velocity = VelocityParams {
+                inv_view_proj: view_proj.inverse().to_cols_array_2d(),
+                prev_view_proj: prev_view_proj.to_cols_array_2d(),
+                max_velocity: settings.motion_blur_max_velocity,
+                _pad1: 0.0,
+                _pad2: 0.0,
+                _pad3: 0.0,
+            };
+            let blur = MotionBlurParams {
+                samples: settings.motion_blur_samples as i32,
+                intensity: settings.motion_blur_intensity,
+                _pad1: 0.0,
+                _pad2: 0.0,
+            };
+            nodes::add_motion_blur(&mut graph, &ctx, &velocity, &blur);
+        }
 
-        // --- 7. Present (tone map to swapchain) ---
-        let present_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Present BG"),
-            layout: &dp.present_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.pp_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.pp_target_b.color_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&self.default_sampler) },
-            ],
-        });
-        passes::present::render_present_pass(&mut encoder, surface_view, &dp.present_pipeline, &present_bg);
+        // --- 10–11. Bloom, exposure, tone-mapping composite, FXAA ---
+        let delta_time = renderer.prev_time.map_or(0.0, |prev| (time - prev).max(0.0));
+        let final_target = nodes::add_postprocess(&mut graph, &ctx, &settings.postprocess_params(delta_time), settings.bloom_enabled, settings.fxaa_enabled);
+
+        // --- 12. Present and selection highlight, or a debug view ---
+        if settings.debug_view == DebugView::Final {
+            let [lut_a, lut_b] = &renderer.color_luts;
+            nodes::add_present(&mut graph, &ctx, final_target, surface, [lut_a.as_ref(), lut_b.as_ref()], settings.lut_blend, settings.lut_intensity);
+            nodes::add_selection(&mut graph, &ctx, surface, &renderer.selection, &settings.selection);
+        } else {
+            nodes::add_debug_view(&mut graph, &ctx, settings.debug_view, cascades, surface, camera.near, camera.far);
+        }
 
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Scene Render Encoder"),
+        });
+        let result = match profiler.as_mut() {
+            Some(profiler) => {
+                profiler.poll(device);
+                profiler.begin_frame();
+                graph.execute_profiled(device, &mut encoder, &mut pool, profiler)
+            }
+            None => graph.execute(device, &mut encoder, &mut pool),
+        };
+        if let Err(e) = result {
+            log::error!("Render graph failed: {e}");
+        }
         queue.submit(std::iter::once(encoder.finish()));
+        if let Some(profiler) = profiler.as_mut() {
+            profiler.end_frame();
+        }
+
+        self.transient_pool = pool;
+        self.profiler = profiler;
+        // History is only valid if TAA wrote it this frame.
+        self.deferred.taa_first_frame = !self.settings.taa_enabled;
+        self.prev_view_proj = Some(view_proj);
+        self.prev_time = Some(time);
+        self.cull_stats = cull_stats;
+        self.frame_index = self.frame_index.wrapping_add(1);
     }
 
     /// Resize all render targets (called on window/canvas resize).
@@ -620,19 +1470,23 @@ impl SceneRenderer {
         let dp = &mut self.deferred;
         dp.gbuffer = render_targets::create_gbuffer(device, width, height);
         dp.lighting_target = render_targets::create_hdr_target(device, width, height, "Lighting Target", true);
-        dp.ssao_targets = render_targets::create_ssao_targets(device, width, height);
         dp.ssr_target = render_targets::create_ssr_target(device, width, height);
         dp.taa_targets = render_targets::create_taa_targets(device, width, height);
-        dp.bloom_targets = render_targets::create_bloom_targets(device, width, height);
-        dp.pp_target_a = render_targets::create_hdr_target(device, width, height, "PP Target A", false);
-        dp.pp_target_b = render_targets::create_hdr_target(device, width, height, "PP Target B", false);
-        dp.dof_targets = render_targets::create_dof_targets(device, width, height);
-        dp.mblur_targets = render_targets::create_motion_blur_targets(device, width, height);
         dp.taa_first_frame = true;
+        if let Some(ids) = &mut dp.entity_ids {
+            ids.resize(device, width, height);
+        }
+        self.transient_pool.clear();
 
         log::info!("SceneRenderer resized to {}x{}", width, height);
     }
 
+    /// Read back the last frame's HDR lighting buffer (linear, before post-processing)
+    /// as f32 RGBA rows. Native only; see `offscreen::read_texture`.
+    pub fn read_hdr(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<Vec<f32>, String> {
+        offscreen::read_texture_f32(device, queue, &self.deferred.lighting_target.color_texture)
+    }
+
     /// Internal: create the full deferred pipeline.
     fn create_deferred_pipeline_inner(
         device: &wgpu::Device,
@@ -643,24 +1497,14 @@ impl SceneRenderer {
         per_frame_bgl: &wgpu::BindGroupLayout,
         material_bgl: &wgpu::BindGroupLayout,
     ) -> Result<DeferredPipeline, String> {
-        // Per-object bind group layout
-        let per_object_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Per-Object BGL"),
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }],
-        });
+        // Per-object bind group layout (dynamic offset into `objects`)
+        let per_object_bgl = pipeline::create_per_object_bgl(device);
+        let objects = ObjectBuffer::new(device, &per_object_bgl);
 
         // Bind group layouts
         let lighting_bgl = pipeline::create_lighting_bind_group_layout(device);
         let light_data_bgl = pipeline::create_light_data_bind_group_layout(device);
+        let lighting_shadow_bgl = pipeline::create_lighting_shadow_bgl(device);
         let particle_bgl = pipeline::create_particle_bgl(device);
         let ui_bgl = pipeline::create_ui_bgl(device);
         let terrain_bgl = pipeline::create_terrain_bgl(device);
@@ -675,20 +1519,25 @@ impl SceneRenderer {
         let taa_bgl = pipeline::create_taa_bind_group_layout(device);
         let bloom_extract_bgl = pipeline::create_effect_bind_group_layout(device, "Bloom Extract BGL", 1, false);
         let bloom_blur_bgl = pipeline::create_effect_bind_group_layout(device, "Bloom Blur BGL", 1, false);
-        let bloom_composite_bgl = pipeline::create_effect_bind_group_layout(device, "Bloom Composite BGL", 2, false);
+        let bloom_composite_bgl = pipeline::create_bloom_composite_bgl(device);
         let fxaa_bgl = pipeline::create_fxaa_bind_group_layout(device);
 
         // Render pipelines
-        let gbuffer_pipeline = pipeline::create_gbuffer_pipeline(device, per_frame_bgl, material_bgl, &per_object_bgl);
+        let gbuffer_pipeline = pipeline::create_gbuffer_pipeline(device, per_frame_bgl, material_bgl, &per_object_bgl, false);
         let shadow_pipeline = pipeline::create_shadow_pipeline(device, per_frame_bgl, &per_object_bgl);
-        let lighting_pipeline = pipeline::create_lighting_pipeline(device, &lighting_bgl, &light_data_bgl);
-        let forward_pipeline = pipeline::create_forward_pipeline(device, per_frame_bgl, material_bgl, &per_object_bgl, &forward_light_shadow_bgl);
+        let shadow_skinned_pipeline = pipeline::create_shadow_skinned_pipeline(device, per_frame_bgl, &per_object_bgl, &bone_bgl);
+        let lighting_pipeline = pipeline::create_lighting_pipeline(device, &lighting_bgl, &light_data_bgl, &lighting_shadow_bgl);
+        let forward_pipeline = pipeline::create_forward_pipeline(device, per_frame_bgl, material_bgl, &per_object_bgl, &forward_light_shadow_bgl, false);
         let present_pipeline = pipeline::create_present_pipeline(device, &present_bgl, surface_format);
+        let selection_bgl = pipeline::create_selection_bgl(device);
+        let selection_pipeline = pipeline::create_selection_pipeline(device, &selection_bgl, surface_format);
+        let debug_view_bgl = pipeline::create_debug_view_bgl(device);
+        let debug_view_pipeline = pipeline::create_debug_view_pipeline(device, &debug_view_bgl, surface_format);
         let particle_pipeline = pipeline::create_particle_pipeline(device, &particle_bgl, surface_format);
         let ui_pipeline = pipeline::create_ui_pipeline(device, &ui_bgl, surface_format);
-        let terrain_pipeline = pipeline::create_terrain_pipeline(device, per_frame_bgl, &terrain_bgl);
-        let gbuffer_skinned_pipeline = pipeline::create_gbuffer_skinned_pipeline(device, per_frame_bgl, material_bgl, &per_object_bgl, &bone_bgl);
-        let gbuffer_instanced_pipeline = pipeline::create_gbuffer_instanced_pipeline(device, per_frame_bgl, material_bgl);
+        let terrain_pipeline = pipeline::create_terrain_pipeline(device, per_frame_bgl, &terrain_bgl, false);
+        let gbuffer_skinned_pipeline = pipeline::create_gbuffer_skinned_pipeline(device, per_frame_bgl, material_bgl, &per_object_bgl, &bone_bgl, false);
+        let gbuffer_instanced_pipeline = pipeline::create_gbuffer_instanced_pipeline(device, per_frame_bgl, material_bgl, false);
 
         // Effect pipelines
         let ssao_pipeline = pipeline::create_fullscreen_effect_pipeline(device, "SSAO Pipeline", shaders::SSAO_FRAG, "fs_main", &ssao_bgl, render_targets::R16_FORMAT);
@@ -717,14 +1566,17 @@ impl SceneRenderer {
         // Render targets
         let gbuffer = render_targets::create_gbuffer(device, w, h);
         let lighting_target = render_targets::create_hdr_target(device, w, h, "Lighting Target", true);
-        let ssao_targets = render_targets::create_ssao_targets(device, w, h);
         let ssr_target = render_targets::create_ssr_target(device, w, h);
         let taa_targets = render_targets::create_taa_targets(device, w, h);
-        let bloom_targets = render_targets::create_bloom_targets(device, w, h);
-        let pp_target_a = render_targets::create_hdr_target(device, w, h, "PP Target A", false);
-        let pp_target_b = render_targets::create_hdr_target(device, w, h, "PP Target B", false);
-        let dof_targets = render_targets::create_dof_targets(device, w, h);
-        let mblur_targets = render_targets::create_motion_blur_targets(device, w, h);
+        // Intra-frame targets are render graph transients here (see
+        // `DeferredTargets::with_transients`); these 1x1 stand-ins only fill
+        // the fields the FFI backend renders into.
+        let ssao_targets = render_targets::create_ssao_targets(device, 1, 1);
+        let bloom_targets = render_targets::create_bloom_targets(device, 1, 1);
+        let pp_target_a = render_targets::create_hdr_target(device, 1, 1, "PP Target A", false);
+        let pp_target_b = render_targets::create_hdr_target(device, 1, 1, "PP Target B", false);
+        let dof_targets = render_targets::create_dof_targets(device, 1, 1);
+        let mblur_targets = render_targets::create_motion_blur_targets(device, 1, 1);
 
         // Default resources
         let (default_texture, default_texture_view) = render_targets::create_default_texture(device, queue);
@@ -826,10 +1678,33 @@ impl SceneRenderer {
             usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST, mapped_at_creation: false,
         });
 
+        // Sky background
+        let sky_bgl = pipeline::create_sky_bgl(device);
+        let sky_pipeline = pipeline::create_sky_pipeline(device, &sky_bgl);
+        let sky_uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Sky Uniforms"), size: std::mem::size_of::<SkyUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST, mapped_at_creation: false,
+        });
+
+        // Auto exposure
+        let auto_exposure_bgl = pipeline::create_auto_exposure_bgl(device);
+        let exposure_histogram_pipeline = pipeline::create_auto_exposure_pipeline(device, &auto_exposure_bgl, "cs_histogram");
+        let exposure_average_pipeline = pipeline::create_auto_exposure_pipeline(device, &auto_exposure_bgl, "cs_average");
+        let exposure_histogram_buffer = render_targets::create_exposure_histogram_buffer(device);
+        let (exposure_texture, exposure_view) = render_targets::create_exposure_target(device);
+
+        // Colour grading
+        let color_grading_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Color Grading Uniforms"), size: std::mem::size_of::<ColorGradingUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST, mapped_at_creation: false,
+        });
+        let identity_lut = ColorLut::identity(device, queue);
+
         Ok(DeferredPipeline {
             gbuffer_pipeline,
             lighting_pipeline,
             shadow_pipeline,
+            shadow_skinned_pipeline,
             forward_pipeline,
             present_pipeline,
             particle_pipeline,
@@ -874,8 +1749,10 @@ impl SceneRenderer {
             ssao_noise_texture,
             ssao_noise_view,
             fullscreen_quad_vbo,
+            objects,
             lighting_bgl,
             light_data_bgl,
+            lighting_shadow_bgl,
             per_object_bgl,
             particle_bgl,
             ui_bgl,
@@ -913,7 +1790,74 @@ impl SceneRenderer {
             debug_lines_uniform_buffer,
             debug_lines_vbo,
             debug_lines_vbo_size: 1024 * 24,
+            sky_pipeline,
+            sky_bgl,
+            sky_uniform_buffer,
+            exposure_histogram_pipeline,
+            exposure_average_pipeline,
+            auto_exposure_bgl,
+            exposure_histogram_buffer,
+            exposure_texture,
+            exposure_view,
+            color_grading_buffer,
+            identity_lut,
+            entity_ids: None,
+            selection_pipeline,
+            selection_bgl,
+            debug_view_pipeline,
+            debug_view_bgl,
             taa_first_frame: true,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(mesh: usize, albedo: f32, x: f32) -> EntityRenderData {
+        let mut per_object = PerObjectUniforms::zeroed();
+        per_object.model = glam::Mat4::from_translation(glam::Vec3::new(x, 0.0, 0.0)).to_cols_array_2d();
+        let mut material = MaterialUniforms::zeroed();
+        material.albedo = [albedo, albedo, albedo, 1.0];
+        EntityRenderData {
+            mesh_index: Some(mesh),
+            material_index: None,
+            per_object,
+            texture_indices: [-1; 7],
+            material,
+            is_transparent: false,
+            has_skinning: false,
+            skeleton_index: None,
+        }
+    }
+
+    #[test]
+    fn test_batch_instances_groups_shared_mesh_and_material() {
+        let entities = [entity(0, 1.0, 1.0), entity(1, 1.0, 2.0), entity(0, 1.0, 3.0), entity(0, 0.5, 4.0), entity(0, 1.0, 5.0)];
+        let draws: Vec<_> = entities.iter().zip(0u32..).collect();
+        let (groups, data, singles) = batch_instances(&draws);
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!((groups[0].first_instance, groups[0].instance_count), (0, 3));
+        assert_eq!(data.len() as u64, 3 * pipeline::INSTANCE_STRIDE);
+        // Instances keep draw order; the translation sits in column 3 of each model.
+        let floats: &[f32] = bytemuck::cast_slice(&data);
+        let stride = pipeline::INSTANCE_STRIDE as usize / 4;
+        let xs: Vec<f32> = (0..3).map(|i| floats[i * stride + 12]).collect();
+        assert_eq!(xs, [1.0, 3.0, 5.0]);
+
+        // Different mesh and different material stay single draws.
+        let single_offsets: Vec<u32> = singles.iter().map(|&(_, o)| o).collect();
+        assert_eq!(single_offsets, [1, 3]);
+    }
+
+    #[test]
+    fn test_batch_instances_without_duplicates() {
+        let entities = [entity(0, 1.0, 0.0), entity(1, 1.0, 0.0)];
+        let draws: Vec<_> = entities.iter().zip(0u32..).collect();
+        let (groups, data, singles) = batch_instances(&draws);
+        assert!(groups.is_empty() && data.is_empty());
+        assert_eq!(singles.len(), 2);
+    }
+}
@@ -0,0 +1,541 @@
+//! Render settings for `SceneRenderer` — which post-processing effects run
+//! and how they're parameterized.
+//!
+//! Field names and defaults mirror Julia's `PostProcessConfig`, except that
+//! SSAO, bloom and FXAA default to on (SceneRenderer always ran them).
+
+use glam::{Mat4, Vec3};
+use openreality_gpu_shared::uniforms::{
+    DebugViewUniforms, PostProcessParams, SSAOParams, SSRParams, SelectionUniforms, SkyUniforms,
+    DEBUG_REMAP_CHANNEL, DEBUG_REMAP_COLOR, DEBUG_REMAP_DEPTH, DEBUG_REMAP_HDR, EXPOSURE_MODE_AUTO,
+    EXPOSURE_MODE_MANUAL, MAX_SELECTION_OUTLINE_WIDTH, SKY_MODE_ATMOSPHERE, SKY_MODE_ENVIRONMENT,
+};
+
+/// Tone mapping operator applied in the bloom composite pass.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ToneMapping {
+    #[default]
+    Reinhard,
+    Aces,
+    Uncharted2,
+    /// Troy Sobotka's AgX: desaturates highlights instead of skewing their hue.
+    Agx,
+}
+
+impl ToneMapping {
+    /// Value of `tone_mapping_mode` in the post-process shaders.
+    pub fn shader_mode(self) -> i32 {
+        match self {
+            ToneMapping::Reinhard => 0,
+            ToneMapping::Aces => 1,
+            ToneMapping::Uncharted2 => 2,
+            ToneMapping::Agx => 3,
+        }
+    }
+}
+
+/// How HDR radiance is scaled before tone mapping.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Exposure {
+    /// Fixed exposure of `ev` stops; 0 leaves radiance unscaled.
+    Manual { ev: f32 },
+    /// Metered each frame from a GPU luminance histogram.
+    Auto(AutoExposure),
+}
+
+impl Default for Exposure {
+    fn default() -> Self {
+        Exposure::Manual { ev: 0.0 }
+    }
+}
+
+/// Histogram auto exposure: the average scene luminance is exposed to
+/// middle grey, approached over time like an eye adapting.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AutoExposure {
+    /// Stops added to the metered exposure.
+    pub compensation: f32,
+    /// Adaptation rate (1/s); larger reacts faster to brightness changes.
+    pub adaptation_speed: f32,
+}
+
+impl Default for AutoExposure {
+    fn default() -> Self {
+        Self { compensation: 0.0, adaptation_speed: 1.5 }
+    }
+}
+
+/// What is drawn behind the scene where the G-Buffer is empty.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Background {
+    /// The lighting pass's flat grey.
+    #[default]
+    None,
+    /// The loaded IBL environment cubemap, scaled by `SceneRenderer::ibl_intensity`.
+    Environment,
+    /// Analytic atmospheric scattering lit by the first directional light.
+    Atmosphere(AtmosphereSettings),
+}
+
+/// Parameters of the analytic sky in `sky.wgsl`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AtmosphereSettings {
+    /// Radiance scale of sunlight entering the atmosphere.
+    pub sun_intensity: f32,
+    /// Aerosol (Mie) density multiplier: 1 is a clear day, higher is hazier.
+    pub turbidity: f32,
+    pub sun_disk: bool,
+}
+
+impl Default for AtmosphereSettings {
+    fn default() -> Self {
+        Self { sun_intensity: 20.0, turbidity: 1.0, sun_disk: true }
+    }
+}
+
+impl AtmosphereSettings {
+    /// Sky uniforms for a sun in direction `to_sun` (need not be normalized).
+    pub fn sky_uniforms(&self, to_sun: Vec3) -> SkyUniforms {
+        let sun = to_sun.try_normalize().unwrap_or(Vec3::Y);
+        SkyUniforms {
+            sun_direction: [sun.x, sun.y, sun.z, 0.0],
+            sun_intensity: self.sun_intensity,
+            turbidity: self.turbidity,
+            mode: SKY_MODE_ATMOSPHERE,
+            face: 0,
+            environment_intensity: 0.0,
+            sun_disk: self.sun_disk as i32,
+            _pad1: 0.0,
+            _pad2: 0.0,
+        }
+    }
+}
+
+/// How selected entities are highlighted (see `SceneRenderer::set_selection`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SelectionStyle {
+    pub outline_color: [f32; 4],
+    /// Outline width in pixels, clamped to `MAX_SELECTION_OUTLINE_WIDTH`; 0
+    /// draws no outline.
+    pub outline_width: f32,
+    /// Blended over the selected entities; alpha is the tint strength.
+    pub tint_color: [f32; 4],
+}
+
+impl Default for SelectionStyle {
+    fn default() -> Self {
+        Self { outline_color: [1.0, 0.55, 0.1, 1.0], outline_width: 2.0, tint_color: [1.0, 0.55, 0.1, 0.15] }
+    }
+}
+
+impl SelectionStyle {
+    /// Shader parameters for `count` selected IDs.
+    pub fn uniforms(&self, count: u32) -> SelectionUniforms {
+        SelectionUniforms {
+            outline_color: self.outline_color,
+            tint_color: self.tint_color,
+            outline_width: self.outline_width.clamp(0.0, MAX_SELECTION_OUTLINE_WIDTH),
+            count,
+            _pad1: 0.0,
+            _pad2: 0.0,
+        }
+    }
+}
+
+/// What the present pass shows: the final image, or an intermediate target
+/// remapped for display.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DebugView {
+    #[default]
+    Final,
+    Albedo,
+    Metallic,
+    /// World-space normals as stored in the G-Buffer (0.5 + 0.5 * n).
+    Normal,
+    Roughness,
+    Emissive,
+    /// Material ambient occlusion (see `Ssao` for the screen-space term).
+    Occlusion,
+    /// Linear view depth as a fraction of the far plane.
+    Depth,
+    Ssao,
+    Ssr,
+    Bloom,
+    /// HDR lighting before post-processing.
+    Lighting,
+    /// Depth of shadow cascade 0–3.
+    ShadowCascade(u32),
+}
+
+impl DebugView {
+    /// Names accepted by `from_name`.
+    pub const NAMES: [&'static str; 16] = [
+        "final", "albedo", "metallic", "normal", "roughness", "emissive", "occlusion", "depth",
+        "ssao", "ssr", "bloom", "lighting", "cascade0", "cascade1", "cascade2", "cascade3",
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "final" => DebugView::Final,
+            "albedo" => DebugView::Albedo,
+            "metallic" => DebugView::Metallic,
+            "normal" => DebugView::Normal,
+            "roughness" => DebugView::Roughness,
+            "emissive" => DebugView::Emissive,
+            "occlusion" => DebugView::Occlusion,
+            "depth" => DebugView::Depth,
+            "ssao" => DebugView::Ssao,
+            "ssr" => DebugView::Ssr,
+            "bloom" => DebugView::Bloom,
+            "lighting" => DebugView::Lighting,
+            _ => {
+                let cascade = name.strip_prefix("cascade")?.parse().ok().filter(|&c: &u32| c < 4)?;
+                DebugView::ShadowCascade(cascade)
+            }
+        })
+    }
+
+    /// Remapping of the view's target in `debug_view.wgsl`, given the camera
+    /// clip planes. `Final` is drawn by the regular present pass.
+    pub fn uniforms(self, near: f32, far: f32) -> DebugViewUniforms {
+        let (remap, channel) = match self {
+            DebugView::Final | DebugView::Albedo | DebugView::Normal | DebugView::Emissive => (DEBUG_REMAP_COLOR, 0),
+            DebugView::Metallic | DebugView::Roughness | DebugView::Occlusion => (DEBUG_REMAP_CHANNEL, 3),
+            DebugView::Ssao | DebugView::ShadowCascade(_) => (DEBUG_REMAP_CHANNEL, 0),
+            DebugView::Depth => (DEBUG_REMAP_DEPTH, 0),
+            DebugView::Ssr | DebugView::Bloom | DebugView::Lighting => (DEBUG_REMAP_HDR, 0),
+        };
+        DebugViewUniforms { remap, channel, near, far }
+    }
+}
+
+/// Number of frames in the TAA jitter sequence.
+pub const TAA_JITTER_PHASES: u64 = 8;
+
+/// Maximum SSAO kernel size (length of `SSAOParams::samples`).
+pub const MAX_SSAO_SAMPLES: u32 = 64;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderSettings {
+    pub ssao_enabled: bool,
+    pub ssao_radius: f32,
+    pub ssao_samples: u32,
+
+    pub ssr_enabled: bool,
+    pub ssr_max_steps: u32,
+    pub ssr_max_distance: f32,
+    pub ssr_thickness: f32,
+
+    pub taa_enabled: bool,
+    /// History weight in [0, 1]; higher is smoother but ghosts more.
+    pub taa_feedback: f32,
+
+    pub dof_enabled: bool,
+    pub dof_focus_distance: f32,
+    pub dof_focus_range: f32,
+    pub dof_bokeh_radius: f32,
+
+    pub motion_blur_enabled: bool,
+    pub motion_blur_intensity: f32,
+    pub motion_blur_samples: u32,
+    pub motion_blur_max_velocity: f32,
+
+    pub bloom_enabled: bool,
+    pub bloom_threshold: f32,
+    pub bloom_intensity: f32,
+
+    pub fxaa_enabled: bool,
+    pub tone_mapping: ToneMapping,
+    pub exposure: Exposure,
+    pub gamma: f32,
+    /// Cross-fade from the colour LUT in slot A (0) to slot B (1).
+    pub lut_blend: f32,
+    /// Strength of LUT grading; ignored while no LUT is loaded.
+    pub lut_intensity: f32,
+
+    pub background: Background,
+    pub selection: SelectionStyle,
+    pub debug_view: DebugView,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            ssao_enabled: true,
+            ssao_radius: 0.5,
+            ssao_samples: 16,
+            ssr_enabled: false,
+            ssr_max_steps: 64,
+            ssr_max_distance: 50.0,
+            ssr_thickness: 0.1,
+            taa_enabled: false,
+            taa_feedback: 0.9,
+            dof_enabled: false,
+            dof_focus_distance: 10.0,
+            dof_focus_range: 5.0,
+            dof_bokeh_radius: 3.0,
+            motion_blur_enabled: false,
+            motion_blur_intensity: 1.0,
+            motion_blur_samples: 8,
+            motion_blur_max_velocity: 40.0,
+            bloom_enabled: true,
+            bloom_threshold: 1.0,
+            bloom_intensity: 0.3,
+            fxaa_enabled: true,
+            tone_mapping: ToneMapping::Reinhard,
+            exposure: Exposure::default(),
+            gamma: 2.2,
+            lut_blend: 0.0,
+            lut_intensity: 1.0,
+            background: Background::None,
+            selection: SelectionStyle::default(),
+            debug_view: DebugView::Final,
+        }
+    }
+}
+
+impl RenderSettings {
+    /// Bloom, exposure and tone mapping parameters. With bloom off the
+    /// composite pass still tone maps, it just adds nothing. `delta_time` is
+    /// the time since the previous frame, for exposure adaptation.
+    pub fn postprocess_params(&self, delta_time: f32) -> PostProcessParams {
+        let (exposure_mode, exposure_ev, adaptation_speed) = match self.exposure {
+            Exposure::Manual { ev } => (EXPOSURE_MODE_MANUAL, ev, 0.0),
+            Exposure::Auto(auto) => (EXPOSURE_MODE_AUTO, auto.compensation, auto.adaptation_speed),
+        };
+        PostProcessParams {
+            bloom_threshold: self.bloom_threshold,
+            bloom_intensity: if self.bloom_enabled { self.bloom_intensity } else { 0.0 },
+            gamma: self.gamma,
+            tone_mapping_mode: self.tone_mapping.shader_mode(),
+            horizontal: 0,
+            vignette_intensity: 0.0,
+            vignette_radius: 0.8,
+            vignette_softness: 0.5,
+            color_brightness: 0.0,
+            color_contrast: 1.0,
+            color_saturation: 1.0,
+            exposure_mode,
+            exposure_ev,
+            adaptation_speed,
+            delta_time,
+            _pad1: 0.0,
+        }
+    }
+
+    /// Background pass parameters, or `None` when the background is not
+    /// drawn. `to_sun` points toward the sun; `environment_intensity` scales
+    /// the IBL cubemap.
+    pub fn sky_uniforms(&self, to_sun: Vec3, environment_intensity: f32) -> Option<SkyUniforms> {
+        match self.background {
+            Background::None => None,
+            Background::Environment => Some(SkyUniforms {
+                mode: SKY_MODE_ENVIRONMENT,
+                environment_intensity,
+                ..AtmosphereSettings::default().sky_uniforms(to_sun)
+            }),
+            Background::Atmosphere(atmosphere) => Some(atmosphere.sky_uniforms(to_sun)),
+        }
+    }
+
+    pub fn ssao_params(&self, projection: &Mat4, width: u32, height: u32) -> SSAOParams {
+        SSAOParams {
+            samples: ssao_kernel(),
+            projection: projection.to_cols_array_2d(),
+            kernel_size: self.ssao_samples.min(MAX_SSAO_SAMPLES) as i32,
+            radius: self.ssao_radius,
+            bias: 0.025,
+            power: 2.0,
+            screen_width: width as f32,
+            screen_height: height as f32,
+            _pad1: 0.0,
+            _pad2: 0.0,
+        }
+    }
+
+    pub fn ssr_params(&self, projection: &Mat4, view: &Mat4, camera_pos: Vec3, width: u32, height: u32) -> SSRParams {
+        SSRParams {
+            projection: projection.to_cols_array_2d(),
+            view: view.to_cols_array_2d(),
+            inv_projection: projection.inverse().to_cols_array_2d(),
+            camera_pos: [camera_pos.x, camera_pos.y, camera_pos.z, 0.0],
+            screen_size: [width as f32, height as f32],
+            max_steps: self.ssr_max_steps as i32,
+            max_distance: self.ssr_max_distance,
+            thickness: self.ssr_thickness,
+            _pad1: 0.0,
+            _pad2: 0.0,
+            _pad3: 0.0,
+        }
+    }
+}
+
+/// SSAO hemisphere kernel: `MAX_SSAO_SAMPLES` points in the +Z hemisphere,
+/// denser near the origin. Deterministic so frames don't flicker.
+pub fn ssao_kernel() -> [[f32; 4]; 64] {
+    // xorshift32; any fixed seed works.
+    let mut state = 0x9E37_79B9u32;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state as f32 / u32::MAX as f32
+    };
+
+    let mut samples = [[0.0; 4]; 64];
+    for (i, s) in samples.iter_mut().enumerate() {
+        let (xi1, xi2) = (next(), next());
+        let r = (1.0 - xi2 * xi2).sqrt();
+        let phi = 2.0 * std::f32::consts::PI * xi1;
+        let t = (i + 1) as f32 / 64.0;
+        let scale = 0.1 + t * t * 0.9;
+        *s = [phi.cos() * r * scale, phi.sin() * r * scale, xi2 * scale, 0.0];
+    }
+    samples
+}
+
+/// Radical inverse of `index` in `base` (Halton sequence), in [0, 1).
+pub fn halton(mut index: u64, base: u64) -> f32 {
+    let mut f = 1.0;
+    let mut r = 0.0;
+    while index > 0 {
+        f /= base as f32;
+        r += f * (index % base) as f32;
+        index /= base;
+    }
+    r
+}
+
+/// Sub-pixel TAA jitter for a frame, in pixels within [-0.5, 0.5).
+pub fn taa_jitter(frame_index: u64) -> [f32; 2] {
+    let i = frame_index % TAA_JITTER_PHASES + 1;
+    [halton(i, 2) - 0.5, halton(i, 3) - 0.5]
+}
+
+/// Offset a projection matrix by a jitter given in pixels.
+pub fn jitter_projection(projection: &Mat4, jitter: [f32; 2], width: u32, height: u32) -> Mat4 {
+    let offset = Vec3::new(2.0 * jitter[0] / width.max(1) as f32, 2.0 * jitter[1] / height.max(1) as f32, 0.0);
+    Mat4::from_translation(offset) * *projection
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_halton_sequence() {
+        assert_eq!(halton(1, 2), 0.5);
+        assert_eq!(halton(2, 2), 0.25);
+        assert_eq!(halton(3, 2), 0.75);
+        assert!((halton(1, 3) - 1.0 / 3.0).abs() < 1e-6);
+        assert!((halton(2, 3) - 2.0 / 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_taa_jitter_cycles_within_pixel() {
+        for i in 0..TAA_JITTER_PHASES {
+            let j = taa_jitter(i);
+            assert!((-0.5..0.5).contains(&j[0]) && (-0.5..0.5).contains(&j[1]), "{j:?}");
+        }
+        assert_eq!(taa_jitter(3), taa_jitter(3 + TAA_JITTER_PHASES));
+        assert_ne!(taa_jitter(0), taa_jitter(1));
+    }
+
+    #[test]
+    fn test_jitter_projection_shifts_by_pixels() {
+        let proj = Mat4::perspective_rh(1.0, 1.0, 0.1, 100.0);
+        let jittered = jitter_projection(&proj, [0.5, -0.25], 200, 100);
+        let p = Vec3::new(0.3, -0.2, -5.0);
+        let a = proj.project_point3(p);
+        let b = jittered.project_point3(p);
+        // NDC spans 2 units over the viewport.
+        assert!(((b.x - a.x) * 100.0 - 0.5).abs() < 1e-4);
+        assert!(((b.y - a.y) * 50.0 + 0.25).abs() < 1e-4);
+        assert!((b.z - a.z).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_ssao_kernel_in_hemisphere() {
+        let kernel = ssao_kernel();
+        for s in &kernel {
+            let len = (s[0] * s[0] + s[1] * s[1] + s[2] * s[2]).sqrt();
+            assert!(s[2] >= 0.0 && len <= 1.0 + 1e-5, "{s:?}");
+        }
+        assert_eq!(kernel, ssao_kernel());
+    }
+
+    #[test]
+    fn test_postprocess_params_follow_toggles() {
+        let mut settings = RenderSettings::default();
+        assert_eq!(settings.postprocess_params(0.0).bloom_intensity, 0.3);
+        settings.bloom_enabled = false;
+        settings.tone_mapping = ToneMapping::Aces;
+        let pp = settings.postprocess_params(0.0);
+        assert_eq!(pp.bloom_intensity, 0.0);
+        assert_eq!(pp.tone_mapping_mode, 1);
+        assert_eq!(pp.gamma, 2.2);
+    }
+
+    #[test]
+    fn test_postprocess_params_follow_exposure() {
+        let mut settings = RenderSettings::default();
+        let pp = settings.postprocess_params(0.016);
+        assert_eq!((pp.exposure_mode, pp.exposure_ev), (EXPOSURE_MODE_MANUAL, 0.0));
+
+        settings.exposure = Exposure::Auto(AutoExposure { compensation: -1.0, adaptation_speed: 3.0 });
+        settings.tone_mapping = ToneMapping::Agx;
+        let pp = settings.postprocess_params(0.016);
+        assert_eq!(pp.exposure_mode, EXPOSURE_MODE_AUTO);
+        assert_eq!((pp.exposure_ev, pp.adaptation_speed, pp.delta_time), (-1.0, 3.0, 0.016));
+        assert_eq!(pp.tone_mapping_mode, 3);
+    }
+
+    #[test]
+    fn test_sky_uniforms_follow_background() {
+        let mut settings = RenderSettings::default();
+        assert!(settings.sky_uniforms(Vec3::Y, 1.0).is_none());
+
+        settings.background = Background::Environment;
+        let sky = settings.sky_uniforms(Vec3::Y, 0.5).unwrap();
+        assert_eq!((sky.mode, sky.environment_intensity), (SKY_MODE_ENVIRONMENT, 0.5));
+
+        settings.background = Background::Atmosphere(AtmosphereSettings { turbidity: 3.0, ..Default::default() });
+        let sky = settings.sky_uniforms(Vec3::new(0.0, 2.0, 0.0), 1.0).unwrap();
+        assert_eq!(sky.mode, SKY_MODE_ATMOSPHERE);
+        assert_eq!(sky.turbidity, 3.0);
+        assert_eq!(sky.sun_direction, [0.0, 1.0, 0.0, 0.0]);
+        assert_eq!(sky.sun_disk, 1);
+    }
+
+    #[test]
+    fn test_selection_outline_width_clamped() {
+        let style = SelectionStyle { outline_width: 20.0, ..Default::default() };
+        let uniforms = style.uniforms(3);
+        assert_eq!((uniforms.outline_width, uniforms.count), (MAX_SELECTION_OUTLINE_WIDTH, 3));
+        let style = SelectionStyle { outline_width: -1.0, ..Default::default() };
+        assert_eq!(style.uniforms(1).outline_width, 0.0);
+    }
+
+    #[test]
+    fn test_debug_view_names_round_trip() {
+        for name in DebugView::NAMES {
+            assert!(DebugView::from_name(name).is_some(), "{name}");
+        }
+        assert_eq!(DebugView::from_name("cascade2"), Some(DebugView::ShadowCascade(2)));
+        assert_eq!(DebugView::from_name("cascade4"), None);
+        assert_eq!(DebugView::from_name("albedo "), None);
+
+        let depth = DebugView::Depth.uniforms(0.1, 100.0);
+        assert_eq!((depth.remap, depth.near, depth.far), (DEBUG_REMAP_DEPTH, 0.1, 100.0));
+        let roughness = DebugView::Roughness.uniforms(0.1, 100.0);
+        assert_eq!((roughness.remap, roughness.channel), (DEBUG_REMAP_CHANNEL, 3));
+    }
+
+    #[test]
+    fn test_ssao_samples_clamped_to_kernel() {
+        let settings = RenderSettings { ssao_samples: 500, ..Default::default() };
+        let params = settings.ssao_params(&Mat4::IDENTITY, 64, 32);
+        assert_eq!(params.kernel_size, MAX_SSAO_SAMPLES as i32);
+        assert_eq!(params.screen_width, 64.0);
+    }
+}
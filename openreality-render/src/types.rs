@@ -1,6 +1,9 @@
 //! GPU resource type definitions for the deferred rendering pipeline.
 //! These types are platform-independent and shared between native (FFI) and WASM backends.
 
+use crate::color_lut::ColorLut;
+use crate::object_buffer::ObjectBuffer;
+use crate::picking::EntityIds;
 use crate::render_targets;
 
 /// GPU mesh with vertex and index buffers.
@@ -10,12 +13,38 @@ pub struct GPUMesh {
     pub uv_buffer: wgpu::Buffer,
     pub index_buffer: wgpu::Buffer,
     pub index_count: u32,
+    /// Uint16 when every index fits, halving index bandwidth.
+    pub index_format: wgpu::IndexFormat,
     // Optional skinning data (bone weights + bone indices per vertex)
     pub bone_weight_buffer: Option<wgpu::Buffer>,
     pub bone_index_buffer: Option<wgpu::Buffer>,
     pub has_skinning: bool,
 }
 
+impl GPUMesh {
+    /// Create an index buffer, narrowing to 16-bit indices when possible.
+    pub fn create_index_buffer(
+        device: &wgpu::Device,
+        indices: &[u32],
+    ) -> (wgpu::Buffer, wgpu::IndexFormat) {
+        use wgpu::util::DeviceExt;
+
+        let narrow: Vec<u16>;
+        let (contents, format): (&[u8], _) = if indices.iter().all(|&i| i <= u16::MAX as u32) {
+            narrow = indices.iter().map(|&i| i as u16).collect();
+            (bytemuck::cast_slice(&narrow), wgpu::IndexFormat::Uint16)
+        } else {
+            (bytemuck::cast_slice(indices), wgpu::IndexFormat::Uint32)
+        };
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Mesh Indices"),
+            contents,
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        (buffer, format)
+    }
+}
+
 /// GPU texture with associated view and sampler.
 pub struct GPUTexture {
     pub texture: wgpu::Texture,
@@ -47,7 +76,7 @@ pub struct GBuffer {
     /// RGB = emissive, A = AO
     pub emissive_ao: wgpu::Texture,
     pub emissive_ao_view: wgpu::TextureView,
-    /// R = clearcoat, G = subsurface, BA = reserved
+    /// R = clearcoat, G = subsurface
     pub advanced: wgpu::Texture,
     pub advanced_view: wgpu::TextureView,
     /// Depth buffer
@@ -66,6 +95,13 @@ pub struct CascadedShadowMap {
     pub resolution: u32,
 }
 
+/// Square depth atlas holding the shadow tiles of point and spot lights.
+pub struct ShadowAtlas {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub size: u32,
+}
+
 /// Post-processing pipeline state.
 pub struct PostProcessPipeline {
     pub bloom_extract_pipeline: wgpu::RenderPipeline,
@@ -113,6 +149,7 @@ pub struct DeferredPipeline {
     pub gbuffer_pipeline: wgpu::RenderPipeline,
     pub lighting_pipeline: wgpu::RenderPipeline,
     pub shadow_pipeline: wgpu::RenderPipeline,
+    pub shadow_skinned_pipeline: wgpu::RenderPipeline,
     pub forward_pipeline: wgpu::RenderPipeline,
     pub present_pipeline: wgpu::RenderPipeline,
     pub particle_pipeline: wgpu::RenderPipeline,
@@ -169,10 +206,13 @@ pub struct DeferredPipeline {
     pub ssao_noise_texture: wgpu::Texture,
     pub ssao_noise_view: wgpu::TextureView,
     pub fullscreen_quad_vbo: wgpu::Buffer,
+    /// Per-draw object uniforms for the shadow, G-buffer and forward passes.
+    pub objects: ObjectBuffer,
 
     // Bind group layouts
     pub lighting_bgl: wgpu::BindGroupLayout,
     pub light_data_bgl: wgpu::BindGroupLayout,
+    pub lighting_shadow_bgl: wgpu::BindGroupLayout,
     pub per_object_bgl: wgpu::BindGroupLayout,
     pub particle_bgl: wgpu::BindGroupLayout,
     pub ui_bgl: wgpu::BindGroupLayout,
@@ -221,6 +261,89 @@ pub struct DeferredPipeline {
     pub debug_lines_vbo: wgpu::Buffer,
     pub debug_lines_vbo_size: u64,
 
+    // Sky background
+    pub sky_pipeline: wgpu::RenderPipeline,
+    pub sky_bgl: wgpu::BindGroupLayout,
+    pub sky_uniform_buffer: wgpu::Buffer,
+
+    // Auto exposure
+    pub exposure_histogram_pipeline: wgpu::ComputePipeline,
+    pub exposure_average_pipeline: wgpu::ComputePipeline,
+    pub auto_exposure_bgl: wgpu::BindGroupLayout,
+    pub exposure_histogram_buffer: wgpu::Buffer,
+    pub exposure_texture: wgpu::Texture,
+    pub exposure_view: wgpu::TextureView,
+
+    // Colour grading (present pass)
+    pub color_grading_buffer: wgpu::Buffer,
+    /// Bound for empty LUT slots.
+    pub identity_lut: ColorLut,
+
+    // Picking
+    /// Entity-ID target and pipelines, while picking is enabled.
+    pub entity_ids: Option<EntityIds>,
+    /// Highlights selected entity IDs over the presented frame.
+    pub selection_pipeline: wgpu::RenderPipeline,
+    pub selection_bgl: wgpu::BindGroupLayout,
+
+    // Debug views (present pass)
+    pub debug_view_pipeline: wgpu::RenderPipeline,
+    pub debug_view_bgl: wgpu::BindGroupLayout,
+
     // TAA state
     pub taa_first_frame: bool,
 }
+
+/// Pipelines that draw scene geometry, and the entity-ID target they also
+/// write when picking is enabled.
+#[derive(Clone, Copy)]
+pub struct GeometryPipelines<'a> {
+    pub gbuffer: &'a wgpu::RenderPipeline,
+    pub gbuffer_skinned: &'a wgpu::RenderPipeline,
+    pub gbuffer_instanced: &'a wgpu::RenderPipeline,
+    pub terrain: &'a wgpu::RenderPipeline,
+    pub forward: &'a wgpu::RenderPipeline,
+    pub entity_ids: Option<&'a wgpu::TextureView>,
+}
+
+impl DeferredPipeline {
+    /// The geometry pipelines to draw with: the entity-ID variants while
+    /// picking is enabled.
+    pub fn geometry(&self) -> GeometryPipelines<'_> {
+        match &self.entity_ids {
+            Some(ids) => GeometryPipelines {
+                gbuffer: &ids.gbuffer_pipeline,
+                gbuffer_skinned: &ids.gbuffer_skinned_pipeline,
+                gbuffer_instanced: &ids.gbuffer_instanced_pipeline,
+                terrain: &ids.terrain_pipeline,
+                forward: &ids.forward_pipeline,
+                entity_ids: Some(&ids.target.color_view),
+            },
+            None => GeometryPipelines {
+                gbuffer: &self.gbuffer_pipeline,
+                gbuffer_skinned: &self.gbuffer_skinned_pipeline,
+                gbuffer_instanced: &self.gbuffer_instanced_pipeline,
+                terrain: &self.terrain_pipeline,
+                forward: &self.forward_pipeline,
+                entity_ids: None,
+            },
+        }
+    }
+
+    /// Write per-instance data (`pipeline::INSTANCE_STRIDE` bytes per
+    /// instance) to `instance_vbo`, growing it if needed.
+    pub fn write_instances(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[u8]) {
+        let required_size = data.len() as u64;
+        if required_size > self.instance_vbo_size {
+            let new_size = required_size.next_power_of_two();
+            self.instance_vbo = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Instance VBO (resized)"),
+                size: new_size,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            self.instance_vbo_size = new_size;
+        }
+        queue.write_buffer(&self.instance_vbo, 0, data);
+    }
+}
@@ -0,0 +1,849 @@
+//! Golden-image regression tests for `SceneRenderer`.
+//!
+//! Each test renders a canonical scene on a software (fallback) adapter into
+//! an offscreen target and compares it with `tests/golden/<name>.png`. Pixels
+//! are compared with a perceptual YIQ delta so that small rasterization
+//! differences between software adapters pass; on failure the rendered image
+//! and a diff image are written under the cargo target tmp dir.
+//!
+//! Run with `UPDATE_GOLDEN=1` to (re)record the references. Tests are skipped
+//! when the platform has no fallback adapter.
+
+use glam::{Mat3, Mat4, Vec3};
+use openreality_gpu_shared::cube_lut::CubeLut;
+use openreality_gpu_shared::hdr::HdrImage;
+use openreality_gpu_shared::uniforms::{DirLightData, MaterialUniforms, PerObjectUniforms, PointLightData, SpotLightData};
+use openreality_render::offscreen::{self, OffscreenTarget};
+use openreality_render::scene_renderer::{CameraParams, EntityRenderData, SceneLights, SceneRenderer};
+use openreality_render::settings::{AtmosphereSettings, AutoExposure, Background, DebugView, Exposure, ToneMapping};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+const WIDTH: u32 = 160;
+const HEIGHT: u32 = 120;
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// Per-pixel YIQ delta (0..1) above which a pixel counts as different.
+const PIXEL_THRESHOLD: f32 = 0.1;
+/// Fraction of differing pixels tolerated before a comparison fails.
+const MAX_MISMATCH_FRACTION: f32 = 0.005;
+
+// ---- GPU setup ----
+
+struct Gpu {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+}
+
+/// Shared fallback device, or `None` when the platform has no software adapter.
+fn gpu() -> Option<&'static Gpu> {
+    static GPU: OnceLock<Option<Gpu>> = OnceLock::new();
+    GPU.get_or_init(|| {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+        match pollster::block_on(offscreen::request_headless_device(&instance, true)) {
+            Ok((_adapter, device, queue)) => Some(Gpu { device, queue }),
+            Err(e) => {
+                eprintln!("skipping golden-image tests: {e}");
+                None
+            }
+        }
+    })
+    .as_ref()
+}
+
+// ---- Scene building ----
+
+struct Mesh {
+    positions: Vec<f32>,
+    normals: Vec<f32>,
+    uvs: Vec<f32>,
+    indices: Vec<u32>,
+}
+
+impl Mesh {
+    fn new() -> Self {
+        Self { positions: Vec::new(), normals: Vec::new(), uvs: Vec::new(), indices: Vec::new() }
+    }
+
+    fn vertex(&mut self, p: Vec3, n: Vec3, uv: [f32; 2]) {
+        self.positions.extend_from_slice(&p.to_array());
+        self.normals.extend_from_slice(&n.to_array());
+        self.uvs.extend_from_slice(&uv);
+    }
+
+    /// A unit quad centred at `normal * offset`, facing `normal`.
+    fn face(&mut self, normal: Vec3, u: Vec3, offset: f32) {
+        let v = normal.cross(u);
+        let centre = normal * offset;
+        let base = (self.positions.len() / 3) as u32;
+        self.vertex(centre - u - v, normal, [0.0, 1.0]);
+        self.vertex(centre + u - v, normal, [1.0, 1.0]);
+        self.vertex(centre + u + v, normal, [1.0, 0.0]);
+        self.vertex(centre - u + v, normal, [0.0, 0.0]);
+        self.indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+}
+
+/// Square in the XZ plane spanning [-1, 1], facing +Y.
+fn plane() -> Mesh {
+    let mut mesh = Mesh::new();
+    mesh.face(Vec3::Y, Vec3::X, 0.0);
+    mesh
+}
+
+/// Cube spanning [-1, 1] on every axis.
+fn cube() -> Mesh {
+    let mut mesh = Mesh::new();
+    for (normal, u) in [
+        (Vec3::X, Vec3::Y),
+        (Vec3::NEG_X, Vec3::Y),
+        (Vec3::Y, Vec3::Z),
+        (Vec3::NEG_Y, Vec3::Z),
+        (Vec3::Z, Vec3::X),
+        (Vec3::NEG_Z, Vec3::X),
+    ] {
+        mesh.face(normal, u, 1.0);
+    }
+    mesh
+}
+
+/// Unit-radius UV sphere.
+fn sphere(segments: u32, rings: u32) -> Mesh {
+    let mut mesh = Mesh::new();
+    for ring in 0..=rings {
+        let theta = ring as f32 / rings as f32 * std::f32::consts::PI;
+        for seg in 0..=segments {
+            let phi = seg as f32 / segments as f32 * std::f32::consts::TAU;
+            let n = Vec3::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin());
+            mesh.vertex(n, n, [seg as f32 / segments as f32, ring as f32 / rings as f32]);
+        }
+    }
+    let stride = segments + 1;
+    for ring in 0..rings {
+        for seg in 0..segments {
+            let a = ring * stride + seg;
+            let (b, c, d) = (a + stride, a + stride + 1, a + 1);
+            mesh.indices.extend_from_slice(&[a, c, b, a, d, c]);
+        }
+    }
+    mesh
+}
+
+fn material(albedo: [f32; 4], metallic: f32, roughness: f32) -> MaterialUniforms {
+    MaterialUniforms {
+        albedo,
+        metallic,
+        roughness,
+        ao: 1.0,
+        alpha_cutoff: 0.0,
+        emissive_factor: [0.0; 4],
+        clearcoat: 0.0,
+        clearcoat_roughness: 0.0,
+        subsurface: 0.0,
+        parallax_scale: 0.0,
+        has_albedo_map: 0,
+        has_normal_map: 0,
+        has_metallic_roughness_map: 0,
+        has_ao_map: 0,
+        has_emissive_map: 0,
+        has_height_map: 0,
+        lod_alpha_bits: 0x3f800000,
+        _pad2: 0,
+    }
+}
+
+fn entity(mesh_index: usize, model: Mat4, material: MaterialUniforms) -> EntityRenderData {
+    let normal = Mat3::from_mat4(model).inverse().transpose();
+    EntityRenderData {
+        mesh_index: Some(mesh_index),
+        material_index: None,
+        per_object: PerObjectUniforms {
+            model: model.to_cols_array_2d(),
+            normal_matrix_col0: normal.x_axis.extend(0.0).to_array(),
+            normal_matrix_col1: normal.y_axis.extend(0.0).to_array(),
+            normal_matrix_col2: normal.z_axis.extend(0.0).to_array(),
+            entity_id: 0,
+            _pad: [0; 3],
+        },
+        texture_indices: [-1; 7],
+        is_transparent: material.albedo[3] < 1.0,
+        material,
+        has_skinning: false,
+        skeleton_index: None,
+    }
+}
+
+fn camera(position: Vec3, target: Vec3) -> CameraParams {
+    let (near, far) = (0.1, 100.0);
+    CameraParams {
+        view: Mat4::look_at_rh(position, target, Vec3::Y),
+        projection: Mat4::perspective_rh(50f32.to_radians(), WIDTH as f32 / HEIGHT as f32, near, far),
+        position,
+        near,
+        far,
+    }
+}
+
+fn sun(direction: [f32; 3], intensity: f32) -> DirLightData {
+    DirLightData {
+        direction: [direction[0], direction[1], direction[2], 0.0],
+        color: [1.0, 0.98, 0.95, 1.0],
+        intensity,
+        _pad1: 0.0,
+        _pad2: 0.0,
+        _pad3: 0.0,
+    }
+}
+
+fn point_light(position: [f32; 3], color: [f32; 3], intensity: f32, range: f32) -> PointLightData {
+    PointLightData {
+        position: [position[0], position[1], position[2], 1.0],
+        color: [color[0], color[1], color[2], 1.0],
+        intensity,
+        range,
+        _pad1: 0.0,
+        _pad2: 0.0,
+    }
+}
+
+fn spot_light(position: [f32; 3], direction: [f32; 3], intensity: f32, range: f32, cone_degrees: [f32; 2]) -> SpotLightData {
+    SpotLightData {
+        position: [position[0], position[1], position[2], 1.0],
+        direction: [direction[0], direction[1], direction[2], 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
+        intensity,
+        range,
+        inner_cos: cone_degrees[0].to_radians().cos(),
+        outer_cos: cone_degrees[1].to_radians().cos(),
+    }
+}
+
+/// Render one frame of a scene built by `build` and read it back as RGBA8.
+fn render(
+    gpu: &Gpu,
+    build: impl FnOnce(&mut SceneRenderer, &wgpu::Device) -> (CameraParams, SceneLights, Vec<EntityRenderData>),
+) -> image::RgbaImage {
+    let target = OffscreenTarget::new(&gpu.device, WIDTH, HEIGHT, FORMAT);
+    let mut renderer = SceneRenderer::new(&gpu.device, &gpu.queue, WIDTH, HEIGHT, FORMAT).expect("create renderer");
+    renderer.create_csm(&gpu.device, 4, 1024);
+    let (camera, lights, entities) = build(&mut renderer, &gpu.device);
+    renderer.render_frame(&gpu.device, &gpu.queue, &target.view, &camera, &lights, &entities, 0.0);
+    let pixels = target.read_rgba8(&gpu.device, &gpu.queue).expect("read back frame");
+    image::RgbaImage::from_raw(WIDTH, HEIGHT, pixels).expect("frame size")
+}
+
+fn upload(renderer: &mut SceneRenderer, device: &wgpu::Device, mesh: &Mesh) -> usize {
+    renderer.upload_mesh(device, &mesh.positions, &mesh.normals, &mesh.uvs, &mesh.indices, None, None)
+}
+
+// ---- Comparison ----
+
+struct Comparison {
+    mismatched: usize,
+    diff: image::RgbaImage,
+}
+
+/// Perceptual colour difference of two sRGB pixels (the YIQ metric used by
+/// pixelmatch), normalised to 0..1.
+fn yiq_delta(a: &image::Rgba<u8>, b: &image::Rgba<u8>) -> f32 {
+    // Blend onto white so alpha differences count as colour differences.
+    let blend = |p: &image::Rgba<u8>, i: usize| 255.0 + (p[i] as f32 - 255.0) * p[3] as f32 / 255.0;
+    let (r1, g1, b1) = (blend(a, 0), blend(a, 1), blend(a, 2));
+    let (r2, g2, b2) = (blend(b, 0), blend(b, 1), blend(b, 2));
+    let y = (r1 - r2) * 0.2988953 + (g1 - g2) * 0.5866225 + (b1 - b2) * 0.1144822;
+    let i = (r1 - r2) * 0.595978 - (g1 - g2) * 0.2741761 - (b1 - b2) * 0.3218019;
+    let q = (r1 - r2) * 0.2114702 - (g1 - g2) * 0.5226171 + (b1 - b2) * 0.3111469;
+    (0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q) / 35215.0
+}
+
+/// Count pixels whose delta exceeds `PIXEL_THRESHOLD` and build a diff image:
+/// the expected image faded to grey, with mismatches in red.
+fn compare(actual: &image::RgbaImage, expected: &image::RgbaImage) -> Comparison {
+    assert_eq!(actual.dimensions(), expected.dimensions(), "image sizes differ");
+    let mut diff = image::RgbaImage::new(actual.width(), actual.height());
+    let mut mismatched = 0;
+    for ((a, e), d) in actual.pixels().zip(expected.pixels()).zip(diff.pixels_mut()) {
+        *d = if yiq_delta(a, e) > PIXEL_THRESHOLD * PIXEL_THRESHOLD {
+            mismatched += 1;
+            image::Rgba([255, 0, 0, 255])
+        } else {
+            let luma = (e[0] as u32 * 299 + e[1] as u32 * 587 + e[2] as u32 * 114) / 1000;
+            let faded = (255 - (255 - luma) / 4) as u8;
+            image::Rgba([faded, faded, faded, 255])
+        };
+    }
+    Comparison { mismatched, diff }
+}
+
+/// Compare against (or with `UPDATE_GOLDEN` set, record) `tests/golden/<name>.png`.
+fn assert_golden(name: &str, actual: &image::RgbaImage) {
+    let reference = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("{name}.png"));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(reference.parent().unwrap()).unwrap();
+        actual.save(&reference).unwrap();
+        return;
+    }
+
+    let expected = match image::open(&reference) {
+        Ok(img) => img.to_rgba8(),
+        Err(e) => panic!("missing reference {} ({e}); run with UPDATE_GOLDEN=1 to record it", reference.display()),
+    };
+    let result = compare(actual, &expected);
+    let allowed = (MAX_MISMATCH_FRACTION * (actual.width() * actual.height()) as f32) as usize;
+    if result.mismatched > allowed {
+        let out_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("golden");
+        std::fs::create_dir_all(&out_dir).unwrap();
+        let actual_path = out_dir.join(format!("{name}.actual.png"));
+        let diff_path = out_dir.join(format!("{name}.diff.png"));
+        actual.save(&actual_path).unwrap();
+        result.diff.save(&diff_path).unwrap();
+        panic!(
+            "{name}: {} pixels differ (allowed {allowed}); wrote {} and {}",
+            result.mismatched,
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+// ---- Scenes ----
+
+#[test]
+fn golden_lit_cube() {
+    let Some(gpu) = gpu() else { return };
+    let image = render(gpu, |renderer, device| {
+        let ground = upload(renderer, device, &plane());
+        let cube = upload(renderer, device, &cube());
+        let entities = vec![
+            entity(ground, Mat4::from_scale(Vec3::splat(6.0)), material([0.7, 0.7, 0.7, 1.0], 0.0, 0.9)),
+            entity(cube, Mat4::from_translation(Vec3::new(0.0, 1.0, 0.0)), material([0.8, 0.2, 0.15, 1.0], 0.0, 0.5)),
+        ];
+        let lights = SceneLights { dir_lights: vec![sun([-0.4, -1.0, -0.3], 3.0)], point_lights: vec![], spot_lights: vec![] };
+        (camera(Vec3::new(4.0, 4.0, 6.0), Vec3::new(0.0, 0.5, 0.0)), lights, entities)
+    });
+    assert_golden("lit_cube", &image);
+}
+
+#[test]
+fn golden_pbr_spheres() {
+    let Some(gpu) = gpu() else { return };
+    let image = render(gpu, |renderer, device| {
+        let sphere = upload(renderer, device, &sphere(32, 16));
+        let mut entities = Vec::new();
+        for (i, x) in [-3.0f32, -1.0, 1.0, 3.0].into_iter().enumerate() {
+            let roughness = 0.15 + 0.25 * i as f32;
+            let model = Mat4::from_translation(Vec3::new(x, 1.0, 0.0)) * Mat4::from_scale(Vec3::splat(0.9));
+            entities.push(entity(sphere, model, material([0.9, 0.6, 0.2, 1.0], 1.0, roughness)));
+            let model = Mat4::from_translation(Vec3::new(x, -1.0, 0.0)) * Mat4::from_scale(Vec3::splat(0.9));
+            entities.push(entity(sphere, model, material([0.2, 0.4, 0.9, 1.0], 0.0, roughness)));
+        }
+        let lights = SceneLights {
+            dir_lights: vec![sun([0.2, -0.5, -1.0], 1.0)],
+            point_lights: vec![
+                point_light([-3.0, 2.5, 3.0], [1.0, 0.9, 0.8], 20.0, 12.0),
+                point_light([3.0, -1.5, 3.0], [0.6, 0.8, 1.0], 20.0, 12.0),
+            ],
+            spot_lights: vec![],
+        };
+        (camera(Vec3::new(0.0, 0.0, 9.0), Vec3::ZERO), lights, entities)
+    });
+    assert_golden("pbr_spheres", &image);
+}
+
+#[test]
+fn golden_transparent_over_opaque() {
+    let Some(gpu) = gpu() else { return };
+    let image = render(gpu, |renderer, device| {
+        let cube = upload(renderer, device, &cube());
+        let quad = upload(renderer, device, &plane());
+        let glass = Mat4::from_translation(Vec3::new(0.5, 0.5, 2.0))
+            * Mat4::from_rotation_x(std::f32::consts::FRAC_PI_2)
+            * Mat4::from_scale(Vec3::splat(1.5));
+        let entities = vec![
+            entity(cube, Mat4::from_rotation_y(0.6), material([0.2, 0.7, 0.3, 1.0], 0.0, 0.6)),
+            entity(quad, glass, material([0.3, 0.5, 1.0, 0.4], 0.0, 0.1)),
+        ];
+        let lights = SceneLights { dir_lights: vec![sun([-0.3, -0.8, -0.5], 3.0)], point_lights: vec![], spot_lights: vec![] };
+        (camera(Vec3::new(0.0, 1.5, 7.0), Vec3::ZERO), lights, entities)
+    });
+    assert_golden("transparent_over_opaque", &image);
+}
+
+#[test]
+fn test_transparent_entities_keep_their_transforms() {
+    let Some(gpu) = gpu() else { return };
+    let eye = || camera(Vec3::new(0.0, 3.0, 7.0), Vec3::ZERO);
+    let scene = |with_glass: bool| render(gpu, |renderer, device| {
+        let ground = upload(renderer, device, &plane());
+        let cube = upload(renderer, device, &cube());
+        let mut entities = vec![entity(ground, Mat4::from_scale(Vec3::splat(6.0)), material([0.7, 0.7, 0.7, 1.0], 0.0, 0.9))];
+        if with_glass {
+            for x in [-1.2, 1.2] {
+                let model = Mat4::from_translation(Vec3::new(x, 0.5, 0.0)) * Mat4::from_scale(Vec3::splat(0.5));
+                entities.push(entity(cube, model, material([0.9, 0.2, 0.1, 0.6], 0.0, 0.3)));
+            }
+        }
+        let lights = SceneLights { dir_lights: vec![sun([-0.4, -1.0, -0.3], 3.0)], point_lights: vec![], spot_lights: vec![] };
+        (eye(), lights, entities)
+    });
+    let (image, empty) = (scene(true), scene(false));
+    for x in [-1.2, 1.2] {
+        let (px, py) = project(&eye(), Vec3::new(x, 0.5, 0.5));
+        assert_ne!(image.get_pixel(px, py), empty.get_pixel(px, py), "transparent cube at x = {x} is missing");
+    }
+}
+
+#[test]
+fn golden_instanced_grid() {
+    let Some(gpu) = gpu() else { return };
+    let image = render(gpu, |renderer, device| {
+        let ground = upload(renderer, device, &plane());
+        let cube = upload(renderer, device, &cube());
+        let mut entities = vec![entity(ground, Mat4::from_scale(Vec3::splat(8.0)), material([0.6, 0.6, 0.6, 1.0], 0.0, 0.9))];
+        for x in -2..=2 {
+            for z in -2..=2 {
+                let model = Mat4::from_translation(Vec3::new(x as f32 * 2.0, 0.5, z as f32 * 2.0))
+                    * Mat4::from_scale(Vec3::splat(0.5));
+                entities.push(entity(cube, model, material([0.9, 0.85, 0.3, 1.0], 0.0, 0.4)));
+            }
+        }
+        let lights = SceneLights { dir_lights: vec![sun([-0.7, -1.0, 0.6], 3.0)], point_lights: vec![], spot_lights: vec![] };
+        (camera(Vec3::new(6.0, 7.0, 9.0), Vec3::ZERO), lights, entities)
+    });
+    assert_golden("instanced_grid", &image);
+}
+
+#[test]
+fn golden_many_point_lights() {
+    let Some(gpu) = gpu() else { return };
+    let image = render(gpu, |renderer, device| {
+        let ground = upload(renderer, device, &plane());
+        let quad = upload(renderer, device, &plane());
+        let glass = Mat4::from_translation(Vec3::new(0.0, 1.0, 2.0))
+            * Mat4::from_rotation_x(std::f32::consts::FRAC_PI_2)
+            * Mat4::from_scale(Vec3::new(3.0, 1.0, 1.0));
+        let entities = vec![
+            entity(ground, Mat4::from_scale(Vec3::splat(10.0)), material([0.8, 0.8, 0.8, 1.0], 0.0, 0.8)),
+            entity(quad, glass, material([0.9, 0.9, 1.0, 0.3], 0.0, 0.2)),
+        ];
+        // 256 small lights, far beyond the 16 the uniform array holds.
+        let mut point_lights = Vec::new();
+        for i in 0..16 {
+            for j in 0..16 {
+                let color = [i as f32 / 15.0, 1.0 - j as f32 / 15.0, 0.5];
+                let position = [i as f32 * 1.2 - 9.0, 0.3, j as f32 * 1.2 - 9.0];
+                point_lights.push(point_light(position, color, 0.3, 1.0));
+            }
+        }
+        let lights = SceneLights { dir_lights: vec![], point_lights, spot_lights: vec![] };
+        (camera(Vec3::new(0.0, 8.0, 10.0), Vec3::ZERO), lights, entities)
+    });
+    assert_golden("many_point_lights", &image);
+}
+
+#[test]
+fn golden_local_light_shadows() {
+    let Some(gpu) = gpu() else { return };
+    let image = render(gpu, |renderer, device| {
+        renderer.create_shadow_atlas(device, 1024);
+        let ground = upload(renderer, device, &plane());
+        let cube = upload(renderer, device, &cube());
+        let entities = vec![
+            entity(ground, Mat4::from_scale(Vec3::splat(8.0)), material([0.8, 0.8, 0.8, 1.0], 0.0, 0.9)),
+            entity(cube, Mat4::from_translation(Vec3::new(-1.5, 0.5, 0.0)) * Mat4::from_scale(Vec3::splat(0.5)), material([0.8, 0.3, 0.2, 1.0], 0.0, 0.6)),
+            entity(cube, Mat4::from_translation(Vec3::new(2.0, 0.5, 0.0)) * Mat4::from_scale(Vec3::splat(0.5)), material([0.2, 0.4, 0.8, 1.0], 0.0, 0.6)),
+        ];
+        // A point light between the cubes and a spot light above the right one:
+        // the cubes cast shadows away from the point light, and the right cube
+        // also shadows the spot's pool of light.
+        let lights = SceneLights {
+            dir_lights: vec![],
+            point_lights: vec![point_light([0.0, 1.2, 0.5], [1.0, 0.85, 0.6], 6.0, 8.0)],
+            spot_lights: vec![spot_light([3.0, 4.0, 1.0], [-0.2, -1.0, -0.25], 25.0, 10.0, [20.0, 30.0])],
+        };
+        (camera(Vec3::new(0.0, 6.0, 8.0), Vec3::new(0.0, 0.0, 0.0)), lights, entities)
+    });
+    assert_golden("local_light_shadows", &image);
+}
+
+/// Equirectangular sky: a blue gradient above a dark ground, with a small
+/// bright sun so reflections show a highlight.
+fn sky_environment() -> HdrImage {
+    let (width, height) = (128, 64);
+    let mut texels = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            let up = 1.0 - 2.0 * (y as f32 + 0.5) / height as f32;
+            let mut rgb = if up > 0.0 { [0.2 + 0.2 * (1.0 - up), 0.3 + 0.15 * (1.0 - up), 0.6] } else { [0.08, 0.06, 0.05] };
+            let (sun_x, sun_y) = (x as i32 - 40, y as i32 - 14);
+            if sun_x * sun_x + sun_y * sun_y <= 4 {
+                rgb = [20.0, 18.0, 15.0];
+            }
+            texels.push(rgb);
+        }
+    }
+    HdrImage::from_linear(width, height, &texels).unwrap()
+}
+
+#[test]
+fn golden_ibl_spheres() {
+    let Some(gpu) = gpu() else { return };
+    let image = render(gpu, |renderer, device| {
+        renderer.create_ibl(device, &gpu.queue, &sky_environment()).expect("bake IBL");
+        let sphere = upload(renderer, device, &sphere(32, 16));
+        let mut entities = Vec::new();
+        for (i, x) in [-3.0f32, -1.0, 1.0, 3.0].into_iter().enumerate() {
+            let roughness = 0.1 + 0.3 * i as f32;
+            let model = Mat4::from_translation(Vec3::new(x, 1.0, 0.0)) * Mat4::from_scale(Vec3::splat(0.9));
+            entities.push(entity(sphere, model, material([1.0, 0.8, 0.5, 1.0], 1.0, roughness)));
+            let model = Mat4::from_translation(Vec3::new(x, -1.0, 0.0)) * Mat4::from_scale(Vec3::splat(0.9));
+            entities.push(entity(sphere, model, material([0.8, 0.2, 0.2, 1.0], 0.0, roughness)));
+        }
+        // No analytic lights: everything comes from the environment.
+        let lights = SceneLights { dir_lights: vec![], point_lights: vec![], spot_lights: vec![] };
+        (camera(Vec3::new(0.0, 0.0, 9.0), Vec3::ZERO), lights, entities)
+    });
+    assert_golden("ibl_spheres", &image);
+}
+
+// ---- Harness self-tests (no GPU) ----
+
+#[test]
+fn golden_environment_background() {
+    let Some(gpu) = gpu() else { return };
+    let image = render(gpu, |renderer, device| {
+        renderer.create_ibl(device, &gpu.queue, &sky_environment()).expect("bake IBL");
+        renderer.settings.background = Background::Environment;
+        let cube = upload(renderer, device, &cube());
+        let model = Mat4::from_rotation_y(0.6) * Mat4::from_scale(Vec3::splat(1.5));
+        let entities = vec![entity(cube, model, material([0.9, 0.9, 0.9, 1.0], 0.0, 0.6))];
+        let lights = SceneLights { dir_lights: vec![], point_lights: vec![], spot_lights: vec![] };
+        (camera(Vec3::new(0.0, 1.0, 6.0), Vec3::new(0.0, 0.5, 0.0)), lights, entities)
+    });
+    assert_golden("environment_background", &image);
+}
+
+#[test]
+fn golden_atmosphere_sky() {
+    let Some(gpu) = gpu() else { return };
+    let image = render(gpu, |renderer, device| {
+        // Low sun ahead and to the left, lighting the scene and (baked) its ambient.
+        let direction = [0.5, -0.3, 1.0];
+        let atmosphere = AtmosphereSettings::default();
+        renderer.settings.background = Background::Atmosphere(atmosphere);
+        // No bloom, so the sun disk stays a crisp feature of the image.
+        renderer.settings.bloom_enabled = false;
+        renderer.create_sky_ibl(device, &gpu.queue, &atmosphere, -Vec3::from(direction));
+        let ground = upload(renderer, device, &plane());
+        let sphere = upload(renderer, device, &sphere(32, 16));
+        let entities = vec![
+            entity(ground, Mat4::from_scale(Vec3::splat(6.0)), material([0.5, 0.5, 0.5, 1.0], 0.0, 0.9)),
+            entity(sphere, Mat4::from_translation(Vec3::new(-1.2, 1.0, 0.0)), material([0.9, 0.9, 0.9, 1.0], 1.0, 0.1)),
+            entity(sphere, Mat4::from_translation(Vec3::new(1.2, 1.0, 0.0)), material([0.2, 0.5, 0.2, 1.0], 0.0, 0.6)),
+        ];
+        let lights = SceneLights { dir_lights: vec![sun(direction, 3.0)], point_lights: vec![], spot_lights: vec![] };
+        (camera(Vec3::new(0.0, 1.5, 7.0), Vec3::new(0.0, 1.5, 0.0)), lights, entities)
+    });
+    assert_golden("atmosphere_sky", &image);
+}
+
+#[test]
+fn golden_auto_exposure_agx() {
+    let Some(gpu) = gpu() else { return };
+    let image = render(gpu, |renderer, device| {
+        // A dusk-dim scene that the metered exposure brings back up to mid grey.
+        renderer.settings.exposure = Exposure::Auto(AutoExposure::default());
+        renderer.settings.tone_mapping = ToneMapping::Agx;
+        renderer.settings.background = Background::Atmosphere(AtmosphereSettings { sun_intensity: 0.5, ..Default::default() });
+        let ground = upload(renderer, device, &plane());
+        let cube = upload(renderer, device, &cube());
+        let entities = vec![
+            entity(ground, Mat4::from_scale(Vec3::splat(6.0)), material([0.7, 0.7, 0.7, 1.0], 0.0, 0.9)),
+            entity(cube, Mat4::from_translation(Vec3::new(0.0, 1.0, 0.0)), material([0.8, 0.2, 0.15, 1.0], 0.0, 0.5)),
+        ];
+        let lights = SceneLights { dir_lights: vec![sun([-0.4, -1.0, -0.3], 0.1)], point_lights: vec![], spot_lights: vec![] };
+        (camera(Vec3::new(4.0, 4.0, 6.0), Vec3::new(0.0, 0.5, 0.0)), lights, entities)
+    });
+    assert_golden("auto_exposure_agx", &image);
+}
+
+/// A `.cube` file with `grade` applied to every lattice point.
+fn cube_file(size: u32, grade: impl Fn([f32; 3]) -> [f32; 3]) -> String {
+    let mut text = format!("TITLE \"golden\"\nLUT_3D_SIZE {size}\n");
+    for entry in CubeLut::identity(size).table {
+        let [r, g, b] = grade(entry);
+        text.push_str(&format!("{r:.6} {g:.6} {b:.6}\n"));
+    }
+    text
+}
+
+#[test]
+fn golden_color_lut_blend() {
+    let Some(gpu) = gpu() else { return };
+    let image = render(gpu, |renderer, device| {
+        // Halfway through a fade from a cool look to a sepia look.
+        let cool = cube_file(17, |[r, g, b]| [r * 0.8, g * 0.95, (b * 1.1 + 0.1).min(1.0)]);
+        let sepia = cube_file(17, |[r, g, b]| {
+            let luma = 0.299 * r + 0.587 * g + 0.114 * b;
+            [(luma * 1.1).min(1.0), luma * 0.9, luma * 0.65]
+        });
+        for (slot, text) in [cool, sepia].iter().enumerate() {
+            let lut = CubeLut::parse(text).expect("parse LUT");
+            renderer.set_color_lut(device, &gpu.queue, slot, Some(&lut)).expect("upload LUT");
+        }
+        renderer.settings.lut_blend = 0.5;
+        let ground = upload(renderer, device, &plane());
+        let cube = upload(renderer, device, &cube());
+        let entities = vec![
+            entity(ground, Mat4::from_scale(Vec3::splat(6.0)), material([0.7, 0.7, 0.7, 1.0], 0.0, 0.9)),
+            entity(cube, Mat4::from_translation(Vec3::new(0.0, 1.0, 0.0)), material([0.8, 0.2, 0.15, 1.0], 0.0, 0.5)),
+        ];
+        let lights = SceneLights { dir_lights: vec![sun([-0.4, -1.0, -0.3], 3.0)], point_lights: vec![], spot_lights: vec![] };
+        (camera(Vec3::new(4.0, 4.0, 6.0), Vec3::new(0.0, 0.5, 0.0)), lights, entities)
+    });
+    assert_golden("color_lut_blend", &image);
+}
+
+/// Pixel (top-left origin) that `point` projects to.
+fn project(camera: &CameraParams, point: Vec3) -> (u32, u32) {
+    let ndc = (camera.projection * camera.view).project_point3(point);
+    (((ndc.x * 0.5 + 0.5) * WIDTH as f32) as u32, ((0.5 - ndc.y * 0.5) * HEIGHT as f32) as u32)
+}
+
+#[test]
+fn test_pick_entity_ids() {
+    let Some(gpu) = gpu() else { return };
+    let (device, queue) = (&gpu.device, &gpu.queue);
+    let target = OffscreenTarget::new(device, WIDTH, HEIGHT, FORMAT);
+    let mut renderer = SceneRenderer::new(device, queue, WIDTH, HEIGHT, FORMAT).expect("create renderer");
+    let ground = upload(&mut renderer, device, &plane());
+    let cube = upload(&mut renderer, device, &cube());
+    let quad = upload(&mut renderer, device, &plane());
+    let glass = Mat4::from_translation(Vec3::new(0.0, 2.5, 1.0))
+        * Mat4::from_rotation_x(std::f32::consts::FRAC_PI_2)
+        * Mat4::from_scale(Vec3::splat(0.6));
+    // The two identical cubes are drawn as one instanced batch.
+    let mut entities = vec![
+        entity(ground, Mat4::from_scale(Vec3::splat(6.0)), material([0.7, 0.7, 0.7, 1.0], 0.0, 0.9)),
+        entity(cube, Mat4::from_translation(Vec3::new(-1.5, 0.5, 0.0)) * Mat4::from_scale(Vec3::splat(0.5)), material([0.8, 0.2, 0.15, 1.0], 0.0, 0.5)),
+        entity(cube, Mat4::from_translation(Vec3::new(1.5, 0.5, 0.0)) * Mat4::from_scale(Vec3::splat(0.5)), material([0.8, 0.2, 0.15, 1.0], 0.0, 0.5)),
+        entity(quad, glass, material([0.3, 0.5, 1.0, 0.4], 0.0, 0.1)),
+    ];
+    for (e, id) in entities.iter_mut().zip(1..) {
+        e.per_object.entity_id = id;
+    }
+    let camera = camera(Vec3::new(0.0, 3.0, 8.0), Vec3::ZERO);
+    let lights = SceneLights { dir_lights: vec![sun([-0.4, -1.0, -0.3], 3.0)], point_lights: vec![], spot_lights: vec![] };
+
+    let frame = |renderer: &mut SceneRenderer| {
+        renderer.render_frame(device, queue, &target.view, &camera, &lights, &entities, 0.0);
+        let pixels = target.read_rgba8(device, queue).expect("read back frame");
+        image::RgbaImage::from_raw(WIDTH, HEIGHT, pixels).expect("frame size")
+    };
+    let plain = frame(&mut renderer);
+    renderer.set_picking(device, true);
+    let picking = frame(&mut renderer);
+    assert_eq!(compare(&picking, &plain).mismatched, 0, "picking changed the image");
+
+    let pick = |renderer: &SceneRenderer, (x, y): (u32, u32)| {
+        let request = renderer.pick(device, queue, x, y);
+        device.poll(wgpu::Maintain::Wait);
+        pollster::block_on(request)
+    };
+    assert_eq!(pick(&renderer, project(&camera, Vec3::new(0.0, 0.0, 3.0))), Some(1));
+    assert_eq!(pick(&renderer, project(&camera, Vec3::new(-1.5, 0.5, 0.5))), Some(2));
+    assert_eq!(pick(&renderer, project(&camera, Vec3::new(1.5, 0.5, 0.5))), Some(3));
+    assert_eq!(pick(&renderer, project(&camera, Vec3::new(0.0, 2.5, 1.0))), Some(4));
+    assert_eq!(pick(&renderer, (0, 0)), None, "background");
+    assert_eq!(pick(&renderer, (WIDTH, 0)), None, "out of bounds");
+
+    renderer.set_picking(device, false);
+    assert_eq!(pick(&renderer, project(&camera, Vec3::new(0.0, 0.0, 3.0))), None, "picking disabled");
+}
+
+#[test]
+fn golden_selection_outline() {
+    let Some(gpu) = gpu() else { return };
+    let (device, queue) = (&gpu.device, &gpu.queue);
+    let target = OffscreenTarget::new(device, WIDTH, HEIGHT, FORMAT);
+    let mut renderer = SceneRenderer::new(device, queue, WIDTH, HEIGHT, FORMAT).expect("create renderer");
+    let ground = upload(&mut renderer, device, &plane());
+    let cube = upload(&mut renderer, device, &cube());
+    let mut entities = vec![
+        entity(ground, Mat4::from_scale(Vec3::splat(6.0)), material([0.7, 0.7, 0.7, 1.0], 0.0, 0.9)),
+        entity(cube, Mat4::from_translation(Vec3::new(-1.2, 0.5, 0.0)) * Mat4::from_scale(Vec3::splat(0.5)), material([0.2, 0.4, 0.8, 1.0], 0.0, 0.5)),
+        entity(cube, Mat4::from_translation(Vec3::new(1.2, 0.5, 0.0)) * Mat4::from_scale(Vec3::splat(0.5)), material([0.2, 0.4, 0.8, 1.0], 0.0, 0.5)),
+    ];
+    for (e, id) in entities.iter_mut().zip(1..) {
+        e.per_object.entity_id = id;
+    }
+    let camera = camera(Vec3::new(0.0, 3.0, 7.0), Vec3::ZERO);
+    let lights = SceneLights { dir_lights: vec![sun([-0.4, -1.0, -0.3], 3.0)], point_lights: vec![], spot_lights: vec![] };
+
+    let frame = |renderer: &mut SceneRenderer| {
+        renderer.render_frame(device, queue, &target.view, &camera, &lights, &entities, 0.0);
+        let pixels = target.read_rgba8(device, queue).expect("read back frame");
+        image::RgbaImage::from_raw(WIDTH, HEIGHT, pixels).expect("frame size")
+    };
+    let plain = frame(&mut renderer);
+
+    renderer.settings.selection.outline_width = 3.0;
+    renderer.set_selection(device, &[3]);
+    let selected = frame(&mut renderer);
+    assert_golden("selection_outline", &selected);
+    // Only the right cube and its outline change.
+    let changed = compare(&selected, &plain).mismatched;
+    assert!(changed > 0, "selection drew nothing");
+    let (x, y) = project(&camera, Vec3::new(-1.2, 0.5, 0.5));
+    assert_eq!(selected.get_pixel(x, y), plain.get_pixel(x, y), "left cube");
+
+    renderer.set_selection(device, &[]);
+    assert_eq!(compare(&frame(&mut renderer), &plain).mismatched, 0, "cleared selection");
+}
+
+#[test]
+fn golden_skinned_entities() {
+    let Some(gpu) = gpu() else { return };
+    let (device, queue) = (&gpu.device, &gpu.queue);
+    let target = OffscreenTarget::new(device, WIDTH, HEIGHT, FORMAT);
+    let mut renderer = SceneRenderer::new(device, queue, WIDTH, HEIGHT, FORMAT).expect("create renderer");
+    renderer.create_csm(device, 4, 1024);
+    let ground = upload(&mut renderer, device, &plane());
+    // Every vertex follows bone 0.
+    let mesh = cube();
+    let vertex_count = mesh.positions.len() / 3;
+    let weights: Vec<f32> = (0..vertex_count).flat_map(|_| [1.0, 0.0, 0.0, 0.0]).collect();
+    let bones = vec![0u16; vertex_count * 4];
+    let skinned = renderer.upload_mesh(device, &mesh.positions, &mesh.normals, &mesh.uvs, &mesh.indices, Some(&weights), Some(&bones));
+    renderer.update_skeleton(device, queue, 0, &[Mat4::from_translation(Vec3::new(0.0, 1.0, 0.0))]);
+
+    let skinned_entity = |model: Mat4, material: MaterialUniforms| EntityRenderData {
+        has_skinning: true,
+        skeleton_index: Some(0),
+        ..entity(skinned, model, material)
+    };
+    let mut entities = vec![
+        entity(ground, Mat4::from_scale(Vec3::splat(6.0)), material([0.7, 0.7, 0.7, 1.0], 0.0, 0.9)),
+        skinned_entity(Mat4::from_translation(Vec3::new(-1.2, 0.5, 0.0)) * Mat4::from_scale(Vec3::splat(0.5)), material([0.2, 0.4, 0.8, 1.0], 0.0, 0.5)),
+        skinned_entity(Mat4::from_translation(Vec3::new(1.2, 0.5, 0.0)) * Mat4::from_scale(Vec3::splat(0.5)), material([0.9, 0.2, 0.1, 0.5], 0.0, 0.3)),
+    ];
+    let camera = camera(Vec3::new(0.0, 3.0, 7.0), Vec3::ZERO);
+    let lights = SceneLights { dir_lights: vec![sun([-0.4, -1.0, -0.3], 3.0)], point_lights: vec![], spot_lights: vec![] };
+
+    let mut frame = |entities: &[EntityRenderData]| {
+        renderer.render_frame(device, queue, &target.view, &camera, &lights, entities, 0.0);
+        let pixels = target.read_rgba8(device, queue).expect("read back frame");
+        image::RgbaImage::from_raw(WIDTH, HEIGHT, pixels).expect("frame size")
+    };
+    let image = frame(&entities);
+    assert_golden("skinned_entities", &image);
+
+    entities.truncate(1);
+    let empty = frame(&entities);
+    // The opaque cube is lifted by its bone; the transparent one draws in bind pose.
+    for (label, point) in [("skinned opaque", Vec3::new(-1.2, 1.5, 0.5)), ("skinned transparent", Vec3::new(1.2, 0.5, 0.5))] {
+        let (x, y) = project(&camera, point);
+        assert_ne!(image.get_pixel(x, y), empty.get_pixel(x, y), "{label} is missing");
+    }
+}
+
+#[test]
+fn golden_debug_view_normals() {
+    let Some(gpu) = gpu() else { return };
+    let (device, queue) = (&gpu.device, &gpu.queue);
+    let target = OffscreenTarget::new(device, WIDTH, HEIGHT, FORMAT);
+    let mut renderer = SceneRenderer::new(device, queue, WIDTH, HEIGHT, FORMAT).expect("create renderer");
+    let ground = upload(&mut renderer, device, &plane());
+    let cube = upload(&mut renderer, device, &cube());
+    let entities = vec![
+        entity(ground, Mat4::from_scale(Vec3::splat(6.0)), material([0.7, 0.7, 0.7, 1.0], 0.0, 0.9)),
+        entity(cube, Mat4::from_translation(Vec3::new(0.0, 0.5, 0.0)) * Mat4::from_scale(Vec3::splat(0.5)), material([0.8, 0.3, 0.2, 1.0], 0.0, 0.5)),
+    ];
+    let camera = camera(Vec3::new(2.0, 3.0, 5.0), Vec3::ZERO);
+    let lights = SceneLights { dir_lights: vec![sun([-0.4, -1.0, -0.3], 3.0)], point_lights: vec![], spot_lights: vec![] };
+
+    let mut frame = |view: DebugView| {
+        renderer.settings.debug_view = view;
+        renderer.render_frame(device, queue, &target.view, &camera, &lights, &entities, 0.0);
+        let pixels = target.read_rgba8(device, queue).expect("read back frame");
+        image::RgbaImage::from_raw(WIDTH, HEIGHT, pixels).expect("frame size")
+    };
+    let normals = frame(DebugView::Normal);
+    assert_golden("debug_view_normals", &normals);
+
+    // Every view renders, and each G-Buffer view differs from the final image.
+    let plain = frame(DebugView::Final);
+    for name in DebugView::NAMES {
+        let view = DebugView::from_name(name).unwrap();
+        let image = frame(view);
+        if matches!(view, DebugView::Albedo | DebugView::Normal | DebugView::Depth) {
+            assert!(compare(&image, &plain).mismatched > 0, "{name}");
+        }
+    }
+    assert_eq!(compare(&frame(DebugView::Final), &plain).mismatched, 0, "back to final");
+}
+
+#[test]
+fn test_profiled_frame_reports_every_pass() {
+    let Some(gpu) = gpu() else { return };
+    let (device, queue) = (&gpu.device, &gpu.queue);
+    let target = OffscreenTarget::new(device, WIDTH, HEIGHT, FORMAT);
+    let mut renderer = SceneRenderer::new(device, queue, WIDTH, HEIGHT, FORMAT).expect("create renderer");
+    let cube = upload(&mut renderer, device, &cube());
+    let entities = vec![entity(cube, Mat4::IDENTITY, material([0.8, 0.3, 0.2, 1.0], 0.0, 0.5))];
+    let camera = camera(Vec3::new(2.0, 2.0, 4.0), Vec3::ZERO);
+    let lights = SceneLights { dir_lights: vec![sun([-0.4, -1.0, -0.3], 3.0)], point_lights: vec![], spot_lights: vec![] };
+
+    renderer.render_frame(device, queue, &target.view, &camera, &lights, &entities, 0.0);
+    assert!(renderer.frame_timings().is_none(), "profiling is off by default");
+
+    renderer.set_profiling(device, queue, true);
+    // GPU timestamps are read back asynchronously: render a few frames and wait.
+    for frame in 0..3 {
+        renderer.render_frame(device, queue, &target.view, &camera, &lights, &entities, frame as f32 / 60.0);
+    }
+    device.poll(wgpu::Maintain::Wait);
+    let profiler = renderer.profiler.as_mut().expect("profiler");
+    profiler.poll(device);
+    let timings = profiler.timings().expect("timing report");
+    assert_eq!(timings.source, profiler.source());
+
+    let names: Vec<_> = timings.passes.iter().map(|p| p.name).collect();
+    for pass in ["G-Buffer", "Deferred Lighting", "Bloom Composite", "Present"] {
+        assert!(names.contains(&pass), "{pass} missing from {names:?}");
+    }
+    assert!(timings.passes.iter().all(|p| p.ms.is_finite() && p.ms >= 0.0));
+    assert!(timings.total_ms() >= timings.passes[0].ms);
+}
+
+fn solid(rgba: [u8; 4]) -> image::RgbaImage {
+    image::RgbaImage::from_pixel(16, 16, image::Rgba(rgba))
+}
+
+#[test]
+fn test_compare_identical_images() {
+    let img = solid([40, 80, 120, 255]);
+    assert_eq!(compare(&img, &img).mismatched, 0);
+}
+
+#[test]
+fn test_compare_tolerates_small_differences() {
+    let result = compare(&solid([40, 80, 120, 255]), &solid([42, 79, 121, 255]));
+    assert_eq!(result.mismatched, 0);
+}
+
+#[test]
+fn test_compare_flags_changed_pixels() {
+    let expected = solid([40, 80, 120, 255]);
+    let mut actual = expected.clone();
+    for x in 0..4 {
+        actual.put_pixel(x, 0, image::Rgba([250, 20, 20, 255]));
+    }
+    let result = compare(&actual, &expected);
+    assert_eq!(result.mismatched, 4);
+    assert_eq!(result.diff.get_pixel(0, 0), &image::Rgba([255, 0, 0, 255]));
+    assert_ne!(result.diff.get_pixel(5, 5), &image::Rgba([255, 0, 0, 255]));
+}
@@ -3,9 +3,13 @@ use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use web_sys::HtmlCanvasElement;
 
+use openreality_render::picking::PickRequest;
+use openreality_render::profiler::{self, TimingSource};
+use openreality_render::settings::{DebugView, SelectionStyle};
 use openreality_render::scene_renderer::{SceneRenderer, CameraParams, SceneLights, EntityRenderData};
+use openreality_gpu_shared::cube_lut::CubeLut;
 use openreality_gpu_shared::uniforms::{MaterialUniforms, PerObjectUniforms, DirLightData, PointLightData};
-use crate::scene::LoadedScene;
+use crate::scene::{LoadedBundle, LoadedScene};
 use crate::input::{self, InputState};
 use crate::scripting::ScriptEngine;
 use crate::{animation, transform, skinning};
@@ -13,6 +17,8 @@ use crate::{animation, transform, skinning};
 /// Main application state for the WASM runtime.
 #[wasm_bindgen]
 pub struct App {
+    bundle: LoadedBundle,
+    active_scene: usize,
     scene: LoadedScene,
     input: Arc<Mutex<InputState>>,
     scripts: ScriptEngine,
@@ -24,11 +30,13 @@ pub struct App {
     last_time: f64,
     canvas: HtmlCanvasElement,
     total_time: f32,
+    /// In-flight pick requested by a script via `request_pick`.
+    script_pick: Option<PickRequest>,
 }
 
 #[wasm_bindgen]
 impl App {
-    /// Create a new App from canvas ID and ORSB scene data.
+    /// Create a new App from canvas ID and ORSB scene or bundle data.
     pub async fn new(canvas_id: &str, scene_data: &[u8]) -> Result<App, JsValue> {
         let window = web_sys::window().ok_or("No window")?;
         let document = window.document().ok_or("No document")?;
@@ -41,15 +49,20 @@ impl App {
         let width = canvas.width();
         let height = canvas.height();
 
-        // Parse ORSB scene
-        let scene = LoadedScene::from_orsb(scene_data)
+        // Parse ORSB scene or multi-scene bundle
+        let bundle = LoadedBundle::from_bytes(scene_data)
             .map_err(|e| JsValue::from_str(&format!("Failed to load scene: {e}")))?;
+        let active_scene = bundle.default_scene;
+        let scene = bundle.instantiate(active_scene)
+            .ok_or("Bundle default scene is missing")?;
 
         log::info!(
-            "Loaded scene: {} entities, {} meshes, {} textures, {} scripts",
+            "Loaded bundle: {} scenes, {} meshes, {} textures; scene '{}': {} entities, {} scripts",
+            bundle.scene_names().len(),
+            bundle.num_meshes(),
+            bundle.num_textures(),
+            bundle.scene_name(active_scene).unwrap_or_default(),
             scene.num_entities(),
-            scene.num_meshes(),
-            scene.num_textures(),
             scene.scripts.len(),
         );
 
@@ -76,9 +89,10 @@ impl App {
             .request_device(
                 &wgpu::DeviceDescriptor {
                     label: Some("OpenReality Device"),
-                    required_features: wgpu::Features::empty(),
-                    required_limits: wgpu::Limits::downlevel_webgl2_defaults()
-                        .using_resolution(adapter.limits()),
+                    required_features: profiler::supported_features(&adapter),
+                    // WebGPU guarantees the default limits; the renderer needs its
+                    // storage buffers and compute (WebGL2 limits have neither).
+                    required_limits: wgpu::Limits::default().using_resolution(adapter.limits()),
                     memory_hints: wgpu::MemoryHints::MemoryUsage,
                 },
                 None,
@@ -118,9 +132,11 @@ impl App {
         // Create renderer
         let mut renderer = SceneRenderer::new(&device, &queue, width, height, surface_format)
             .map_err(|e| JsValue::from_str(&format!("Failed to create renderer: {e}")))?;
+        renderer.create_csm(&device, 4, 2048);
+        renderer.create_shadow_atlas(&device, 2048);
 
-        // Upload meshes to GPU
-        for (i, mesh) in scene.meshes.iter().enumerate() {
+        // Upload the shared asset pool to GPU (once for all scenes)
+        for (i, mesh) in bundle.assets.meshes.iter().enumerate() {
             renderer.upload_mesh(
                 &device,
                 &mesh.positions,
@@ -135,12 +151,17 @@ impl App {
         }
 
         // Upload textures to GPU
-        for (i, tex) in scene.textures.iter().enumerate() {
+        for (i, tex) in bundle.assets.textures.iter().enumerate() {
             let is_png = tex.compression > 0;
             renderer.upload_texture(&device, &queue, tex.width, tex.height, tex.channels, &tex.data, is_png);
             log::info!("Uploaded texture {} ({}x{})", i, tex.width, tex.height);
         }
 
+        // Terrains belong to the scene; their layers reference pool textures
+        for terrain in &scene.terrains {
+            renderer.upload_terrain(&device, &queue, terrain);
+        }
+
         // Create script engine
         let scripts = ScriptEngine::new(
             &scene.scripts,
@@ -155,6 +176,8 @@ impl App {
         log::info!("OpenReality Web Runtime ready ({}x{}, {:?})", width, height, surface_format);
 
         Ok(App {
+            bundle,
+            active_scene,
             scene,
             input: input_state,
             scripts,
@@ -166,6 +189,7 @@ impl App {
             last_time: 0.0,
             canvas,
             total_time: 0.0,
+            script_pick: None,
         })
     }
 
@@ -195,16 +219,32 @@ impl App {
             snapshot
         };
 
+        // Hand a finished script pick back before scripts run
+        if let Some(result) = self.script_pick.as_mut().and_then(PickRequest::try_take) {
+            self.scripts.set_picked_entity(result.map(|id| id - 1));
+            self.script_pick = None;
+        }
+
         // Run on_start scripts (once)
         self.scripts.run_start(&mut self.scene);
 
         // Run on_update scripts
         self.scripts.run_update(&mut self.scene, &input_snapshot, dt);
 
+        // `transition(name)` naming another scene of the bundle switches level
+        if let Some(target) = self.scripts.pending_transition() {
+            if let Some(index) = self.bundle.scene_index(&target) {
+                self.switch_scene(index);
+            }
+        }
+
         // Update systems
         animation::update_animations(&mut self.scene, dt);
         transform::compute_world_transforms(&mut self.scene);
         skinning::update_skinned_meshes(&mut self.scene);
+        for (k, skeleton) in self.scene.skeletons.iter().enumerate() {
+            self.renderer.update_skeleton(&self.device, &self.queue, k, &skeleton.bone_matrices);
+        }
 
         // Render
         let surface_texture = match self.surface.get_current_texture() {
@@ -225,6 +265,16 @@ impl App {
         let lights = self.build_lights();
         let entities = self.build_entities();
 
+        if let Some(indices) = self.scripts.take_selection() {
+            self.select(&indices);
+        }
+
+        // A script pick needs this frame to write entity IDs
+        let pick_at = self.scripts.take_pick_request();
+        if pick_at.is_some() && !self.renderer.picking_enabled() {
+            self.renderer.set_picking(&self.device, true);
+        }
+
         self.renderer.render_frame(
             &self.device,
             &self.queue,
@@ -235,6 +285,18 @@ impl App {
             self.total_time,
         );
 
+        if let Some((x, y)) = pick_at {
+            // Scripts pick at mouse coordinates, which are CSS pixels
+            let scale = |pixels: u32, css: i32| if css > 0 { pixels as f32 / css as f32 } else { 1.0 };
+            let x = x * scale(self.canvas.width(), self.canvas.client_width());
+            let y = y * scale(self.canvas.height(), self.canvas.client_height());
+            if x >= 0.0 && y >= 0.0 {
+                self.script_pick = Some(self.renderer.pick(&self.device, &self.queue, x as u32, y as u32));
+            } else {
+                self.scripts.set_picked_entity(None);
+            }
+        }
+
         surface_texture.present();
     }
 
@@ -249,6 +311,122 @@ impl App {
         self.renderer.resize(&self.device, width, height);
     }
 
+    /// Switch the active scene to another scene of the loaded bundle.
+    pub fn load_scene(&mut self, name: &str) -> Result<(), JsValue> {
+        let index = self.bundle.scene_index(name)
+            .ok_or_else(|| JsValue::from_str(&format!("Unknown scene '{name}'")))?;
+        self.switch_scene(index);
+        Ok(())
+    }
+
+    /// Names of all scenes in the loaded bundle.
+    pub fn scene_names(&self) -> Vec<String> {
+        self.bundle.scene_names()
+    }
+
+    /// Name of the active scene.
+    pub fn active_scene(&self) -> String {
+        self.bundle.scene_name(self.active_scene).unwrap_or_default().to_string()
+    }
+
+    /// Load a `.cube` colour grading LUT (file contents) into slot 0 (A) or
+    /// 1 (B). An empty string clears the slot.
+    pub fn load_color_lut(&mut self, slot: usize, cube: &str) -> Result<(), JsValue> {
+        let lut = if cube.is_empty() { None } else { Some(CubeLut::parse(cube).map_err(|e| JsValue::from_str(&e))?) };
+        self.renderer.set_color_lut(&self.device, &self.queue, slot, lut.as_ref())
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Cross-fade from LUT A (0) to LUT B (1) at the given grading strength.
+    pub fn set_color_grading(&mut self, blend: f32, intensity: f32) {
+        self.renderer.settings.lut_blend = blend;
+        self.renderer.settings.lut_intensity = intensity;
+    }
+
+    /// Enable or disable entity-ID picking. Scripts calling `request_pick`
+    /// enable it on demand.
+    pub fn set_picking(&mut self, enabled: bool) {
+        self.renderer.set_picking(&self.device, enabled);
+    }
+
+    /// Read the entity under canvas pixel (`x`, `y`) in the last rendered
+    /// frame. Resolves to the entity index, or `null` for background or when
+    /// picking is disabled.
+    pub fn pick(&self, x: u32, y: u32) -> js_sys::Promise {
+        let request = self.renderer.pick(&self.device, &self.queue, x, y);
+        wasm_bindgen_futures::future_to_promise(async move {
+            Ok(match request.await {
+                Some(id) => JsValue::from(id - 1),
+                None => JsValue::NULL,
+            })
+        })
+    }
+
+    /// Highlight these entities (indices) with the selection outline and
+    /// tint. An empty array clears the selection.
+    pub fn set_selection(&mut self, entities: Vec<u32>) {
+        self.select(&entities);
+    }
+
+    /// Selection outline colour (RGBA) and width in pixels, and the tint
+    /// (RGBA, alpha = strength) blended over selected entities.
+    pub fn set_selection_style(&mut self, outline_color: &[f32], outline_width: f32, tint_color: &[f32]) -> Result<(), JsValue> {
+        let rgba = |c: &[f32]| <[f32; 4]>::try_from(c).map_err(|_| JsValue::from_str("Colours must have 4 components"));
+        self.renderer.settings.selection = SelectionStyle {
+            outline_color: rgba(outline_color)?,
+            outline_width,
+            tint_color: rgba(tint_color)?,
+        };
+        Ok(())
+    }
+
+    /// Show an intermediate target in place of the final image: "final",
+    /// "albedo", "normal", "depth", "ssao", "cascade0" etc.
+    pub fn set_debug_view(&mut self, name: &str) -> Result<(), JsValue> {
+        self.renderer.settings.debug_view = DebugView::from_name(name)
+            .ok_or_else(|| JsValue::from_str(&format!("Unknown debug view '{name}'")))?;
+        Ok(())
+    }
+
+    /// Time every render pass: on the GPU when the adapter has timestamp
+    /// queries inside encoders, otherwise as the CPU time spent recording
+    /// each pass (`frame_timings` reports which).
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.renderer.set_profiling(&self.device, &self.queue, enabled);
+    }
+
+    /// Pass timings of the latest profiled frame as
+    /// `{ source: "gpu" | "cpu", passes: [{ name, ms }] }`, or `null` when
+    /// profiling is off.
+    pub fn frame_timings(&self) -> Result<JsValue, JsValue> {
+        let Some(timings) = self.renderer.frame_timings() else { return Ok(JsValue::NULL) };
+        let passes = js_sys::Array::new();
+        for pass in &timings.passes {
+            let entry = js_sys::Object::new();
+            js_sys::Reflect::set(&entry, &"name".into(), &pass.name.into())?;
+            js_sys::Reflect::set(&entry, &"ms".into(), &pass.ms.into())?;
+            passes.push(&entry);
+        }
+        let source = match timings.source {
+            TimingSource::Gpu => "gpu",
+            TimingSource::Cpu => "cpu",
+        };
+        let report = js_sys::Object::new();
+        js_sys::Reflect::set(&report, &"source".into(), &source.into())?;
+        js_sys::Reflect::set(&report, &"passes".into(), &passes)?;
+        Ok(report.into())
+    }
+
+    /// Entities drawn for the camera in the last frame.
+    pub fn visible_entities(&self) -> u32 {
+        self.renderer.cull_stats.camera_visible
+    }
+
+    /// Entities frustum-culled from the camera in the last frame.
+    pub fn culled_entities(&self) -> u32 {
+        self.renderer.cull_stats.camera_culled()
+    }
+
     /// Get the canvas width.
     pub fn width(&self) -> u32 {
         self.canvas.width()
@@ -262,6 +440,45 @@ impl App {
 
 // Private helpers
 impl App {
+    /// Select entity indices; picking IDs are indices + 1.
+    fn select(&mut self, entities: &[u32]) {
+        let ids: Vec<u32> = entities.iter().map(|&i| i + 1).collect();
+        self.renderer.set_selection(&self.device, &ids);
+    }
+
+    /// Tear down the active scene and instantiate scene `index` from the bundle.
+    /// GPU assets are shared and stay resident; game state carries over.
+    fn switch_scene(&mut self, index: usize) {
+        let Some(scene) = self.bundle.instantiate(index) else {
+            return;
+        };
+
+        let outgoing: Vec<u32> = (0..self.scene.num_entities() as u32).collect();
+        self.scripts.run_destroy(&mut self.scene, &outgoing);
+
+        self.scripts = ScriptEngine::with_game_state(
+            &scene.scripts,
+            self.scripts.game_state(),
+            scene.num_entities(),
+        );
+        self.script_pick = None;
+        self.renderer.set_selection(&self.device, &[]);
+        self.renderer.clear_terrains();
+        self.renderer.clear_skeletons();
+        for terrain in &scene.terrains {
+            self.renderer.upload_terrain(&self.device, &self.queue, terrain);
+        }
+
+        self.scene = scene;
+        self.active_scene = index;
+
+        log::info!(
+            "Switched to scene '{}' ({} entities)",
+            self.bundle.scene_name(index).unwrap_or_default(),
+            self.scene.num_entities(),
+        );
+    }
+
     fn build_camera(&self) -> CameraParams {
         use glam::{Mat4, Vec3};
 
@@ -328,14 +545,15 @@ impl App {
             });
         }
 
-        SceneLights { dir_lights, point_lights }
+        // The scene format has no spot lights.
+        SceneLights { dir_lights, point_lights, spot_lights: Vec::new() }
     }
 
     fn build_entities(&self) -> Vec<EntityRenderData> {
         use bytemuck::Zeroable;
 
         let mut entities = Vec::new();
-        for entity in &self.scene.entities {
+        for (ei, entity) in self.scene.entities.iter().enumerate() {
             if let (Some(mesh_idx), Some(mat_idx)) = (entity.mesh_index, entity.material_index) {
                 let wt = entity.world_transform;
                 let normal_matrix = wt.inverse().transpose();
@@ -345,12 +563,14 @@ impl App {
                     normal_matrix_col0: [normal_matrix.x_axis.x, normal_matrix.x_axis.y, normal_matrix.x_axis.z, 0.0],
                     normal_matrix_col1: [normal_matrix.y_axis.x, normal_matrix.y_axis.y, normal_matrix.y_axis.z, 0.0],
                     normal_matrix_col2: [normal_matrix.z_axis.x, normal_matrix.z_axis.y, normal_matrix.z_axis.z, 0.0],
-                    _pad: [0.0; 4],
+                    // Picking IDs are entity indices + 1 (0 = nothing)
+                    entity_id: ei as u32 + 1,
+                    _pad: [0; 3],
                 };
 
                 // Build material uniforms from scene material
-                let mat = if mat_idx < self.scene.materials.len() {
-                    let m = &self.scene.materials[mat_idx];
+                let mat = if mat_idx < self.bundle.assets.materials.len() {
+                    let m = &self.bundle.assets.materials[mat_idx];
                     MaterialUniforms {
                         albedo: m.color,
                         metallic: m.metallic,
@@ -375,14 +595,16 @@ impl App {
                     MaterialUniforms::zeroed()
                 };
 
-                let texture_indices = if mat_idx < self.scene.materials.len() {
-                    self.scene.materials[mat_idx].texture_indices
+                let texture_indices = if mat_idx < self.bundle.assets.materials.len() {
+                    self.bundle.assets.materials[mat_idx].texture_indices
                 } else {
                     [-1; 7]
                 };
 
-                let is_transparent = if mat_idx < self.scene.materials.len() {
-                    self.scene.materials[mat_idx].opacity < 1.0
+                let skeleton_index = self.scene.skeletons.iter().position(|s| s.entity_index == ei);
+
+                let is_transparent = if mat_idx < self.bundle.assets.materials.len() {
+                    self.bundle.assets.materials[mat_idx].opacity < 1.0
                 } else {
                     false
                 };
@@ -394,7 +616,8 @@ impl App {
                     texture_indices,
                     material: mat,
                     is_transparent,
-                    has_skinning: false,
+                    has_skinning: skeleton_index.is_some(),
+                    skeleton_index,
                 });
             }
         }
@@ -26,7 +26,7 @@ pub fn start() {
     log::info!("OpenReality Web Runtime initialized");
 }
 
-/// Create a new application instance from an ORSB scene file.
+/// Create a new application instance from an ORSB scene file or multi-scene bundle.
 ///
 /// Called from JavaScript after fetching the .orsb binary data.
 #[cfg(target_arch = "wasm32")]
@@ -1,7 +1,8 @@
 use openreality_gpu_shared::scene_format::*;
+use openreality_gpu_shared::scene_bundle::{self, BundleScene};
 use glam::{DVec3, DQuat, Mat4};
 
-pub use openreality_gpu_shared::scene_format::{ScriptParsed, GameRefParsed};
+pub use openreality_gpu_shared::scene_format::{ScriptParsed, GameRefParsed, TerrainParsed};
 
 /// A loaded entity with component data.
 pub struct Entity {
@@ -114,46 +115,27 @@ pub struct Camera {
     pub aspect: f32,
 }
 
-/// Complete loaded scene.
-pub struct LoadedScene {
-    pub entities: Vec<Entity>,
+/// Asset pool shared by every scene of a bundle (uploaded to the GPU once).
+pub struct AssetPool {
     pub meshes: Vec<MeshData>,
     pub materials: Vec<MaterialInfo>,
     pub textures: Vec<TextureData>,
-    pub animations: Vec<AnimationState>,
-    pub skeletons: Vec<SkeletonData>,
-    pub point_lights: Vec<PointLight>,
-    pub dir_lights: Vec<DirLight>,
-    pub cameras: Vec<Camera>,
-    pub physics_config: Option<PhysicsConfigData>,
-    pub scripts: Vec<ScriptParsed>,
-    pub game_refs: Vec<GameRefParsed>,
 }
 
-impl LoadedScene {
-    /// Parse an ORSB binary file into a LoadedScene.
-    pub fn from_orsb(data: &[u8]) -> Result<Self, String> {
-        let parsed = parse_orsb(data)?;
+/// A loaded bundle — the shared asset pool plus the scenes that can be
+/// instantiated from it. Single-scene .orsb files load as a one-scene bundle.
+pub struct LoadedBundle {
+    pub assets: AssetPool,
+    scenes: Vec<BundleScene>,
+    pub default_scene: usize,
+}
 
-        // Build entities
-        let num_entities = parsed.entity_ids.len();
-        let mut entities = Vec::with_capacity(num_entities);
-        for i in 0..num_entities {
-            let t = &parsed.transforms[i];
-            entities.push(Entity {
-                id: parsed.entity_ids[i],
-                parent_index: parsed.parent_indices[i],
-                transform: TransformState {
-                    position: DVec3::new(t.position[0], t.position[1], t.position[2]),
-                    rotation: DQuat::from_xyzw(t.rotation[1], t.rotation[2], t.rotation[3], t.rotation[0]),
-                    scale: DVec3::new(t.scale[0], t.scale[1], t.scale[2]),
-                    dirty: true,
-                },
-                world_transform: Mat4::IDENTITY,
-                mesh_index: parsed.mesh_indices[i],
-                material_index: parsed.material_indices[i],
-                mask: parsed.component_masks[i],
-            });
+impl LoadedBundle {
+    /// Parse a multi-scene bundle or a plain ORSB file.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, String> {
+        let parsed = scene_bundle::parse_bundle(data)?;
+        if parsed.scenes.is_empty() {
+            return Err("Bundle contains no scenes".to_string());
         }
 
         // Build meshes
@@ -196,6 +178,82 @@ impl LoadedScene {
             data: t.data,
         }).collect();
 
+        Ok(LoadedBundle {
+            assets: AssetPool { meshes, materials, textures },
+            scenes: parsed.scenes,
+            default_scene: parsed.default_scene,
+        })
+    }
+
+    /// Names of all scenes in the bundle.
+    pub fn scene_names(&self) -> Vec<String> {
+        self.scenes.iter().map(|s| s.name.clone()).collect()
+    }
+
+    /// Name of scene `index`.
+    pub fn scene_name(&self, index: usize) -> Option<&str> {
+        self.scenes.get(index).map(|s| s.name.as_str())
+    }
+
+    /// Look up a scene by name.
+    pub fn scene_index(&self, name: &str) -> Option<usize> {
+        self.scenes.iter().position(|s| s.name == name)
+    }
+
+    /// Build a fresh runtime scene from scene `index`. Mesh and material
+    /// indices of the result refer to `self.assets`.
+    pub fn instantiate(&self, index: usize) -> Option<LoadedScene> {
+        self.scenes.get(index).map(|s| LoadedScene::from_parsed(s.scene.clone()))
+    }
+
+    pub fn num_meshes(&self) -> usize {
+        self.assets.meshes.len()
+    }
+
+    pub fn num_textures(&self) -> usize {
+        self.assets.textures.len()
+    }
+}
+
+/// Complete loaded scene.
+pub struct LoadedScene {
+    pub entities: Vec<Entity>,
+    pub animations: Vec<AnimationState>,
+    pub skeletons: Vec<SkeletonData>,
+    pub point_lights: Vec<PointLight>,
+    pub dir_lights: Vec<DirLight>,
+    pub cameras: Vec<Camera>,
+    pub physics_config: Option<PhysicsConfigData>,
+    pub scripts: Vec<ScriptParsed>,
+    pub game_refs: Vec<GameRefParsed>,
+    pub terrains: Vec<TerrainParsed>,
+}
+
+impl LoadedScene {
+    /// Build runtime scene state from a parsed ORSB scene. Assets are not
+    /// copied; mesh and material indices refer to the owning bundle's pool.
+    pub fn from_parsed(parsed: ParsedScene) -> Self {
+        // Build entities
+        let num_entities = parsed.entity_ids.len();
+        let mut entities = Vec::with_capacity(num_entities);
+        for i in 0..num_entities {
+            let t = &parsed.transforms[i];
+            entities.push(Entity {
+                id: parsed.entity_ids[i],
+                parent_index: parsed.parent_indices[i],
+                transform: TransformState {
+                    position: DVec3::new(t.position[0], t.position[1], t.position[2]),
+                    rotation: DQuat::from_xyzw(t.rotation[1], t.rotation[2], t.rotation[3], t.rotation[0]),
+                    scale: DVec3::new(t.scale[0], t.scale[1], t.scale[2]),
+                    dirty: true,
+                },
+                world_transform: Mat4::IDENTITY,
+                mesh_index: parsed.mesh_indices[i],
+                material_index: parsed.material_indices[i],
+                mask: parsed.component_masks[i],
+            });
+        }
+
         // Build lights
         let point_lights = parsed.point_lights.into_iter().map(|l| PointLight {
             position: l.position,
@@ -238,14 +296,17 @@ impl LoadedScene {
             speed: a.speed,
         }).collect();
 
-        Ok(LoadedScene {
+        // Skeletons are written in entity order, one per skinned entity.
+        let skinned_entities: Vec<usize> = entities.iter().enumerate()
+            .filter(|(_, e)| e.mask.has(ComponentMask::SKELETON))
+            .map(|(i, _)| i)
+            .collect();
+
+        LoadedScene {
             entities,
-            meshes,
-            materials,
-            textures,
             animations,
             skeletons: parsed.skeletons.into_iter().enumerate().map(|(i, s)| SkeletonData {
-                entity_index: i,
+                entity_index: skinned_entities.get(i).copied().unwrap_or(usize::MAX),
                 bone_entity_indices: s.bones.iter().map(|b| b.entity_index as usize).collect(),
                 inverse_bind_matrices: s.bones.iter().map(|b| {
                     Mat4::from_cols_array_2d(&b.inverse_bind_matrix)
@@ -258,18 +319,11 @@ impl LoadedScene {
             physics_config: parsed.physics_config,
             scripts: parsed.scripts,
             game_refs: parsed.game_refs,
-        })
+            terrains: parsed.terrains,
+        }
     }
 
     pub fn num_entities(&self) -> usize {
         self.entities.len()
     }
-
-    pub fn num_meshes(&self) -> usize {
-        self.meshes.len()
-    }
-
-    pub fn num_textures(&self) -> usize {
-        self.textures.len()
-    }
 }
@@ -50,6 +50,12 @@ pub struct SceneBridge {
     pub despawn_queue: Vec<u32>,
     /// Active FSM state transition request.
     pub pending_transition: Option<String>,
+    /// Pixel position of the latest `request_pick`, taken by the app after render.
+    pub pick_request: Option<(f32, f32)>,
+    /// Entity index from the last completed pick, or -1 for none.
+    pub picked_entity: i64,
+    /// Entity indices passed to `set_selection`, taken by the app.
+    pub selection: Option<Vec<u32>>,
     /// Number of entities.
     pub num_entities: usize,
 }
@@ -66,6 +72,9 @@ impl SceneBridge {
             spawn_queue: Vec::new(),
             despawn_queue: Vec::new(),
             pending_transition: None,
+            pick_request: None,
+            picked_entity: -1,
+            selection: None,
             num_entities,
         }
     }
@@ -134,6 +143,8 @@ type SharedUi = Arc<Mutex<UiCommandBuffer>>;
 #[derive(Clone)]
 pub struct InputSnapshot {
     pub keys_down: [bool; 256],
+    pub mouse_x: f64,
+    pub mouse_y: f64,
     pub mouse_dx: f64,
     pub mouse_dy: f64,
     pub mouse_buttons: [bool; 3],
@@ -143,6 +154,8 @@ impl InputSnapshot {
     pub fn from_input(input: &InputState) -> Self {
         Self {
             keys_down: input.keys_down,
+            mouse_x: input.mouse_x,
+            mouse_y: input.mouse_y,
             mouse_dx: input.mouse_dx,
             mouse_dy: input.mouse_dy,
             mouse_buttons: input.mouse_buttons,
@@ -170,10 +183,22 @@ impl ScriptEngine {
         game_refs: &[openreality_gpu_shared::scene_format::GameRefParsed],
         num_entities: usize,
     ) -> Self {
-        let bridge: SharedBridge = Arc::new(Mutex::new(SceneBridge::new(num_entities)));
         let game_state: SharedGameState = Arc::new(Mutex::new(GameState::from_refs(game_refs)));
+        Self::with_game_state(scripts, game_state, num_entities)
+    }
+
+    /// Create a ScriptEngine that shares an existing game state, so values set
+    /// by one scene's scripts survive a scene switch.
+    pub fn with_game_state(
+        scripts: &[ScriptParsed],
+        game_state: Arc<Mutex<GameState>>,
+        num_entities: usize,
+    ) -> Self {
+        let bridge: SharedBridge = Arc::new(Mutex::new(SceneBridge::new(num_entities)));
         let input: SharedInput = Arc::new(Mutex::new(InputSnapshot {
             keys_down: [false; 256],
+            mouse_x: 0.0,
+            mouse_y: 0.0,
             mouse_dx: 0.0,
             mouse_dy: 0.0,
             mouse_buttons: [false; 3],
@@ -205,6 +230,33 @@ impl ScriptEngine {
             });
         }
 
+        // Register picking at CSS-pixel coordinates (as from input_mouse_position);
+        // the result arrives a frame or more later
+        {
+            let b = bridge.clone();
+            engine.register_fn("request_pick", move |x: f32, y: f32| {
+                b.lock().unwrap().pick_request = Some((x, y));
+            });
+        }
+        {
+            let b = bridge.clone();
+            engine.register_fn("picked_entity", move || -> i64 {
+                b.lock().unwrap().picked_entity
+            });
+        }
+
+        // Register selection highlight: set_selection([eid, ...])
+        {
+            let b = bridge.clone();
+            engine.register_fn("set_selection", move |entities: Array| {
+                let indices = entities.iter()
+                    .filter_map(|e| e.as_int().ok())
+                    .filter_map(|e| u32::try_from(e).ok())
+                    .collect();
+                b.lock().unwrap().selection = Some(indices);
+            });
+        }
+
         // Compile scripts
         let mut on_start_scripts = Vec::new();
         let mut on_update_scripts = Vec::new();
@@ -330,6 +382,21 @@ impl ScriptEngine {
         self.bridge.lock().unwrap().pending_transition.clone()
     }
 
+    /// Take the pixel position a script asked to pick, if any.
+    pub fn take_pick_request(&self) -> Option<(f32, f32)> {
+        self.bridge.lock().unwrap().pick_request.take()
+    }
+
+    /// Store a completed pick for `picked_entity()`.
+    pub fn set_picked_entity(&self, entity_index: Option<u32>) {
+        self.bridge.lock().unwrap().picked_entity = entity_index.map_or(-1, i64::from);
+    }
+
+    /// Take the entity indices a script selected, if it called `set_selection`.
+    pub fn take_selection(&self) -> Option<Vec<u32>> {
+        self.bridge.lock().unwrap().selection.take()
+    }
+
     /// Get the UI command buffer for rendering.
     pub fn ui_commands(&self) -> Arc<Mutex<UiCommandBuffer>> {
         self.ui.clone()
@@ -539,6 +606,18 @@ impl ScriptEngine {
             });
         }
 
+        // input_mouse_position() -> map {x, y}
+        {
+            let inp = input.clone();
+            engine.register_fn("input_mouse_position", move || -> Map {
+                let snap = inp.lock().unwrap();
+                let mut m = Map::new();
+                m.insert("x".into(), Dynamic::from(snap.mouse_x as f32));
+                m.insert("y".into(), Dynamic::from(snap.mouse_y as f32));
+                m
+            });
+        }
+
         // input_mouse_delta() -> map {x, y}
         {
             let inp = input.clone();
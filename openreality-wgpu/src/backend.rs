@@ -2,6 +2,91 @@
 pub use openreality_render::types::*;
 pub use openreality_render::handle::HandleStore;
 pub use openreality_render::render_targets;
+use bytemuck::Zeroable;
+use std::cell::RefCell;
+use openreality_gpu_shared::uniforms::{LocalLightData, PerFrameUniforms, PointLightData};
+use openreality_gpu_shared::{clusters, math};
+use openreality_render::color_lut::ColorLut;
+use openreality_render::ibl::IBLEnvironment;
+use openreality_render::light_clusters::LightClusterBuffers;
+use openreality_render::offscreen::{self, OffscreenTarget};
+use openreality_render::picking::PickRequest;
+use openreality_render::profiler::{self, FrameProfiler};
+use openreality_render::settings::{DebugView, SelectionStyle};
+
+/// Color format of the headless backend's offscreen target.
+pub const OFFSCREEN_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// Where finished frames go: a window swapchain, or an owned texture when
+/// the backend was created headless.
+pub enum OutputTarget {
+    Surface {
+        surface: wgpu::Surface<'static>,
+        config: wgpu::SurfaceConfiguration,
+    },
+    Offscreen(OffscreenTarget),
+}
+
+/// One frame's color target. `present()` hands swapchain images back to the
+/// window; offscreen frames stay in the target for readback.
+pub struct Frame {
+    output: Option<wgpu::SurfaceTexture>,
+    pub view: wgpu::TextureView,
+}
+
+impl Frame {
+    pub fn present(self) {
+        if let Some(output) = self.output {
+            output.present();
+        }
+    }
+}
+
+impl OutputTarget {
+    pub fn format(&self) -> wgpu::TextureFormat {
+        match self {
+            Self::Surface { config, .. } => config.format,
+            Self::Offscreen(target) => target.format,
+        }
+    }
+
+    /// Acquire the color target for the next frame.
+    pub fn acquire(&self) -> Result<Frame, String> {
+        match self {
+            Self::Surface { surface, .. } => {
+                let output = surface
+                    .get_current_texture()
+                    .map_err(|e| format!("Surface texture error: {e}"))?;
+                let view = output
+                    .texture
+                    .create_view(&wgpu::TextureViewDescriptor::default());
+                Ok(Frame {
+                    output: Some(output),
+                    view,
+                })
+            }
+            Self::Offscreen(target) => Ok(Frame {
+                output: None,
+                view: target
+                    .texture
+                    .create_view(&wgpu::TextureViewDescriptor::default()),
+            }),
+        }
+    }
+
+    fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        match self {
+            Self::Surface { surface, config } => {
+                config.width = width;
+                config.height = height;
+                surface.configure(device, config);
+            }
+            Self::Offscreen(target) => {
+                *target = OffscreenTarget::new(device, width, height, target.format);
+            }
+        }
+    }
+}
 
 /// Main backend state — owns all wgpu resources.
 pub struct WGPUBackendState {
@@ -9,8 +94,7 @@ pub struct WGPUBackendState {
     pub adapter: wgpu::Adapter,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
-    pub surface: wgpu::Surface<'static>,
-    pub surface_config: wgpu::SurfaceConfiguration,
+    pub target: OutputTarget,
     pub width: u32,
     pub height: u32,
 
@@ -33,14 +117,42 @@ pub struct WGPUBackendState {
     // Shared GPU resources
     pub per_frame_buffer: wgpu::Buffer,
     pub per_frame_bind_group_layout: wgpu::BindGroupLayout,
-    pub per_object_buffer: wgpu::Buffer,
     pub material_bind_group_layout: wgpu::BindGroupLayout,
     pub light_buffer: wgpu::Buffer,
     pub default_sampler: wgpu::Sampler,
 
+    // Clustered point lights, rebinned when the camera or lights change
+    pub light_clusters: LightClusterBuffers,
+    /// Point lights from the last light upload.
+    pub point_lights: Vec<PointLightData>,
+    /// Camera from the last `or_wgpu_begin_frame`.
+    pub camera: PerFrameUniforms,
+    pub light_clusters_dirty: bool,
+
+    // Image-based lighting; the lighting pass binds `fallback_ibl` until an
+    // environment is loaded
+    pub ibl: Option<IBLEnvironment>,
+    pub fallback_ibl: IBLEnvironment,
+
+    // Colour grading LUTs in slots A and B, applied in the present pass
+    pub color_luts: [Option<ColorLut>; 2],
+    pub lut_blend: f32,
+    pub lut_intensity: f32,
+
     // Deferred rendering pipeline (created on demand)
     pub deferred: Option<DeferredPipeline>,
 
+    // In-flight entity-ID readback (see `or_wgpu_request_pick`)
+    pub pick: Option<PickRequest>,
+    // Entity IDs highlighted in the present pass
+    pub selection: Vec<u32>,
+    pub selection_style: SelectionStyle,
+    // Target shown by the present pass instead of the final image
+    pub debug_view: DebugView,
+    // Per-pass timing from `or_wgpu_begin_frame` to `or_wgpu_present`. In a
+    // RefCell because graph submissions borrow the whole state.
+    pub profiler: RefCell<Option<FrameProfiler>>,
+
     // Error state
     pub last_error: Option<String>,
 }
@@ -52,8 +164,6 @@ impl WGPUBackendState {
         width: u32,
         height: u32,
     ) -> Result<Self, String> {
-        use openreality_gpu_shared::uniforms::*;
-
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
             backends: wgpu::Backends::all(),
             ..Default::default()
@@ -73,7 +183,7 @@ impl WGPUBackendState {
         let (device, queue) = pollster::block_on(adapter.request_device(
             &wgpu::DeviceDescriptor {
                 label: Some("OpenReality WebGPU Device"),
-                required_features: wgpu::Features::empty(),
+                required_features: profiler::supported_features(&adapter),
                 required_limits: wgpu::Limits::default(),
                 memory_hints: wgpu::MemoryHints::default(),
             },
@@ -101,6 +211,42 @@ impl WGPUBackendState {
         };
         surface.configure(&device, &surface_config);
 
+        let target = OutputTarget::Surface {
+            surface,
+            config: surface_config,
+        };
+        Self::with_device(instance, adapter, device, queue, target, width, height)
+    }
+
+    /// Create a backend without a window. Frames render into an offscreen
+    /// `OFFSCREEN_FORMAT` texture that `read_pixels()` copies back to the CPU.
+    /// `force_fallback_adapter` requests a software adapter.
+    pub fn new_headless(width: u32, height: u32, force_fallback_adapter: bool) -> Result<Self, String> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+        let (adapter, device, queue) = pollster::block_on(offscreen::request_headless_device(
+            &instance,
+            force_fallback_adapter,
+        ))?;
+
+        let target = OutputTarget::Offscreen(OffscreenTarget::new(&device, width, height, OFFSCREEN_FORMAT));
+        Self::with_device(instance, adapter, device, queue, target, width, height)
+    }
+
+    /// Shared setup once a device and output target exist.
+    fn with_device(
+        instance: wgpu::Instance,
+        adapter: wgpu::Adapter,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        target: OutputTarget,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, String> {
+        use openreality_gpu_shared::uniforms::*;
+
         // Create per-frame uniform buffer
         let per_frame_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Per-Frame Uniforms"),
@@ -124,14 +270,6 @@ impl WGPUBackendState {
                 }],
             });
 
-        // Per-object uniform buffer
-        let per_object_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Per-Object Uniforms"),
-            size: std::mem::size_of::<PerObjectUniforms>() as u64,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
         // Material bind group layout
         let material_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -227,6 +365,10 @@ impl WGPUBackendState {
             mapped_at_creation: false,
         });
 
+        // Clustered point light buffers
+        let light_clusters = LightClusterBuffers::new(&device);
+        let fallback_ibl = IBLEnvironment::placeholder(&device);
+
         // Default sampler
         let default_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             label: Some("Default Sampler"),
@@ -250,8 +392,7 @@ impl WGPUBackendState {
             adapter,
             device,
             queue,
-            surface,
-            surface_config,
+            target,
             width,
             height,
             meshes: HandleStore::new(),
@@ -266,36 +407,76 @@ impl WGPUBackendState {
             post_process: None,
             per_frame_buffer,
             per_frame_bind_group_layout,
-            per_object_buffer,
             material_bind_group_layout,
             light_buffer,
             default_sampler,
+            light_clusters,
+            point_lights: Vec::new(),
+            camera: PerFrameUniforms::zeroed(),
+            light_clusters_dirty: false,
+            ibl: None,
+            fallback_ibl,
+            color_luts: [None, None],
+            lut_blend: 0.0,
+            lut_intensity: 1.0,
             deferred: None,
+            pick: None,
+            selection: Vec::new(),
+            selection_style: SelectionStyle::default(),
+            debug_view: DebugView::Final,
+            profiler: RefCell::new(None),
             last_error: None,
         })
     }
 
-    /// Resize the surface and recreate dependent resources.
+    /// Resize the surface (or offscreen target) and recreate dependent resources.
     pub fn resize(&mut self, width: u32, height: u32) {
         if width > 0 && height > 0 {
             self.width = width;
             self.height = height;
-            self.surface_config.width = width;
-            self.surface_config.height = height;
-            self.surface.configure(&self.device, &self.surface_config);
+            self.target.resize(&self.device, width, height);
+            self.light_clusters_dirty = true;
+        }
+    }
+
+    /// Rebin point lights into view clusters if the camera, lights or size
+    /// changed since the last lighting or forward pass. Nothing is binned
+    /// until a frame with a perspective camera has begun. Local light shadows
+    /// aren't rendered by this backend, so no light has a shadow tile.
+    pub fn update_light_clusters(&mut self) {
+        if !self.light_clusters_dirty {
+            return;
+        }
+        let view = glam::Mat4::from_cols_array_2d(&self.camera.view);
+        let projection = glam::Mat4::from_cols_array_2d(&self.camera.projection);
+        let (z_near, z_far) = math::perspective_near_far(&projection);
+        if !(z_near > 0.0 && z_far > z_near && z_far.is_finite()) {
+            return;
         }
+        let screen_size = [self.width as f32, self.height as f32];
+        let lights: Vec<LocalLightData> = self.point_lights.iter().map(LocalLightData::from).collect();
+        let clusters = clusters::build_light_clusters(&view, &projection, z_near, z_far, screen_size, &lights);
+        self.light_clusters.upload(&self.device, &self.queue, &lights, &clusters, &[]);
+        self.light_clusters_dirty = false;
+    }
+
+    /// Read the headless target back as RGBA8 rows, top row first.
+    pub fn read_pixels(&self) -> Result<Vec<u8>, String> {
+        match &self.target {
+            OutputTarget::Offscreen(target) => target.read_rgba8(&self.device, &self.queue),
+            OutputTarget::Surface { .. } => Err("Pixel readback requires a headless backend".into()),
+        }
+    }
+
+    /// Read the HDR lighting target back as linear f32 RGBA rows.
+    pub fn read_hdr_pixels(&self) -> Result<Vec<f32>, String> {
+        let dp = self.deferred.as_ref().ok_or("Deferred pipeline not created")?;
+        offscreen::read_texture_f32(&self.device, &self.queue, &dp.lighting_target.color_texture)
     }
 
     /// Render a frame that just clears to a color (bootstrap pass).
     pub fn render_clear(&mut self, r: f64, g: f64, b: f64) -> Result<(), String> {
-        let output = self
-            .surface
-            .get_current_texture()
-            .map_err(|e| format!("Surface texture error: {e}"))?;
-
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        let output = self.target.acquire()?;
 
         let mut encoder = self
             .device
@@ -307,7 +488,7 @@ impl WGPUBackendState {
             let _render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Clear Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
+                    view: &output.view,
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(wgpu::Color {
@@ -364,13 +545,7 @@ impl WGPUBackendState {
                 usage: wgpu::BufferUsages::VERTEX,
             });
 
-        let index_buffer = self
-            .device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Index Buffer"),
-                contents: bytemuck::cast_slice(indices),
-                usage: wgpu::BufferUsages::INDEX,
-            });
+        let (index_buffer, index_format) = GPUMesh::create_index_buffer(&self.device, indices);
 
         let mesh = GPUMesh {
             vertex_buffer,
@@ -378,6 +553,7 @@ impl WGPUBackendState {
             uv_buffer,
             index_buffer,
             index_count: indices.len() as u32,
+            index_format,
             bone_weight_buffer: None,
             bone_index_buffer: None,
             has_skinning: false,
@@ -485,32 +661,23 @@ impl WGPUBackendState {
     /// Create the full deferred rendering pipeline (all pipelines and targets).
     pub fn create_deferred_pipeline(&mut self) -> Result<(), String> {
         use crate::pipeline;
+        use openreality_render::object_buffer::ObjectBuffer;
         use openreality_gpu_shared::uniforms::*;
 
         let device = &self.device;
         let queue = &self.queue;
         let w = self.width;
         let h = self.height;
-        let surface_format = self.surface_config.format;
-
-        // Per-object bind group layout
-        let per_object_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Per-Object BGL"),
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }],
-        });
+        let surface_format = self.target.format();
+
+        // Per-object bind group layout (dynamic offset into `objects`)
+        let per_object_bgl = pipeline::create_per_object_bgl(device);
+        let objects = ObjectBuffer::new(device, &per_object_bgl);
 
         // Create all bind group layouts
         let lighting_bgl = pipeline::create_lighting_bind_group_layout(device);
         let light_data_bgl = pipeline::create_light_data_bind_group_layout(device);
+        let lighting_shadow_bgl = pipeline::create_lighting_shadow_bgl(device);
         let particle_bgl = pipeline::create_particle_bgl(device);
         let ui_bgl = pipeline::create_ui_bgl(device);
         let terrain_bgl = pipeline::create_terrain_bgl(device);
@@ -525,7 +692,7 @@ impl WGPUBackendState {
         let taa_bgl = pipeline::create_taa_bind_group_layout(device);
         let bloom_extract_bgl = pipeline::create_effect_bind_group_layout(device, "Bloom Extract BGL", 1, false);
         let bloom_blur_bgl = pipeline::create_effect_bind_group_layout(device, "Bloom Blur BGL", 1, false);
-        let bloom_composite_bgl = pipeline::create_effect_bind_group_layout(device, "Bloom Composite BGL", 2, false);
+        let bloom_composite_bgl = pipeline::create_bloom_composite_bgl(device);
         let fxaa_bgl = pipeline::create_fxaa_bind_group_layout(device);
 
         // Create render pipelines (with logging to diagnose driver crashes)
@@ -535,6 +702,7 @@ impl WGPUBackendState {
             &self.per_frame_bind_group_layout,
             &self.material_bind_group_layout,
             &per_object_bgl,
+            false,
         );
 
         log::info!("Creating shadow pipeline...");
@@ -543,12 +711,19 @@ impl WGPUBackendState {
             &self.per_frame_bind_group_layout,
             &per_object_bgl,
         );
+        let shadow_skinned_pipeline = pipeline::create_shadow_skinned_pipeline(
+            device,
+            &self.per_frame_bind_group_layout,
+            &per_object_bgl,
+            &bone_bgl,
+        );
 
         log::info!("Creating lighting pipeline...");
         let lighting_pipeline = pipeline::create_lighting_pipeline(
             device,
             &lighting_bgl,
             &light_data_bgl,
+            &lighting_shadow_bgl,
         );
 
         log::info!("Creating forward pipeline...");
@@ -558,6 +733,7 @@ impl WGPUBackendState {
             &self.material_bind_group_layout,
             &per_object_bgl,
             &forward_light_shadow_bgl,
+            false,
         );
 
         log::info!("Creating present pipeline...");
@@ -566,13 +742,17 @@ impl WGPUBackendState {
             &present_bgl,
             surface_format,
         );
+        let selection_bgl = pipeline::create_selection_bgl(device);
+        let selection_pipeline = pipeline::create_selection_pipeline(device, &selection_bgl, surface_format);
+        let debug_view_bgl = pipeline::create_debug_view_bgl(device);
+        let debug_view_pipeline = pipeline::create_debug_view_pipeline(device, &debug_view_bgl, surface_format);
 
         log::info!("Creating particle pipeline...");
         let particle_pipeline = pipeline::create_particle_pipeline(device, &particle_bgl, surface_format);
         log::info!("Creating UI pipeline...");
         let ui_pipeline = pipeline::create_ui_pipeline(device, &ui_bgl, surface_format);
         log::info!("Creating terrain pipeline...");
-        let terrain_pipeline = pipeline::create_terrain_pipeline(device, &self.per_frame_bind_group_layout, &terrain_bgl);
+        let terrain_pipeline = pipeline::create_terrain_pipeline(device, &self.per_frame_bind_group_layout, &terrain_bgl, false);
 
         log::info!("Creating skinned G-Buffer pipeline...");
         let gbuffer_skinned_pipeline = pipeline::create_gbuffer_skinned_pipeline(
@@ -581,6 +761,7 @@ impl WGPUBackendState {
             &self.material_bind_group_layout,
             &per_object_bgl,
             &bone_bgl,
+            false,
         );
 
         log::info!("Creating instanced G-Buffer pipeline...");
@@ -588,6 +769,7 @@ impl WGPUBackendState {
             device,
             &self.per_frame_bind_group_layout,
             &self.material_bind_group_layout,
+            false,
         );
 
         // Effect pipelines
@@ -785,7 +967,7 @@ impl WGPUBackendState {
             mapped_at_creation: false,
         });
 
-        let initial_instance_vbo_size = 256 * pipeline::INSTANCE_STRIDE; // 256 instances * 112 bytes
+        let initial_instance_vbo_size = 256 * pipeline::INSTANCE_STRIDE; // 256 instances * 128 bytes
         let instance_vbo = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Instance VBO"),
             size: initial_instance_vbo_size,
@@ -796,7 +978,7 @@ impl WGPUBackendState {
         // Debug lines
         let debug_lines_bgl = pipeline::create_debug_lines_bgl(device);
         let debug_lines_pipeline = pipeline::create_debug_lines_pipeline(
-            device, &debug_lines_bgl, self.surface_config.format,
+            device, &debug_lines_bgl, self.target.format(),
         );
         let debug_lines_uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Debug Lines Uniform"),
@@ -812,10 +994,37 @@ impl WGPUBackendState {
             mapped_at_creation: false,
         });
 
+        // Sky background
+        let sky_bgl = pipeline::create_sky_bgl(device);
+        let sky_pipeline = pipeline::create_sky_pipeline(device, &sky_bgl);
+        let sky_uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Sky Uniforms"),
+            size: std::mem::size_of::<SkyUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Auto exposure
+        let auto_exposure_bgl = pipeline::create_auto_exposure_bgl(device);
+        let exposure_histogram_pipeline = pipeline::create_auto_exposure_pipeline(device, &auto_exposure_bgl, "cs_histogram");
+        let exposure_average_pipeline = pipeline::create_auto_exposure_pipeline(device, &auto_exposure_bgl, "cs_average");
+        let exposure_histogram_buffer = render_targets::create_exposure_histogram_buffer(device);
+        let (exposure_texture, exposure_view) = render_targets::create_exposure_target(device);
+
+        // Colour grading
+        let color_grading_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Color Grading Uniforms"),
+            size: std::mem::size_of::<ColorGradingUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let identity_lut = ColorLut::identity(device, queue);
+
         self.deferred = Some(DeferredPipeline {
             gbuffer_pipeline,
             lighting_pipeline,
             shadow_pipeline,
+            shadow_skinned_pipeline,
             forward_pipeline,
             present_pipeline,
             particle_pipeline,
@@ -860,8 +1069,10 @@ impl WGPUBackendState {
             ssao_noise_texture,
             ssao_noise_view,
             fullscreen_quad_vbo,
+            objects,
             lighting_bgl,
             light_data_bgl,
+            lighting_shadow_bgl,
             per_object_bgl,
             particle_bgl,
             ui_bgl,
@@ -899,6 +1110,22 @@ impl WGPUBackendState {
             debug_lines_uniform_buffer,
             debug_lines_vbo,
             debug_lines_vbo_size: initial_debug_lines_vbo_size,
+            sky_pipeline,
+            sky_bgl,
+            sky_uniform_buffer,
+            exposure_histogram_pipeline,
+            exposure_average_pipeline,
+            auto_exposure_bgl,
+            exposure_histogram_buffer,
+            exposure_texture,
+            exposure_view,
+            color_grading_buffer,
+            identity_lut,
+            entity_ids: None,
+            selection_pipeline,
+            selection_bgl,
+            debug_view_pipeline,
+            debug_view_bgl,
             taa_first_frame: true,
         });
 
@@ -920,6 +1147,9 @@ impl WGPUBackendState {
             dp.pp_target_a = render_targets::create_hdr_target(device, width, height, "PP Target A", false);
             dp.pp_target_b = render_targets::create_hdr_target(device, width, height, "PP Target B", false);
             dp.taa_first_frame = true;
+            if let Some(ids) = &mut dp.entity_ids {
+                ids.resize(device, width, height);
+            }
 
             log::info!("Deferred pipeline resized to {}x{}", width, height);
         }
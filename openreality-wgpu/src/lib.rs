@@ -12,11 +12,27 @@ pub use openreality_render::render_targets;
 pub use openreality_render::pipeline;
 pub use openreality_render::passes;
 pub use openreality_render::ibl;
+pub use openreality_render::color_lut;
+pub use openreality_render::graph;
+pub use openreality_render::picking;
+pub use openreality_render::profiler;
 
 use backend::WGPUBackendState;
 use bytemuck::Zeroable;
+use graph::nodes::{self, DeferredTargets, LightingInputs, NodeContext};
+use graph::{RenderGraph, ResourceId, TransientPool};
+use profiler::{FrameProfiler, TimingSource};
+use openreality_gpu_shared::cube_lut::CubeLut;
+use openreality_gpu_shared::hdr::HdrImage;
+use openreality_gpu_shared::uniforms::{
+    DOFCoCParams, LightUniforms, MotionBlurParams, PerFrameUniforms, PointLightData,
+    PostProcessParams, SSAOParams, SSRParams, TAAParams, VelocityParams,
+};
+use openreality_render::settings::{DebugView, SelectionStyle};
+use openreality_render::types::DeferredPipeline;
 use handle::HandleStore;
-use std::ffi::CString;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
 use std::sync::Mutex;
 
@@ -146,6 +162,27 @@ pub extern "C" fn or_wgpu_initialize(
     }
 }
 
+/// Initialize the backend without a window. Frames render into an offscreen
+/// RGBA8 target read back with `or_wgpu_read_pixels`. Pass a nonzero
+/// `force_fallback_adapter` to request a software adapter (CI, screenshot tools).
+///
+/// Returns a backend handle (> 0) on success, 0 on failure.
+#[no_mangle]
+pub extern "C" fn or_wgpu_initialize_headless(width: i32, height: i32, force_fallback_adapter: i32) -> u64 {
+    let _ = env_logger::try_init();
+
+    match WGPUBackendState::new_headless(width as u32, height as u32, force_fallback_adapter != 0) {
+        Ok(state) => {
+            let mut backends = BACKENDS.lock().unwrap();
+            backends.insert(state)
+        }
+        Err(e) => {
+            log::error!("WebGPU headless initialization failed: {e}");
+            0
+        }
+    }
+}
+
 /// Shutdown the backend and release all GPU resources.
 #[no_mangle]
 pub extern "C" fn or_wgpu_shutdown(backend: u64) {
@@ -341,6 +378,257 @@ pub extern "C" fn or_wgpu_destroy_texture(backend: u64, texture: u64) {
     }
 }
 
+// ============================================================
+// FFI: Framebuffer readback
+// ============================================================
+
+/// Copy the presented frame of a headless backend into `out` as RGBA8 rows,
+/// top row first. `len` is the size of `out` in bytes and must be at least
+/// width * height * 4. Returns 0 on success, -1 on failure.
+#[no_mangle]
+pub extern "C" fn or_wgpu_read_pixels(backend: u64, out: *mut u8, len: u64) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        match state.read_pixels() {
+            Ok(pixels) => copy_readback(state, &pixels, out, len),
+            Err(e) => { state.last_error = Some(e); -1 }
+        }
+    } else {
+        -1
+    }
+}
+
+/// Copy the HDR lighting target (linear radiance before post-processing) into
+/// `out` as f32 RGBA rows. `len` is the size of `out` in floats and must be at
+/// least width * height * 4. Returns 0 on success, -1 on failure.
+#[no_mangle]
+pub extern "C" fn or_wgpu_read_hdr_pixels(backend: u64, out: *mut f32, len: u64) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        match state.read_hdr_pixels() {
+            Ok(pixels) => copy_readback(state, &pixels, out, len),
+            Err(e) => { state.last_error = Some(e); -1 }
+        }
+    } else {
+        -1
+    }
+}
+
+/// Copy readback data into a caller-owned buffer of `len` elements.
+fn copy_readback<T: Copy>(state: &mut WGPUBackendState, data: &[T], out: *mut T, len: u64) -> i32 {
+    if out.is_null() || (len as usize) < data.len() {
+        state.last_error = Some(format!("Readback buffer too small: need {} elements, got {len}", data.len()));
+        return -1;
+    }
+    let out = unsafe { std::slice::from_raw_parts_mut(out, data.len()) };
+    out.copy_from_slice(data);
+    0
+}
+
+// ============================================================
+// FFI: Picking
+// ============================================================
+
+/// Enable or disable writing entity IDs (the `entity_id` of each
+/// EntityDrawData) during the G-Buffer and forward passes. Requires the
+/// deferred pipeline. Returns 0 on success, -1 on failure.
+#[no_mangle]
+pub extern "C" fn or_wgpu_set_picking(backend: u64, enabled: i32) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let Some(dp) = state.deferred.as_mut() else {
+            state.last_error = Some("Deferred pipeline not created".into());
+            return -1;
+        };
+        if enabled == 0 {
+            dp.entity_ids = None;
+            state.pick = None;
+        } else if dp.entity_ids.is_none() {
+            let ids = picking::EntityIds::new(&state.device, dp, &state.per_frame_bind_group_layout, &state.material_bind_group_layout);
+            dp.entity_ids = Some(ids);
+        }
+        0
+    } else {
+        -1
+    }
+}
+
+/// Start reading the entity ID at pixel (`x`, `y`) of the last rendered
+/// frame, top-left origin, replacing any pending pick. Poll the result with
+/// `or_wgpu_pick_result`. Returns 0 on success, -1 on failure.
+#[no_mangle]
+pub extern "C" fn or_wgpu_request_pick(backend: u64, x: i32, y: i32) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let Some(ids) = state.deferred.as_ref().and_then(|dp| dp.entity_ids.as_ref()) else {
+            state.last_error = Some("Picking not enabled".into());
+            return -1;
+        };
+        state.pick = Some(if x < 0 || y < 0 {
+            picking::PickRequest::resolved(None)
+        } else {
+            ids.pick(&state.device, &state.queue, x as u32, y as u32)
+        });
+        0
+    } else {
+        -1
+    }
+}
+
+/// Result of the last `or_wgpu_request_pick`: the entity ID, 0 for no
+/// entity, -2 while the readback is in flight, or -1 without a request.
+/// A finished result is returned once.
+#[no_mangle]
+pub extern "C" fn or_wgpu_pick_result(backend: u64) -> i64 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let Some(pick) = state.pick.as_mut() else { return -1 };
+        state.device.poll(wgpu::Maintain::Poll);
+        match pick.try_take() {
+            Some(id) => {
+                state.pick = None;
+                id.map_or(0, i64::from)
+            }
+            None => -2,
+        }
+    } else {
+        -1
+    }
+}
+
+/// Highlight the entities with these IDs in the present pass, enabling
+/// picking (which provides the IDs). `count` 0 clears the selection.
+/// Returns 0 on success, -1 on failure.
+///
+/// # Safety
+///
+/// `ids_ptr` must be null or point to `count` readable u32 IDs.
+#[no_mangle]
+pub unsafe extern "C" fn or_wgpu_set_selection(backend: u64, ids_ptr: *const u32, count: u32) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        if count == 0 || ids_ptr.is_null() {
+            state.selection.clear();
+            return 0;
+        }
+        let Some(dp) = state.deferred.as_mut() else {
+            state.last_error = Some("Deferred pipeline not created".into());
+            return -1;
+        };
+        if dp.entity_ids.is_none() {
+            let ids = picking::EntityIds::new(&state.device, dp, &state.per_frame_bind_group_layout, &state.material_bind_group_layout);
+            dp.entity_ids = Some(ids);
+        }
+        state.selection = unsafe { std::slice::from_raw_parts(ids_ptr, count as usize) }.to_vec();
+        0
+    } else {
+        -1
+    }
+}
+
+/// Set the selection outline colour (RGBA) and width in pixels, and the tint
+/// (RGBA, alpha = strength) blended over selected entities.
+#[no_mangle]
+pub extern "C" fn or_wgpu_set_selection_style(backend: u64, outline_color: *const f32, outline_width: f32, tint_color: *const f32) {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let rgba = |ptr: *const f32| -> [f32; 4] { unsafe { std::slice::from_raw_parts(ptr, 4) }.try_into().unwrap() };
+        state.selection_style = SelectionStyle { outline_color: rgba(outline_color), outline_width, tint_color: rgba(tint_color) };
+    }
+}
+
+/// Show an intermediate target in place of the final image: "final",
+/// "albedo", "normal", "depth", "ssao", "cascade0" etc. (see `DebugView`).
+/// Returns -1 for unknown names.
+///
+/// # Safety
+///
+/// `name` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn or_wgpu_set_debug_view(backend: u64, name: *const c_char) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let name = unsafe { CStr::from_ptr(name) }.to_string_lossy();
+        match DebugView::from_name(&name) {
+            Some(view) => { state.debug_view = view; 0 }
+            None => { state.last_error = Some(format!("Unknown debug view '{name}'")); -1 }
+        }
+    } else {
+        -1
+    }
+}
+
+// ============================================================
+// FFI: Profiling
+// ============================================================
+
+/// Enable or disable per-pass timing of the frames between
+/// `or_wgpu_begin_frame` and `or_wgpu_present`. Returns 1 when passes are
+/// timed with GPU timestamps, 0 when on the CPU (or when disabling), -1 on
+/// failure.
+#[no_mangle]
+pub extern "C" fn or_wgpu_set_profiling(backend: u64, enabled: i32) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let profiler = state.profiler.get_mut();
+        if enabled == 0 {
+            *profiler = None;
+        } else if profiler.is_none() {
+            *profiler = Some(FrameProfiler::new(&state.device, &state.queue));
+        }
+        match profiler {
+            Some(profiler) if profiler.source() == TimingSource::Gpu => 1,
+            _ => 0,
+        }
+    } else {
+        -1
+    }
+}
+
+/// Number of passes in the latest frame timing report, 0 when there is none
+/// yet. GPU timings arrive a frame or two after the frame they describe.
+#[no_mangle]
+pub extern "C" fn or_wgpu_frame_timing_count(backend: u64) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let Some(profiler) = state.profiler.get_mut() else { return 0 };
+        profiler.poll(&state.device);
+        profiler.timings().map_or(0, |t| t.passes.len() as i32)
+    } else {
+        -1
+    }
+}
+
+/// Pass `index` of the latest frame timing report: writes its duration in
+/// milliseconds to `ms_out` and up to `name_cap` bytes of its UTF-8 name to
+/// `name_buf`. Returns the name's length in bytes, or -1 for a bad index.
+///
+/// # Safety
+///
+/// `name_buf` must be writable for `name_cap` bytes and `ms_out` must point
+/// to a writable f64.
+#[no_mangle]
+pub unsafe extern "C" fn or_wgpu_frame_timing(backend: u64, index: i32, name_buf: *mut u8, name_cap: u32, ms_out: *mut f64) -> i32 {
+    let backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get(backend) {
+        let profiler = state.profiler.borrow();
+        let Some(pass) = profiler.as_ref()
+            .and_then(|p| p.timings())
+            .and_then(|t| t.passes.get(usize::try_from(index).ok()?))
+        else {
+            return -1;
+        };
+        let len = pass.name.len().min(name_cap as usize);
+        unsafe {
+            std::ptr::copy_nonoverlapping(pass.name.as_ptr(), name_buf, len);
+            *ms_out = pass.ms;
+        }
+        len as i32
+    } else {
+        -1
+    }
+}
+
 // ============================================================
 // FFI: Error handling
 // ============================================================
@@ -426,6 +714,84 @@ pub extern "C" fn or_wgpu_create_csm(
     }
 }
 
+/// Load a Radiance `.hdr` equirectangular environment from `path` and bake
+/// its IBL maps for the lighting pass. Returns 0 on success, -1 on failure.
+///
+/// # Safety
+///
+/// `path` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn or_wgpu_create_ibl_environment(backend: u64, path: *const c_char) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let path = unsafe { CStr::from_ptr(path) }.to_string_lossy().into_owned();
+        let result = std::fs::read(&path)
+            .map_err(|e| format!("Failed to read {path}: {e}"))
+            .and_then(|bytes| HdrImage::parse(&bytes))
+            .and_then(|image| ibl::IBLEnvironment::from_equirect(&state.device, &state.queue, &image));
+        match result {
+            Ok(environment) => {
+                state.ibl = Some(environment);
+                0
+            }
+            Err(e) => {
+                state.last_error = Some(e);
+                -1
+            }
+        }
+    } else {
+        -1
+    }
+}
+
+/// Load a `.cube` colour grading LUT from `path` into slot 0 (A) or 1 (B),
+/// or clear the slot when `path` is null. Returns 0 on success, -1 on failure.
+///
+/// # Safety
+///
+/// `path` must be null or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn or_wgpu_load_color_lut(backend: u64, slot: i32, path: *const c_char) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let Some(slot) = usize::try_from(slot).ok().filter(|&s| s < state.color_luts.len()) else {
+            state.last_error = Some(format!("Invalid LUT slot {slot}"));
+            return -1;
+        };
+        if path.is_null() {
+            state.color_luts[slot] = None;
+            return 0;
+        }
+        let path = unsafe { CStr::from_ptr(path) }.to_string_lossy().into_owned();
+        let result = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {path}: {e}"))
+            .and_then(|text| CubeLut::parse(&text))
+            .and_then(|lut| color_lut::ColorLut::new(&state.device, &state.queue, &lut));
+        match result {
+            Ok(lut) => {
+                state.color_luts[slot] = Some(lut);
+                0
+            }
+            Err(e) => {
+                state.last_error = Some(e);
+                -1
+            }
+        }
+    } else {
+        -1
+    }
+}
+
+/// Set the cross-fade from LUT A (0) to LUT B (1) and the grading strength.
+#[no_mangle]
+pub extern "C" fn or_wgpu_set_color_grading(backend: u64, blend: f32, intensity: f32) {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        state.lut_blend = blend;
+        state.lut_intensity = intensity;
+    }
+}
+
 /// Create post-processing pipeline. Returns 1 on success, 0 on failure.
 #[no_mangle]
 pub extern "C" fn or_wgpu_create_post_process(
@@ -491,13 +857,23 @@ pub extern "C" fn or_wgpu_begin_frame(
     if let Some(state) = backends.get_mut(backend) {
         let data = unsafe { std::slice::from_raw_parts(per_frame_ptr, per_frame_size as usize) };
         state.queue.write_buffer(&state.per_frame_buffer, 0, data);
+        if let Ok(camera) = bytemuck::try_pod_read_unaligned::<PerFrameUniforms>(data) {
+            state.camera = camera;
+            state.light_clusters_dirty = true;
+        }
+        if let Some(profiler) = state.profiler.get_mut() {
+            profiler.poll(&state.device);
+            profiler.begin_frame();
+        }
         0
     } else {
         -1
     }
 }
 
-/// Upload light data (LightUniforms struct).
+/// Upload light data (LightUniforms struct). Its point lights (at most 16)
+/// replace the clustered point light list; upload more with
+/// `or_wgpu_upload_point_lights` afterwards.
 #[no_mangle]
 pub extern "C" fn or_wgpu_upload_lights(
     backend: u64,
@@ -507,7 +883,49 @@ pub extern "C" fn or_wgpu_upload_lights(
     let mut backends = BACKENDS.lock().unwrap();
     if let Some(state) = backends.get_mut(backend) {
         let data = unsafe { std::slice::from_raw_parts(light_data_ptr, light_data_size as usize) };
-        state.queue.write_buffer(&state.light_buffer, 0, data);
+        match bytemuck::try_pod_read_unaligned::<LightUniforms>(data) {
+            Ok(mut lights) => {
+                let count = lights.num_point_lights.clamp(0, 16) as usize;
+                state.point_lights = lights.point_lights[..count].to_vec();
+                state.light_clusters_dirty = true;
+                // The placeholder maps are black; keep the flat ambient term
+                // until an environment has been baked.
+                if state.ibl.is_none() {
+                    lights.has_ibl = 0;
+                }
+                state.queue.write_buffer(&state.light_buffer, 0, bytemuck::bytes_of(&lights));
+            }
+            Err(_) => state.queue.write_buffer(&state.light_buffer, 0, data),
+        }
+        0
+    } else {
+        -1
+    }
+}
+
+/// Replace the point lights shaded by the lighting and forward passes.
+/// `lights_ptr` points to `count` PointLightData structs; there is no upper
+/// limit, lights are culled per view cluster.
+///
+/// # Safety
+///
+/// `lights_ptr` must point to `count` readable `PointLightData` structs
+/// (it may be null when `count` is 0).
+#[no_mangle]
+pub unsafe extern "C" fn or_wgpu_upload_point_lights(
+    backend: u64,
+    lights_ptr: *const u8,
+    count: u32,
+) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let size = count as usize * std::mem::size_of::<PointLightData>();
+        let data = if size == 0 { &[][..] } else { unsafe { std::slice::from_raw_parts(lights_ptr, size) } };
+        state.point_lights = data
+            .chunks_exact(std::mem::size_of::<PointLightData>())
+            .map(bytemuck::pod_read_unaligned)
+            .collect();
+        state.light_clusters_dirty = true;
         0
     } else {
         -1
@@ -529,7 +947,7 @@ pub extern "C" fn or_wgpu_shadow_pass(
 ) -> i32 {
     let mut backends = BACKENDS.lock().unwrap();
     if let Some(state) = backends.get_mut(backend) {
-        let dp = match state.deferred.as_ref() {
+        let dp = match state.deferred.as_mut() {
             Some(dp) => dp,
             None => { state.last_error = Some("Deferred pipeline not created".into()); return -1; }
         };
@@ -542,9 +960,36 @@ pub extern "C" fn or_wgpu_shadow_pass(
         let model_data = unsafe { std::slice::from_raw_parts(entity_models_ptr, (entity_count * 16) as usize) };
         let cascade_data = unsafe { std::slice::from_raw_parts(cascade_matrices_ptr, (num_cascades * 16) as usize) };
 
+        // Entity transforms are the same for every cascade: stage them once.
+        dp.objects.clear();
+        let mut shadow_meshes = Vec::new();
+        for i in 0..entity_count as usize {
+            let mesh_handle = mesh_handles[i];
+            if let Some(mesh) = state.meshes.get(mesh_handle) {
+                let base = i * 16;
+                let model: [[f32; 4]; 4] = [
+                    [model_data[base], model_data[base + 1], model_data[base + 2], model_data[base + 3]],
+                    [model_data[base + 4], model_data[base + 5], model_data[base + 6], model_data[base + 7]],
+                    [model_data[base + 8], model_data[base + 9], model_data[base + 10], model_data[base + 11]],
+                    [model_data[base + 12], model_data[base + 13], model_data[base + 14], model_data[base + 15]],
+                ];
+                let object_offset = dp.objects.push(&openreality_gpu_shared::uniforms::PerObjectUniforms {
+                    model,
+                    normal_matrix_col0: [0.0; 4], // Not needed for depth-only
+                    normal_matrix_col1: [0.0; 4],
+                    normal_matrix_col2: [0.0; 4],
+                    entity_id: 0,
+                    _pad: [0; 3],
+                });
+                shadow_meshes.push((mesh_handle, mesh, object_offset));
+            }
+        }
+        dp.objects.upload(&state.device, &dp.per_object_bgl, &state.queue);
+
         let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Shadow Encoder"),
         });
+        begin_timed(&state.profiler, &mut encoder, "Shadow");
 
         for c in 0..(num_cascades as usize).min(csm.num_cascades as usize) {
             // Upload cascade VP matrix as per-frame data for this cascade
@@ -581,36 +1026,18 @@ pub extern "C" fn or_wgpu_shadow_pass(
                 }],
             });
 
-            // Collect entities for this cascade
-            let mut shadow_meshes = Vec::new();
-            for i in 0..entity_count as usize {
-                let mesh_handle = mesh_handles[i];
-                if let Some(mesh) = state.meshes.get(mesh_handle) {
-                    let base = i * 16;
-                    let model: [[f32; 4]; 4] = [
-                        [model_data[base], model_data[base + 1], model_data[base + 2], model_data[base + 3]],
-                        [model_data[base + 4], model_data[base + 5], model_data[base + 6], model_data[base + 7]],
-                        [model_data[base + 8], model_data[base + 9], model_data[base + 10], model_data[base + 11]],
-                        [model_data[base + 12], model_data[base + 13], model_data[base + 14], model_data[base + 15]],
-                    ];
-                    shadow_meshes.push((mesh_handle, mesh, model));
-                }
-            }
-
             passes::shadow::render_shadow_cascade(
                 &mut encoder,
                 csm,
                 c,
                 &dp.shadow_pipeline,
                 &per_frame_bg,
-                &dp.per_object_bgl,
-                &state.device,
-                &state.queue,
-                &state.per_object_buffer,
+                dp.objects.bind_group(),
                 &shadow_meshes,
             );
         }
 
+        end_timed(&state.profiler, &mut encoder);
         state.queue.submit(std::iter::once(encoder.finish()));
         0
     } else {
@@ -618,6 +1045,12 @@ pub extern "C" fn or_wgpu_shadow_pass(
     }
 }
 
+/// Picking ID of an EntityDrawData: the u32 after the texture handles when
+/// `entity_stride` leaves room for it, else 0 ("no entity").
+fn entity_draw_id(entity_bytes: &[u8]) -> u32 {
+    entity_bytes.get(264..268).map_or(0, |b| u32::from_le_bytes(b.try_into().unwrap()))
+}
+
 /// G-Buffer pass: render all opaque entities.
 /// Each entity is described by: mesh_handle (u64), model_matrix (16 f32), normal_matrix (12 f32),
 /// material (MaterialUniforms bytes), texture_handles (6 u64).
@@ -632,7 +1065,7 @@ pub extern "C" fn or_wgpu_gbuffer_pass(
 ) -> i32 {
     let mut backends = BACKENDS.lock().unwrap();
     if let Some(state) = backends.get_mut(backend) {
-        let dp = match state.deferred.as_ref() {
+        let dp = match state.deferred.as_mut() {
             Some(dp) => dp,
             None => { state.last_error = Some("Deferred pipeline not created".into()); return -1; }
         };
@@ -649,6 +1082,7 @@ pub extern "C" fn or_wgpu_gbuffer_pass(
         // Parse entities from packed data
         let entities_data = unsafe { std::slice::from_raw_parts(entities_ptr, (entity_count * entity_stride) as usize) };
         let mut gbuffer_entities = Vec::new();
+        dp.objects.clear();
 
         for i in 0..entity_count as usize {
             let offset = i * entity_stride as usize;
@@ -662,7 +1096,8 @@ pub extern "C" fn or_wgpu_gbuffer_pass(
             // 104: normal_col2 vec4 (16 bytes)
             // 120: material (MaterialUniforms, 96 bytes)
             // 216: texture_handles [6]u64 (48 bytes)
-            // Total: 264 bytes
+            // 264: entity_id u32 (4 bytes, optional; see `entity_draw_id`)
+            // Total: 264 bytes, or 268+ with an entity ID
 
             let mesh_handle = u64::from_le_bytes(entity_bytes[0..8].try_into().unwrap());
             let mesh = match state.meshes.get(mesh_handle) {
@@ -691,37 +1126,43 @@ pub extern "C" fn or_wgpu_gbuffer_pass(
 
             gbuffer_entities.push(passes::gbuffer::GBufferEntity {
                 mesh,
-                per_object: openreality_gpu_shared::uniforms::PerObjectUniforms {
+                object_offset: dp.objects.push(&openreality_gpu_shared::uniforms::PerObjectUniforms {
                     model,
                     normal_matrix_col0: nc0,
                     normal_matrix_col1: nc1,
                     normal_matrix_col2: nc2,
-                    _pad: [0.0; 4],
-                },
+                    entity_id: entity_draw_id(entity_bytes),
+                    _pad: [0; 3],
+                }),
                 material,
                 texture_views,
             });
         }
 
+        dp.objects.upload(&state.device, &dp.per_object_bgl, &state.queue);
+
         let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("GBuffer Encoder"),
         });
+        begin_timed(&state.profiler, &mut encoder, "G-Buffer");
 
+        let geometry = dp.geometry();
         passes::gbuffer::render_gbuffer_pass(
             &mut encoder,
             &dp.gbuffer,
-            &dp.gbuffer_pipeline,
+            geometry.entity_ids,
+            geometry.gbuffer,
             &per_frame_bg,
-            &dp.per_object_bgl,
+            dp.objects.bind_group(),
             &state.material_bind_group_layout,
             &state.device,
             &state.queue,
-            &state.per_object_buffer,
             &gbuffer_entities,
             &dp.default_texture_view,
             &state.default_sampler,
         );
 
+        end_timed(&state.profiler, &mut encoder);
         state.queue.submit(std::iter::once(encoder.finish()));
         0
     } else {
@@ -744,7 +1185,7 @@ pub extern "C" fn or_wgpu_gbuffer_skinned_pass(
     }
     let mut backends = BACKENDS.lock().unwrap();
     if let Some(state) = backends.get_mut(backend) {
-        let dp = match state.deferred.as_ref() {
+        let dp = match state.deferred.as_mut() {
             Some(dp) => dp,
             None => { state.last_error = Some("Deferred pipeline not created".into()); return -1; }
         };
@@ -770,6 +1211,7 @@ pub extern "C" fn or_wgpu_gbuffer_skinned_pass(
         // Parse entities (same format as gbuffer_pass)
         let entities_data = unsafe { std::slice::from_raw_parts(entities_ptr, (entity_count * entity_stride) as usize) };
         let mut skinned_entities = Vec::new();
+        dp.objects.clear();
 
         for i in 0..entity_count as usize {
             let offset = i * entity_stride as usize;
@@ -801,13 +1243,14 @@ pub extern "C" fn or_wgpu_gbuffer_skinned_pass(
 
             skinned_entities.push(passes::gbuffer::GBufferEntity {
                 mesh,
-                per_object: openreality_gpu_shared::uniforms::PerObjectUniforms {
+                object_offset: dp.objects.push(&openreality_gpu_shared::uniforms::PerObjectUniforms {
                     model,
                     normal_matrix_col0: nc0,
                     normal_matrix_col1: nc1,
                     normal_matrix_col2: nc2,
-                    _pad: [0.0; 4],
-                },
+                    entity_id: entity_draw_id(entity_bytes),
+                    _pad: [0; 3],
+                }),
                 material,
                 texture_views,
             });
@@ -816,17 +1259,21 @@ pub extern "C" fn or_wgpu_gbuffer_skinned_pass(
         if skinned_entities.is_empty() {
             return 0;
         }
+        dp.objects.upload(&state.device, &dp.per_object_bgl, &state.queue);
 
         let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Skinned GBuffer Encoder"),
         });
+        begin_timed(&state.profiler, &mut encoder, "G-Buffer Skinned");
 
+        let geometry = dp.geometry();
         passes::gbuffer::render_gbuffer_skinned_pass(
             &mut encoder,
             &dp.gbuffer,
-            &dp.gbuffer_skinned_pipeline,
+            geometry.entity_ids,
+            geometry.gbuffer_skinned,
             &per_frame_bg,
-            &dp.per_object_bgl,
+            dp.objects.bind_group(),
             &state.material_bind_group_layout,
             &bone_bg,
             &state.device,
@@ -836,6 +1283,7 @@ pub extern "C" fn or_wgpu_gbuffer_skinned_pass(
             &state.default_sampler,
         );
 
+        end_timed(&state.profiler, &mut encoder);
         state.queue.submit(std::iter::once(encoder.finish()));
         0
     } else {
@@ -851,14 +1299,22 @@ pub extern "C" fn or_wgpu_gbuffer_skinned_pass(
 /// - `material_ptr`: 96-byte MaterialUniforms
 /// - `texture_handles_ptr`: 6 u64 texture handles
 /// - `instance_data_ptr`: 28 floats per instance (model mat4 column-major + 3 normal vec4)
+/// - `entity_ids_ptr`: one picking ID per instance, or null for none
 /// - `instance_count`: number of instances
+///
+/// # Safety
+///
+/// `material_ptr` must point to 96 readable bytes, `texture_handles_ptr` to 6
+/// u64 handles and `instance_data_ptr` to `instance_count * 28` floats.
+/// `entity_ids_ptr` must be null or point to `instance_count` u32 IDs.
 #[no_mangle]
-pub extern "C" fn or_wgpu_gbuffer_instanced_pass(
+pub unsafe extern "C" fn or_wgpu_gbuffer_instanced_pass(
     backend: u64,
     mesh_handle: u64,
     material_ptr: *const u8,
     texture_handles_ptr: *const u64,
     instance_data_ptr: *const f32,
+    entity_ids_ptr: *const u32,
     instance_count: u32,
 ) -> i32 {
     if instance_count == 0 {
@@ -891,26 +1347,24 @@ pub extern "C" fn or_wgpu_gbuffer_instanced_pass(
             }
         }
 
-        // Upload instance data to the instance VBO (resize if needed)
-        let instance_floats = 28u64; // 4*4 model + 3*4 normal columns
-        let instance_stride_bytes = instance_floats * 4; // 112 bytes
-        let required_size = instance_count as u64 * instance_stride_bytes;
-
-        if required_size > dp.instance_vbo_size {
-            let new_size = required_size.next_power_of_two();
-            dp.instance_vbo = state.device.create_buffer(&wgpu::BufferDescriptor {
-                label: Some("Instance VBO (resized)"),
-                size: new_size,
-                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-                mapped_at_creation: false,
-            });
-            dp.instance_vbo_size = new_size;
-        }
-
-        let instance_data = unsafe {
-            std::slice::from_raw_parts(instance_data_ptr as *const u8, required_size as usize)
-        };
-        state.queue.write_buffer(&dp.instance_vbo, 0, instance_data);
+        // Expand to one PerObjectUniforms per instance and upload to the
+        // instance VBO (resized if needed)
+        let floats = unsafe { std::slice::from_raw_parts(instance_data_ptr, instance_count as usize * 28) };
+        let entity_ids = (!entity_ids_ptr.is_null())
+            .then(|| unsafe { std::slice::from_raw_parts(entity_ids_ptr, instance_count as usize) });
+        let instances: Vec<openreality_gpu_shared::uniforms::PerObjectUniforms> = floats
+            .chunks_exact(28)
+            .enumerate()
+            .map(|(i, m)| openreality_gpu_shared::uniforms::PerObjectUniforms {
+                model: *bytemuck::from_bytes(bytemuck::cast_slice(&m[0..16])),
+                normal_matrix_col0: m[16..20].try_into().unwrap(),
+                normal_matrix_col1: m[20..24].try_into().unwrap(),
+                normal_matrix_col2: m[24..28].try_into().unwrap(),
+                entity_id: entity_ids.map_or(0, |ids| ids[i]),
+                _pad: [0; 3],
+            })
+            .collect();
+        dp.write_instances(&state.device, &state.queue, bytemuck::cast_slice(&instances));
 
         // Create per-frame bind group
         let per_frame_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -925,24 +1379,31 @@ pub extern "C" fn or_wgpu_gbuffer_instanced_pass(
         let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Instanced GBuffer Encoder"),
         });
+        begin_timed(&state.profiler, &mut encoder, "G-Buffer Instanced");
 
+        let geometry = dp.geometry();
         passes::gbuffer::render_gbuffer_instanced_pass(
             &mut encoder,
             &dp.gbuffer,
-            &dp.gbuffer_instanced_pipeline,
+            geometry.entity_ids,
+            geometry.gbuffer_instanced,
             &per_frame_bg,
             &state.material_bind_group_layout,
             &state.device,
             &state.queue,
-            mesh,
-            material,
-            texture_views,
+            &[passes::gbuffer::InstanceBatch {
+                mesh,
+                material,
+                texture_views,
+                first_instance: 0,
+                instance_count,
+            }],
             &dp.instance_vbo,
-            instance_count,
             &dp.default_texture_view,
             &state.default_sampler,
         );
 
+        end_timed(&state.profiler, &mut encoder);
         state.queue.submit(std::iter::once(encoder.finish()));
         0
     } else {
@@ -955,51 +1416,33 @@ pub extern "C" fn or_wgpu_gbuffer_instanced_pass(
 pub extern "C" fn or_wgpu_lighting_pass(backend: u64) -> i32 {
     let mut backends = BACKENDS.lock().unwrap();
     if let Some(state) = backends.get_mut(backend) {
+        state.update_light_clusters();
         let dp = match state.deferred.as_ref() {
             Some(dp) => dp,
             None => { state.last_error = Some("Deferred pipeline not created".into()); return -1; }
         };
 
-        let depth_sampler = &dp.depth_sampler;
-
-        // Use SSAO if available, else use default white texture
-        let ssao_view = &dp.ssao_targets.blur.color_view;
-        let ssr_view = &dp.ssr_target.color_view;
-
-        let lighting_bg = passes::lighting::create_lighting_bind_group(
-            &state.device,
-            &dp.lighting_bgl,
-            &state.per_frame_buffer,
-            &dp.gbuffer,
-            ssao_view,
-            ssr_view,
-            &state.default_sampler,
-            depth_sampler,
-        );
-
-        let light_data_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Light Data BG"),
-            layout: &dp.light_data_bgl,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: state.light_buffer.as_entire_binding(),
-            }],
+        let mut graph = RenderGraph::new();
+        let ctx = import_context(state, dp, &mut graph);
+        // Shadows come from the shadow uniform buffer; with no CSM every slot
+        // gets the gbuffer depth and num_cascades stays 0.
+        let cascades: [ResourceId; 4] = std::array::from_fn(|i| {
+            state.csm.as_ref()
+                .and_then(|csm| csm.depth_views.get(i))
+                .map_or(ctx.targets.depth, |view| graph.import("Shadow Cascade", view, None))
         });
-
-        let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Lighting Encoder"),
+        // No light has a shadow tile, so the atlas slot is never sampled.
+        nodes::add_lighting(&mut graph, &ctx, &state.per_frame_buffer, LightingInputs {
+            light_buffer: &state.light_buffer,
+            light_clusters: &state.light_clusters,
+            cascades,
+            shadow_atlas: ctx.targets.depth,
+            ibl: state.ibl.as_ref().unwrap_or(&state.fallback_ibl),
         });
+        graph.mark_output(ctx.targets.lighting);
 
-        passes::lighting::render_lighting_pass(
-            &mut encoder,
-            &dp.lighting_target,
-            &dp.lighting_pipeline,
-            &lighting_bg,
-            &light_data_bg,
-        );
-
-        state.queue.submit(std::iter::once(encoder.finish()));
-        0
+        let result = submit_graph(state, graph, "Lighting Encoder");
+        graph_status(state, result)
     } else {
         -1
     }
@@ -1016,43 +1459,14 @@ pub extern "C" fn or_wgpu_ssao_pass(backend: u64, params_ptr: *const u8) -> i32
             None => { state.last_error = Some("Deferred pipeline not created".into()); return -1; }
         };
 
-        let params_size = std::mem::size_of::<openreality_gpu_shared::uniforms::SSAOParams>();
-        let data = unsafe { std::slice::from_raw_parts(params_ptr, params_size) };
-        state.queue.write_buffer(&dp.ssao_params_buffer, 0, data);
-
-        // Create SSAO bind group (matches ssao.wgsl: uniform, depth, normal, noise, 2 samplers)
-        let ssao_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("SSAO BG"),
-            layout: &dp.ssao_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.ssao_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.gbuffer.depth_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(&dp.gbuffer.normal_roughness_view) },
-                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(&dp.ssao_noise_view) },
-                wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
-                wgpu::BindGroupEntry { binding: 5, resource: wgpu::BindingResource::Sampler(&dp.depth_sampler) },
-            ],
-        });
+        let params: SSAOParams = unsafe { read_params(params_ptr) };
+        let mut graph = RenderGraph::new();
+        let ctx = import_context(state, dp, &mut graph);
+        nodes::add_ssao(&mut graph, &ctx, &params);
+        graph.mark_output(ctx.targets.ssao_blur);
 
-        let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("SSAO Encoder"),
-        });
-        passes::ssao::render_ssao_pass(&mut encoder, &dp.ssao_targets.ao, &dp.ssao_pipeline, &ssao_bg);
-
-        // Blur pass
-        let blur_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("SSAO Blur BG"),
-            layout: &dp.ssao_blur_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.ssao_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.ssao_targets.ao.color_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
-            ],
-        });
-        passes::ssao::render_ssao_blur(&mut encoder, &dp.ssao_targets.blur, &dp.ssao_blur_pipeline, &blur_bg);
-
-        state.queue.submit(std::iter::once(encoder.finish()));
-        0
+        let result = submit_graph(state, graph, "SSAO Encoder");
+        graph_status(state, result)
     } else {
         -1
     }
@@ -1068,80 +1482,42 @@ pub extern "C" fn or_wgpu_ssr_pass(backend: u64, params_ptr: *const u8) -> i32 {
             None => { state.last_error = Some("Deferred pipeline not created".into()); return -1; }
         };
 
-        let params_size = std::mem::size_of::<openreality_gpu_shared::uniforms::SSRParams>();
-        let data = unsafe { std::slice::from_raw_parts(params_ptr, params_size) };
-        state.queue.write_buffer(&dp.ssr_params_buffer, 0, data);
+        let params: SSRParams = unsafe { read_params(params_ptr) };
+        let mut graph = RenderGraph::new();
+        let ctx = import_context(state, dp, &mut graph);
+        nodes::add_ssr(&mut graph, &ctx, &params);
+        graph.mark_output(ctx.targets.ssr);
 
-        let ssr_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("SSR BG"),
-            layout: &dp.ssr_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.ssr_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.gbuffer.depth_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(&dp.gbuffer.normal_roughness_view) },
-                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(&dp.lighting_target.color_view) },
-                wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
-                wgpu::BindGroupEntry { binding: 5, resource: wgpu::BindingResource::Sampler(&dp.depth_sampler) },
-            ],
-        });
-
-        let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("SSR Encoder"),
-        });
-        passes::ssr::render_ssr_pass(&mut encoder, &dp.ssr_target, &dp.ssr_pipeline, &ssr_bg);
-        state.queue.submit(std::iter::once(encoder.finish()));
-        0
+        let result = submit_graph(state, graph, "SSR Encoder");
+        graph_status(state, result)
     } else {
         -1
     }
 }
 
-/// TAA pass: temporal anti-aliasing.
+/// TAA pass: temporal anti-aliasing. The resolved frame is copied to the
+/// history and back into the lighting target.
 #[no_mangle]
 pub extern "C" fn or_wgpu_taa_pass(backend: u64, params_ptr: *const u8) -> i32 {
     let mut backends = BACKENDS.lock().unwrap();
     if let Some(state) = backends.get_mut(backend) {
-        let dp = match state.deferred.as_mut() {
+        let dp = match state.deferred.as_ref() {
             Some(dp) => dp,
             None => { state.last_error = Some("Deferred pipeline not created".into()); return -1; }
         };
 
-        let params_size = std::mem::size_of::<openreality_gpu_shared::uniforms::TAAParams>();
-        let data = unsafe { std::slice::from_raw_parts(params_ptr, params_size) };
-        state.queue.write_buffer(&dp.taa_params_buffer, 0, data);
+        let params: TAAParams = unsafe { read_params(params_ptr) };
+        let mut graph = RenderGraph::new();
+        let ctx = import_context(state, dp, &mut graph);
+        nodes::add_taa(&mut graph, &ctx, &params);
+        graph.mark_output(ctx.targets.lighting);
+        graph.mark_output(ctx.targets.taa_history);
 
-        let taa_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("TAA BG"),
-            layout: &dp.taa_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.taa_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.lighting_target.color_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(&dp.taa_targets.history_view) },
-                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(&dp.gbuffer.depth_view) },
-                wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
-                wgpu::BindGroupEntry { binding: 5, resource: wgpu::BindingResource::Sampler(&dp.depth_sampler) },
-            ],
-        });
-
-        let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("TAA Encoder"),
-        });
-        passes::taa::render_taa_pass(&mut encoder, &dp.taa_targets.current, &dp.taa_pipeline, &taa_bg);
-
-        // Copy to history
-        let w = dp.taa_targets.current.width;
-        let h = dp.taa_targets.current.height;
-        passes::taa::copy_taa_to_history(
-            &mut encoder,
-            &dp.taa_targets.current.color_texture,
-            &dp.taa_targets.history_texture,
-            w,
-            h,
-        );
-
-        state.queue.submit(std::iter::once(encoder.finish()));
-        dp.taa_first_frame = false;
-        0
+        let result = submit_graph(state, graph, "TAA Encoder");
+        if let Some(dp) = state.deferred.as_mut() {
+            dp.taa_first_frame = false;
+        }
+        graph_status(state, result)
     } else {
         -1
     }
@@ -1157,76 +1533,15 @@ pub extern "C" fn or_wgpu_postprocess_pass(backend: u64, params_ptr: *const u8)
             None => { state.last_error = Some("Deferred pipeline not created".into()); return -1; }
         };
 
-        let params_size = std::mem::size_of::<openreality_gpu_shared::uniforms::PostProcessParams>();
-        let data = unsafe { std::slice::from_raw_parts(params_ptr, params_size) };
-        state.queue.write_buffer(&dp.pp_params_buffer, 0, data);
-
-        let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("PostProcess Encoder"),
-        });
+        let params: PostProcessParams = unsafe { read_params(params_ptr) };
+        let mut graph = RenderGraph::new();
+        let ctx = import_context(state, dp, &mut graph);
+        // Present reads the FXAA output (pp_target_b).
+        let output = nodes::add_postprocess(&mut graph, &ctx, &params, true, true);
+        graph.mark_output(output);
 
-        // Bloom extract
-        let extract_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Bloom Extract BG"),
-            layout: &dp.bloom_extract_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.pp_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.lighting_target.color_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
-            ],
-        });
-        passes::postprocess::render_bloom_extract(&mut encoder, &dp.bloom_targets.extract, &dp.bloom_extract_pipeline, &extract_bg);
-
-        // Bloom blur horizontal
-        let blur_h_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Bloom Blur H BG"),
-            layout: &dp.bloom_blur_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.pp_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.bloom_targets.extract.color_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
-            ],
-        });
-        passes::postprocess::render_bloom_blur(&mut encoder, &dp.bloom_targets.blur_h, &dp.bloom_blur_pipeline, &blur_h_bg, "Bloom Blur H");
-
-        // Bloom blur vertical
-        let blur_v_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Bloom Blur V BG"),
-            layout: &dp.bloom_blur_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.pp_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.bloom_targets.blur_h.color_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
-            ],
-        });
-        passes::postprocess::render_bloom_blur(&mut encoder, &dp.bloom_targets.blur_v, &dp.bloom_blur_pipeline, &blur_v_bg, "Bloom Blur V");
-
-        // Bloom composite (scene + bloom)
-        let composite_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Bloom Composite BG"),
-            layout: &dp.bloom_composite_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.pp_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.lighting_target.color_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(&dp.bloom_targets.blur_v.color_view) },
-                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
-            ],
-        });
-        passes::postprocess::render_bloom_composite(&mut encoder, &dp.pp_target_a, &dp.bloom_composite_pipeline, &composite_bg);
-
-        // FXAA (no uniform buffer — just texture + sampler)
-        let fxaa_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("FXAA BG"),
-            layout: &dp.fxaa_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&dp.pp_target_a.color_view) },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
-            ],
-        });
-        passes::postprocess::render_fxaa(&mut encoder, &dp.pp_target_b, &dp.fxaa_pipeline, &fxaa_bg);
-
-        state.queue.submit(std::iter::once(encoder.finish()));
-        0
+        let result = submit_graph(state, graph, "PostProcess Encoder");
+        graph_status(state, result)
     } else {
         -1
     }
@@ -1244,120 +1559,21 @@ pub extern "C" fn or_wgpu_dof_pass(backend: u64, params_ptr: *const f32) -> i32
         };
 
         let params = unsafe { std::slice::from_raw_parts(params_ptr, 8) };
-        let focus_distance = params[0];
-        let focus_range = params[1];
-        let near_plane = params[2];
-        let far_plane = params[3];
-        let bokeh_radius = params[4];
-
-        // Upload CoC params
-        let coc_params = openreality_gpu_shared::uniforms::DOFCoCParams {
-            focus_distance,
-            focus_range,
-            near_plane,
-            far_plane,
+        let coc = DOFCoCParams {
+            focus_distance: params[0],
+            focus_range: params[1],
+            near_plane: params[2],
+            far_plane: params[3],
         };
-        state.queue.write_buffer(&dp.dof_coc_params_buffer, 0, bytemuck::bytes_of(&coc_params));
-
-        let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("DOF Encoder"),
-        });
-
-        // Pass 1: CoC from depth
-        let coc_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("DOF CoC BG"),
-            layout: &dp.dof_coc_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.dof_coc_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.gbuffer.depth_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&dp.depth_sampler) },
-            ],
-        });
-        passes::dof::render_dof_coc(&mut encoder, &dp.dof_targets.coc, &dp.dof_coc_pipeline, &coc_bg);
-
-        // Pass 2: Horizontal blur (scene + CoC → blur_h)
-        let blur_h_params = openreality_gpu_shared::uniforms::DOFBlurParams {
-            horizontal: 1,
-            bokeh_radius,
-            _pad1: 0.0,
-            _pad2: 0.0,
-        };
-        state.queue.write_buffer(&dp.dof_blur_params_buffer, 0, bytemuck::bytes_of(&blur_h_params));
-
-        let blur_h_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("DOF Blur H BG"),
-            layout: &dp.dof_blur_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.dof_blur_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.lighting_target.color_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(&dp.dof_targets.coc.color_view) },
-                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
-            ],
-        });
-        passes::dof::render_dof_blur(&mut encoder, &dp.dof_targets.blur_h, &dp.dof_blur_pipeline, &blur_h_bg);
-
-        // Pass 3: Vertical blur (blur_h + CoC → blur_v)
-        // Need a second uniform write — submit first encoder, start new one
-        state.queue.submit(std::iter::once(encoder.finish()));
-
-        let blur_v_params = openreality_gpu_shared::uniforms::DOFBlurParams {
-            horizontal: 0,
-            bokeh_radius,
-            _pad1: 0.0,
-            _pad2: 0.0,
-        };
-        state.queue.write_buffer(&dp.dof_blur_params_buffer, 0, bytemuck::bytes_of(&blur_v_params));
-
-        let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("DOF Encoder 2"),
-        });
+        let bokeh_radius = params[4];
 
-        let blur_v_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("DOF Blur V BG"),
-            layout: &dp.dof_blur_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.dof_blur_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.dof_targets.blur_h.color_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(&dp.dof_targets.coc.color_view) },
-                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
-            ],
-        });
-        passes::dof::render_dof_blur(&mut encoder, &dp.dof_targets.blur_v, &dp.dof_blur_pipeline, &blur_v_bg);
+        let mut graph = RenderGraph::new();
+        let ctx = import_context(state, dp, &mut graph);
+        nodes::add_dof(&mut graph, &ctx, &coc, bokeh_radius);
+        graph.mark_output(ctx.targets.lighting);
 
-        // Pass 4: Composite (sharp + blurred + CoC → pp_target_a)
-        let composite_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("DOF Composite BG"),
-            layout: &dp.dof_composite_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&dp.lighting_target.color_view) },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.dof_targets.blur_v.color_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(&dp.dof_targets.coc.color_view) },
-                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
-            ],
-        });
-        passes::dof::render_dof_composite(&mut encoder, &dp.pp_target_a, &dp.dof_composite_pipeline, &composite_bg);
-
-        // Copy pp_target_a back to lighting_target so postprocess chain reads the DOF result
-        let w = dp.lighting_target.width;
-        let h = dp.lighting_target.height;
-        encoder.copy_texture_to_texture(
-            wgpu::ImageCopyTexture {
-                texture: &dp.pp_target_a.color_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            wgpu::ImageCopyTexture {
-                texture: &dp.lighting_target.color_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            wgpu::Extent3d { width: w, height: h, depth_or_array_layers: 1 },
-        );
-
-        state.queue.submit(std::iter::once(encoder.finish()));
-        0
+        let result = submit_graph(state, graph, "DOF Encoder");
+        graph_status(state, result)
     } else {
         -1
     }
@@ -1375,64 +1591,16 @@ pub extern "C" fn or_wgpu_motion_blur_pass(backend: u64, params_ptr: *const u8)
         };
 
         // First 144 bytes = VelocityParams, next 16 bytes = MotionBlurParams
-        let vel_size = std::mem::size_of::<openreality_gpu_shared::uniforms::VelocityParams>();
-        let blur_size = std::mem::size_of::<openreality_gpu_shared::uniforms::MotionBlurParams>();
-        let vel_data = unsafe { std::slice::from_raw_parts(params_ptr, vel_size) };
-        let blur_data = unsafe { std::slice::from_raw_parts(params_ptr.add(vel_size), blur_size) };
+        let velocity: VelocityParams = unsafe { read_params(params_ptr) };
+        let blur: MotionBlurParams = unsafe { read_params(params_ptr.add(std::mem::size_of::<VelocityParams>())) };
 
-        state.queue.write_buffer(&dp.mblur_velocity_params_buffer, 0, vel_data);
-        state.queue.write_buffer(&dp.mblur_blur_params_buffer, 0, blur_data);
+        let mut graph = RenderGraph::new();
+        let ctx = import_context(state, dp, &mut graph);
+        nodes::add_motion_blur(&mut graph, &ctx, &velocity, &blur);
+        graph.mark_output(ctx.targets.lighting);
 
-        let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Motion Blur Encoder"),
-        });
-
-        // Pass 1: Velocity buffer from depth reprojection
-        let vel_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("MBlur Velocity BG"),
-            layout: &dp.mblur_velocity_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.mblur_velocity_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.gbuffer.depth_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&dp.depth_sampler) },
-            ],
-        });
-        passes::motion_blur::render_velocity_pass(&mut encoder, &dp.mblur_targets.velocity, &dp.mblur_velocity_pipeline, &vel_bg);
-
-        // Pass 2: Directional blur along velocity vectors
-        let blur_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("MBlur Blur BG"),
-            layout: &dp.mblur_blur_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.mblur_blur_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.lighting_target.color_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(&dp.mblur_targets.velocity.color_view) },
-                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
-            ],
-        });
-        passes::motion_blur::render_blur_pass(&mut encoder, &dp.mblur_targets.blur, &dp.mblur_blur_pipeline, &blur_bg);
-
-        // Copy motion blur result back to lighting_target so postprocess reads the blurred result
-        let w = dp.lighting_target.width;
-        let h = dp.lighting_target.height;
-        encoder.copy_texture_to_texture(
-            wgpu::ImageCopyTexture {
-                texture: &dp.mblur_targets.blur.color_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            wgpu::ImageCopyTexture {
-                texture: &dp.lighting_target.color_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            wgpu::Extent3d { width: w, height: h, depth_or_array_layers: 1 },
-        );
-
-        state.queue.submit(std::iter::once(encoder.finish()));
-        0
+        let result = submit_graph(state, graph, "Motion Blur Encoder");
+        graph_status(state, result)
     } else {
         -1
     }
@@ -1452,7 +1620,8 @@ pub extern "C" fn or_wgpu_forward_pass(
     }
     let mut backends = BACKENDS.lock().unwrap();
     if let Some(state) = backends.get_mut(backend) {
-        let dp = match state.deferred.as_ref() {
+        state.update_light_clusters();
+        let dp = match state.deferred.as_mut() {
             Some(dp) => dp,
             None => { state.last_error = Some("Deferred pipeline not created".into()); return -1; }
         };
@@ -1466,9 +1635,10 @@ pub extern "C" fn or_wgpu_forward_pass(
             }],
         });
 
-        // Parse entities (same format as gbuffer_pass: 264 bytes each)
+        // Parse entities (same format as gbuffer_pass)
         let entities_data = unsafe { std::slice::from_raw_parts(entities_ptr, (entity_count * entity_stride) as usize) };
         let mut forward_entities = Vec::new();
+        dp.objects.clear();
 
         for i in 0..entity_count as usize {
             let offset = i * entity_stride as usize;
@@ -1499,13 +1669,14 @@ pub extern "C" fn or_wgpu_forward_pass(
 
             forward_entities.push(passes::gbuffer::GBufferEntity {
                 mesh,
-                per_object: openreality_gpu_shared::uniforms::PerObjectUniforms {
+                object_offset: dp.objects.push(&openreality_gpu_shared::uniforms::PerObjectUniforms {
                     model,
                     normal_matrix_col0: nc0,
                     normal_matrix_col1: nc1,
                     normal_matrix_col2: nc2,
-                    _pad: [0.0; 4],
-                },
+                    entity_id: entity_draw_id(entity_bytes),
+                    _pad: [0; 3],
+                }),
                 material,
                 texture_views,
             });
@@ -1526,6 +1697,7 @@ pub extern "C" fn or_wgpu_forward_pass(
             vec![fallback_depth_view; 4]
         };
 
+        let [params, cluster_lights, ranges, indices] = state.light_clusters.entries(7);
         let light_shadow_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Forward Light+Shadow BG"),
             layout: &dp.forward_light_shadow_bgl,
@@ -1537,30 +1709,41 @@ pub extern "C" fn or_wgpu_forward_pass(
                 wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::TextureView(cascade_views[2]) },
                 wgpu::BindGroupEntry { binding: 5, resource: wgpu::BindingResource::TextureView(cascade_views[3]) },
                 wgpu::BindGroupEntry { binding: 6, resource: wgpu::BindingResource::Sampler(&dp.shadow_comparison_sampler) },
+                params,
+                cluster_lights,
+                ranges,
+                indices,
+                wgpu::BindGroupEntry { binding: 11, resource: wgpu::BindingResource::TextureView(fallback_depth_view) },
+                wgpu::BindGroupEntry { binding: 12, resource: state.light_clusters.shadow_tiles().as_entire_binding() },
             ],
         });
 
+        dp.objects.upload(&state.device, &dp.per_object_bgl, &state.queue);
+
         let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Forward Encoder"),
         });
+        begin_timed(&state.profiler, &mut encoder, "Forward");
 
+        let geometry = dp.geometry();
         passes::forward::render_forward_pass(
             &mut encoder,
             &dp.pp_target_b,
             &dp.gbuffer.depth_view,
-            &dp.forward_pipeline,
+            geometry.entity_ids,
+            geometry.forward,
             &per_frame_bg,
             &light_shadow_bg,
-            &dp.per_object_bgl,
+            dp.objects.bind_group(),
             &state.material_bind_group_layout,
             &state.device,
             &state.queue,
-            &state.per_object_buffer,
             &forward_entities,
             &dp.default_texture_view,
             &state.default_sampler,
         );
 
+        end_timed(&state.profiler, &mut encoder);
         state.queue.submit(std::iter::once(encoder.finish()));
         0
     } else {
@@ -1624,19 +1807,19 @@ pub extern "C" fn or_wgpu_particle_pass(
         });
 
         // Get surface texture for rendering
-        let output = match state.surface.get_current_texture() {
+        let output = match state.target.acquire() {
             Ok(o) => o,
-            Err(e) => { state.last_error = Some(format!("Surface error: {e}")); return -1; }
+            Err(e) => { state.last_error = Some(e); return -1; }
         };
-        let surface_view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
 
         let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Particle Encoder"),
         });
+        begin_timed(&state.profiler, &mut encoder, "Particles");
 
         passes::particles::render_particle_pass(
             &mut encoder,
-            &surface_view,
+            &output.view,
             &dp.gbuffer.depth_view,
             &dp.particle_pipeline,
             &particle_bg,
@@ -1645,6 +1828,7 @@ pub extern "C" fn or_wgpu_particle_pass(
             false, // TODO: per-emitter additive blending
         );
 
+        end_timed(&state.profiler, &mut encoder);
         state.queue.submit(std::iter::once(encoder.finish()));
         output.present();
         0
@@ -1725,15 +1909,15 @@ pub extern "C" fn or_wgpu_ui_pass(
         });
 
         // Acquire surface for rendering
-        let output = match state.surface.get_current_texture() {
+        let output = match state.target.acquire() {
             Ok(o) => o,
-            Err(e) => { state.last_error = Some(format!("Surface error: {e}")); return -1; }
+            Err(e) => { state.last_error = Some(e); return -1; }
         };
-        let surface_view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
 
         let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("UI Encoder"),
         });
+        begin_timed(&state.profiler, &mut encoder, "UI");
 
         let draw_cmd = passes::ui::UIDrawCommand {
             first_vertex: 0,
@@ -1744,13 +1928,14 @@ pub extern "C" fn or_wgpu_ui_pass(
 
         passes::ui::render_ui_pass(
             &mut encoder,
-            &surface_view,
+            &output.view,
             &dp.ui_pipeline,
             &dp.ui_vbo,
             &[draw_cmd],
             &[&ui_bg],
         );
 
+        end_timed(&state.profiler, &mut encoder);
         state.queue.submit(std::iter::once(encoder.finish()));
         output.present();
         0
@@ -1810,25 +1995,26 @@ pub extern "C" fn or_wgpu_debug_lines_pass(
         });
 
         // Acquire surface for rendering
-        let output = match state.surface.get_current_texture() {
+        let output = match state.target.acquire() {
             Ok(o) => o,
-            Err(e) => { state.last_error = Some(format!("Surface error: {e}")); return -1; }
+            Err(e) => { state.last_error = Some(e); return -1; }
         };
-        let surface_view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
 
         let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Debug Lines Encoder"),
         });
+        begin_timed(&state.profiler, &mut encoder, "Debug Lines");
 
         passes::debug_lines::render_debug_lines(
             &mut encoder,
-            &surface_view,
+            &output.view,
             &dp.debug_lines_pipeline,
             &bg,
             &dp.debug_lines_vbo,
             vertex_count,
         );
 
+        end_timed(&state.profiler, &mut encoder);
         state.queue.submit(std::iter::once(encoder.finish()));
         output.present();
         0
@@ -1847,32 +2033,99 @@ pub extern "C" fn or_wgpu_present(backend: u64) -> i32 {
             None => { state.last_error = Some("Deferred pipeline not created".into()); return -1; }
         };
 
-        let output = match state.surface.get_current_texture() {
+        let output = match state.target.acquire() {
             Ok(o) => o,
-            Err(e) => { state.last_error = Some(format!("Surface error: {e}")); return -1; }
+            Err(e) => { state.last_error = Some(e); return -1; }
         };
-        let surface_view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
 
-        let present_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Present BG"),
-            layout: &dp.present_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.pp_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.pp_target_b.color_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
-            ],
-        });
-
-        let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Present Encoder"),
-        });
-
-        passes::present::render_present_pass(&mut encoder, &surface_view, &dp.present_pipeline, &present_bg);
+        let mut graph = RenderGraph::new();
+        let ctx = import_context(state, dp, &mut graph);
+        let surface = graph.import("Surface", &output.view, None);
+        if state.debug_view == DebugView::Final {
+            let [lut_a, lut_b] = &state.color_luts;
+            nodes::add_present(&mut graph, &ctx, ctx.targets.pp_b, surface, [lut_a.as_ref(), lut_b.as_ref()], state.lut_blend, state.lut_intensity);
+            nodes::add_selection(&mut graph, &ctx, surface, &state.selection, &state.selection_style);
+        } else {
+            let cascades: [ResourceId; 4] = std::array::from_fn(|i| {
+                state.csm.as_ref()
+                    .and_then(|csm| csm.depth_views.get(i))
+                    .map_or(ctx.targets.depth, |view| graph.import("Shadow Cascade", view, None))
+            });
+            let projection = glam::Mat4::from_cols_array_2d(&state.camera.projection);
+            let (near, far) = openreality_gpu_shared::math::perspective_near_far(&projection);
+            nodes::add_debug_view(&mut graph, &ctx, state.debug_view, cascades, surface, near, far);
+        }
+        graph.mark_output(surface);
 
-        state.queue.submit(std::iter::once(encoder.finish()));
+        let result = submit_graph(state, graph, "Present Encoder");
         output.present();
-        0
+        if let Some(profiler) = state.profiler.get_mut() {
+            profiler.end_frame();
+        }
+        graph_status(state, result)
     } else {
         -1
     }
 }
+
+// ============================================================
+// Render graph helpers
+// ============================================================
+
+/// Read a `#[repr(C)]` parameter struct passed by pointer from Julia.
+unsafe fn read_params<T: bytemuck::Pod>(ptr: *const u8) -> T {
+    bytemuck::pod_read_unaligned(std::slice::from_raw_parts(ptr, std::mem::size_of::<T>()))
+}
+
+/// Node context over the pipeline's own targets. Julia drives the frame one
+/// pass call at a time, so every target must outlive the call's graph.
+fn import_context<'a>(state: &'a WGPUBackendState, dp: &'a DeferredPipeline, graph: &mut RenderGraph<'a>) -> NodeContext<'a> {
+    NodeContext {
+        device: &state.device,
+        queue: &state.queue,
+        dp,
+        sampler: &state.default_sampler,
+        targets: DeferredTargets::import(graph, dp),
+    }
+}
+
+/// Record a graph into a fresh encoder and submit it, timing its passes when
+/// profiling.
+fn submit_graph(state: &WGPUBackendState, graph: RenderGraph, label: &str) -> Result<(), String> {
+    let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some(label) });
+    // Imports only, so the pool stays empty.
+    let mut pool = TransientPool::default();
+    match state.profiler.borrow_mut().as_mut() {
+        Some(profiler) => graph.execute_profiled(&state.device, &mut encoder, &mut pool, profiler)?,
+        None => graph.execute(&state.device, &mut encoder, &mut pool)?,
+    }
+    state.queue.submit(std::iter::once(encoder.finish()));
+    Ok(())
+}
+
+/// Start timing a pass recorded outside a render graph.
+fn begin_timed(profiler: &RefCell<Option<FrameProfiler>>, encoder: &mut wgpu::CommandEncoder, name: &'static str) {
+    if let Some(profiler) = profiler.borrow_mut().as_mut() {
+        profiler.begin_pass(encoder, name);
+    }
+}
+
+/// End a pass started with `begin_timed`, resolving its timestamps into the
+/// frame's report.
+fn end_timed(profiler: &RefCell<Option<FrameProfiler>>, encoder: &mut wgpu::CommandEncoder) {
+    if let Some(profiler) = profiler.borrow_mut().as_mut() {
+        profiler.end_pass(encoder);
+        profiler.resolve(encoder);
+    }
+}
+
+/// FFI status code for a graph submission, recording any error.
+fn graph_status(state: &mut WGPUBackendState, result: Result<(), String>) -> i32 {
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            state.last_error = Some(e);
+            -1
+        }
+    }
+}
@@ -590,7 +590,7 @@ export LightClusterConfig, LightClusterData, assign_lights_to_clusters!
 
 # Export Post-Processing
 export Framebuffer, PostProcessConfig, PostProcessPipeline
-export ToneMappingMode, TONEMAP_REINHARD, TONEMAP_ACES, TONEMAP_UNCHARTED2
+export ToneMappingMode, TONEMAP_REINHARD, TONEMAP_ACES, TONEMAP_UNCHARTED2, TONEMAP_AGX
 export FogMode, FOG_LINEAR, FOG_EXPONENTIAL, FOG_EXPONENTIAL2
 export DOFPass, create_dof_pass!, destroy_dof_pass!, resize_dof_pass!, render_dof!
 export MotionBlurPass, create_motion_blur_pass!, destroy_motion_blur_pass!, resize_motion_blur_pass!, render_motion_blur!
@@ -1547,6 +1547,10 @@ function _render_lighting_pass!(cmd::CommandBuffer, backend::VulkanBackendImpl,
     cmd_end_render_pass(cmd)
 end
 
+# The Vulkan post-process shaders have no AgX, and mode 3 means passthrough.
+_vk_tone_mapping_mode(mode::ToneMappingMode) =
+    mode == TONEMAP_AGX ? Int32(TONEMAP_UNCHARTED2) : Int32(mode)
+
 function _render_present_pass!(cmd::CommandBuffer, backend::VulkanBackendImpl,
                                 image_index::Int, frame_idx::Int,
                                 width::Int, height::Int;
@@ -1585,7 +1589,7 @@ function _render_present_pass!(cmd::CommandBuffer, backend::VulkanBackendImpl,
             backend.transient_pools[frame_idx], backend.fullscreen_layout)
 
         pp = backend.post_process_config !== nothing ? backend.post_process_config : PostProcessConfig()
-        tone_mode = use_passthrough ? Int32(3) : Int32(pp.tone_mapping)
+        tone_mode = use_passthrough ? Int32(3) : _vk_tone_mapping_mode(pp.tone_mapping)
         fxaa_flag = apply_fxaa ? Int32(1) : Int32(0)
         present_uniforms = VulkanPostProcessUniforms(
             pp.bloom_threshold, pp.bloom_intensity, pp.gamma,
@@ -1859,7 +1863,7 @@ function _render_post_process_passes!(cmd::CommandBuffer, backend::VulkanBackend
             backend.transient_pools[frame_idx], backend.fullscreen_layout)
         bright_uniforms = VulkanPostProcessUniforms(
             config.bloom_threshold, config.bloom_intensity, config.gamma,
-            Int32(config.tone_mapping), Int32(0),
+            _vk_tone_mapping_mode(config.tone_mapping), Int32(0),
             0.0f0, 0.0f0, 0.0f0, 0.0f0, 1.0f0, 1.0f0, 0.0f0)
         bright_ubo, bright_mem = vk_create_uniform_buffer(
             backend.device, backend.physical_device, bright_uniforms)
@@ -1886,7 +1890,7 @@ function _render_post_process_passes!(cmd::CommandBuffer, backend::VulkanBackend
                 backend.transient_pools[frame_idx], backend.fullscreen_layout)
             blur_uniforms = VulkanPostProcessUniforms(
                 config.bloom_threshold, config.bloom_intensity, config.gamma,
-                Int32(config.tone_mapping), is_horizontal ? Int32(1) : Int32(0),
+                _vk_tone_mapping_mode(config.tone_mapping), is_horizontal ? Int32(1) : Int32(0),
                 0.0f0, 0.0f0, 0.0f0, 0.0f0, 1.0f0, 1.0f0, 0.0f0)
             blur_ubo, blur_mem = vk_create_uniform_buffer(
                 backend.device, backend.physical_device, blur_uniforms)
@@ -1914,7 +1918,7 @@ function _render_post_process_passes!(cmd::CommandBuffer, backend::VulkanBackend
         backend.transient_pools[frame_idx], backend.fullscreen_layout)
     comp_uniforms = VulkanPostProcessUniforms(
         config.bloom_threshold, config.bloom_intensity, config.gamma,
-        Int32(config.tone_mapping), Int32(0),
+        _vk_tone_mapping_mode(config.tone_mapping), Int32(0),
         config.vignette_enabled ? config.vignette_intensity : 0.0f0,
         config.vignette_radius, config.vignette_softness,
         config.color_grading_enabled ? config.color_grading_brightness : 0.0f0,
@@ -20,6 +20,8 @@ mutable struct WebGPUBackend <: AbstractBackend
     # Rendering resource handles
     csm_handle::UInt64
     post_process_handle::UInt64
+    ibl_path::String                # environment last loaded (or attempted)
+    color_lut_paths::Vector{String} # LUTs last loaded (or attempted) into slots A and B
 
     # Configuration
     post_process_config::Union{PostProcessConfig, Nothing}
@@ -31,6 +33,9 @@ mutable struct WebGPUBackend <: AbstractBackend
     prev_view_proj::Mat4f
     taa_frame_index::Int
 
+    # Exposure adaptation: time of the previous post-process pass (0 = none yet)
+    last_postprocess_time::Float64
+
     # Render graph (opt-in)
     render_graph::Union{RenderGraph, Nothing}
     graph_executor::Union{AbstractGraphExecutor, Nothing}
@@ -50,12 +55,15 @@ function WebGPUBackend()
         Dict{EntityID, BoundingSphere}(), # bounds_cache
         UInt64(0),                      # csm_handle
         UInt64(0),                      # post_process_handle
+        "",                             # ibl_path
+        ["", ""],                       # color_lut_paths
         nothing,                        # post_process_config
         true,                           # use_deferred
         1280,                           # width
         720,                            # height
         Mat4f(I),                       # prev_view_proj
         0,                              # taa_frame_index
+        0.0,                            # last_postprocess_time
         nothing,                        # render_graph
         nothing,                        # graph_executor
         nothing,                        # graph_handles
@@ -194,8 +202,9 @@ function render_frame!(backend::WebGPUBackend, scene)
         return nothing
     end
 
-    # Use frame_preparation to get backend-agnostic frame data (parallel-first via EEVDFScheduler)
-    frame_data = prepare_frame(scene, backend.bounds_cache)
+    # Use frame_preparation to get backend-agnostic frame data (parallel-first via EEVDFScheduler).
+    # Point lights are culled per cluster on the Rust side, so none are dropped here.
+    frame_data = prepare_frame(scene, backend.bounds_cache; max_point_lights=typemax(Int))
     frame_data === nothing && return
 
     view = frame_data.view
@@ -211,6 +220,19 @@ function render_frame!(backend::WebGPUBackend, scene)
     per_frame_data = _pack_per_frame(view, proj, Mat4f(inv_vp), cam_pos, time_val)
     wgpu_begin_frame(backend.backend_handle, per_frame_data)
 
+    # Lazy IBL initialization (first frame with IBLComponent bakes the environment)
+    if frame_data.lights.has_ibl && frame_data.lights.ibl_path != backend.ibl_path
+        backend.ibl_path = frame_data.lights.ibl_path
+        try
+            backend_create_ibl_environment!(backend,
+                frame_data.lights.ibl_path, frame_data.lights.ibl_intensity)
+            @info "WebGPU IBL environment created" path=frame_data.lights.ibl_path
+        catch e
+            @warn "Failed to create IBL environment, using fallback ambient" exception=e
+        end
+    end
+    _sync_color_grading!(backend)
+
     # --- Render graph path (opt-in) ---
     if backend.render_graph !== nothing && backend.graph_executor !== nothing
         pp_config = backend.post_process_config !== nothing ? backend.post_process_config : PostProcessConfig()
@@ -234,6 +256,9 @@ function render_frame!(backend::WebGPUBackend, scene)
     # 2. Upload lights
     light_data = _pack_lights(frame_data.lights)
     wgpu_upload_lights(backend.backend_handle, light_data)
+    if length(frame_data.lights.point_positions) > 16
+        wgpu_upload_point_lights(backend.backend_handle, _pack_point_lights(frame_data.lights))
+    end
 
     # 3. Shadow pass (if directional light exists)
     if frame_data.primary_light_dir !== nothing
@@ -665,6 +690,24 @@ end
 
 # ---- Helper: Pack post-process params ----
 
+"""
+    _sync_color_grading!(backend)
+
+Load the config's LUTs whose paths changed and upload the blend and intensity.
+"""
+function _sync_color_grading!(backend::WebGPUBackend)
+    config = backend.post_process_config
+    config === nothing && return
+    for (slot, path) in enumerate((config.color_lut, config.color_lut_b))
+        path == backend.color_lut_paths[slot] && continue
+        backend.color_lut_paths[slot] = path
+        if wgpu_load_color_lut(backend.backend_handle, slot - 1, path) != 0
+            @warn "Failed to load color LUT" path error=wgpu_last_error(backend.backend_handle)
+        end
+    end
+    wgpu_set_color_grading(backend.backend_handle, config.color_lut_blend, config.color_lut_intensity)
+end
+
 """
     _pack_postprocess_params(backend) -> Vector{UInt8}
 
@@ -684,6 +727,13 @@ function _pack_postprocess_params(backend::WebGPUBackend)::Vector{UInt8}
     color_brightness = 0.0f0
     color_contrast = 1.0f0
     color_saturation = 1.0f0
+    exposure_mode = Int32(0)  # manual
+    exposure_ev = 0.0f0
+    adaptation_speed = 0.0f0
+
+    now = get_time()
+    delta_time = backend.last_postprocess_time > 0.0 ? Float32(now - backend.last_postprocess_time) : 0.0f0
+    backend.last_postprocess_time = now
 
     if config !== nothing
         bloom_threshold = config.bloom_threshold
@@ -696,6 +746,9 @@ function _pack_postprocess_params(backend::WebGPUBackend)::Vector{UInt8}
         color_brightness = config.color_grading_enabled ? config.color_grading_brightness : 0.0f0
         color_contrast = config.color_grading_enabled ? config.color_grading_contrast : 1.0f0
         color_saturation = config.color_grading_enabled ? config.color_grading_saturation : 1.0f0
+        exposure_mode = config.auto_exposure ? Int32(1) : Int32(0)
+        exposure_ev = config.exposure_ev
+        adaptation_speed = config.exposure_adaptation_speed
     end
 
     pp = WGPUPostProcessParams(
@@ -710,6 +763,10 @@ function _pack_postprocess_params(backend::WebGPUBackend)::Vector{UInt8}
         color_brightness,
         color_contrast,
         color_saturation,
+        exposure_mode,
+        exposure_ev,
+        adaptation_speed,
+        delta_time,
         0.0f0,                  # _pad1
     )
     return _struct_to_bytes(pp)
@@ -1100,7 +1157,10 @@ end
 # ---- IBL operations ----
 
 function backend_create_ibl_environment!(backend::WebGPUBackend, path::String, intensity::Float32)
-    return WebGPUIBLEnvironment(UInt64(0))
+    if wgpu_create_ibl_environment(backend.backend_handle, path) != 0
+        error("WebGPU IBL environment creation failed: $(wgpu_last_error(backend.backend_handle))")
+    end
+    return WebGPUIBLEnvironment(UInt64(1))
 end
 
 # ---- Screen-space effect operations ----
@@ -112,7 +112,7 @@ end
 
 """
     wgpu_gbuffer_instanced_pass(backend, mesh_handle, material_data, texture_handles,
-                                  instance_data, instance_count) -> Int32
+                                  instance_data, instance_count; entity_ids=nothing) -> Int32
 
 Render an instanced batch into the G-Buffer. All instances share the same mesh + material.
 - `mesh_handle`: UInt64 GPU mesh handle
@@ -120,6 +120,7 @@ Render an instanced batch into the G-Buffer. All instances share the same mesh +
 - `texture_handles`: Vector{UInt64} of 6 texture handles
 - `instance_data`: Vector{Float32} of 28 floats per instance (model mat4 + 3 normal vec4)
 - `instance_count`: number of instances
+- `entity_ids`: optional Vector{UInt32} picking ID per instance
 Returns 0 on success, -1 on failure.
 """
 function wgpu_gbuffer_instanced_pass(backend::UInt64,
@@ -127,10 +128,13 @@ function wgpu_gbuffer_instanced_pass(backend::UInt64,
                                        material_data::Vector{UInt8},
                                        texture_handles::Vector{UInt64},
                                        instance_data::Vector{Float32},
-                                       instance_count::Integer)
-    ccall((:or_wgpu_gbuffer_instanced_pass, _webgpu_lib()), Int32,
-          (UInt64, UInt64, Ptr{UInt8}, Ptr{UInt64}, Ptr{Float32}, UInt32),
-          backend, mesh_handle, material_data, texture_handles, instance_data, UInt32(instance_count))
+                                       instance_count::Integer;
+                                       entity_ids::Union{Vector{UInt32}, Nothing}=nothing)
+    ids_ptr = entity_ids === nothing ? Ptr{UInt32}(C_NULL) : pointer(entity_ids)
+    GC.@preserve entity_ids ccall((:or_wgpu_gbuffer_instanced_pass, _webgpu_lib()), Int32,
+          (UInt64, UInt64, Ptr{UInt8}, Ptr{UInt64}, Ptr{Float32}, Ptr{UInt32}, UInt32),
+          backend, mesh_handle, material_data, texture_handles, instance_data, ids_ptr,
+          UInt32(instance_count))
 end
 
 """
@@ -173,6 +177,129 @@ function wgpu_create_csm(backend::UInt64, num_cascades::Int, resolution::Int,
           backend, Int32(num_cascades), Int32(resolution), near, far)
 end
 
+function wgpu_create_ibl_environment(backend::UInt64, path::String)
+    ccall((:or_wgpu_create_ibl_environment, _webgpu_lib()), Int32,
+          (UInt64, Cstring), backend, path)
+end
+
+# ---- Color grading LUTs ----
+
+"""
+    wgpu_load_color_lut(backend, slot, path) -> Int32
+
+Load a `.cube` LUT into slot 0 (A) or 1 (B); an empty `path` clears the slot.
+Returns 0 on success, -1 on failure.
+"""
+function wgpu_load_color_lut(backend::UInt64, slot::Int, path::String)
+    ccall((:or_wgpu_load_color_lut, _webgpu_lib()), Int32,
+          (UInt64, Int32, Cstring), backend, Int32(slot), isempty(path) ? C_NULL : path)
+end
+
+function wgpu_set_color_grading(backend::UInt64, blend::Float32, intensity::Float32)
+    ccall((:or_wgpu_set_color_grading, _webgpu_lib()), Cvoid,
+          (UInt64, Float32, Float32), backend, blend, intensity)
+end
+
+# ---- Picking ----
+
+"""
+    wgpu_set_picking(backend, enabled) -> Int32
+
+Enable or disable writing entity IDs (bytes 264..268 of each packed entity)
+during the G-Buffer and forward passes. Returns 0 on success, -1 on failure.
+"""
+function wgpu_set_picking(backend::UInt64, enabled::Bool)
+    ccall((:or_wgpu_set_picking, _webgpu_lib()), Int32,
+          (UInt64, Int32), backend, Int32(enabled ? 1 : 0))
+end
+
+"""
+    wgpu_request_pick(backend, x, y) -> Int32
+
+Start reading the entity ID at pixel (`x`, `y`) of the last rendered frame.
+Returns 0 on success, -1 on failure.
+"""
+function wgpu_request_pick(backend::UInt64, x::Integer, y::Integer)
+    ccall((:or_wgpu_request_pick, _webgpu_lib()), Int32,
+          (UInt64, Int32, Int32), backend, Int32(x), Int32(y))
+end
+
+"""
+    wgpu_pick_result(backend) -> Int64
+
+Entity ID from the last `wgpu_request_pick`: 0 for no entity, -2 while the
+readback is in flight, -1 without a request.
+"""
+function wgpu_pick_result(backend::UInt64)
+    ccall((:or_wgpu_pick_result, _webgpu_lib()), Int64, (UInt64,), backend)
+end
+
+"""
+    wgpu_set_selection(backend, ids) -> Int32
+
+Outline and tint the entities with these picking IDs when presenting; enables
+picking. An empty `ids` clears the selection. Returns 0 on success, -1 on failure.
+"""
+function wgpu_set_selection(backend::UInt64, ids::Vector{UInt32})
+    ccall((:or_wgpu_set_selection, _webgpu_lib()), Int32,
+          (UInt64, Ptr{UInt32}, UInt32), backend, ids, UInt32(length(ids)))
+end
+
+function wgpu_set_selection_style(backend::UInt64, outline_color::NTuple{4, Float32},
+                                   outline_width::Float32, tint_color::NTuple{4, Float32})
+    ccall((:or_wgpu_set_selection_style, _webgpu_lib()), Cvoid,
+          (UInt64, Ref{NTuple{4, Float32}}, Float32, Ref{NTuple{4, Float32}}),
+          backend, outline_color, outline_width, tint_color)
+end
+
+# ---- Debug views ----
+
+"""
+    wgpu_set_debug_view(backend, name) -> Int32
+
+Show an intermediate target in place of the final image: "final", "albedo",
+"metallic", "normal", "roughness", "emissive", "occlusion", "depth", "ssao",
+"ssr", "bloom", "lighting" or "cascade0"–"cascade3". Returns -1 for unknown names.
+"""
+function wgpu_set_debug_view(backend::UInt64, name::String)
+    ccall((:or_wgpu_set_debug_view, _webgpu_lib()), Int32,
+          (UInt64, Cstring), backend, name)
+end
+
+# ---- Profiling ----
+
+"""
+    wgpu_set_profiling(backend, enabled) -> Int32
+
+Time every pass between `wgpu_begin_frame` and `wgpu_present`. Returns 1 when
+passes are timed with GPU timestamps, 0 when on the CPU, -1 on failure.
+"""
+function wgpu_set_profiling(backend::UInt64, enabled::Bool)
+    ccall((:or_wgpu_set_profiling, _webgpu_lib()), Int32,
+          (UInt64, Int32), backend, Int32(enabled))
+end
+
+"""
+    wgpu_frame_timings(backend) -> Vector{Pair{String, Float64}}
+
+Pass names and durations in milliseconds of the latest profiled frame, in
+execution order. GPU timings arrive a frame or two after their frame.
+"""
+function wgpu_frame_timings(backend::UInt64)
+    count = ccall((:or_wgpu_frame_timing_count, _webgpu_lib()), Int32, (UInt64,), backend)
+    timings = Pair{String, Float64}[]
+    name_buf = Vector{UInt8}(undef, 64)
+    ms = Ref{Float64}(0.0)
+    for i in 0:(count - 1)
+        len = ccall((:or_wgpu_frame_timing, _webgpu_lib()), Int32,
+                    (UInt64, Int32, Ptr{UInt8}, UInt32, Ref{Float64}),
+                    backend, Int32(i), name_buf, UInt32(length(name_buf)), ms)
+        len < 0 && break
+        push!(timings, String(name_buf[1:len]) => ms[])
+    end
+    return timings
+end
+
 # ---- Post-processing ----
 
 function wgpu_create_post_process(backend::UInt64, width::Int, height::Int,
@@ -311,7 +438,7 @@ Matches the layout parsed in lib.rs:
   offset 104: normal_col2 (vec4, 16 bytes)
   offset 120: material (WGPUMaterialUniforms, 96 bytes)
   offset 216: texture_handles (6 x u64, 48 bytes)
-  Total: 264 bytes
+  Total: 264 bytes (a u32 picking ID may follow at offset 264; see `wgpu_set_picking`)
 """
 struct WGPUEntityDrawData
     mesh_handle::UInt64                        # 8 bytes
@@ -385,8 +512,8 @@ end
     WGPUPostProcessParams
 
 Matches Rust `PostProcessParams`.
-Bloom, tone mapping, vignette, and color grading control params.
-Total: 48 bytes.
+Bloom, exposure, tone mapping, vignette, and color grading control params.
+Total: 64 bytes.
 """
 struct WGPUPostProcessParams
     bloom_threshold::Float32                   # 4
@@ -400,6 +527,10 @@ struct WGPUPostProcessParams
     color_brightness::Float32                  # 4
     color_contrast::Float32                    # 4
     color_saturation::Float32                  # 4
+    exposure_mode::Int32                       # 4  (0 = manual, 1 = auto)
+    exposure_ev::Float32                       # 4
+    adaptation_speed::Float32                  # 4
+    delta_time::Float32                        # 4
     _pad1::Float32                             # 4
 end
 
@@ -524,6 +655,21 @@ function wgpu_upload_lights(backend::UInt64, light_data::Vector{UInt8})
           backend, light_data, UInt32(length(light_data)))
 end
 
+"""
+    wgpu_upload_point_lights(backend, lights) -> Int32
+
+Replace the point lights shaded by the lighting and forward passes with
+`lights` (a Vector{WGPUPointLightData}, any length). Lights are culled per view
+cluster on the Rust side. Call after `wgpu_upload_lights`, which resets the
+list to the (at most 16) point lights in LightUniforms.
+Returns 0 on success, -1 on failure.
+"""
+function wgpu_upload_point_lights(backend::UInt64, lights::Vector{WGPUPointLightData})
+    ccall((:or_wgpu_upload_point_lights, _webgpu_lib()), Int32,
+          (UInt64, Ptr{WGPUPointLightData}, UInt32),
+          backend, lights, UInt32(length(lights)))
+end
+
 """
     wgpu_shadow_pass(backend, mesh_handles, models, entity_count, cascade_matrices, num_cascades) -> Int32
 
@@ -813,6 +959,26 @@ end
 # Helper: _pack_lights
 # ==================================================================
 
+"""
+    _pack_point_lights(frame_light_data) -> Vector{WGPUPointLightData}
+
+Pack every point light of a FrameLightData (no 16-light cap) for
+`wgpu_upload_point_lights`.
+"""
+function _pack_point_lights(fld)::Vector{WGPUPointLightData}
+    return [
+        WGPUPointLightData(
+            (Float32(fld.point_positions[i][1]), Float32(fld.point_positions[i][2]), Float32(fld.point_positions[i][3]), 0.0f0),
+            (Float32(fld.point_colors[i].r), Float32(fld.point_colors[i].g), Float32(fld.point_colors[i].b), 1.0f0),
+            Float32(fld.point_intensities[i]),
+            Float32(fld.point_ranges[i]),
+            0.0f0,
+            0.0f0,
+        )
+        for i in eachindex(fld.point_positions)
+    ]
+end
+
 """
     _pack_lights(frame_light_data) -> Vector{UInt8}
 
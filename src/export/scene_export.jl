@@ -21,6 +21,7 @@ const SECTION_PARTICLES    = UInt32(12)
 const SECTION_PHYSICS_CFG  = UInt32(13)
 const SECTION_SCRIPTS      = UInt32(14)
 const SECTION_GAME_STATE   = UInt32(15)
+const SECTION_TERRAINS     = UInt32(16)
 
 # Component mask bit flags
 const CMASK_TRANSFORM    = UInt64(1) << 0
@@ -37,6 +38,7 @@ const CMASK_PARTICLE     = UInt64(1) << 10
 const CMASK_AUDIO_SRC    = UInt64(1) << 11
 const CMASK_AUDIO_LIST   = UInt64(1) << 12
 const CMASK_IBL          = UInt64(1) << 13
+const CMASK_TERRAIN      = UInt64(1) << 14
 
 """
     export_scene(scene::Scene, path::String; physics_config, compress_textures)
@@ -108,6 +110,17 @@ function export_scene(scene::Scene, path::String;
                 end
             end
         end
+        if has_component(eid, TerrainComponent)
+            # Terrain layer textures share the scene texture table
+            for layer in get_component(eid, TerrainComponent).layers
+                for tex_path in (layer.albedo_path, layer.normal_path)
+                    if tex_path != "" && !haskey(texture_index_map, tex_path)
+                        texture_index_map[tex_path] = Int32(length(unique_textures))
+                        push!(unique_textures, tex_path)
+                    end
+                end
+            end
+        end
     end
 
     open(path, "w") do io
@@ -166,6 +179,9 @@ function export_scene(scene::Scene, path::String;
 
         # ---- Game State Section (from @webref registry) ----
         _write_game_state(io)
+
+        # ---- Terrains Section ----
+        _write_terrains(io, entities, entity_index, texture_index_map)
     end
 
     @info "Exported scene to $path ($(num_entities) entities, $(length(unique_meshes)) meshes, $(length(unique_textures)) textures)"
@@ -198,6 +214,7 @@ function _write_entity_graph(io, entities, entity_index, parent_map,
         has_component(eid, AnimationComponent)      && (mask |= CMASK_ANIMATION)
         has_component(eid, SkinnedMeshComponent)    && (mask |= CMASK_SKELETON)
         has_component(eid, ParticleSystemComponent) && (mask |= CMASK_PARTICLE)
+        has_component(eid, TerrainComponent)        && (mask |= CMASK_TERRAIN)
         write(io, mask)
 
         # Component indices (UInt32_MAX if not present)
@@ -249,7 +266,7 @@ function _write_meshes(io, meshes)
         write(io, UInt32(nv))
         write(io, UInt32(ni))
         write(io, UInt32(has_bones ? 1 : 0))
-        write(io, UInt32(0))  # padding
+        write(io, UInt32(0))  # encoding flags (0 = f32 attributes, u32 indices)
 
         # Positions
         for v in mesh.vertices
@@ -657,3 +674,50 @@ function _write_game_state(io)
         end
     end
 end
+
+function _write_terrains(io, entities, entity_index, texture_index_map)
+    terrain_eids = filter(eid -> has_component(eid, TerrainComponent), entities)
+    write(io, UInt32(length(terrain_eids)))
+
+    for eid in terrain_eids
+        comp = get_component(eid, TerrainComponent)
+        td = initialize_terrain!(eid, comp)
+        hm = td.heightmap  # (res_x+1) x (res_z+1), x varies fastest in memory
+        layers = comp.layers[1:min(4, length(comp.layers))]
+
+        # Splatmap is stored as raw RGBA8 rows (no PNG) so the runtime can
+        # upload it as linear data
+        splat = UInt8[]
+        splat_w, splat_h = 0, 0
+        if !isempty(comp.splatmap_path) && isfile(comp.splatmap_path)
+            img = FileIO.load(comp.splatmap_path)
+            splat_h, splat_w = size(img)
+            sizehint!(splat, splat_w * splat_h * 4)
+            for row in 1:splat_h, col in 1:splat_w
+                p = convert(RGBA{Float32}, img[row, col])
+                for c in (red(p), green(p), blue(p), alpha(p))
+                    push!(splat, round(UInt8, clamp(c, 0.0f0, 1.0f0) * 255))
+                end
+            end
+        end
+
+        write(io, entity_index[eid])
+        write(io, Float32(comp.terrain_size[1]), Float32(comp.terrain_size[2]))
+        write(io, Float32(comp.max_height))
+        write(io, UInt32(comp.chunk_size))
+        write(io, UInt32(comp.num_lod_levels))
+        write(io, UInt32(size(hm, 1)), UInt32(size(hm, 2)))
+        write(io, UInt32(splat_w), UInt32(splat_h))
+        write(io, UInt32(length(layers)))
+
+        for layer in layers
+            write(io, get(texture_index_map, layer.albedo_path, Int32(-1)))
+            write(io, get(texture_index_map, layer.normal_path, Int32(-1)))
+            write(io, Float32(layer.uv_scale))
+            write(io, UInt32(0))  # padding
+        end
+
+        write(io, vec(hm))
+        write(io, splat)
+    end
+end
@@ -79,14 +79,16 @@ struct FrameData
 end
 
 """
-    collect_lights() -> FrameLightData
+    collect_lights(; max_point_lights=16) -> FrameLightData
 
-Query all light components from the ECS and return structured data.
+Query all light components from the ECS and return structured data. Point
+lights are capped at `max_point_lights`; backends with clustered lighting
+pass `typemax(Int)`.
 """
-function collect_lights()
+function collect_lights(; max_point_lights::Int=16)
     # Point lights
     point_entities = entities_with_component(PointLightComponent)
-    num_point = min(length(point_entities), 16)
+    num_point = min(length(point_entities), max_point_lights)
     point_positions = Vec3f[]
     point_colors = RGB{Float32}[]
     point_intensities = Float32[]
@@ -140,7 +142,7 @@ function collect_lights()
 end
 
 """
-    prepare_frame(scene::Scene, bounds_cache::Dict{EntityID, BoundingSphere}) -> Union{FrameData, Nothing}
+    prepare_frame(scene::Scene, bounds_cache::Dict{EntityID, BoundingSphere}; max_point_lights=16) -> Union{FrameData, Nothing}
 
 Perform backend-agnostic frame setup: find camera, extract frustum, classify
 entities (opaque vs transparent), collect lights. Returns `nothing` if no
@@ -151,9 +153,10 @@ phase that touches Observable values in the ECS), then fans out frustum
 culling, LOD selection, normal-matrix construction, and opaque/transparent
 classification across the engine-wide [`EEVDFScheduler`](@ref) via
 [`parallel_for_chunks`](@ref). Results from each chunk are merged on the
-calling thread before returning.
+calling thread before returning. `max_point_lights` is forwarded to
+[`collect_lights`](@ref).
 """
-function prepare_frame(scene::Scene, bounds_cache::Dict{EntityID, BoundingSphere})
+function prepare_frame(scene::Scene, bounds_cache::Dict{EntityID, BoundingSphere}; max_point_lights::Int=16)
     # Find active camera (main thread)
     camera_id = find_active_camera()
     camera_id === nothing && return nothing
@@ -261,7 +264,7 @@ function prepare_frame(scene::Scene, bounds_cache::Dict{EntityID, BoundingSphere
     opaque_entities = reduce(vcat, local_opaque; init=EntityRenderData[])
     transparent_entities = reduce(vcat, local_transparent; init=TransparentEntityData[])
 
-    lights = collect_lights()
+    lights = collect_lights(; max_point_lights=max_point_lights)
     primary_light_dir = isempty(lights.dir_directions) ? nothing : lights.dir_directions[1]
 
     return FrameData(
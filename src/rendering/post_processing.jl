@@ -4,9 +4,10 @@
 """
     ToneMappingMode
 
-Selectable tone mapping operator.
+Selectable tone mapping operator. `TONEMAP_AGX` is implemented by the WebGPU
+backend; the others fall back to Uncharted2 for it.
 """
-@enum ToneMappingMode TONEMAP_REINHARD TONEMAP_ACES TONEMAP_UNCHARTED2
+@enum ToneMappingMode TONEMAP_REINHARD TONEMAP_ACES TONEMAP_UNCHARTED2 TONEMAP_AGX
 
 """
     FogMode
@@ -31,6 +32,12 @@ mutable struct PostProcessConfig
     fxaa_enabled::Bool
     gamma::Float32
 
+    # Exposure (WebGPU backend): manual stops, or histogram auto exposure
+    # with `exposure_ev` as compensation
+    auto_exposure::Bool
+    exposure_ev::Float32
+    exposure_adaptation_speed::Float32
+
     # Depth of Field
     dof_enabled::Bool
     dof_focus_distance::Float32
@@ -55,6 +62,14 @@ mutable struct PostProcessConfig
     color_grading_contrast::Float32
     color_grading_saturation::Float32
 
+    # LUT color grading (WebGPU backend): `.cube` files in slots A and B,
+    # cross-faded by `color_lut_blend` (0 = A, 1 = B). An empty path grades
+    # with the identity, so fading toward it fades the look out.
+    color_lut::String
+    color_lut_b::String
+    color_lut_blend::Float32
+    color_lut_intensity::Float32
+
     # Fog
     fog_enabled::Bool
     fog_mode::FogMode
@@ -76,6 +91,9 @@ mutable struct PostProcessConfig
         tone_mapping::ToneMappingMode = TONEMAP_REINHARD,
         fxaa_enabled::Bool = false,
         gamma::Float32 = 2.2f0,
+        auto_exposure::Bool = false,
+        exposure_ev::Float32 = 0.0f0,
+        exposure_adaptation_speed::Float32 = 1.5f0,
         dof_enabled::Bool = false,
         dof_focus_distance::Float32 = 10.0f0,
         dof_focus_range::Float32 = 5.0f0,
@@ -92,6 +110,10 @@ mutable struct PostProcessConfig
         color_grading_brightness::Float32 = 0.0f0,
         color_grading_contrast::Float32 = 1.0f0,
         color_grading_saturation::Float32 = 1.0f0,
+        color_lut::String = "",
+        color_lut_b::String = "",
+        color_lut_blend::Float32 = 0.0f0,
+        color_lut_intensity::Float32 = 1.0f0,
         fog_enabled::Bool = false,
         fog_mode::FogMode = FOG_EXPONENTIAL,
         fog_color::Vec3f = Vec3f(0.7f0, 0.7f0, 0.8f0),
@@ -104,10 +126,12 @@ mutable struct PostProcessConfig
     ) = new(bloom_enabled, bloom_threshold, bloom_intensity,
             ssao_enabled, ssao_radius, ssao_samples,
             tone_mapping, fxaa_enabled, gamma,
+            auto_exposure, exposure_ev, exposure_adaptation_speed,
             dof_enabled, dof_focus_distance, dof_focus_range, dof_bokeh_radius,
             motion_blur_enabled, motion_blur_intensity, motion_blur_samples, motion_blur_max_velocity,
             vignette_enabled, vignette_intensity, vignette_radius, vignette_softness,
             color_grading_enabled, color_grading_brightness, color_grading_contrast, color_grading_saturation,
+            color_lut, color_lut_b, color_lut_blend, color_lut_intensity,
             fog_enabled, fog_mode, fog_color, fog_density, fog_start, fog_end,
             fog_height_enabled, fog_height_falloff, fog_height_offset)
 end
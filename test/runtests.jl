@@ -2141,6 +2141,11 @@ using ColorTypes: RGBA, red, green, blue, alpha
             @test config.tone_mapping == TONEMAP_REINHARD
             @test config.fxaa_enabled == false
             @test config.gamma == 2.2f0
+            @test config.auto_exposure == false
+            @test config.exposure_ev == 0.0f0
+            @test config.color_lut == ""
+            @test config.color_lut_blend == 0.0f0
+            @test config.color_lut_intensity == 1.0f0
         end
 
         @testset "PostProcessConfig custom" begin
@@ -2162,6 +2167,7 @@ using ColorTypes: RGBA, red, green, blue, alpha
             @test TONEMAP_REINHARD isa ToneMappingMode
             @test TONEMAP_ACES isa ToneMappingMode
             @test TONEMAP_UNCHARTED2 isa ToneMappingMode
+            @test TONEMAP_AGX isa ToneMappingMode
         end
 
         @testset "Framebuffer struct" begin